@@ -0,0 +1,105 @@
+//! Throughput of the Fast-Packet receive path: `add_frame` followed by
+//! `get_payload`, `copy_payload` or an in-place read of the payload.
+//!
+//! Run with `cargo bench`, adding `--features alloc` for the bulk
+//! `assemble_into` path. Each case reassembles 10,000 GNSS Position Data
+//! messages (43 bytes, 7 frames) interleaved from four sources.
+use nmea::nmea_message::{Message, MAX_NMEA_PACKET_SIZE};
+use nmea::reassembler::Reassembler;
+use std::hint::black_box;
+use std::time::Instant;
+
+const MESSAGES: usize = 10_000;
+const SOURCES: u8 = 4;
+
+fn frames() -> Vec<(u8, [u8; 8])> {
+    let payload: Vec<u8> = (0..43).collect();
+    let mut msgs: Vec<_> = (0..SOURCES)
+        .map(|source| {
+            let mut msg = Message::from_payload(&payload, source).unwrap();
+            let frames: Vec<[u8; 8]> = std::iter::from_fn(|| msg.pop_frame())
+                .map(|frame| frame.bytes)
+                .collect();
+            (source, frames)
+        })
+        .collect();
+    let mut out = Vec::new();
+    for _ in 0..MESSAGES / SOURCES as usize {
+        for i in 0..7 {
+            for (source, frames) in &mut msgs {
+                out.push((*source, frames[i]));
+            }
+        }
+    }
+    out
+}
+
+fn bench(name: &str, mut f: impl FnMut() -> usize) {
+    // Warm up, then take the best of several runs.
+    f();
+    let best = (0..10)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .min()
+        .unwrap();
+    let rate = MESSAGES as f64 / best.as_secs_f64();
+    println!("{name:<24} {best:>10.2?}  {rate:>12.0} messages/s");
+}
+
+fn main() {
+    let frames = frames();
+    bench("get_payload", || {
+        let mut reassembler: Reassembler<8> = Reassembler::new();
+        let mut buf = [0; MAX_NMEA_PACKET_SIZE];
+        let mut total = 0;
+        for (source, frame) in &frames {
+            if reassembler.add_frame(*source, 129029, frame).unwrap() {
+                total += reassembler.get_payload(*source, 129029, &mut buf).unwrap();
+            }
+        }
+        total
+    });
+    bench("with_message", || {
+        let mut reassembler: Reassembler<8> = Reassembler::new();
+        let mut total = 0;
+        for (source, frame) in &frames {
+            if reassembler.add_frame(*source, 129029, frame).unwrap() {
+                total += reassembler
+                    .with_message(*source, 129029, |msg| {
+                        msg.payload_chunks().map(|chunk| chunk.len()).sum::<usize>()
+                    })
+                    .unwrap();
+            }
+        }
+        total
+    });
+    #[cfg(feature = "alloc")]
+    bench("assemble_into", || {
+        let mut reassembler: Reassembler<8> = Reassembler::new();
+        let mut out = Vec::with_capacity(MESSAGES * 43);
+        for (source, frame) in &frames {
+            if reassembler.add_frame(*source, 129029, frame).unwrap() {
+                reassembler
+                    .assemble_into(*source, 129029, &mut out)
+                    .unwrap();
+            }
+        }
+        out.len()
+    });
+    bench("message copy_payload", || {
+        let mut msgs: [Message; SOURCES as usize] = std::array::from_fn(|_| Message::new());
+        let mut buf = [0; MAX_NMEA_PACKET_SIZE];
+        let mut total = 0;
+        for (source, frame) in &frames {
+            let msg = &mut msgs[*source as usize];
+            if msg.add_frame(frame).unwrap() {
+                total += msg.copy_payload(&mut buf);
+                msg.clear();
+            }
+        }
+        total
+    });
+}
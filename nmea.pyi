@@ -0,0 +1,146 @@
+"""Type stubs for the nmea extension module."""
+
+from typing import Any, Optional, Union
+
+class Message:
+    num_frames: int
+    sequence_counter: int
+    data_len: int
+    kind: str
+    direction: str
+    def __init__(self) -> None: ...
+    @staticmethod
+    def from_payload(payload: bytes, sequence_counter: int) -> Message: ...
+    def add_frame(self, payload: bytes) -> bool: ...
+    def pop_frame(self) -> Optional[bytes]: ...
+    def get_payload(self) -> bytes: ...
+    def abort(self) -> bool: ...
+    def clear(self) -> None: ...
+    def __getstate__(self) -> bytes: ...
+    def __setstate__(self, state: bytes) -> None: ...
+    def __copy__(self) -> Message: ...
+    def __deepcopy__(self, memo: Any) -> Message: ...
+
+def pgn_info(pgn: int) -> Optional[dict[str, Any]]: ...
+
+# Requires the `numpy` feature.
+def decode_frames(frames: Any, fast_packet: list[int]) -> list[dict[str, Any]]: ...
+
+class VesselHeading:
+    sid: Optional[int]
+    heading: Optional[float]
+    deviation: Optional[float]
+    variation: Optional[float]
+    reference: str
+    def to_dict(self) -> dict[str, Any]: ...
+
+class MagneticVariation:
+    sid: Optional[int]
+    age_of_service: Optional[int]
+    variation: Optional[float]
+    source: str
+    def to_dict(self) -> dict[str, Any]: ...
+
+class FluidLevel:
+    instance: int
+    level: Optional[float]
+    capacity: Optional[float]
+    fluid_type: str
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Leeway:
+    sid: Optional[int]
+    leeway: Optional[float]
+    def to_dict(self) -> dict[str, Any]: ...
+
+class DistanceLog:
+    date: Optional[int]
+    time: Optional[float]
+    log: Optional[int]
+    trip_log: Optional[int]
+    def to_dict(self) -> dict[str, Any]: ...
+
+class PositionRapidUpdate:
+    latitude: Optional[float]
+    longitude: Optional[float]
+    def to_dict(self) -> dict[str, Any]: ...
+
+class CogSogRapidUpdate:
+    sid: Optional[int]
+    cog: Optional[float]
+    sog: Optional[float]
+    cog_reference: str
+    def to_dict(self) -> dict[str, Any]: ...
+
+class GnssPositionData:
+    sid: Optional[int]
+    date: Optional[int]
+    time: Optional[float]
+    latitude: Optional[float]
+    longitude: Optional[float]
+    altitude: Optional[float]
+    gnss_type: int
+    integrity: int
+    satellites: Optional[int]
+    hdop: Optional[float]
+    pdop: Optional[float]
+    geoidal_separation: Optional[float]
+    method: str
+    def to_dict(self) -> dict[str, Any]: ...
+
+class WindData:
+    sid: Optional[int]
+    speed: Optional[float]
+    angle: Optional[float]
+    reference: str
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Temperature:
+    sid: Optional[int]
+    instance: int
+    temperature: Optional[float]
+    set_temperature: Optional[float]
+    source: str
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Humidity:
+    sid: Optional[int]
+    instance: int
+    humidity: Optional[float]
+    set_humidity: Optional[float]
+    source: str
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Pressure:
+    sid: Optional[int]
+    instance: int
+    pressure: Optional[float]
+    source: str
+    def to_dict(self) -> dict[str, Any]: ...
+
+class SpeedComponents:
+    longitudinal_water: Optional[float]
+    transverse_water: Optional[float]
+    longitudinal_ground: Optional[float]
+    transverse_ground: Optional[float]
+    stern_water: Optional[float]
+    stern_ground: Optional[float]
+    def to_dict(self) -> dict[str, Any]: ...
+
+Decoded = Union[
+    VesselHeading,
+    MagneticVariation,
+    FluidLevel,
+    Leeway,
+    DistanceLog,
+    PositionRapidUpdate,
+    CogSogRapidUpdate,
+    GnssPositionData,
+    WindData,
+    Temperature,
+    Humidity,
+    Pressure,
+    SpeedComponents,
+]
+
+def decode(pgn: int, payload: bytes) -> Optional[Decoded]: ...
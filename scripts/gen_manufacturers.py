@@ -0,0 +1,43 @@
+#!/usr/bin/env python3
+"""Generates src/manufacturers.rs from manufacturers.csv.
+
+The CSV lists NMEA 2000 manufacturer codes as published in the NMEA
+registry. Run from the repository root after editing it:
+
+    python3 scripts/gen_manufacturers.py
+"""
+import csv
+import json
+import pathlib
+
+root = pathlib.Path(__file__).resolve().parent.parent
+with open(root / "scripts" / "manufacturers.csv", newline="", encoding="utf-8") as f:
+    rows = sorted((int(row["code"]), row["name"]) for row in csv.DictReader(f))
+
+codes = [code for code, _ in rows]
+assert len(codes) == len(set(codes)), "duplicate manufacturer code"
+assert all(code < 2048 for code in codes), "manufacturer codes are 11 bits"
+
+lines = [
+    "//! NMEA 2000 manufacturer names by manufacturer code.",
+    "//!",
+    "//! Generated by `scripts/gen_manufacturers.py` from",
+    "//! `scripts/manufacturers.csv`; edit those instead.",
+    "",
+    "/// Manufacturer codes and names, sorted by code.",
+    "pub const MANUFACTURERS: &[(u16, &str)] = &[",
+]
+lines += [f"    ({code}, {json.dumps(name, ensure_ascii=False)})," for code, name in rows]
+lines += [
+    "];",
+    "",
+    "/// The name of the manufacturer with NMEA code `code`, if known.",
+    "pub fn name(code: u16) -> Option<&'static str> {",
+    "    MANUFACTURERS",
+    "        .binary_search_by_key(&code, |(c, _)| *c)",
+    "        .ok()",
+    "        .map(|i| MANUFACTURERS[i].1)",
+    "}",
+    "",
+]
+(root / "src" / "manufacturers.rs").write_text("\n".join(lines), encoding="utf-8")
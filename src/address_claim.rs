@@ -0,0 +1,325 @@
+//! Claiming a source address with ISO Address Claims, remembering it across
+//! restarts through an `AddressStore`.
+use crate::bridge::FrameSink;
+use crate::clock::Clock;
+use crate::device::{self, Destination, N2kDevice, ADDRESS_CLAIM_PGN, NULL_ADDRESS};
+use crate::frame_queue::RxFrame;
+use crate::rng::Rng;
+
+/// Highest address a device may claim; 252 and 253 are reserved.
+pub const MAX_ADDRESS: u8 = 251;
+
+/// Non-volatile storage for the last claimed address, such as a byte of
+/// EEPROM or a flash page.
+pub trait AddressStore {
+    /// The stored address, if one was saved.
+    fn load(&mut self) -> Option<u8>;
+    fn save(&mut self, address: u8);
+}
+
+/// An `AddressStore` kept in RAM, for tests and devices without storage.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryStore(pub Option<u8>);
+
+impl AddressStore for MemoryStore {
+    fn load(&mut self) -> Option<u8> {
+        self.0
+    }
+
+    fn save(&mut self, address: u8) {
+        self.0 = Some(address);
+    }
+}
+
+/// Claims an address for the device with `name` and defends it.
+///
+/// `claim` starts with the address in the store, or `preferred` if there is
+/// none. Every received frame is also passed to `observe`: when a device
+/// with a lower NAME claims the same address, the claimer moves on to the
+/// next address and saves it; against a higher NAME it claims the address
+/// again. With every address taken, the device claims the null
+/// address and can't send.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::address_claim::{AddressClaimer, MemoryStore};
+/// use nmea::bridge::{self, FrameSink};
+/// use nmea::device::N2kDevice;
+/// use nmea::frame_queue::RxFrame;
+///
+/// struct Bus(Vec<RxFrame>);
+///
+/// impl FrameSink for Bus {
+///     fn transmit(&mut self, frame: &RxFrame) -> Result<(), bridge::Error> {
+///         self.0.push(*frame);
+///         Ok(())
+///     }
+/// }
+///
+/// let mut device: N2kDevice<Bus, 8> = N2kDevice::new(Bus(Vec::new()), 0);
+/// let mut claimer = AddressClaimer::new(0x1234, 0x20, MemoryStore(Some(0x42)));
+/// assert_eq!(claimer.claim(&mut device), Ok(0x42));
+/// assert_eq!(device.address(), 0x42);
+/// ```
+pub struct AddressClaimer<S: AddressStore> {
+    name: u64,
+    preferred: u8,
+    store: S,
+    /// The address the current round of claims started at.
+    first: u8,
+    rng: Rng,
+    /// When the delayed claim is due.
+    pending_ms: Option<u64>,
+}
+
+impl<S: AddressStore> AddressClaimer<S> {
+    pub const fn new(name: u64, preferred: u8, store: S) -> Self {
+        Self {
+            name,
+            preferred,
+            store,
+            first: preferred,
+            rng: Rng::new(name),
+            pending_ms: None,
+        }
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Claims the stored or preferred address for `device`. Returns the
+    /// address claimed.
+    pub fn claim<K: FrameSink, const N: usize>(
+        &mut self,
+        device: &mut N2kDevice<K, N>,
+    ) -> Result<u8, device::Error> {
+        let address = self
+            .store
+            .load()
+            .filter(|address| *address <= MAX_ADDRESS)
+            .unwrap_or(self.preferred);
+        self.first = address;
+        self.claim_address(device, address)?;
+        Ok(address)
+    }
+
+    /// Handles an Address Claim from another device at the time on `clock`;
+    /// other frames are ignored.
+    pub fn observe<K: FrameSink, const N: usize>(
+        &mut self,
+        device: &mut N2kDevice<K, N>,
+        frame: &RxFrame,
+        clock: &impl Clock,
+    ) -> Result<(), device::Error> {
+        let name = u64::from_le_bytes(frame.data);
+        let address = device.address();
+        if frame.id.pgn() != ADDRESS_CLAIM_PGN
+            || frame.id.source() != address
+            || name == self.name
+            || address == NULL_ADDRESS
+        {
+            return Ok(());
+        }
+        if name > self.name {
+            return self.send_claim(device);
+        }
+        let next = if address >= MAX_ADDRESS {
+            0
+        } else {
+            address + 1
+        };
+        device.set_address(if next == self.first {
+            NULL_ADDRESS
+        } else {
+            next
+        });
+        self.pending_ms = Some(clock.now_ms() + self.delay_ms());
+        Ok(())
+    }
+
+    /// Sends a delayed claim if it is due at the time on `clock`. Returns
+    /// whether one was sent.
+    pub fn poll<K: FrameSink, const N: usize>(
+        &mut self,
+        device: &mut N2kDevice<K, N>,
+        clock: &impl Clock,
+    ) -> Result<bool, device::Error> {
+        match self.pending_ms {
+            Some(due_ms) if clock.now_ms() >= due_ms => {
+                self.pending_ms = None;
+                let address = device.address();
+                if address == NULL_ADDRESS {
+                    self.send_claim(device)?;
+                } else {
+                    self.claim_address(device, address)?;
+                }
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Whether a claim is waiting for its delay. The device shouldn't send
+    /// anything else meanwhile.
+    pub fn is_pending(&self) -> bool {
+        self.pending_ms.is_some()
+    }
+
+    /// The next pseudo-random delay: 0.6 ms times a random byte.
+    fn delay_ms(&mut self) -> u64 {
+        (self.rng.next_u64() & 0xFF) * 6 / 10
+    }
+
+    fn claim_address<K: FrameSink, const N: usize>(
+        &mut self,
+        device: &mut N2kDevice<K, N>,
+        address: u8,
+    ) -> Result<(), device::Error> {
+        device.set_address(address);
+        self.send_claim(device)?;
+        self.store.save(address);
+        Ok(())
+    }
+
+    fn send_claim<K: FrameSink, const N: usize>(
+        &self,
+        device: &mut N2kDevice<K, N>,
+    ) -> Result<(), device::Error> {
+        device.send_single(
+            6,
+            ADDRESS_CLAIM_PGN,
+            Destination::Broadcast,
+            &self.name.to_le_bytes(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge;
+    use crate::can_id::{CanId, BROADCAST};
+    use crate::clock::ManualClock;
+    use std::vec::Vec;
+
+    struct Bus(Vec<RxFrame>);
+
+    impl FrameSink for Bus {
+        fn transmit(&mut self, frame: &RxFrame) -> Result<(), bridge::Error> {
+            self.0.push(*frame);
+            Ok(())
+        }
+    }
+
+    /// An address byte and its complement, so that erased (0xFF) or torn
+    /// cells read as empty. Unchanged addresses aren't rewritten, to spare
+    /// the cells.
+    struct Eeprom {
+        cells: [u8; 2],
+        writes: usize,
+    }
+
+    impl AddressStore for Eeprom {
+        fn load(&mut self) -> Option<u8> {
+            let [address, check] = self.cells;
+            (check == !address).then_some(address)
+        }
+
+        fn save(&mut self, address: u8) {
+            if self.load() != Some(address) {
+                self.cells = [address, !address];
+                self.writes += 1;
+            }
+        }
+    }
+
+    fn claim(address: u8, name: u64) -> RxFrame {
+        RxFrame {
+            id: CanId::new(6, ADDRESS_CLAIM_PGN, address, BROADCAST).unwrap(),
+            data: name.to_le_bytes(),
+        }
+    }
+
+    #[test]
+    fn test_claim() {
+        let mut device: N2kDevice<Bus, 4> = N2kDevice::new(Bus(Vec::new()), 0);
+        let eeprom = Eeprom {
+            cells: [0xFF; 2],
+            writes: 0,
+        };
+        let clock = ManualClock::new(0);
+        let mut claimer = AddressClaimer::new(100, 0x20, eeprom);
+        assert_eq!(claimer.claim(&mut device), Ok(0x20));
+        // A higher NAME contends: defend.
+        claimer
+            .observe(&mut device, &claim(0x20, 200), &clock)
+            .unwrap();
+        assert_eq!(device.address(), 0x20);
+        // A lower NAME takes the address.
+        claimer
+            .observe(&mut device, &claim(0x20, 50), &clock)
+            .unwrap();
+        assert_eq!(device.address(), 0x21);
+        assert!(claimer.is_pending());
+        // Claims at other addresses, and our own, are ignored.
+        claimer
+            .observe(&mut device, &claim(0x30, 50), &clock)
+            .unwrap();
+        claimer
+            .observe(&mut device, &claim(0x21, 100), &clock)
+            .unwrap();
+        assert_eq!(device.sink().0.len(), 2);
+        clock.set(153);
+        assert_eq!(claimer.poll(&mut device, &clock), Ok(true));
+        assert_eq!(claimer.poll(&mut device, &clock), Ok(false));
+
+        let frames = &device.sink().0;
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1].id.source(), 0x20);
+        assert_eq!(frames[2].id.source(), 0x21);
+        assert_eq!(frames[2].data, 100u64.to_le_bytes());
+        assert_eq!(claimer.store().cells, [0x21, 0xDE]);
+        assert_eq!(claimer.store().writes, 2);
+
+        // After a restart, the saved address is claimed again.
+        let mut device: N2kDevice<Bus, 4> = N2kDevice::new(Bus(Vec::new()), 0);
+        let mut claimer = AddressClaimer::new(100, 0x20, claimer.store);
+        assert_eq!(claimer.claim(&mut device), Ok(0x21));
+        assert_eq!(claimer.store().writes, 2);
+    }
+
+    #[test]
+    fn test_no_address() {
+        let mut device: N2kDevice<Bus, 4> = N2kDevice::new(Bus(Vec::new()), 0);
+        let clock = ManualClock::new(0);
+        let mut claimer = AddressClaimer::new(100, MAX_ADDRESS, MemoryStore::default());
+        claimer.claim(&mut device).unwrap();
+        claimer
+            .observe(&mut device, &claim(MAX_ADDRESS, 1), &clock)
+            .unwrap();
+        assert_eq!(device.address(), 0);
+        for address in 0..MAX_ADDRESS {
+            claimer
+                .observe(&mut device, &claim(address, 1), &clock)
+                .unwrap();
+            clock.advance(153);
+            assert!(claimer.poll(&mut device, &clock).unwrap());
+        }
+        assert_eq!(device.address(), NULL_ADDRESS);
+        assert_eq!(device.sink().0.last().unwrap().id.source(), NULL_ADDRESS);
+        assert_eq!(claimer.store(), &MemoryStore(Some(MAX_ADDRESS - 1)));
+    }
+
+    #[test]
+    fn test_delay() {
+        let delays = |name| {
+            let mut claimer = AddressClaimer::new(name, 0, MemoryStore::default());
+            [(); 8].map(|_| claimer.delay_ms())
+        };
+        assert!(delays(1).iter().all(|delay| *delay <= 153));
+        assert_eq!(delays(1), delays(1));
+        assert_ne!(delays(1), delays(2));
+    }
+}
@@ -0,0 +1,180 @@
+//! Address translation between two bus segments.
+use crate::can_id::CanId;
+use fixed_queue::LinearMap;
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Address table is full")]
+    FullTable,
+    #[error("Alias {0} is already in use on the other segment")]
+    AliasInUse(u8),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Segment {
+    A,
+    B,
+}
+
+/// Rewrites the addresses of frames crossing between segments `A` and `B`.
+///
+/// A node at `address` on one segment can be given an `alias` under which it
+/// appears on the other, for instance when both segments have claimed the
+/// same address. Frames leaving its segment carry the alias as source, and
+/// frames sent to the alias from the other segment are delivered to the
+/// original address. Aliases are unique per segment so that claims stay
+/// consistent. Up to `M` nodes per segment can be aliased; others keep their
+/// address.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::address_map::{AddressMap, Segment};
+/// use nmea::can_id::CanId;
+///
+/// let mut map: AddressMap<4> = AddressMap::new();
+/// map.insert(Segment::A, 0x10, 0x80).unwrap();
+///
+/// let heading = CanId::new(2, 127250, 0x10, 255).unwrap();
+/// assert_eq!(map.to_other(Segment::A, heading).source(), 0x80);
+///
+/// let request = CanId::new(6, 59904, 0x20, 0x80).unwrap();
+/// assert_eq!(map.to_other(Segment::B, request).destination(), 0x10);
+/// ```
+pub struct AddressMap<const M: usize> {
+    a: LinearMap<u8, u8, M>,
+    b: LinearMap<u8, u8, M>,
+}
+
+impl<const M: usize> Default for AddressMap<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const M: usize> AddressMap<M> {
+    pub const fn new() -> Self {
+        Self {
+            a: LinearMap::new(),
+            b: LinearMap::new(),
+        }
+    }
+
+    fn table(&self, segment: Segment) -> &LinearMap<u8, u8, M> {
+        match segment {
+            Segment::A => &self.a,
+            Segment::B => &self.b,
+        }
+    }
+
+    fn table_mut(&mut self, segment: Segment) -> &mut LinearMap<u8, u8, M> {
+        match segment {
+            Segment::A => &mut self.a,
+            Segment::B => &mut self.b,
+        }
+    }
+
+    /// Makes the node at `address` on `segment` appear as `alias` on the
+    /// other segment, replacing any previous alias of that node.
+    pub fn insert(&mut self, segment: Segment, address: u8, alias: u8) -> Result<(), Error> {
+        let table = self.table_mut(segment);
+        if table.iter().any(|(a, b)| *b == alias && *a != address) {
+            return Err(Error::AliasInUse(alias));
+        }
+        if let Some(existing) = table.get_mut(&address) {
+            *existing = alias;
+            return Ok(());
+        }
+        table.insert(address, alias).map_err(|_| Error::FullTable)?;
+        Ok(())
+    }
+
+    pub fn remove(&mut self, segment: Segment, address: u8) -> Option<u8> {
+        self.table_mut(segment).remove(&address)
+    }
+
+    /// The address under which the node at `address` on `segment` appears on
+    /// the other segment.
+    pub fn alias(&self, segment: Segment, address: u8) -> u8 {
+        self.table(segment)
+            .get(&address)
+            .copied()
+            .unwrap_or(address)
+    }
+
+    /// The address on the other segment of the node known as `alias` on
+    /// `segment`.
+    fn resolve(&self, segment: Segment, alias: u8) -> u8 {
+        let other = match segment {
+            Segment::A => Segment::B,
+            Segment::B => Segment::A,
+        };
+        self.table(other)
+            .iter()
+            .find(|(_, a)| *a == alias)
+            .map_or(alias, |(address, _)| *address)
+    }
+
+    /// Rewrites `id` of a frame received on `from` for transmission on the
+    /// other segment. Broadcast destinations are left alone.
+    pub fn to_other(&self, from: Segment, id: CanId) -> CanId {
+        let destination = if id.is_broadcast() {
+            id.destination()
+        } else {
+            self.resolve(from, id.destination())
+        };
+        id.with_addresses(self.alias(from, id.source()), destination)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert() {
+        let mut map: AddressMap<2> = AddressMap::new();
+        map.insert(Segment::A, 0x10, 0x80).unwrap();
+        map.insert(Segment::A, 0x10, 0x81).unwrap();
+        assert_eq!(map.alias(Segment::A, 0x10), 0x81);
+        assert_eq!(map.alias(Segment::B, 0x10), 0x10);
+        assert_eq!(
+            map.insert(Segment::A, 0x11, 0x81),
+            Err(Error::AliasInUse(0x81))
+        );
+        // Each segment has its own aliases.
+        map.insert(Segment::B, 0x11, 0x81).unwrap();
+        map.insert(Segment::A, 0x12, 0x82).unwrap();
+        assert_eq!(map.insert(Segment::A, 0x13, 0x83), Err(Error::FullTable));
+        assert_eq!(map.remove(Segment::A, 0x10), Some(0x81));
+        map.insert(Segment::A, 0x13, 0x83).unwrap();
+    }
+
+    #[test]
+    fn test_to_other() {
+        let mut map: AddressMap<2> = AddressMap::new();
+        map.insert(Segment::A, 0x10, 0x80).unwrap();
+        map.insert(Segment::B, 0x10, 0x90).unwrap();
+
+        // Request from B's 0x10 to A's 0x10, which each know as 0x80 and
+        // 0x90 respectively.
+        let request = CanId::new(6, 59904, 0x10, 0x80).unwrap();
+        assert_eq!(
+            map.to_other(Segment::B, request),
+            CanId::new(6, 59904, 0x90, 0x10).unwrap()
+        );
+        let reply = CanId::new(6, 59392, 0x10, 0x90).unwrap();
+        assert_eq!(
+            map.to_other(Segment::A, reply),
+            CanId::new(6, 59392, 0x80, 0x10).unwrap()
+        );
+
+        let broadcast = CanId::new(2, 127250, 0x20, 255).unwrap();
+        assert_eq!(map.to_other(Segment::A, broadcast), broadcast);
+    }
+}
@@ -0,0 +1,352 @@
+//! Threshold and rate-of-change rules over decoded values, raising and
+//! clearing alerts for monitoring devices.
+use crate::pgn::alert::{Alert, AlertState, ThresholdStatus, TriggerCondition};
+use fixed_queue::Vec;
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Rule table is full")]
+    FullTable,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Condition {
+    /// The value is below the threshold.
+    Below(f32),
+    /// The value is above the threshold.
+    Above(f32),
+    /// The value rises faster than this many units per second.
+    RisingFaster(f32),
+    /// The value falls faster than this many units per second.
+    FallingFaster(f32),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Rule {
+    pub pgn: u32,
+    /// Source address to watch, or `None` for every source.
+    pub source: Option<u8>,
+    /// Extracts the watched value from a payload of `pgn`, e.g. through its
+    /// decoder. Payloads without it are ignored.
+    pub value: fn(&[u8]) -> Option<f32>,
+    pub condition: Condition,
+    /// How far past the threshold the value, or its rate, must recover
+    /// before the alert clears.
+    pub hysteresis: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transition {
+    Raised,
+    Cleared,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AlertEvent {
+    /// Index of the rule, as returned by `AlertEngine::add`.
+    pub rule: usize,
+    pub timestamp_ms: u64,
+    pub source: u8,
+    pub transition: Transition,
+    /// The value, or for rate-of-change rules its rate per second.
+    pub value: f32,
+}
+
+impl AlertEvent {
+    /// Fills the state of `alert`, an Alert PGN describing the rule, for
+    /// announcing this event on the bus.
+    pub fn to_alert(&self, condition: Condition, mut alert: Alert) -> Alert {
+        alert.trigger = TriggerCondition::Auto;
+        match self.transition {
+            Transition::Raised => {
+                alert.state = AlertState::Active;
+                alert.threshold_status = match condition {
+                    Condition::Below(_) | Condition::FallingFaster(_) => {
+                        ThresholdStatus::LowExceeded
+                    }
+                    Condition::Above(_) | Condition::RisingFaster(_) => ThresholdStatus::Exceeded,
+                };
+            }
+            Transition::Cleared => {
+                alert.state = AlertState::Normal;
+                alert.threshold_status = ThresholdStatus::Normal;
+            }
+        }
+        alert
+    }
+}
+
+struct RuleState {
+    rule: Rule,
+    active: bool,
+    /// Previous value and its time, for rates of change.
+    last: Option<(u64, f32)>,
+}
+
+impl RuleState {
+    /// The value compared against the condition, if there is one yet.
+    fn measure(&mut self, timestamp_ms: u64, value: f32) -> Option<f32> {
+        let last = self.last.replace((timestamp_ms, value));
+        match self.rule.condition {
+            Condition::Below(_) | Condition::Above(_) => Some(value),
+            Condition::RisingFaster(_) | Condition::FallingFaster(_) => {
+                let (last_ms, last_value) = last?;
+                let dt = timestamp_ms.checked_sub(last_ms).filter(|dt| *dt > 0)?;
+                Some((value - last_value) * 1000.0 / dt as f32)
+            }
+        }
+    }
+
+    fn raises(&self, x: f32) -> bool {
+        match self.rule.condition {
+            Condition::Below(t) => x < t,
+            Condition::Above(t) => x > t,
+            Condition::RisingFaster(r) => x > r,
+            Condition::FallingFaster(r) => x < -r,
+        }
+    }
+
+    fn clears(&self, x: f32) -> bool {
+        let h = self.rule.hysteresis;
+        match self.rule.condition {
+            Condition::Below(t) => x >= t + h,
+            Condition::Above(t) => x <= t - h,
+            Condition::RisingFaster(r) => x <= r - h,
+            Condition::FallingFaster(r) => x >= -r + h,
+        }
+    }
+}
+
+/// Evaluates up to `N` rules against received messages, reporting an
+/// `AlertEvent` whenever a rule's alert is raised or cleared.
+///
+/// A rule watching every source keeps a single state, so it suits values
+/// that only one device on the bus sends.
+///
+/// ## Example (shallow water below 2 m):
+///
+/// ```
+/// use nmea::alert::{AlertEngine, Condition, Rule, Transition};
+///
+/// fn depth(payload: &[u8]) -> Option<f32> {
+///     let raw = u32::from_le_bytes(payload.get(1..5)?.try_into().ok()?);
+///     (raw < 0xFFFF_FFFE).then_some(raw as f32 * 0.01)
+/// }
+///
+/// let mut engine: AlertEngine<4> = AlertEngine::new();
+/// let shallow = engine
+///     .add(Rule {
+///         pgn: 128267,
+///         source: None,
+///         value: depth,
+///         condition: Condition::Below(2.0),
+///         hysteresis: 0.5,
+///     })
+///     .unwrap();
+///
+/// let mut events = Vec::new();
+/// for (t, cm) in [(0, 300u32), (1000, 190), (2000, 210), (3000, 260)] {
+///     let mut payload = [0xFF; 8];
+///     payload[1..5].copy_from_slice(&cm.to_le_bytes());
+///     engine.update(t, 0x10, 128267, &payload, |e| events.push((e.timestamp_ms, e.transition)));
+/// }
+/// assert_eq!(events, [(1000, Transition::Raised), (3000, Transition::Cleared)]);
+/// assert!(!engine.is_active(shallow));
+/// ```
+pub struct AlertEngine<const N: usize> {
+    rules: Vec<RuleState, N>,
+}
+
+impl<const N: usize> Default for AlertEngine<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> AlertEngine<N> {
+    pub const fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Adds `rule`, returning its index.
+    pub fn add(&mut self, rule: Rule) -> Result<usize, Error> {
+        self.rules
+            .push(RuleState {
+                rule,
+                active: false,
+                last: None,
+            })
+            .map_err(|_| Error::FullTable)?;
+        Ok(self.rules.len() - 1)
+    }
+
+    pub fn rule(&self, index: usize) -> Option<&Rule> {
+        self.rules.get(index).map(|state| &state.rule)
+    }
+
+    /// Whether the alert of rule `index` is raised.
+    pub fn is_active(&self, index: usize) -> bool {
+        self.rules.get(index).is_some_and(|state| state.active)
+    }
+
+    /// Evaluates the rules watching `pgn` from `source` against `payload`,
+    /// received at `timestamp_ms`.
+    pub fn update(
+        &mut self,
+        timestamp_ms: u64,
+        source: u8,
+        pgn: u32,
+        payload: &[u8],
+        mut report: impl FnMut(AlertEvent),
+    ) {
+        for (index, state) in self.rules.iter_mut().enumerate() {
+            if state.rule.pgn != pgn || state.rule.source.is_some_and(|s| s != source) {
+                continue;
+            }
+            let Some(value) = (state.rule.value)(payload) else {
+                continue;
+            };
+            let Some(x) = state.measure(timestamp_ms, value) else {
+                continue;
+            };
+            let transition = if !state.active && state.raises(x) {
+                Transition::Raised
+            } else if state.active && state.clears(x) {
+                Transition::Cleared
+            } else {
+                continue;
+            };
+            state.active = transition == Transition::Raised;
+            report(AlertEvent {
+                rule: index,
+                timestamp_ms,
+                source,
+                transition,
+                value: x,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::alert::{AlertCategory, AlertType};
+    use std::vec::Vec;
+
+    const BATTERY_PGN: u32 = 127508;
+
+    /// Battery voltage from Battery Status, in volts.
+    fn voltage(payload: &[u8]) -> Option<f32> {
+        let raw = u16::from_le_bytes(payload.get(1..3)?.try_into().ok()?);
+        (raw < 0xFFFE).then_some(raw as f32 * 0.01)
+    }
+
+    fn battery(volts: f32) -> [u8; 8] {
+        let mut payload = [0xFF; 8];
+        payload[0] = 0;
+        payload[1..3].copy_from_slice(&((volts * 100.0) as u16).to_le_bytes());
+        payload
+    }
+
+    fn run(engine: &mut AlertEngine<4>, samples: &[(u64, u8, f32)]) -> Vec<(u64, Transition)> {
+        let mut events = Vec::new();
+        for (t, source, volts) in samples {
+            engine.update(*t, *source, BATTERY_PGN, &battery(*volts), |e| {
+                events.push((e.timestamp_ms, e.transition))
+            });
+        }
+        events
+    }
+
+    #[test]
+    fn test_threshold() {
+        let mut engine: AlertEngine<4> = AlertEngine::new();
+        engine
+            .add(Rule {
+                pgn: BATTERY_PGN,
+                source: Some(0x20),
+                value: voltage,
+                condition: Condition::Below(11.8),
+                hysteresis: 0.2,
+            })
+            .unwrap();
+        let samples = [
+            (0, 0x20, 12.4),
+            (1, 0x20, 11.7),
+            (2, 0x20, 11.5),
+            // Another battery monitor.
+            (3, 0x21, 13.0),
+            (4, 0x20, 11.9),
+            (5, 0x20, 12.1),
+            (6, 0x20, 11.7),
+        ];
+        assert_eq!(
+            run(&mut engine, &samples),
+            [
+                (1, Transition::Raised),
+                (5, Transition::Cleared),
+                (6, Transition::Raised)
+            ]
+        );
+        assert!(engine.is_active(0));
+    }
+
+    #[test]
+    fn test_rate_of_change() {
+        let mut engine: AlertEngine<4> = AlertEngine::new();
+        engine
+            .add(Rule {
+                pgn: BATTERY_PGN,
+                source: None,
+                value: voltage,
+                condition: Condition::FallingFaster(0.5),
+                hysteresis: 0.1,
+            })
+            .unwrap();
+        let samples = [
+            (0, 0x20, 12.6),
+            (1000, 0x20, 12.5),
+            (2000, 0x20, 11.5),
+            (3000, 0x20, 11.05),
+            (4000, 0x20, 11.05),
+        ];
+        assert_eq!(
+            run(&mut engine, &samples),
+            [(2000, Transition::Raised), (4000, Transition::Cleared)]
+        );
+    }
+
+    #[test]
+    fn test_to_alert() {
+        let template = Alert {
+            alert_type: AlertType::Warning,
+            category: AlertCategory::Technical,
+            system: 1,
+            sub_system: 0,
+            id: 7,
+            source_name: 0,
+            source_instance: 0,
+            source_index: 0,
+            occurrence: 0,
+            flags: 0,
+            acknowledge_name: u64::MAX,
+            trigger: TriggerCondition::Manual,
+            threshold_status: ThresholdStatus::Normal,
+            priority: 0,
+            state: AlertState::Normal,
+        };
+        let event = AlertEvent {
+            rule: 0,
+            timestamp_ms: 0,
+            source: 0x20,
+            transition: Transition::Raised,
+            value: 11.5,
+        };
+        let alert = event.to_alert(Condition::Below(11.8), template);
+        assert_eq!(alert.state, AlertState::Active);
+        assert_eq!(alert.threshold_status, ThresholdStatus::LowExceeded);
+        assert_eq!(alert.trigger, TriggerCondition::Auto);
+    }
+}
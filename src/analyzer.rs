@@ -0,0 +1,207 @@
+//! Bus analyzer keeping a window of raw frames next to reassembly.
+use crate::can_id::CanId;
+use crate::nmea_frame::Frame;
+use crate::reassembler::{self, Reassembler};
+use fixed_queue::VecDeque;
+
+/// Whether a frame was received from or transmitted onto the bus.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    #[default]
+    Rx,
+    Tx,
+}
+
+/// A frame as seen on the bus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapturedFrame {
+    pub timestamp_ms: u64,
+    pub id: CanId,
+    pub data: [u8; 8],
+    pub direction: Direction,
+}
+
+/// Records every frame, addressed to us or not, into a ring buffer holding
+/// the last `CAP` frames, and reassembles up to `N` concurrent Fast-Packet
+/// sessions.
+///
+/// When a message turns out to be malformed its raw frames can be pulled
+/// back out of the buffer with `message_frames`.
+///
+/// Frames we transmit can be recorded into the same buffer with
+/// `record_tx`, e.g. for a gateway to check with `unechoed` that what it
+/// sends reappears on the bus.
+pub struct Analyzer<const CAP: usize, const N: usize> {
+    frames: VecDeque<CapturedFrame, CAP>,
+    reassembler: Reassembler<N>,
+}
+
+impl<const CAP: usize, const N: usize> Default for Analyzer<CAP, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const CAP: usize, const N: usize> Analyzer<CAP, N> {
+    pub const fn new() -> Self {
+        Self {
+            frames: VecDeque::new(),
+            reassembler: Reassembler::new(),
+        }
+    }
+
+    /// Records a single-frame PGN.
+    pub fn record(&mut self, id: CanId, data: &[u8; 8], now_ms: u64) {
+        self.push(id, data, now_ms, Direction::Rx);
+    }
+
+    /// Records a frame we transmitted. It isn't reassembled.
+    pub fn record_tx(&mut self, id: CanId, data: &[u8; 8], now_ms: u64) {
+        self.push(id, data, now_ms, Direction::Tx);
+    }
+
+    fn push(&mut self, id: CanId, data: &[u8; 8], now_ms: u64, direction: Direction) {
+        if self.frames.is_full() {
+            self.frames.pop_front();
+        }
+        let _ = self.frames.push_back(CapturedFrame {
+            timestamp_ms: now_ms,
+            id,
+            data: *data,
+            direction,
+        });
+    }
+
+    /// Records a frame of a Fast-Packet PGN and passes it to the reassembler.
+    /// Returns `true` once the message is complete; its payload can then be
+    /// taken with `get_payload`. The frame is recorded even if reassembly
+    /// fails.
+    pub fn record_fast_packet(
+        &mut self,
+        id: CanId,
+        data: &[u8; 8],
+        now_ms: u64,
+    ) -> Result<bool, reassembler::Error> {
+        self.record(id, data, now_ms);
+        self.reassembler.add_frame(id.source(), id.pgn(), data)
+    }
+
+    pub fn get_payload(
+        &mut self,
+        source: u8,
+        pgn: u32,
+        buf: &mut [u8],
+    ) -> Result<usize, reassembler::Error> {
+        self.reassembler.get_payload(source, pgn, buf)
+    }
+
+    /// Iterates over the recorded frames, oldest first.
+    pub fn frames(&self) -> impl Iterator<Item = &CapturedFrame> {
+        let (front, back) = self.frames.as_slices();
+        front.iter().chain(back)
+    }
+
+    /// Iterates over the recorded frames of the Fast-Packet message with
+    /// `sequence_counter` sent by `source` on `pgn`.
+    pub fn message_frames(
+        &self,
+        source: u8,
+        pgn: u32,
+        sequence_counter: u8,
+    ) -> impl Iterator<Item = &CapturedFrame> {
+        self.frames().filter(move |f| {
+            f.id.source() == source
+                && f.id.pgn() == pgn
+                && Frame::from_bytes(&f.data).sequence_counter() == sequence_counter
+        })
+    }
+
+    /// Iterates over the recorded transmitted frames that weren't followed
+    /// by a received frame with the same identifier and data.
+    pub fn unechoed(&self) -> impl Iterator<Item = &CapturedFrame> {
+        self.frames().enumerate().filter_map(move |(i, tx)| {
+            let echoed = self
+                .frames()
+                .skip(i + 1)
+                .any(|rx| rx.direction == Direction::Rx && rx.id == tx.id && rx.data == tx.data);
+            (tx.direction == Direction::Tx && !echoed).then_some(tx)
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Drops the recorded frames and in-progress sessions.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.reassembler.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nmea_message;
+
+    const BUF_1: [u8; 8] = [0x00, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D];
+    const BUF_3: [u8; 8] = [0x02, 0x03, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00];
+
+    #[test]
+    fn test_ring_buffer() {
+        let mut analyzer: Analyzer<2, 1> = Analyzer::new();
+        let id = CanId::new(2, 127250, 1, 255).unwrap();
+        analyzer.record(id, &[0; 8], 10);
+        analyzer.record(id, &[1; 8], 20);
+        analyzer.record(id, &[2; 8], 30);
+        assert_eq!(analyzer.len(), 2);
+        let timestamps: [u64; 2] = {
+            let mut it = analyzer.frames().map(|f| f.timestamp_ms);
+            [it.next().unwrap(), it.next().unwrap()]
+        };
+        assert_eq!(timestamps, [20, 30]);
+    }
+
+    #[test]
+    fn test_message_frames() {
+        let mut analyzer: Analyzer<8, 2> = Analyzer::new();
+        let id = CanId::new(3, 129029, 7, 255).unwrap();
+        let other = CanId::new(3, 129029, 8, 255).unwrap();
+        assert!(!analyzer.record_fast_packet(id, &BUF_1, 0).unwrap());
+        assert!(!analyzer.record_fast_packet(other, &BUF_1, 1).unwrap());
+        // Frame 2 is lost, so frame 3 breaks the sequence.
+        assert_eq!(
+            analyzer.record_fast_packet(id, &BUF_3, 2),
+            Err(reassembler::Error::Message(
+                nmea_message::Error::SequenceMismatch {
+                    expected: 1,
+                    received: 2,
+                    frame: BUF_3,
+                }
+            ))
+        );
+        assert_eq!(analyzer.message_frames(7, 129029, 0).count(), 2);
+        assert_eq!(analyzer.frames().count(), 3);
+    }
+
+    #[test]
+    fn test_unechoed() {
+        let mut analyzer: Analyzer<8, 1> = Analyzer::new();
+        let id = CanId::new(2, 127250, 1, 255).unwrap();
+        analyzer.record(id, &[0; 8], 0);
+        analyzer.record_tx(id, &[1; 8], 10);
+        analyzer.record_tx(id, &[2; 8], 20);
+        analyzer.record(id, &[1; 8], 30);
+        let unechoed: [u64; 1] = {
+            let mut it = analyzer.unechoed().map(|f| f.timestamp_ms);
+            [it.next().unwrap()]
+        };
+        assert_eq!(unechoed, [20]);
+        assert_eq!(analyzer.unechoed().count(), 1);
+        assert_eq!(analyzer.frames().nth(1).unwrap().direction, Direction::Tx);
+    }
+}
@@ -0,0 +1,154 @@
+//! Anchor watch: an alarm for a boat leaving the circle it swings in at
+//! anchor.
+use crate::nav_state::{NavState, Position};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnchorEvent {
+    /// The boat has left the circle, and is `distance` metres from the
+    /// anchor.
+    Breach { distance: f64 },
+    /// The boat is back inside the circle.
+    Returned { distance: f64 },
+}
+
+/// Watches positions against an anchor position and radius, reporting an
+/// `AnchorEvent` when the boat leaves the circle and when it comes back.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::anchor_watch::{AnchorEvent, AnchorWatch};
+/// use nmea::nav_state::Position;
+///
+/// let anchor = Position { latitude: 50.0, longitude: -4.0 };
+/// let mut watch = AnchorWatch::new();
+/// watch.set(anchor, 40.0);
+///
+/// let swung = Position { latitude: 50.0003, ..anchor };
+/// assert_eq!(watch.update(&swung), None);
+/// let dragged = Position { latitude: 50.0005, ..anchor };
+/// assert!(matches!(watch.update(&dragged), Some(AnchorEvent::Breach { .. })));
+/// // Reported once per breach.
+/// assert_eq!(watch.update(&dragged), None);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AnchorWatch {
+    anchor: Option<Position>,
+    radius: f64,
+    breached: bool,
+}
+
+impl AnchorWatch {
+    pub const fn new() -> Self {
+        Self {
+            anchor: None,
+            radius: 0.0,
+            breached: false,
+        }
+    }
+
+    /// Starts watching a circle of `radius` metres around `anchor`.
+    pub fn set(&mut self, anchor: Position, radius: f64) {
+        self.anchor = Some(anchor);
+        self.radius = radius;
+        self.breached = false;
+    }
+
+    /// Changes the radius of the circle, keeping the anchor position.
+    pub fn set_radius(&mut self, radius: f64) {
+        self.radius = radius;
+    }
+
+    /// Stops watching.
+    pub fn clear(&mut self) {
+        self.anchor = None;
+        self.breached = false;
+    }
+
+    pub fn anchor(&self) -> Option<Position> {
+        self.anchor
+    }
+
+    /// Radius of the circle in metres.
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// Whether the boat was last seen outside the circle.
+    pub fn is_breached(&self) -> bool {
+        self.breached
+    }
+
+    /// Distance from the anchor to `position` in metres, if watching.
+    pub fn distance(&self, position: &Position) -> Option<f64> {
+        Some(self.anchor?.distance_to(position))
+    }
+
+    /// Checks the boat's `position`.
+    pub fn update(&mut self, position: &Position) -> Option<AnchorEvent> {
+        let distance = self.distance(position)?;
+        let outside = distance > self.radius;
+        if outside == self.breached {
+            return None;
+        }
+        self.breached = outside;
+        Some(if outside {
+            AnchorEvent::Breach { distance }
+        } else {
+            AnchorEvent::Returned { distance }
+        })
+    }
+
+    /// Checks the position in `nav` at `now_ms`. Dead-reckoned estimates are
+    /// ignored, as is a position older than `max_age_ms`.
+    pub fn check(&mut self, nav: &NavState, now_ms: u64, max_age_ms: u64) -> Option<AnchorEvent> {
+        let position = nav.position()?.fresh(now_ms, max_age_ms)?;
+        self.update(&position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position_payload(latitude: f64, longitude: f64) -> [u8; 8] {
+        let mut payload = [0; 8];
+        payload[..4].copy_from_slice(&((latitude * 1e7) as i32).to_le_bytes());
+        payload[4..].copy_from_slice(&((longitude * 1e7) as i32).to_le_bytes());
+        payload
+    }
+
+    #[test]
+    fn test_watch() {
+        let mut nav = NavState::new();
+        let mut watch = AnchorWatch::new();
+        nav.ingest(129025, &position_payload(50.0, -4.0), 0)
+            .unwrap();
+        // Not set.
+        assert_eq!(watch.check(&nav, 0, 5000), None);
+
+        watch.set(nav.position().unwrap().value, 50.0);
+        assert_eq!(watch.check(&nav, 0, 5000), None);
+
+        nav.ingest(129025, &position_payload(50.0, -3.999), 1000)
+            .unwrap();
+        let Some(AnchorEvent::Breach { distance }) = watch.check(&nav, 1000, 5000) else {
+            panic!("no breach");
+        };
+        assert!((distance - 71.5).abs() < 0.5);
+        assert!(watch.is_breached());
+
+        watch.set_radius(100.0);
+        assert!(matches!(
+            watch.check(&nav, 2000, 5000),
+            Some(AnchorEvent::Returned { .. })
+        ));
+        // Too old to go on.
+        watch.set_radius(50.0);
+        assert_eq!(watch.check(&nav, 7000, 5000), None);
+
+        watch.clear();
+        assert_eq!(watch.anchor(), None);
+        assert!(!watch.is_breached());
+    }
+}
@@ -0,0 +1,258 @@
+//! Choosing one of several sources sending the same data, such as two GPS
+//! receivers both sending PGN 129029, with failover when it goes quiet.
+use crate::pgn::gnss::{GnssMethod, GnssPositionData};
+use fixed_queue::LinearMap;
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Too many sources")]
+    FullTable,
+}
+
+/// How the primary source is chosen among those heard within the timeout.
+#[derive(Clone, Copy, Debug)]
+pub enum Policy {
+    /// The lowest source address.
+    LowestAddress,
+    /// The listed addresses in order, then the rest by lowest address.
+    Preference(&'static [u8]),
+    /// The highest quality scored from the source's last payload, then the
+    /// lowest address. A source whose payload scores `None` is not used.
+    Quality(fn(&[u8]) -> Option<u8>),
+}
+
+/// The primary source for a PGN changed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Failover {
+    pub pgn: u32,
+    /// The previous primary, if any.
+    pub from: Option<u8>,
+    /// The new primary, or `None` if no source is left.
+    pub to: Option<u8>,
+}
+
+/// Scores GNSS Position Data by fix method, from RTK fixed down to an
+/// estimated, manual or simulated position. Payloads without a position or
+/// fix are not used.
+pub fn gnss_quality(payload: &[u8]) -> Option<u8> {
+    let position = GnssPositionData::from_payload(payload).ok()?;
+    position.latitude?;
+    position.longitude?;
+    match position.method {
+        GnssMethod::RtkFixed => Some(6),
+        GnssMethod::RtkFloat => Some(5),
+        GnssMethod::PreciseGnss => Some(4),
+        GnssMethod::DgnssFix => Some(3),
+        GnssMethod::GnssFix => Some(2),
+        GnssMethod::Estimated | GnssMethod::Manual | GnssMethod::Simulated => Some(1),
+        GnssMethod::NoFix | GnssMethod::Unknown(_) => None,
+    }
+}
+
+struct Source {
+    last_ms: u64,
+    quality: u8,
+}
+
+/// Picks the primary source of one PGN among up to `N` senders.
+///
+/// Messages are passed to `accept`, which says whether to use them. The
+/// primary changes when a better source appears under the policy, or when
+/// the primary hasn't been heard for `timeout_ms`.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::arbitration::{Arbiter, Failover, Policy};
+///
+/// let mut arbiter: Arbiter<4> = Arbiter::new(129025, Policy::Preference(&[0x20]), 3000);
+/// let mut events = Vec::new();
+/// let payload = [0; 8];
+/// assert!(arbiter.accept(0x10, &payload, 0, |e| events.push(e)).unwrap());
+/// assert!(arbiter.accept(0x20, &payload, 100, |e| events.push(e)).unwrap());
+/// assert!(!arbiter.accept(0x10, &payload, 1000, |e| events.push(e)).unwrap());
+/// // The preferred receiver goes quiet.
+/// assert!(arbiter.accept(0x10, &payload, 4000, |e| events.push(e)).unwrap());
+/// assert_eq!(
+///     events.last(),
+///     Some(&Failover { pgn: 129025, from: Some(0x20), to: Some(0x10) })
+/// );
+/// ```
+pub struct Arbiter<const N: usize> {
+    pgn: u32,
+    policy: Policy,
+    timeout_ms: u64,
+    sources: LinearMap<u8, Source, N>,
+    primary: Option<u8>,
+}
+
+impl<const N: usize> Arbiter<N> {
+    pub const fn new(pgn: u32, policy: Policy, timeout_ms: u64) -> Self {
+        Self {
+            pgn,
+            policy,
+            timeout_ms,
+            sources: LinearMap::new(),
+            primary: None,
+        }
+    }
+
+    pub fn pgn(&self) -> u32 {
+        self.pgn
+    }
+
+    pub fn primary(&self) -> Option<u8> {
+        self.primary
+    }
+
+    /// Records a message from `source` at `now_ms` and returns whether it is
+    /// from the primary. Reports a change of primary to `report`.
+    pub fn accept(
+        &mut self,
+        source: u8,
+        payload: &[u8],
+        now_ms: u64,
+        report: impl FnMut(Failover),
+    ) -> Result<bool, Error> {
+        let quality = match self.policy {
+            Policy::Quality(score) => score(payload),
+            _ => Some(0),
+        };
+        match quality {
+            Some(quality) => {
+                let entry = Source {
+                    last_ms: now_ms,
+                    quality,
+                };
+                self.sources
+                    .insert(source, entry)
+                    .map_err(|_| Error::FullTable)?;
+            }
+            None => {
+                self.sources.remove(&source);
+            }
+        }
+        self.check(now_ms, report);
+        Ok(self.primary == Some(source))
+    }
+
+    /// Drops sources not heard for the timeout at `now_ms` and fails over
+    /// if the primary was among them. Call this periodically so a primary
+    /// that has gone quiet is replaced even if no other source sends.
+    pub fn check(&mut self, now_ms: u64, mut report: impl FnMut(Failover)) {
+        while let Some(stale) = self
+            .sources
+            .iter()
+            .find(|(_, s)| now_ms.saturating_sub(s.last_ms) > self.timeout_ms)
+            .map(|(address, _)| *address)
+        {
+            self.sources.remove(&stale);
+        }
+        let best = self
+            .sources
+            .iter()
+            .map(|(address, source)| (*address, source.quality))
+            .max_by_key(|(address, quality)| (*quality, self.rank(*address)))
+            .map(|(address, _)| address);
+        if best != self.primary {
+            report(Failover {
+                pgn: self.pgn,
+                from: self.primary,
+                to: best,
+            });
+            self.primary = best;
+        }
+    }
+
+    /// Higher ranks first.
+    fn rank(&self, address: u8) -> (u8, u8) {
+        let preferred = match self.policy {
+            Policy::Preference(order) => order
+                .iter()
+                .position(|a| *a == address)
+                .map_or(0, |i| u8::MAX - i as u8),
+            _ => 0,
+        };
+        (preferred, u8::MAX - address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    fn gnss(method: u8) -> [u8; 43] {
+        let mut payload = [0xFF; 43];
+        payload[7..15].copy_from_slice(&0i64.to_le_bytes());
+        payload[15..23].copy_from_slice(&0i64.to_le_bytes());
+        payload[31] = method << 4;
+        payload
+    }
+
+    #[test]
+    fn test_lowest_address() {
+        let mut arbiter: Arbiter<2> = Arbiter::new(127250, Policy::LowestAddress, 1000);
+        let mut events = Vec::new();
+        assert!(arbiter.accept(0x30, &[], 0, |e| events.push(e)).unwrap());
+        assert!(arbiter.accept(0x20, &[], 10, |e| events.push(e)).unwrap());
+        assert!(!arbiter.accept(0x30, &[], 20, |e| events.push(e)).unwrap());
+        assert_eq!(
+            arbiter.accept(0x40, &[], 30, |e| events.push(e)),
+            Err(Error::FullTable)
+        );
+        arbiter.check(1015, |e| events.push(e));
+        arbiter.check(1100, |e| events.push(e));
+        assert_eq!(
+            events,
+            [
+                Failover {
+                    pgn: 127250,
+                    from: None,
+                    to: Some(0x30)
+                },
+                Failover {
+                    pgn: 127250,
+                    from: Some(0x30),
+                    to: Some(0x20)
+                },
+                Failover {
+                    pgn: 127250,
+                    from: Some(0x20),
+                    to: Some(0x30)
+                },
+                Failover {
+                    pgn: 127250,
+                    from: Some(0x30),
+                    to: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quality() {
+        let mut arbiter: Arbiter<4> =
+            Arbiter::new(GnssPositionData::PGN, Policy::Quality(gnss_quality), 3000);
+        let mut events = Vec::new();
+        // A GPS fix, then a DGPS fix from a higher address.
+        assert!(arbiter
+            .accept(0x10, &gnss(1), 0, |e| events.push(e))
+            .unwrap());
+        assert!(arbiter
+            .accept(0x11, &gnss(2), 100, |e| events.push(e))
+            .unwrap());
+        assert!(!arbiter
+            .accept(0x10, &gnss(1), 1000, |e| events.push(e))
+            .unwrap());
+        // The DGPS receiver loses its fix.
+        assert!(!arbiter
+            .accept(0x11, &gnss(0), 1100, |e| events.push(e))
+            .unwrap());
+        assert_eq!(arbiter.primary(), Some(0x10));
+        assert_eq!(events.len(), 3);
+        assert_eq!(gnss_quality(&gnss(4)), Some(6));
+        assert_eq!(gnss_quality(&[0; 8]), None);
+    }
+}
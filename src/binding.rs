@@ -1,15 +1,110 @@
+#[cfg(feature = "numpy")]
+use crate::can_id::CanId;
 use crate::nmea_message;
+use crate::pgn::info;
+#[cfg(feature = "numpy")]
+use crate::reassembler::Reassembler;
+#[cfg(feature = "numpy")]
+use numpy::PyReadonlyArray2;
 use pyo3::prelude::*;
-use pyo3::types::{PyBytes, PyModule};
+use pyo3::types::{PyBytes, PyDict, PyList, PyModule};
 use pyo3::wrap_pyfunction;
 
+mod decoded;
+
 #[pymodule]
 fn nmea(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<Message>()?;
+    m.add_function(wrap_pyfunction!(pgn_info, m)?)?;
+    decoded::register(m)?;
+    #[cfg(feature = "numpy")]
+    m.add_function(wrap_pyfunction!(decode_frames, m)?)?;
     Ok(())
 }
 
-#[pyclass]
+/// Describes the fields decoded for `pgn`, or returns `None` if it is not in
+/// the PGN table.
+#[pyfunction]
+fn pgn_info(py: Python<'_>, pgn: u32) -> PyResult<Option<PyObject>> {
+    let Some(info) = info::find(pgn) else {
+        return Ok(None);
+    };
+    let fields = PyList::empty(py);
+    for field in info.fields {
+        let dict = PyDict::new(py);
+        dict.set_item("name", field.name)?;
+        dict.set_item("type", field.kind.as_str())?;
+        dict.set_item("scale", field.scale)?;
+        dict.set_item("unit", field.unit)?;
+        fields.append(dict)?;
+    }
+    let dict = PyDict::new(py);
+    dict.set_item("pgn", info.pgn)?;
+    dict.set_item("name", info.name)?;
+    dict.set_item("fields", fields)?;
+    Ok(Some(dict.to_object(py)))
+}
+
+/// Decodes an int64 array of shape (N, 9+), one frame per row holding the
+/// 29-bit CAN ID followed by the eight data bytes; further columns are
+/// ignored. PGNs listed in `fast_packet` are reassembled and incomplete or
+/// broken sessions dropped. Returns a dict per message, with `row` being the
+/// row of its last frame.
+#[cfg(feature = "numpy")]
+#[pyfunction]
+fn decode_frames(
+    py: Python<'_>,
+    frames: PyReadonlyArray2<'_, i64>,
+    fast_packet: Vec<u32>,
+) -> PyResult<PyObject> {
+    let frames = frames.as_array();
+    if frames.ncols() < 9 {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "Frames must have at least 9 columns",
+        ));
+    }
+    let mut reassembler: Reassembler<64> = Reassembler::new();
+    let messages = PyList::empty(py);
+    for (row, frame) in frames.rows().into_iter().enumerate() {
+        let invalid = || {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid frame in row {row}"))
+        };
+        let id = u32::try_from(frame[0])
+            .ok()
+            .and_then(|id| CanId::try_from(id).ok())
+            .ok_or_else(invalid)?;
+        let mut data = [0; 8];
+        for (byte, value) in data.iter_mut().zip(frame.iter().skip(1)) {
+            *byte = u8::try_from(*value).map_err(|_| invalid())?;
+        }
+
+        let (source, pgn) = (id.source(), id.pgn());
+        let mut payload = [0xFF; nmea_message::MAX_NMEA_PACKET_SIZE];
+        let len = if fast_packet.contains(&pgn) {
+            match reassembler.add_frame(source, pgn, &data) {
+                Ok(true) => reassembler
+                    .get_payload(source, pgn, &mut payload)
+                    .unwrap_or(0),
+                _ => continue,
+            }
+        } else {
+            payload[..8].copy_from_slice(&data);
+            8
+        };
+        let message = PyDict::new(py);
+        message.set_item("row", row)?;
+        message.set_item("priority", id.priority())?;
+        message.set_item("pgn", pgn)?;
+        message.set_item("source", source)?;
+        message.set_item("destination", id.destination())?;
+        message.set_item("payload", PyBytes::new(py, &payload[..len]))?;
+        messages.append(message)?;
+    }
+    Ok(messages.to_object(py))
+}
+
+// Exported under `nmea` so that pickle can find the class again.
+#[pyclass(module = "nmea")]
 struct Message {
     inner: nmea_message::Message,
 }
@@ -37,10 +132,10 @@ impl Message {
     }
 
     #[staticmethod]
-    fn from_payload(payload: &[u8], sequence_counter: u8) -> Self {
-        Self {
-            inner: nmea_message::Message::from_payload(payload, sequence_counter),
-        }
+    fn from_payload(payload: &[u8], sequence_counter: u8) -> PyResult<Self> {
+        let inner = nmea_message::Message::from_payload(payload, sequence_counter)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        Ok(Self { inner })
     }
 
     fn pop_frame(&mut self) -> Option<PyObject> {
@@ -57,10 +152,39 @@ impl Message {
     fn get_payload(&mut self) -> PyResult<PyObject> {
         let mut buf: [u8; nmea_message::MAX_NMEA_PACKET_SIZE] =
             [0xFF; nmea_message::MAX_NMEA_PACKET_SIZE];
-        let len = self.inner.get_payload(&mut buf);
+        let len = self
+            .inner
+            .get_payload(&mut buf)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
         Python::with_gil(|py| Ok(PyBytes::new(py, &buf[..len]).to_object(py)))
     }
 
+    fn __getstate__(&self, py: Python<'_>) -> PyObject {
+        let mut buf = [0; nmea_message::STATE_LEN];
+        let len = self.inner.to_state(&mut buf);
+        PyBytes::new(py, &buf[..len]).to_object(py)
+    }
+
+    fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        self.inner = nmea_message::Message::from_state(state)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        Ok(())
+    }
+
+    fn __copy__(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+
+    fn __deepcopy__(&self, _memo: &PyAny) -> Self {
+        self.__copy__()
+    }
+
+    fn abort(&mut self) -> bool {
+        self.inner.abort()
+    }
+
     fn clear(&mut self) {
         self.inner.clear();
     }
@@ -79,4 +203,21 @@ impl Message {
     fn data_len(&self) -> u8 {
         self.inner.data_len
     }
+
+    #[getter]
+    fn kind(&self) -> &'static str {
+        match self.inner.kind() {
+            nmea_message::MessageType::Single => "single",
+            nmea_message::MessageType::Consecutive => "consecutive",
+            nmea_message::MessageType::Unknown => "unknown",
+        }
+    }
+
+    #[getter]
+    fn direction(&self) -> &'static str {
+        match self.inner.direction() {
+            nmea_message::TransmissionType::Rx => "rx",
+            nmea_message::TransmissionType::Tx => "tx",
+        }
+    }
 }
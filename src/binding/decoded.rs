@@ -0,0 +1,187 @@
+//! Python classes for decoded PGNs, covering the PGNs in `pgn::info`.
+// Emitted by the pyo3 0.20 macros on newer compilers, once per class here.
+#![allow(non_local_definitions)]
+use crate::pgn::{distance_log, environment, fluid_level, gnss, heading, speed, wind};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Declares a frozen Python class mirroring a decoded PGN struct. Plain
+/// fields are copied; lookup fields become the name of their variant.
+macro_rules! decoded {
+    ($name:ident: $decoded:ty { $($field:ident: $ty:ty),* } lookups { $($lookup:ident),* }) => {
+        #[pyclass(module = "nmea", frozen, get_all)]
+        #[derive(Clone, PartialEq)]
+        pub struct $name {
+            $($field: $ty,)*
+            $($lookup: String,)*
+        }
+
+        impl From<$decoded> for $name {
+            fn from(decoded: $decoded) -> Self {
+                Self {
+                    $($field: decoded.$field,)*
+                    $($lookup: format!("{:?}", decoded.$lookup),)*
+                }
+            }
+        }
+
+        #[pymethods]
+        impl $name {
+            fn __eq__(&self, other: &Self) -> bool {
+                self == other
+            }
+
+            fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+                let mut fields = Vec::new();
+                $(fields.push(format!(
+                    "{}={}",
+                    stringify!($field),
+                    self.$field.clone().into_py(py).as_ref(py).repr()?
+                ));)*
+                $(fields.push(format!(
+                    "{}={}",
+                    stringify!($lookup),
+                    self.$lookup.clone().into_py(py).as_ref(py).repr()?
+                ));)*
+                Ok(format!("{}({})", stringify!($name), fields.join(", ")))
+            }
+
+            fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+                let dict = PyDict::new(py);
+                $(dict.set_item(stringify!($field), self.$field.clone())?;)*
+                $(dict.set_item(stringify!($lookup), self.$lookup.clone())?;)*
+                Ok(dict.to_object(py))
+            }
+        }
+    };
+}
+
+decoded!(VesselHeading: heading::VesselHeading {
+    sid: Option<u8>, heading: Option<f32>, deviation: Option<f32>, variation: Option<f32>
+} lookups { reference });
+
+decoded!(MagneticVariation: heading::MagneticVariation {
+    sid: Option<u8>, age_of_service: Option<u16>, variation: Option<f32>
+} lookups { source });
+
+decoded!(FluidLevel: fluid_level::FluidLevel {
+    instance: u8, level: Option<f32>, capacity: Option<f32>
+} lookups { fluid_type });
+
+decoded!(Leeway: speed::Leeway { sid: Option<u8>, leeway: Option<f32> } lookups {});
+
+decoded!(DistanceLog: distance_log::DistanceLog {
+    date: Option<u16>, time: Option<f64>, log: Option<u32>, trip_log: Option<u32>
+} lookups {});
+
+decoded!(PositionRapidUpdate: gnss::PositionRapidUpdate {
+    latitude: Option<f64>, longitude: Option<f64>
+} lookups {});
+
+decoded!(CogSogRapidUpdate: gnss::CogSogRapidUpdate {
+    sid: Option<u8>, cog: Option<f32>, sog: Option<f32>
+} lookups { cog_reference });
+
+decoded!(GnssPositionData: gnss::GnssPositionData {
+    sid: Option<u8>, date: Option<u16>, time: Option<f64>, latitude: Option<f64>,
+    longitude: Option<f64>, altitude: Option<f64>, gnss_type: u8, integrity: u8,
+    satellites: Option<u8>, hdop: Option<f32>, pdop: Option<f32>,
+    geoidal_separation: Option<f32>
+} lookups { method });
+
+decoded!(WindData: wind::WindData {
+    sid: Option<u8>, speed: Option<f32>, angle: Option<f32>
+} lookups { reference });
+
+decoded!(Temperature: environment::Temperature {
+    sid: Option<u8>, instance: u8, temperature: Option<f32>, set_temperature: Option<f32>
+} lookups { source });
+
+decoded!(Humidity: environment::Humidity {
+    sid: Option<u8>, instance: u8, humidity: Option<f32>, set_humidity: Option<f32>
+} lookups { source });
+
+decoded!(Pressure: environment::Pressure {
+    sid: Option<u8>, instance: u8, pressure: Option<f64>
+} lookups { source });
+
+decoded!(SpeedComponents: speed::SpeedComponents {
+    longitudinal_water: Option<f32>, transverse_water: Option<f32>,
+    longitudinal_ground: Option<f32>, transverse_ground: Option<f32>,
+    stern_water: Option<f32>, stern_ground: Option<f32>
+} lookups {});
+
+pub fn register(m: &PyModule) -> PyResult<()> {
+    m.add_class::<VesselHeading>()?;
+    m.add_class::<MagneticVariation>()?;
+    m.add_class::<FluidLevel>()?;
+    m.add_class::<Leeway>()?;
+    m.add_class::<DistanceLog>()?;
+    m.add_class::<PositionRapidUpdate>()?;
+    m.add_class::<CogSogRapidUpdate>()?;
+    m.add_class::<GnssPositionData>()?;
+    m.add_class::<WindData>()?;
+    m.add_class::<Temperature>()?;
+    m.add_class::<Humidity>()?;
+    m.add_class::<Pressure>()?;
+    m.add_class::<SpeedComponents>()?;
+    m.add_function(wrap_pyfunction!(decode, m)?)?;
+    Ok(())
+}
+
+/// Decodes `payload` of `pgn` into the matching class, or returns `None` if
+/// there is no decoder for it.
+#[pyfunction]
+fn decode(py: Python<'_>, pgn: u32, payload: &[u8]) -> PyResult<Option<PyObject>> {
+    fn convert<T, P>(py: Python<'_>, decoded: Result<T, crate::pgn::Error>) -> PyResult<PyObject>
+    where
+        P: From<T> + IntoPy<PyObject>,
+    {
+        let decoded = decoded.map_err(|e| PyErr::new::<PyValueError, _>(e.to_string()))?;
+        Ok(P::from(decoded).into_py(py))
+    }
+
+    let decoded = match pgn {
+        heading::VesselHeading::PGN => {
+            convert::<_, VesselHeading>(py, heading::VesselHeading::from_payload(payload))
+        }
+        heading::MagneticVariation::PGN => {
+            convert::<_, MagneticVariation>(py, heading::MagneticVariation::from_payload(payload))
+        }
+        fluid_level::FluidLevel::PGN => {
+            convert::<_, FluidLevel>(py, fluid_level::FluidLevel::from_payload(payload))
+        }
+        speed::Leeway::PGN => convert::<_, Leeway>(py, speed::Leeway::from_payload(payload)),
+        distance_log::DistanceLog::PGN => {
+            convert::<_, DistanceLog>(py, distance_log::DistanceLog::from_payload(payload))
+        }
+        gnss::PositionRapidUpdate::PGN => {
+            convert::<_, PositionRapidUpdate>(py, gnss::PositionRapidUpdate::from_payload(payload))
+        }
+        gnss::CogSogRapidUpdate::PGN => {
+            convert::<_, CogSogRapidUpdate>(py, gnss::CogSogRapidUpdate::from_payload(payload))
+        }
+        gnss::GnssPositionData::PGN => {
+            convert::<_, GnssPositionData>(py, gnss::GnssPositionData::from_payload(payload))
+        }
+        wind::WindData::PGN => convert::<_, WindData>(py, wind::WindData::from_payload(payload)),
+        environment::Temperature::PGN => {
+            convert::<_, Temperature>(py, environment::Temperature::from_payload(payload))
+        }
+        environment::Temperature::EXTENDED_PGN => {
+            convert::<_, Temperature>(py, environment::Temperature::from_extended_payload(payload))
+        }
+        environment::Humidity::PGN => {
+            convert::<_, Humidity>(py, environment::Humidity::from_payload(payload))
+        }
+        environment::Pressure::ACTUAL_PGN | environment::Pressure::SET_PGN => {
+            convert::<_, Pressure>(py, environment::Pressure::from_payload(payload))
+        }
+        speed::SpeedComponents::PGN => {
+            convert::<_, SpeedComponents>(py, speed::SpeedComponents::from_payload(payload))
+        }
+        _ => return Ok(None),
+    };
+    decoded.map(Some)
+}
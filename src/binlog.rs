@@ -0,0 +1,390 @@
+//! Compact binary logs of bus traffic for long-term recording.
+//!
+//! A log starts with the magic `N2KB` and a version byte, followed by one
+//! record per frame:
+//!
+//! | Bytes | Field                                                    |
+//! |-------|----------------------------------------------------------|
+//! | 1-10  | Time since the previous frame in ms, zigzag LEB128       |
+//! | 4     | CAN ID, LE; bit 31 set for Tx, bit 30 for trimmed data   |
+//! | 8     | Data, or a length byte and that many bytes when trimmed  |
+//!
+//! A typical record takes 13 bytes against about 40 for a candump line.
+//! With the `log-compression` feature, the encoder can trim the trailing
+//! 0xFF padding and not-available fields that fill many frames; decoders
+//! always accept trimmed records.
+use crate::analyzer::{CapturedFrame, Direction};
+use crate::can_id::{self, CanId};
+use thiserror_no_std::Error;
+
+pub const MAGIC: [u8; 4] = *b"N2KB";
+pub const VERSION: u8 = 1;
+pub const HEADER_LEN: usize = 5;
+/// Longest encoded record.
+pub const MAX_RECORD_LEN: usize = 10 + 4 + 8;
+
+const TX: u32 = 1 << 31;
+const TRIMMED: u32 = 1 << 30;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Not a binary log")]
+    BadMagic,
+    #[error("Unsupported log version {0}")]
+    UnsupportedVersion(u8),
+    #[error("Record is truncated")]
+    Truncated,
+    #[error("Buffer is too small for the record")]
+    BufferTooSmall,
+    #[error("Malformed record")]
+    Malformed,
+    #[error(transparent)]
+    Id(#[from] can_id::Error),
+}
+
+/// The log header.
+pub const fn header() -> [u8; HEADER_LEN] {
+    [MAGIC[0], MAGIC[1], MAGIC[2], MAGIC[3], VERSION]
+}
+
+/// Checks the header at the start of `bytes`.
+pub fn check_header(bytes: &[u8]) -> Result<(), Error> {
+    let header = bytes.get(..HEADER_LEN).ok_or(Error::Truncated)?;
+    if header[..4] != MAGIC {
+        return Err(Error::BadMagic);
+    }
+    match header[4] {
+        VERSION => Ok(()),
+        version => Err(Error::UnsupportedVersion(version)),
+    }
+}
+
+/// Encodes frames as records, each timestamp relative to the previous one.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::analyzer::{CapturedFrame, Direction};
+/// use nmea::binlog::{LogDecoder, LogEncoder, MAX_RECORD_LEN};
+/// use nmea::can_id::CanId;
+///
+/// let frame = CapturedFrame {
+///     timestamp_ms: 1_700_000_000_000,
+///     id: CanId::new(2, 127250, 0x10, 255).unwrap(),
+///     data: [0xFF, 0x10, 0x27, 0xFF, 0x7F, 0xFF, 0x7F, 0xFD],
+///     direction: Direction::Rx,
+/// };
+/// let mut encoder = LogEncoder::new();
+/// let mut decoder = LogDecoder::new();
+/// let mut buf = [0; MAX_RECORD_LEN];
+/// let len = encoder.encode(&frame, &mut buf).unwrap();
+/// assert_eq!(decoder.decode(&buf[..len]), Ok(Some((frame, len))));
+///
+/// let next = CapturedFrame { timestamp_ms: frame.timestamp_ms + 50, ..frame };
+/// assert_eq!(encoder.encode(&next, &mut buf), Ok(13));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LogEncoder {
+    last_ms: u64,
+    trim: bool,
+}
+
+impl LogEncoder {
+    pub const fn new() -> Self {
+        Self {
+            last_ms: 0,
+            trim: false,
+        }
+    }
+
+    /// Trims trailing 0xFF bytes from the data of each record.
+    #[cfg(any(test, feature = "log-compression"))]
+    pub const fn with_compression(mut self) -> Self {
+        self.trim = true;
+        self
+    }
+
+    /// Encodes `frame` into `buf`, returning the record length.
+    pub fn encode(&mut self, frame: &CapturedFrame, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut record = [0; MAX_RECORD_LEN];
+        let delta = frame.timestamp_ms.wrapping_sub(self.last_ms) as i64;
+        let mut len = write_varint(((delta << 1) ^ (delta >> 63)) as u64, &mut record);
+        let mut id = u32::from(frame.id);
+        if frame.direction == Direction::Tx {
+            id |= TX;
+        }
+        let data_len = if self.trim {
+            frame
+                .data
+                .iter()
+                .rposition(|b| *b != 0xFF)
+                .map_or(0, |i| i + 1)
+        } else {
+            8
+        };
+        if data_len < 8 {
+            id |= TRIMMED;
+        }
+        record[len..len + 4].copy_from_slice(&id.to_le_bytes());
+        len += 4;
+        if data_len < 8 {
+            record[len] = data_len as u8;
+            len += 1;
+        }
+        record[len..len + data_len].copy_from_slice(&frame.data[..data_len]);
+        len += data_len;
+        buf.get_mut(..len)
+            .ok_or(Error::BufferTooSmall)?
+            .copy_from_slice(&record[..len]);
+        self.last_ms = frame.timestamp_ms;
+        Ok(len)
+    }
+}
+
+/// Decodes the records written by a `LogEncoder`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LogDecoder {
+    last_ms: u64,
+}
+
+impl LogDecoder {
+    pub const fn new() -> Self {
+        Self { last_ms: 0 }
+    }
+
+    /// Decodes the record at the start of `bytes`. Returns the frame and the
+    /// record length, or `None` if `bytes` is empty.
+    pub fn decode(&mut self, bytes: &[u8]) -> Result<Option<(CapturedFrame, usize)>, Error> {
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+        let (zigzag, mut len) = read_varint(bytes)?;
+        let delta = (zigzag >> 1) as i64 ^ -((zigzag & 1) as i64);
+        let raw = bytes.get(len..len + 4).ok_or(Error::Truncated)?;
+        let raw = u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]);
+        len += 4;
+        let data_len = if raw & TRIMMED != 0 {
+            let data_len = *bytes.get(len).ok_or(Error::Truncated)? as usize;
+            len += 1;
+            if data_len >= 8 {
+                return Err(Error::Malformed);
+            }
+            data_len
+        } else {
+            8
+        };
+        let mut data = [0xFF; 8];
+        data[..data_len].copy_from_slice(bytes.get(len..len + data_len).ok_or(Error::Truncated)?);
+        len += data_len;
+        let frame = CapturedFrame {
+            timestamp_ms: self.last_ms.wrapping_add(delta as u64),
+            id: CanId::try_from(raw & !(TX | TRIMMED))?,
+            data,
+            direction: if raw & TX != 0 {
+                Direction::Tx
+            } else {
+                Direction::Rx
+            },
+        };
+        self.last_ms = frame.timestamp_ms;
+        Ok(Some((frame, len)))
+    }
+}
+
+fn write_varint(mut value: u64, buf: &mut [u8]) -> usize {
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf[len] = byte;
+            return len + 1;
+        }
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+}
+
+fn read_varint(bytes: &[u8]) -> Result<(u64, usize), Error> {
+    let mut value = 0u64;
+    for (i, byte) in bytes.iter().enumerate().take(10) {
+        value |= ((byte & 0x7F) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok((value, i + 1));
+        }
+    }
+    if bytes.len() < 10 {
+        Err(Error::Truncated)
+    } else {
+        Err(Error::Malformed)
+    }
+}
+
+/// Writes a binary log to an `io::Write`, such as a file on an SD card.
+#[cfg(any(test, feature = "std"))]
+pub struct LogWriter<W: std::io::Write> {
+    out: W,
+    encoder: LogEncoder,
+}
+
+#[cfg(any(test, feature = "std"))]
+impl<W: std::io::Write> LogWriter<W> {
+    /// Writes the header and returns the writer.
+    pub fn new(mut out: W, encoder: LogEncoder) -> std::io::Result<Self> {
+        out.write_all(&header())?;
+        Ok(Self { out, encoder })
+    }
+
+    pub fn write(&mut self, frame: &CapturedFrame) -> std::io::Result<()> {
+        let mut buf = [0; MAX_RECORD_LEN];
+        // A record always fits.
+        let len = self.encoder.encode(frame, &mut buf).unwrap_or(0);
+        self.out.write_all(&buf[..len])
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// Reads the frames of a binary log from an `io::Read`.
+///
+/// The iterator ends at the end of the input, or after yielding the first
+/// error; a log cut short by a power loss ends with `Error::Truncated`.
+#[cfg(any(test, feature = "std"))]
+pub struct LogReader<R: std::io::Read> {
+    input: R,
+    decoder: LogDecoder,
+    buf: [u8; MAX_RECORD_LEN],
+    len: usize,
+    done: bool,
+}
+
+#[cfg(any(test, feature = "std"))]
+impl<R: std::io::Read> LogReader<R> {
+    /// Reads and checks the header.
+    pub fn new(mut input: R) -> std::io::Result<Result<Self, Error>> {
+        let mut header = [0; HEADER_LEN];
+        input.read_exact(&mut header)?;
+        Ok(check_header(&header).map(|()| Self {
+            input,
+            decoder: LogDecoder::new(),
+            buf: [0; MAX_RECORD_LEN],
+            len: 0,
+            done: false,
+        }))
+    }
+
+    fn fill(&mut self) -> std::io::Result<()> {
+        while self.len < MAX_RECORD_LEN {
+            match self.input.read(&mut self.buf[self.len..])? {
+                0 => break,
+                n => self.len += n,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(test, feature = "std"))]
+impl<R: std::io::Read> Iterator for LogReader<R> {
+    type Item = std::io::Result<Result<CapturedFrame, Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if let Err(e) = self.fill() {
+            self.done = true;
+            return Some(Err(e));
+        }
+        match self.decoder.decode(&self.buf[..self.len]) {
+            Ok(Some((frame, len))) => {
+                self.buf.copy_within(len..self.len, 0);
+                self.len -= len;
+                Some(Ok(Ok(frame)))
+            }
+            Ok(None) => None,
+            Err(e) => {
+                self.done = true;
+                Some(Ok(Err(e)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    fn frames() -> Vec<CapturedFrame> {
+        let id = CanId::new(2, 129029, 0x10, 255).unwrap();
+        [
+            (1000, [0x00, 0x2B, 1, 2, 3, 4, 5, 6], Direction::Rx),
+            (1003, [0x01, 7, 8, 9, 10, 11, 12, 13], Direction::Rx),
+            // Out of order by a few ms, as from a second interface.
+            (
+                990,
+                [0x06, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
+                Direction::Tx,
+            ),
+            (500_000, [0xFF; 8], Direction::Rx),
+        ]
+        .iter()
+        .map(|(timestamp_ms, data, direction)| CapturedFrame {
+            timestamp_ms: *timestamp_ms,
+            id,
+            data: *data,
+            direction: *direction,
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for encoder in [LogEncoder::new(), LogEncoder::new().with_compression()] {
+            let mut writer = LogWriter::new(Vec::new(), encoder).unwrap();
+            for frame in frames() {
+                writer.write(&frame).unwrap();
+            }
+            let log = writer.into_inner();
+            let read: Vec<CapturedFrame> = LogReader::new(&log[..])
+                .unwrap()
+                .unwrap()
+                .map(|frame| frame.unwrap().unwrap())
+                .collect();
+            assert_eq!(read, frames());
+            let expected = if encoder.trim { 47 } else { 60 };
+            assert_eq!(log.len(), expected);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(check_header(b"N2KB\x02"), Err(Error::UnsupportedVersion(2)));
+        assert_eq!(check_header(b"CAND\x01"), Err(Error::BadMagic));
+        assert!(LogReader::new(&b"N2K"[..]).is_err());
+
+        let mut log = header().to_vec();
+        let mut buf = [0; MAX_RECORD_LEN];
+        let len = LogEncoder::new().encode(&frames()[0], &mut buf).unwrap();
+        log.extend_from_slice(&buf[..len]);
+        log.extend_from_slice(&buf[..len - 3]);
+        let mut reader = LogReader::new(&log[..]).unwrap().unwrap();
+        assert!(reader.next().unwrap().unwrap().is_ok());
+        assert_eq!(reader.next().unwrap().unwrap(), Err(Error::Truncated));
+        assert!(reader.next().is_none());
+
+        let mut decoder = LogDecoder::new();
+        assert_eq!(decoder.decode(&[0x80; 11]), Err(Error::Malformed));
+        assert_eq!(
+            decoder.decode(&[0x00, 0xFF, 0xFF, 0xFF, 0x3F, 0, 0, 0, 0, 0, 0, 0, 0]),
+            Err(Error::Id(can_id::Error::InvalidId))
+        );
+        assert_eq!(
+            LogEncoder::new().encode(&frames()[0], &mut [0; 4]),
+            Err(Error::BufferTooSmall)
+        );
+    }
+}
@@ -0,0 +1,319 @@
+//! Forwarding between two CAN buses, e.g. a backbone and an instrument
+//! network.
+use crate::address_map::{self, AddressMap, Segment};
+use crate::can_id::CanId;
+use crate::frame_queue::{Consumer, RxFrame};
+use crate::nmea_message::{self, Message, MAX_NMEA_PACKET_SIZE};
+use crate::reassembler::{self, Reassembler};
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Frame could not be transmitted")]
+    Transmit,
+    #[error(transparent)]
+    Address(#[from] address_map::Error),
+    #[error(transparent)]
+    Reassembly(#[from] reassembler::Error),
+    #[error(transparent)]
+    Message(#[from] nmea_message::Error),
+}
+
+/// Receiving side of a CAN interface.
+pub trait FrameSource {
+    fn receive(&mut self) -> Option<RxFrame>;
+}
+
+/// Transmitting side of a CAN interface.
+pub trait FrameSink {
+    fn transmit(&mut self, frame: &RxFrame) -> Result<(), Error>;
+}
+
+impl<S: FrameSource, K> FrameSource for (S, K) {
+    fn receive(&mut self) -> Option<RxFrame> {
+        self.0.receive()
+    }
+}
+
+impl<S, K: FrameSink> FrameSink for (S, K) {
+    fn transmit(&mut self, frame: &RxFrame) -> Result<(), Error> {
+        self.1.transmit(frame)
+    }
+}
+
+impl<const N: usize> FrameSource for Consumer<'_, N> {
+    fn receive(&mut self) -> Option<RxFrame> {
+        self.dequeue()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Direction {
+    AToB,
+    BToA,
+}
+
+impl Direction {
+    fn from(&self) -> Segment {
+        match self {
+            Self::AToB => Segment::A,
+            Self::BToA => Segment::B,
+        }
+    }
+}
+
+struct Route<const N: usize> {
+    filter: Option<fn(u32) -> bool>,
+    reassembler: Reassembler<N>,
+    sequence_counter: u8,
+}
+
+impl<const N: usize> Route<N> {
+    const fn new() -> Self {
+        Self {
+            filter: None,
+            reassembler: Reassembler::new(),
+            sequence_counter: 0,
+        }
+    }
+}
+
+/// Forwards traffic between bus `A` and bus `B`, each a `FrameSource` and
+/// `FrameSink` (a `(source, sink)` pair works too).
+///
+/// Frames are forwarded as received by default. With `with_reassembly`,
+/// Fast-Packet PGNs are reassembled first and sent on as whole messages
+/// under the bridge's own sequence counter, so incomplete messages never
+/// reach the other bus; `N` is the number of concurrent sessions per
+/// direction.
+///
+/// Each direction can have a PGN filter. Addresses are rewritten through an
+/// `AddressMap` aliasing up to `M` nodes per bus.
+pub struct Bridge<A, B, const N: usize, const M: usize> {
+    a: A,
+    b: B,
+    fast_packet: Option<fn(u32) -> bool>,
+    addresses: AddressMap<M>,
+    a_to_b: Route<N>,
+    b_to_a: Route<N>,
+}
+
+impl<A, B, const N: usize, const M: usize> Bridge<A, B, N, M>
+where
+    A: FrameSource + FrameSink,
+    B: FrameSource + FrameSink,
+{
+    pub const fn new(a: A, b: B) -> Self {
+        Self {
+            a,
+            b,
+            fast_packet: None,
+            addresses: AddressMap::new(),
+            a_to_b: Route::new(),
+            b_to_a: Route::new(),
+        }
+    }
+
+    /// Creates a bridge reassembling the PGNs for which `fast_packet` returns
+    /// `true`.
+    pub const fn with_reassembly(a: A, b: B, fast_packet: fn(u32) -> bool) -> Self {
+        Self {
+            a,
+            b,
+            fast_packet: Some(fast_packet),
+            addresses: AddressMap::new(),
+            a_to_b: Route::new(),
+            b_to_a: Route::new(),
+        }
+    }
+
+    fn route_mut(&mut self, direction: Direction) -> &mut Route<N> {
+        match direction {
+            Direction::AToB => &mut self.a_to_b,
+            Direction::BToA => &mut self.b_to_a,
+        }
+    }
+
+    /// Only forwards PGNs for which `filter` returns `true`.
+    pub fn set_filter(&mut self, direction: Direction, filter: fn(u32) -> bool) {
+        self.route_mut(direction).filter = Some(filter);
+    }
+
+    /// Rewrites source address `from` to `to` when forwarding in
+    /// `direction`, and destination `to` back to `from` in the other.
+    pub fn translate(&mut self, direction: Direction, from: u8, to: u8) -> Result<(), Error> {
+        self.addresses.insert(direction.from(), from, to)?;
+        Ok(())
+    }
+
+    pub fn addresses(&self) -> &AddressMap<M> {
+        &self.addresses
+    }
+
+    /// Forwards everything received on either bus. Returns the number of
+    /// frames transmitted.
+    pub fn poll(&mut self) -> Result<usize, Error> {
+        let mut transmitted = 0;
+        while let Some(frame) = self.a.receive() {
+            let id = self.addresses.to_other(Segment::A, frame.id);
+            transmitted += forward(&frame, id, self.fast_packet, &mut self.a_to_b, &mut self.b)?;
+        }
+        while let Some(frame) = self.b.receive() {
+            let id = self.addresses.to_other(Segment::B, frame.id);
+            transmitted += forward(&frame, id, self.fast_packet, &mut self.b_to_a, &mut self.a)?;
+        }
+        Ok(transmitted)
+    }
+
+    pub fn into_parts(self) -> (A, B) {
+        (self.a, self.b)
+    }
+}
+
+/// Forwards `frame` as `out_id`.
+fn forward<const N: usize>(
+    frame: &RxFrame,
+    out_id: CanId,
+    fast_packet: Option<fn(u32) -> bool>,
+    route: &mut Route<N>,
+    sink: &mut impl FrameSink,
+) -> Result<usize, Error> {
+    let id = frame.id;
+    let pgn = id.pgn();
+    if route.filter.is_some_and(|filter| !filter(pgn)) {
+        return Ok(0);
+    }
+
+    if !fast_packet.is_some_and(|fast_packet| fast_packet(pgn)) {
+        sink.transmit(&RxFrame {
+            id: out_id,
+            data: frame.data,
+        })?;
+        return Ok(1);
+    }
+
+    if !route.reassembler.add_frame(id.source(), pgn, &frame.data)? {
+        return Ok(0);
+    }
+    let mut payload = [0xFF; MAX_NMEA_PACKET_SIZE];
+    let len = route
+        .reassembler
+        .get_payload(id.source(), pgn, &mut payload)?;
+    let mut msg = Message::from_payload(&payload[..len], route.sequence_counter)?;
+    route.sequence_counter = (route.sequence_counter + 1) & 0x07;
+    let mut transmitted = 0;
+    while let Some(out) = msg.pop_frame() {
+        sink.transmit(&RxFrame {
+            id: out_id,
+            data: out.bytes,
+        })?;
+        transmitted += 1;
+    }
+    Ok(transmitted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fixed_queue::VecDeque;
+
+    struct Bus {
+        rx: VecDeque<RxFrame, 16>,
+        tx: VecDeque<RxFrame, 16>,
+    }
+
+    impl FrameSource for Bus {
+        fn receive(&mut self) -> Option<RxFrame> {
+            self.rx.pop_front()
+        }
+    }
+
+    impl FrameSink for Bus {
+        fn transmit(&mut self, frame: &RxFrame) -> Result<(), Error> {
+            self.tx.push_back(*frame).map_err(|_| Error::Transmit)
+        }
+    }
+
+    fn bus(frames: &[RxFrame]) -> Bus {
+        let mut bus = Bus {
+            rx: VecDeque::new(),
+            tx: VecDeque::new(),
+        };
+        for frame in frames {
+            bus.rx.push_back(*frame).unwrap();
+        }
+        bus
+    }
+
+    fn frame(priority: u8, pgn: u32, source: u8, destination: u8, data: [u8; 8]) -> RxFrame {
+        RxFrame {
+            id: CanId::new(priority, pgn, source, destination).unwrap(),
+            data,
+        }
+    }
+
+    #[test]
+    fn test_frames() {
+        let heading = frame(2, 127250, 0x10, 255, [0x01; 8]);
+        let wind = frame(2, 130306, 0x10, 255, [0x02; 8]);
+        let request = frame(6, 59904, 0x20, 0x11, [0x03; 8]);
+        let mut bridge: Bridge<Bus, Bus, 2, 2> =
+            Bridge::new(bus(&[heading, wind]), bus(&[request]));
+        bridge.set_filter(Direction::AToB, |pgn| pgn != 130306);
+        bridge.translate(Direction::AToB, 0x10, 0x11).unwrap();
+        bridge.translate(Direction::BToA, 0x21, 0x22).unwrap();
+        assert_eq!(
+            bridge.translate(Direction::BToA, 0x23, 0x22),
+            Err(Error::Address(address_map::Error::AliasInUse(0x22)))
+        );
+        assert_eq!(bridge.poll(), Ok(2));
+
+        let (mut a, mut b) = bridge.into_parts();
+        assert_eq!(
+            b.tx.pop_front(),
+            Some(frame(2, 127250, 0x11, 255, [0x01; 8]))
+        );
+        assert_eq!(b.tx.pop_front(), None);
+        // The request addressed to 0x11 on B reaches 0x10 on A.
+        assert_eq!(
+            a.tx.pop_front(),
+            Some(frame(6, 59904, 0x20, 0x10, [0x03; 8]))
+        );
+    }
+
+    #[test]
+    fn test_reassembly() {
+        // A 9-byte message split with sequence counter 3, forwarded under
+        // the bridge's own counter.
+        let first = frame(3, 129029, 0x05, 255, [0x60, 0x09, 1, 2, 3, 4, 5, 6]);
+        let second = frame(
+            3,
+            129029,
+            0x05,
+            255,
+            [0x61, 7, 8, 9, 0xFF, 0xFF, 0xFF, 0xFF],
+        );
+        let mut bridge: Bridge<Bus, Bus, 2, 1> =
+            Bridge::with_reassembly(bus(&[first, second]), bus(&[]), |pgn| pgn == 129029);
+        assert_eq!(bridge.poll(), Ok(2));
+        let (_, mut b) = bridge.into_parts();
+        assert_eq!(
+            b.tx.pop_front().unwrap().data,
+            [0x00, 0x09, 1, 2, 3, 4, 5, 6]
+        );
+        assert_eq!(
+            b.tx.pop_front().unwrap().data,
+            [0x01, 7, 8, 9, 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+
+        // A consecutive frame without its first frame is not forwarded.
+        let mut bridge: Bridge<Bus, Bus, 2, 1> =
+            Bridge::with_reassembly(bus(&[second]), bus(&[]), |pgn| pgn == 129029);
+        assert!(matches!(bridge.poll(), Err(Error::Reassembly(_))));
+        assert!(bridge.into_parts().1.tx.is_empty());
+    }
+}
@@ -0,0 +1,191 @@
+//! Decoding large capture files on several threads.
+//!
+//! Sessions never span source addresses, so a log can be split by source
+//! and each share reassembled and decoded on its own thread. The results
+//! are merged back into log order.
+use crate::analyzer::CapturedFrame;
+use crate::frame_queue::RxFrame;
+use crate::pipeline::{Packet, Pipeline, Reassemble};
+use std::thread;
+use std::vec::Vec;
+
+/// Sessions reassembled at once by each worker.
+const SESSIONS: usize = 64;
+
+/// A message decoded from a log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record<T> {
+    pub timestamp_ms: u64,
+    /// Index in the log of the frame that completed the message.
+    pub frame_index: usize,
+    pub source: u8,
+    pub pgn: u32,
+    pub value: T,
+}
+
+/// Reassembles the PGNs for which `fast_packet` returns `true` and decodes
+/// every message, spread over a number of threads.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::bulk::BulkDecoder;
+/// use nmea::candump;
+///
+/// let log = "\
+/// (1.000000) can0 09F8050A#00090102030405FF
+/// (1.001000) can0 09F1120B#FF00FF7F0000FFFF
+/// (1.002000) can0 09F8050A#01070809FFFFFFFF";
+/// let frames: Vec<_> = log.lines().filter_map(candump::parse_line).collect();
+/// let records = BulkDecoder::new(|pgn| pgn == 129029)
+///     .with_threads(2)
+///     .decode(&frames, |packet| Some(packet.payload().len()));
+/// assert_eq!(records.len(), 2);
+/// assert_eq!((records[0].pgn, records[0].value), (127250, 8));
+/// assert_eq!((records[1].source, records[1].value), (0x0A, 9));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct BulkDecoder {
+    fast_packet: fn(u32) -> bool,
+    threads: usize,
+}
+
+impl BulkDecoder {
+    pub const fn new(fast_packet: fn(u32) -> bool) -> Self {
+        Self {
+            fast_packet,
+            threads: 0,
+        }
+    }
+
+    /// Uses `threads` workers. 0, the default, uses one per available core.
+    pub const fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    fn workers(&self) -> usize {
+        match self.threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        }
+        .min(256)
+    }
+
+    /// Decodes `frames` with `decode`, which returns `None` for messages to
+    /// leave out. Records are in log order of the frames completing them, as
+    /// a single-threaded pass would produce.
+    pub fn decode<T, D>(&self, frames: &[CapturedFrame], decode: D) -> Vec<Record<T>>
+    where
+        T: Send,
+        D: Fn(&Packet) -> Option<T> + Sync,
+    {
+        let workers = self.workers();
+        let decode = &decode;
+        let shares: Vec<Vec<Record<T>>> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|worker| {
+                    scope.spawn(move || self.decode_share(frames, workers, worker, decode))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|e| std::panic::resume_unwind(e))
+                })
+                .collect()
+        });
+        let mut records: Vec<Record<T>> = shares.into_iter().flatten().collect();
+        records.sort_by_key(|record| record.frame_index);
+        records
+    }
+
+    /// Decodes the frames of the sources assigned to `worker`.
+    fn decode_share<T, D>(
+        &self,
+        frames: &[CapturedFrame],
+        workers: usize,
+        worker: usize,
+        decode: &D,
+    ) -> Vec<Record<T>>
+    where
+        D: Fn(&Packet) -> Option<T>,
+    {
+        let mut pipeline = Pipeline::new(Reassemble::<SESSIONS>::new(self.fast_packet));
+        let mut records = Vec::new();
+        for (frame_index, frame) in frames.iter().enumerate() {
+            if frame.id.source() as usize % workers != worker {
+                continue;
+            }
+            let rx = RxFrame {
+                id: frame.id,
+                data: frame.data,
+            };
+            let Some(packet) = pipeline.push(frame.timestamp_ms, &rx) else {
+                continue;
+            };
+            if let Some(value) = decode(packet) {
+                records.push(Record {
+                    timestamp_ms: packet.timestamp_ms,
+                    frame_index,
+                    source: packet.id.source(),
+                    pgn: packet.id.pgn(),
+                    value,
+                });
+            }
+        }
+        records
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::Direction;
+    use crate::can_id::CanId;
+    use crate::nmea_message::Message;
+
+    #[test]
+    fn test_decode() {
+        let payload: Vec<u8> = (0..43).collect();
+        let mut frames = Vec::new();
+        let mut timestamp_ms = 0;
+        for round in 0..50u8 {
+            for source in 0..10u8 {
+                let mut msg = Message::from_payload(&payload, round % 8).unwrap();
+                while let Some(frame) = msg.pop_frame() {
+                    timestamp_ms += 1;
+                    frames.push(CapturedFrame {
+                        timestamp_ms,
+                        id: CanId::new(3, 129029, source, 255).unwrap(),
+                        data: frame.bytes,
+                        direction: Direction::Rx,
+                    });
+                }
+                frames.push(CapturedFrame {
+                    timestamp_ms,
+                    id: CanId::new(2, 127250, source, 255).unwrap(),
+                    data: [source; 8],
+                    direction: Direction::Rx,
+                });
+            }
+        }
+        let decoder = BulkDecoder::new(|pgn| pgn == 129029);
+        let decode = |packet: &Packet| Some(packet.payload()[0]);
+        let single = decoder.with_threads(1).decode(&frames, decode);
+        let parallel = decoder.with_threads(4).decode(&frames, decode);
+        assert_eq!(single.len(), 1000);
+        assert_eq!(single, parallel);
+        assert_eq!(parallel[0].pgn, 129029);
+        assert_eq!(parallel[1].value, 0);
+        assert!(parallel
+            .windows(2)
+            .all(|pair| pair[0].timestamp_ms <= pair[1].timestamp_ms));
+        assert!(decoder
+            .with_threads(3)
+            .decode(&frames, |_| None::<u8>)
+            .is_empty());
+    }
+}
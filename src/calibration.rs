@@ -0,0 +1,348 @@
+//! Per-sensor corrections of decoded values, such as a wind vane's
+//! misalignment or a temperature sensor's offset, applied before the values
+//! are routed on.
+//!
+//! A correction is identified by the sender's source address, the instance
+//! of its PGNs (0 for PGNs without one), the PGN and the name of the field
+//! as listed in `pgn::info`. Corrected values are `value * scale + offset`;
+//! unsigned angles are wrapped back into `[0, 2π)`.
+use crate::pgn::environment::{Humidity, Temperature};
+use crate::pgn::fluid_level::FluidLevel;
+use crate::pgn::gnss::CogSogRapidUpdate;
+use crate::pgn::heading::VesselHeading;
+use crate::pgn::info::{self, FieldInfo, FieldType};
+use crate::pgn::normalize_angle;
+use crate::pgn::wind::WindData;
+use fixed_queue::LinearMap;
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Correction table is full")]
+    FullTable,
+    #[error("PGN has no such field")]
+    UnknownField,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Correction {
+    #[cfg_attr(any(test, feature = "serde"), serde(default))]
+    pub offset: f32,
+    #[cfg_attr(any(test, feature = "serde"), serde(default = "unity"))]
+    pub scale: f32,
+}
+
+#[cfg(any(test, feature = "serde"))]
+fn unity() -> f32 {
+    1.0
+}
+
+impl Correction {
+    pub const fn offset(offset: f32) -> Self {
+        Self { offset, scale: 1.0 }
+    }
+
+    pub fn apply(&self, value: f32) -> f32 {
+        value * self.scale + self.offset
+    }
+}
+
+/// A decoded PGN with values that can be corrected.
+pub trait Calibrate {
+    /// The instance of the sensor within its source, or 0.
+    fn instance(&self) -> u8;
+    /// Calls `f` with the name of each available correctable field and its
+    /// value.
+    fn values(&mut self, f: &mut dyn FnMut(&'static str, &mut f32));
+}
+
+macro_rules! calibrate {
+    (@instance $self:ident) => { 0 };
+    (@instance $self:ident $instance:ident) => { $self.$instance };
+    ($($ty:ty $([$instance:ident])? { $($field:ident),* })*) => {
+        $(impl Calibrate for $ty {
+            fn instance(&self) -> u8 {
+                calibrate!(@instance self $($instance)?)
+            }
+
+            fn values(&mut self, f: &mut dyn FnMut(&'static str, &mut f32)) {
+                $(if let Some(value) = self.$field.as_mut() {
+                    f(stringify!($field), value);
+                })*
+            }
+        })*
+    };
+}
+
+calibrate! {
+    WindData { speed, angle }
+    VesselHeading { heading, deviation, variation }
+    CogSogRapidUpdate { cog, sog }
+    Temperature [instance] { temperature, set_temperature }
+    Humidity [instance] { humidity, set_humidity }
+    FluidLevel [instance] { level, capacity }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Key {
+    source: u8,
+    instance: u8,
+    pgn: u32,
+    field: &'static str,
+}
+
+struct Entry {
+    correction: Correction,
+    /// The field is an angle in `[0, 2π)`.
+    wrap: bool,
+}
+
+fn field_info(pgn: u32, field: &str) -> Option<&'static FieldInfo> {
+    info::find(pgn)?.fields.iter().find(|f| f.name == field)
+}
+
+/// Up to `N` corrections.
+///
+/// ## Example (a wind vane mounted 3° to starboard):
+///
+/// ```
+/// use nmea::calibration::{Calibrations, Correction};
+/// use nmea::pgn::wind::WindData;
+///
+/// let mut calibrations: Calibrations<8> = Calibrations::new();
+/// calibrations
+///     .insert(0x10, 0, WindData::PGN, "angle", Correction::offset(-3f32.to_radians()))
+///     .unwrap();
+///
+/// let mut wind = WindData::from_payload(&[0x01, 0xF4, 0x01, 0x64, 0x00, 0x02, 0xFF, 0xFF]).unwrap();
+/// calibrations.apply(0x10, WindData::PGN, &mut wind);
+/// // 0.01 rad ahead, less 3°, wraps round to port.
+/// assert!((wind.angle.unwrap().to_degrees() - 357.57).abs() < 0.01);
+/// ```
+pub struct Calibrations<const N: usize> {
+    entries: LinearMap<Key, Entry, N>,
+}
+
+impl<const N: usize> Default for Calibrations<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Calibrations<N> {
+    pub const fn new() -> Self {
+        Self {
+            entries: LinearMap::new(),
+        }
+    }
+
+    /// Sets the correction of `field` of `pgn` from the sensor at `source`
+    /// and `instance`, replacing any previous one.
+    pub fn insert(
+        &mut self,
+        source: u8,
+        instance: u8,
+        pgn: u32,
+        field: &str,
+        correction: Correction,
+    ) -> Result<(), Error> {
+        let info = field_info(pgn, field).ok_or(Error::UnknownField)?;
+        let key = Key {
+            source,
+            instance,
+            pgn,
+            field: info.name,
+        };
+        let entry = Entry {
+            correction,
+            wrap: info.unit == Some("rad") && info.kind == FieldType::U16,
+        };
+        if let Some(existing) = self.entries.get_mut(&key) {
+            *existing = entry;
+            return Ok(());
+        }
+        self.entries
+            .insert(key, entry)
+            .map_err(|_| Error::FullTable)?;
+        Ok(())
+    }
+
+    pub fn get(&self, source: u8, instance: u8, pgn: u32, field: &str) -> Option<&Correction> {
+        let key = Key {
+            source,
+            instance,
+            pgn,
+            field: field_info(pgn, field)?.name,
+        };
+        self.entries.get(&key).map(|entry| &entry.correction)
+    }
+
+    pub fn remove(&mut self, source: u8, instance: u8, pgn: u32, field: &str) -> bool {
+        let Some(info) = field_info(pgn, field) else {
+            return false;
+        };
+        let key = Key {
+            source,
+            instance,
+            pgn,
+            field: info.name,
+        };
+        self.entries.remove(&key).is_some()
+    }
+
+    /// Corrects the values of `decoded`, a message of `pgn` from `source`.
+    pub fn apply(&self, source: u8, pgn: u32, decoded: &mut impl Calibrate) {
+        let instance = decoded.instance();
+        decoded.values(&mut |field, value| {
+            let key = Key {
+                source,
+                instance,
+                pgn,
+                field,
+            };
+            if let Some(entry) = self.entries.get(&key) {
+                *value = entry.correction.apply(*value);
+                if entry.wrap {
+                    *value = normalize_angle(*value);
+                }
+            }
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// One correction in the form used for persistence.
+#[cfg(any(test, feature = "serde"))]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CorrectionEntry {
+    pub source: u8,
+    #[serde(default)]
+    pub instance: u8,
+    pub pgn: u32,
+    pub field: alloc::string::String,
+    #[serde(flatten)]
+    pub correction: Correction,
+}
+
+#[cfg(any(test, feature = "serde"))]
+impl<const N: usize> Calibrations<N> {
+    /// Returns the entries to serialize, in insertion order.
+    pub fn to_entries(&self) -> alloc::vec::Vec<CorrectionEntry> {
+        self.entries
+            .iter()
+            .map(|(key, entry)| CorrectionEntry {
+                source: key.source,
+                instance: key.instance,
+                pgn: key.pgn,
+                field: key.field.into(),
+                correction: entry.correction,
+            })
+            .collect()
+    }
+
+    /// Builds the corrections from deserialized entries.
+    pub fn from_entries(entries: &[CorrectionEntry]) -> Result<Self, Error> {
+        let mut calibrations = Self::new();
+        for entry in entries {
+            calibrations.insert(
+                entry.source,
+                entry.instance,
+                entry.pgn,
+                &entry.field,
+                entry.correction,
+            )?;
+        }
+        Ok(calibrations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRIDGE: [u8; 8] = [0xFF, 0x01, 0x07, 0x43, 0x6C, 0xFF, 0xFF, 0xFF];
+
+    #[test]
+    fn test_apply() {
+        let mut calibrations: Calibrations<2> = Calibrations::new();
+        calibrations
+            .insert(
+                35,
+                1,
+                Temperature::PGN,
+                "temperature",
+                Correction::offset(-0.5),
+            )
+            .unwrap();
+        calibrations
+            .insert(
+                35,
+                2,
+                Temperature::PGN,
+                "temperature",
+                Correction {
+                    offset: 0.0,
+                    scale: 2.0,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            calibrations.insert(
+                35,
+                3,
+                Temperature::PGN,
+                "temperature",
+                Correction::offset(1.0)
+            ),
+            Err(Error::FullTable)
+        );
+        assert_eq!(
+            calibrations.insert(35, 1, Temperature::PGN, "depth", Correction::offset(1.0)),
+            Err(Error::UnknownField)
+        );
+
+        let mut fridge = Temperature::from_payload(&FRIDGE).unwrap();
+        let raw = fridge.temperature.unwrap();
+        calibrations.apply(35, Temperature::PGN, &mut fridge);
+        assert!((fridge.temperature.unwrap() - (raw - 0.5)).abs() < 1e-3);
+        assert_eq!(fridge.set_temperature, None);
+
+        // Another sender, and the extended PGN, are left alone.
+        let mut other = Temperature::from_payload(&FRIDGE).unwrap();
+        calibrations.apply(36, Temperature::PGN, &mut other);
+        calibrations.apply(35, Temperature::EXTENDED_PGN, &mut other);
+        assert_eq!(other.temperature, Some(raw));
+
+        assert!(calibrations.remove(35, 2, Temperature::PGN, "temperature"));
+        assert_eq!(calibrations.len(), 1);
+    }
+
+    #[test]
+    fn test_persistence() {
+        let json = r#"[{"source":16,"pgn":130306,"field":"angle","offset":-0.05}]"#;
+        let entries: alloc::vec::Vec<CorrectionEntry> = serde_json::from_str(json).unwrap();
+        let calibrations: Calibrations<4> = Calibrations::from_entries(&entries).unwrap();
+        assert_eq!(
+            calibrations.get(16, 0, WindData::PGN, "angle"),
+            Some(&Correction {
+                offset: -0.05,
+                scale: 1.0
+            })
+        );
+        assert_eq!(
+            serde_json::to_string(&calibrations.to_entries()).unwrap(),
+            r#"[{"source":16,"instance":0,"pgn":130306,"field":"angle","offset":-0.05,"scale":1.0}]"#
+        );
+    }
+}
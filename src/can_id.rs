@@ -0,0 +1,289 @@
+//! The 29-bit extended CAN identifier used by NMEA2000 and J1939.
+use thiserror_no_std::Error;
+
+pub const BROADCAST: u8 = 255;
+
+/// Priority of PGNs missing from `DEFAULT_PRIORITIES`.
+pub const DEFAULT_PRIORITY: u8 = 6;
+
+/// Standard default priorities, sorted by PGN.
+const DEFAULT_PRIORITIES: &[(u32, u8)] = &[
+    (59392, 6),  // ISO Acknowledgement
+    (59904, 6),  // ISO Request
+    (60160, 7),  // ISO Transport Protocol, Data Transfer
+    (60416, 7),  // ISO Transport Protocol, Connection Management
+    (60928, 6),  // ISO Address Claim
+    (65240, 6),  // ISO Commanded Address
+    (126208, 3), // Group Function
+    (126464, 6), // PGN List
+    (126992, 3), // System Time
+    (126993, 7), // Heartbeat
+    (126996, 6), // Product Information
+    (126998, 6), // Configuration Information
+    (127237, 2), // Heading/Track Control
+    (127245, 2), // Rudder
+    (127250, 2), // Vessel Heading
+    (127251, 2), // Rate of Turn
+    (127257, 3), // Attitude
+    (127488, 2), // Engine Parameters, Rapid Update
+    (127489, 2), // Engine Parameters, Dynamic
+    (127493, 2), // Transmission Parameters, Dynamic
+    (127501, 3), // Binary Switch Bank Status
+    (127502, 3), // Switch Bank Control
+    (127505, 6), // Fluid Level
+    (127506, 6), // DC Detailed Status
+    (127508, 6), // Battery Status
+    (128259, 2), // Speed, Water Referenced
+    (128267, 3), // Water Depth
+    (128275, 6), // Distance Log
+    (129025, 2), // Position, Rapid Update
+    (129026, 2), // COG & SOG, Rapid Update
+    (129029, 3), // GNSS Position Data
+    (129033, 3), // Time & Date
+    (129038, 4), // AIS Class A Position Report
+    (129039, 4), // AIS Class B Position Report
+    (129283, 3), // Cross Track Error
+    (129284, 3), // Navigation Data
+    (129539, 6), // GNSS DOPs
+    (129540, 6), // GNSS Satellites in View
+    (130306, 2), // Wind Data
+    (130310, 5), // Environmental Parameters
+    (130311, 5), // Environmental Parameters
+    (130312, 5), // Temperature
+    (130313, 5), // Humidity
+    (130314, 5), // Actual Pressure
+    (130316, 5), // Temperature, Extended Range
+    (130578, 2), // Vessel Speed Components
+];
+
+/// The standard default priority of `pgn`, or `DEFAULT_PRIORITY` for PGNs
+/// without one.
+pub const fn default_priority(pgn: u32) -> u8 {
+    match standard_priority(pgn) {
+        Some(priority) => priority,
+        None => DEFAULT_PRIORITY,
+    }
+}
+
+/// The standard default priority of `pgn`, if `DEFAULT_PRIORITIES` has one.
+/// A `const fn`, so typed PGNs can look theirs up at compile time.
+pub const fn standard_priority(pgn: u32) -> Option<u8> {
+    let (mut low, mut high) = (0, DEFAULT_PRIORITIES.len());
+    while low < high {
+        let mid = (low + high) / 2;
+        let (key, priority) = DEFAULT_PRIORITIES[mid];
+        if key == pgn {
+            return Some(priority);
+        } else if key < pgn {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    None
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Identifier does not fit in 29 bits")]
+    InvalidId,
+    #[error("PGN is not valid for this format")]
+    InvalidPgn,
+    #[error("Priority must be between 0 and 7")]
+    InvalidPriority,
+}
+
+/// A CAN identifier split into priority, PGN, source and destination.
+///
+/// PGNs whose PDU format byte is below 240 (PDU1) are addressed: the PDU
+/// specific byte holds the destination and is not part of the PGN. PDU2
+/// PGNs are always broadcast.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CanId(u32);
+
+impl CanId {
+    pub fn new(priority: u8, pgn: u32, source: u8, destination: u8) -> Result<Self, Error> {
+        if priority > 7 {
+            return Err(Error::InvalidPriority);
+        }
+        if pgn > 0x3_FFFF {
+            return Err(Error::InvalidPgn);
+        }
+        let pdu_format = (pgn >> 8) & 0xFF;
+        let pdu_specific = if pdu_format < 240 {
+            if pgn & 0xFF != 0 {
+                return Err(Error::InvalidPgn);
+            }
+            destination as u32
+        } else {
+            pgn & 0xFF
+        };
+        Ok(Self(
+            (priority as u32) << 26 | (pgn & 0x3_FF00) << 8 | pdu_specific << 8 | source as u32,
+        ))
+    }
+
+    /// An identifier for `pgn` with its default priority, see
+    /// `default_priority`.
+    pub fn for_pgn(pgn: u32, source: u8, destination: u8) -> Result<Self, Error> {
+        Self::new(default_priority(pgn), pgn, source, destination)
+    }
+
+    pub fn priority(&self) -> u8 {
+        (self.0 >> 26) as u8 & 0x07
+    }
+
+    fn pdu_format(&self) -> u8 {
+        (self.0 >> 16) as u8
+    }
+
+    /// Whether the PGN is addressed (PDU1).
+    pub fn is_pdu1(&self) -> bool {
+        self.pdu_format() < 240
+    }
+
+    pub fn pgn(&self) -> u32 {
+        let pgn = (self.0 >> 8) & 0x3_FFFF;
+        if self.is_pdu1() {
+            pgn & 0x3_FF00
+        } else {
+            pgn
+        }
+    }
+
+    pub fn source(&self) -> u8 {
+        self.0 as u8
+    }
+
+    /// Destination address, `BROADCAST` for PDU2 PGNs.
+    pub fn destination(&self) -> u8 {
+        if self.is_pdu1() {
+            (self.0 >> 8) as u8
+        } else {
+            BROADCAST
+        }
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        self.destination() == BROADCAST
+    }
+
+    /// The same identifier with a different source and, for PDU1 PGNs,
+    /// destination address.
+    pub fn with_addresses(&self, source: u8, destination: u8) -> Self {
+        let mut id = self.0 & !0xFF;
+        if self.is_pdu1() {
+            id = id & !0xFF00 | (destination as u32) << 8;
+        }
+        Self(id | source as u32)
+    }
+}
+
+impl TryFrom<u32> for CanId {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self, Error> {
+        if value > 0x1FFF_FFFF {
+            return Err(Error::InvalidId);
+        }
+        Ok(Self(value))
+    }
+}
+
+impl From<CanId> for u32 {
+    fn from(value: CanId) -> u32 {
+        value.0
+    }
+}
+
+/// Decides which received frames a node at `address` should process:
+/// broadcasts and traffic addressed to it. Analyzers that need to see
+/// everything can enable `promiscuous` mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AddressFilter {
+    pub address: u8,
+    pub promiscuous: bool,
+}
+
+impl AddressFilter {
+    pub const fn new(address: u8) -> Self {
+        Self {
+            address,
+            promiscuous: false,
+        }
+    }
+
+    pub fn accepts(&self, id: CanId) -> bool {
+        self.promiscuous || id.is_broadcast() || id.destination() == self.address
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pdu2() {
+        // Priority 2 Vessel Heading from address 0x17.
+        let id = CanId::try_from(0x09F1_1217).unwrap();
+        assert_eq!(id.priority(), 2);
+        assert_eq!(id.pgn(), 127250);
+        assert_eq!(id.source(), 0x17);
+        assert!(!id.is_pdu1());
+        assert!(id.is_broadcast());
+        assert_eq!(CanId::new(2, 127250, 0x17, 0x42), Ok(id));
+        assert_eq!(u32::from(id.with_addresses(0x20, 0x42)), 0x09F1_1220);
+        assert_eq!(CanId::try_from(0x2000_0000), Err(Error::InvalidId));
+    }
+
+    #[test]
+    fn test_pdu1() {
+        // ISO Request (59904) from 0x17 to 0x42.
+        let id = CanId::new(6, 59904, 0x17, 0x42).unwrap();
+        assert_eq!(u32::from(id), 0x18EA_4217);
+        assert!(id.is_pdu1());
+        assert_eq!(id.pgn(), 59904);
+        assert_eq!(id.destination(), 0x42);
+        assert_eq!(
+            id.with_addresses(0x01, 0x02),
+            CanId::new(6, 59904, 0x01, 0x02).unwrap()
+        );
+        assert_eq!(CanId::new(6, 59905, 0x17, 0x42), Err(Error::InvalidPgn));
+        assert_eq!(
+            CanId::new(8, 59904, 0x17, 0x42),
+            Err(Error::InvalidPriority)
+        );
+
+        // Group functions are PDU1 with a data page bit.
+        let id = CanId::new(3, 126208, 0x17, BROADCAST).unwrap();
+        assert_eq!(id.pgn(), 126208);
+        assert!(id.is_broadcast());
+    }
+
+    #[test]
+    fn test_default_priority() {
+        assert!(DEFAULT_PRIORITIES.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(default_priority(129025), 2);
+        assert_eq!(default_priority(126996), 6);
+        assert_eq!(default_priority(130820), DEFAULT_PRIORITY);
+        assert_eq!(
+            CanId::for_pgn(127250, 0x17, BROADCAST),
+            CanId::try_from(0x09F1_1217)
+        );
+        assert_eq!(CanId::for_pgn(59904, 0x17, 0x42).unwrap().priority(), 6);
+    }
+
+    #[test]
+    fn test_address_filter() {
+        let mut filter = AddressFilter::new(0x42);
+        let to_us = CanId::new(6, 59904, 0x17, 0x42).unwrap();
+        let to_other = CanId::new(6, 59904, 0x17, 0x43).unwrap();
+        let broadcast = CanId::new(2, 127250, 0x17, BROADCAST).unwrap();
+        assert!(filter.accepts(to_us));
+        assert!(!filter.accepts(to_other));
+        assert!(filter.accepts(broadcast));
+
+        filter.promiscuous = true;
+        assert!(filter.accepts(to_other));
+    }
+}
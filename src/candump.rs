@@ -0,0 +1,97 @@
+//! Reading and writing candump log lines (`(seconds) interface ID#DATA`).
+//!
+//! Lines may end with `R` or `T`, as written by candump with `-x`, for the
+//! direction of the frame. Frames without one are taken as received, so Rx
+//! and Tx frames can be kept in one log.
+use crate::analyzer::{CapturedFrame, Direction};
+use crate::can_id::CanId;
+use core::fmt;
+
+/// Parses a log line. Data shorter than 8 bytes is padded with `0xFF`.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::analyzer::Direction;
+/// use nmea::candump;
+///
+/// let frame = candump::parse_line("(1700000000.002000) can0 1DFF040A#E100 T").unwrap();
+/// assert_eq!(frame.timestamp_ms, 1_700_000_000_002);
+/// assert_eq!(frame.id.source(), 10);
+/// assert_eq!(frame.data[..3], [0xE1, 0x00, 0xFF]);
+/// assert_eq!(frame.direction, Direction::Tx);
+/// ```
+pub fn parse_line(line: &str) -> Option<CapturedFrame> {
+    let mut fields = line.split_whitespace();
+    let timestamp = fields.next()?.strip_prefix('(')?.strip_suffix(')')?;
+    let (seconds, fraction) = timestamp.split_once('.')?;
+    let millis = fraction.get(..3)?;
+    let timestamp_ms = seconds.parse::<u64>().ok()? * 1000 + millis.parse::<u64>().ok()?;
+    let (id, data) = fields.nth(1)?.split_once('#')?;
+    let id = CanId::try_from(u32::from_str_radix(id, 16).ok()?).ok()?;
+    if data.len() % 2 != 0 || data.len() > 16 {
+        return None;
+    }
+    let mut bytes = [0xFF; 8];
+    for (byte, digits) in bytes.iter_mut().zip(data.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(core::str::from_utf8(digits).ok()?, 16).ok()?;
+    }
+    let direction = match fields.next() {
+        None | Some("R") => Direction::Rx,
+        Some("T") => Direction::Tx,
+        Some(_) => return None,
+    };
+    Some(CapturedFrame {
+        timestamp_ms,
+        id,
+        data: bytes,
+        direction,
+    })
+}
+
+/// Writes `frame` as a log line on `interface`, without a line break and
+/// ending with its direction.
+pub fn write_line(
+    out: &mut impl fmt::Write,
+    interface: &str,
+    frame: &CapturedFrame,
+) -> fmt::Result {
+    write!(
+        out,
+        "({}.{:03}000) {} {:08X}#",
+        frame.timestamp_ms / 1000,
+        frame.timestamp_ms % 1000,
+        interface,
+        u32::from(frame.id)
+    )?;
+    for byte in frame.data {
+        write!(out, "{:02X}", byte)?;
+    }
+    match frame.direction {
+        Direction::Rx => write!(out, " R"),
+        Direction::Tx => write!(out, " T"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    #[test]
+    fn test_round_trip() {
+        let line = "(1700000000.004000) can0 1DFF040A#E20000D08400005E R";
+        let frame = parse_line(line).unwrap();
+        let mut out = String::new();
+        write_line(&mut out, "can0", &frame).unwrap();
+        assert_eq!(out, line);
+
+        let tx = parse_line("(0.010000) can1 09F1120A#0102030405060708 T").unwrap();
+        assert_eq!(tx.direction, Direction::Tx);
+        assert_eq!(tx.timestamp_ms, 10);
+
+        assert_eq!(parse_line("(0.010000) can1 09F1120A#010 T"), None);
+        assert_eq!(parse_line("(0.010000) can1 09F1120A#01 X"), None);
+        assert_eq!(parse_line("# comment"), None);
+    }
+}
@@ -0,0 +1,371 @@
+//! Classic NMEA2000 frames tunneled over CAN-FD, optionally packed several
+//! to an FD frame.
+//!
+//! A container is an FD frame carrying a two-byte header followed by up to
+//! `MAX_FRAMES` classic frames:
+//!
+//! | Bytes  | Field                                        |
+//! |--------|----------------------------------------------|
+//! | 0      | `CONTAINER_TAG`                              |
+//! | 1      | Number of frames                             |
+//! | 2-5    | 29-bit CAN ID of the first frame, LE         |
+//! | 6-13   | Data of the first frame                      |
+//! | ...    | Further frames, 12 bytes each                |
+//!
+//! The payload is padded with 0xFF to the next valid FD length. Frames that
+//! aren't packed are sent as 8-byte FD frames on their own ID, so a receiver
+//! tells the two apart by length.
+use crate::bridge::{self, FrameSink, FrameSource};
+use crate::can_id::CanId;
+use crate::frame_queue::RxFrame;
+use fixed_queue::{Vec, VecDeque};
+use thiserror_no_std::Error;
+
+/// Largest CAN-FD payload.
+pub const FD_LEN: usize = 64;
+/// First byte of every container.
+pub const CONTAINER_TAG: u8 = 0xC5;
+/// Length of the container header.
+pub const HEADER_LEN: usize = 2;
+/// Length of each packed frame.
+pub const RECORD_LEN: usize = 12;
+/// Classic frames that fit in one container.
+pub const MAX_FRAMES: usize = (FD_LEN - HEADER_LEN) / RECORD_LEN;
+
+/// Payload lengths a CAN-FD frame can have.
+const FD_LENGTHS: [usize; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 12, 16, 20, 24, 32, 48, 64];
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Not a container")]
+    NotContainer,
+    #[error("Container holds {0} frames")]
+    BadCount(usize),
+    #[error("Container is shorter than its frames")]
+    Truncated,
+    #[error(transparent)]
+    Id(#[from] crate::can_id::Error),
+}
+
+/// A CAN-FD frame with an extended ID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FdFrame {
+    pub id: CanId,
+    pub len: u8,
+    pub data: [u8; FD_LEN],
+}
+
+impl FdFrame {
+    /// An 8-byte FD frame carrying `frame` unchanged.
+    pub fn classic(frame: &RxFrame) -> Self {
+        let mut data = [0xFF; FD_LEN];
+        data[..8].copy_from_slice(&frame.data);
+        Self {
+            id: frame.id,
+            len: 8,
+            data,
+        }
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.data[..(self.len as usize).min(FD_LEN)]
+    }
+
+    /// Whether the frame is a container rather than a single classic frame.
+    pub fn is_container(&self) -> bool {
+        self.len > 8 && self.data[0] == CONTAINER_TAG
+    }
+}
+
+/// Packs up to `MAX_FRAMES` classic frames into a container, sent on the
+/// highest priority ID among them so the batch doesn't lose arbitration to
+/// traffic its frames would have won against.
+pub fn pack(frames: &[RxFrame]) -> Result<FdFrame, Error> {
+    if frames.is_empty() || frames.len() > MAX_FRAMES {
+        return Err(Error::BadCount(frames.len()));
+    }
+    let mut data = [0xFF; FD_LEN];
+    data[0] = CONTAINER_TAG;
+    data[1] = frames.len() as u8;
+    for (frame, record) in frames.iter().zip(data[HEADER_LEN..].chunks_mut(RECORD_LEN)) {
+        record[..4].copy_from_slice(&u32::from(frame.id).to_le_bytes());
+        record[4..].copy_from_slice(&frame.data);
+    }
+    let used = HEADER_LEN + frames.len() * RECORD_LEN;
+    let len = FD_LENGTHS
+        .iter()
+        .find(|len| **len >= used)
+        .unwrap_or(&FD_LEN);
+    let id = frames
+        .iter()
+        .map(|frame| frame.id)
+        .min_by_key(|id| u32::from(*id))
+        .unwrap_or(frames[0].id);
+    Ok(FdFrame {
+        id,
+        len: *len as u8,
+        data,
+    })
+}
+
+/// Unpacks the classic frames of a container.
+pub fn unpack(payload: &[u8]) -> Result<impl Iterator<Item = RxFrame> + '_, Error> {
+    if payload.len() < HEADER_LEN || payload[0] != CONTAINER_TAG {
+        return Err(Error::NotContainer);
+    }
+    let count = payload[1] as usize;
+    if count == 0 || count > MAX_FRAMES {
+        return Err(Error::BadCount(count));
+    }
+    let records = payload
+        .get(HEADER_LEN..HEADER_LEN + count * RECORD_LEN)
+        .ok_or(Error::Truncated)?;
+    for record in records.chunks(RECORD_LEN) {
+        record_id(record)?;
+    }
+    // The IDs were checked above, so no frame is filtered out.
+    Ok(records.chunks(RECORD_LEN).filter_map(|record| {
+        let mut data = [0; 8];
+        data.copy_from_slice(&record[4..]);
+        Some(RxFrame {
+            id: record_id(record).ok()?,
+            data,
+        })
+    }))
+}
+
+fn record_id(record: &[u8]) -> Result<CanId, Error> {
+    let raw = u32::from_le_bytes([record[0], record[1], record[2], record[3]]);
+    Ok(CanId::try_from(raw)?)
+}
+
+/// Receiving side of a CAN-FD interface.
+pub trait FdSource {
+    fn receive_fd(&mut self) -> Option<FdFrame>;
+}
+
+/// Transmitting side of a CAN-FD interface.
+pub trait FdSink {
+    fn transmit_fd(&mut self, frame: &FdFrame) -> Result<(), bridge::Error>;
+}
+
+/// Adapts a CAN-FD interface to a `FrameSource` and `FrameSink` of classic
+/// frames.
+///
+/// Containers are always unpacked on receive. On transmit, each frame goes
+/// out as its own FD frame unless batching is enabled with `with_batching`,
+/// in which case frames are held until `max_frames` are waiting or `flush`
+/// is called. Call `flush` at the end of every poll loop so a lone frame
+/// isn't held back. Malformed containers are dropped and counted.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::bridge::{self, FrameSink};
+/// use nmea::can_id::CanId;
+/// use nmea::canfd::{FdFrame, FdSink, FdTransport};
+/// use nmea::frame_queue::RxFrame;
+///
+/// struct Bus(Vec<FdFrame>);
+///
+/// impl FdSink for Bus {
+///     fn transmit_fd(&mut self, frame: &FdFrame) -> Result<(), bridge::Error> {
+///         self.0.push(*frame);
+///         Ok(())
+///     }
+/// }
+///
+/// let mut transport = FdTransport::new(Bus(Vec::new())).with_batching(5);
+/// let frame = RxFrame {
+///     id: CanId::new(2, 127250, 0x23, 255).unwrap(),
+///     data: [0; 8],
+/// };
+/// for _ in 0..7 {
+///     transport.transmit(&frame).unwrap();
+/// }
+/// transport.flush().unwrap();
+/// let lens: Vec<u8> = transport.inner_mut().0.iter().map(|f| f.len).collect();
+/// assert_eq!(lens, [64, 32]);
+/// ```
+pub struct FdTransport<T> {
+    inner: T,
+    max_frames: usize,
+    tx: Vec<RxFrame, MAX_FRAMES>,
+    rx: VecDeque<RxFrame, MAX_FRAMES>,
+    errors: u32,
+}
+
+impl<T> FdTransport<T> {
+    pub const fn new(inner: T) -> Self {
+        Self {
+            inner,
+            max_frames: 1,
+            tx: Vec::new(),
+            rx: VecDeque::new(),
+            errors: 0,
+        }
+    }
+
+    /// Packs up to `max_frames` frames, at most `MAX_FRAMES`, into each
+    /// container. 1 disables batching.
+    pub const fn with_batching(mut self, max_frames: usize) -> Self {
+        self.max_frames = if max_frames == 0 {
+            1
+        } else if max_frames > MAX_FRAMES {
+            MAX_FRAMES
+        } else {
+            max_frames
+        };
+        self
+    }
+
+    /// Number of malformed containers dropped so far.
+    pub fn errors(&self) -> u32 {
+        self.errors
+    }
+
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: FdSource> FrameSource for FdTransport<T> {
+    fn receive(&mut self) -> Option<RxFrame> {
+        loop {
+            if let Some(frame) = self.rx.pop_front() {
+                return Some(frame);
+            }
+            let fd = self.inner.receive_fd()?;
+            if !fd.is_container() {
+                let mut data = [0xFF; 8];
+                let payload = fd.payload();
+                let len = payload.len().min(8);
+                data[..len].copy_from_slice(&payload[..len]);
+                return Some(RxFrame { id: fd.id, data });
+            }
+            match unpack(fd.payload()) {
+                Ok(frames) => {
+                    for frame in frames {
+                        let _ = self.rx.push_back(frame);
+                    }
+                }
+                Err(_) => self.errors += 1,
+            };
+        }
+    }
+}
+
+impl<T: FdSink> FrameSink for FdTransport<T> {
+    fn transmit(&mut self, frame: &RxFrame) -> Result<(), bridge::Error> {
+        if self.max_frames == 1 {
+            return self.inner.transmit_fd(&FdFrame::classic(frame));
+        }
+        let _ = self.tx.push(*frame);
+        if self.tx.len() >= self.max_frames {
+            self.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: FdSink> FdTransport<T> {
+    /// Transmits the frames waiting for a container. A single frame is sent
+    /// on its own rather than in a container.
+    pub fn flush(&mut self) -> Result<(), bridge::Error> {
+        let fd = match self.tx.len() {
+            0 => return Ok(()),
+            1 => FdFrame::classic(&self.tx[0]),
+            _ => pack(&self.tx).map_err(|_| bridge::Error::Transmit)?,
+        };
+        self.tx.clear();
+        self.inner.transmit_fd(&fd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque as Queue;
+
+    struct Bus(Queue<FdFrame>);
+
+    impl FdSink for Bus {
+        fn transmit_fd(&mut self, frame: &FdFrame) -> Result<(), bridge::Error> {
+            self.0.push_back(*frame);
+            Ok(())
+        }
+    }
+
+    impl FdSource for Bus {
+        fn receive_fd(&mut self) -> Option<FdFrame> {
+            self.0.pop_front()
+        }
+    }
+
+    fn frame(priority: u8, source: u8) -> RxFrame {
+        RxFrame {
+            id: CanId::new(priority, 130306, source, 255).unwrap(),
+            data: [source; 8],
+        }
+    }
+
+    #[test]
+    fn test_pack() {
+        let frames = [frame(5, 1), frame(2, 2), frame(3, 3)];
+        let fd = pack(&frames).unwrap();
+        assert_eq!(fd.len, 48);
+        assert_eq!(fd.id, frames[1].id);
+        assert!(fd.is_container());
+        assert!(unpack(fd.payload()).unwrap().eq(frames));
+        assert_eq!(pack(&[]), Err(Error::BadCount(0)));
+        assert_eq!(pack(&[frame(2, 2); 6]), Err(Error::BadCount(6)));
+        assert_eq!(pack(&[frame(2, 2)]).unwrap().len, 16);
+        assert_eq!(pack(&[frame(2, 2); 5]).unwrap().len, 64);
+
+        assert!(matches!(unpack(&[0x00, 1]), Err(Error::NotContainer)));
+        assert!(matches!(unpack(&fd.data[..20]), Err(Error::Truncated)));
+        let mut bad = fd.data;
+        bad[5] = 0xFF;
+        assert!(matches!(
+            unpack(&bad),
+            Err(Error::Id(crate::can_id::Error::InvalidId))
+        ));
+    }
+
+    #[test]
+    fn test_transport() {
+        let mut transport = FdTransport::new(Bus(Queue::new())).with_batching(3);
+        let frames: std::vec::Vec<RxFrame> = (0..5).map(|i| frame(2, i)).collect();
+        for frame in &frames {
+            transport.transmit(frame).unwrap();
+        }
+        assert_eq!(transport.inner_mut().0.len(), 1);
+        transport.flush().unwrap();
+        transport.flush().unwrap();
+        let lens: std::vec::Vec<u8> = transport.inner_mut().0.iter().map(|f| f.len).collect();
+        assert_eq!(lens, [48, 32]);
+
+        // A malformed container, then a plain frame.
+        let mut bad = pack(&frames[..2]).unwrap();
+        bad.data[1] = 9;
+        transport.inner_mut().0.push_back(bad);
+        transport
+            .inner_mut()
+            .0
+            .push_back(FdFrame::classic(&frames[0]));
+        let received: std::vec::Vec<RxFrame> =
+            core::iter::from_fn(|| transport.receive()).collect();
+        assert_eq!(&received[..5], &frames[..]);
+        assert_eq!(received[5], frames[0]);
+        assert_eq!(transport.errors(), 1);
+
+        // Without batching, each frame is sent as it comes.
+        let mut transport = FdTransport::new(Bus(Queue::new()));
+        transport.transmit(&frames[0]).unwrap();
+        assert_eq!(transport.inner_mut().0[0], FdFrame::classic(&frames[0]));
+    }
+}
@@ -0,0 +1,136 @@
+//! Monotonic time sources.
+//!
+//! Every time-based API in the crate takes the current time as monotonic
+//! milliseconds (`now_ms`). `Clock` abstracts over where that time comes
+//! from, so the same code can run from a hardware timer on a microcontroller,
+//! from `std::time` on a host, or from a `ManualClock` in tests and replays.
+use core::cell::Cell;
+
+/// A monotonic millisecond clock. The epoch is arbitrary; only differences
+/// between readings are meaningful.
+pub trait Clock {
+    fn now_ms(&self) -> u64;
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now_ms(&self) -> u64 {
+        (**self).now_ms()
+    }
+}
+
+/// A clock that only moves when told to. Useful in tests and when replaying
+/// captures with recorded timestamps.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::clock::{Clock, ManualClock};
+///
+/// let clock = ManualClock::new(1000);
+/// clock.advance(250);
+/// assert_eq!(clock.now_ms(), 1250);
+/// ```
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now_ms: Cell<u64>,
+}
+
+impl ManualClock {
+    pub const fn new(now_ms: u64) -> Self {
+        Self {
+            now_ms: Cell::new(now_ms),
+        }
+    }
+
+    pub fn set(&self, now_ms: u64) {
+        self.now_ms.set(now_ms);
+    }
+
+    pub fn advance(&self, ms: u64) {
+        self.now_ms.set(self.now_ms.get().saturating_add(ms));
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_ms(&self) -> u64 {
+        self.now_ms.get()
+    }
+}
+
+/// Milliseconds elapsed since the clock was created, from `std::time::Instant`.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+pub struct InstantClock {
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl InstantClock {
+    pub fn new() -> Self {
+        Self {
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for InstantClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for InstantClock {
+    fn now_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+}
+
+/// Milliseconds since the Unix epoch, from `std::time::SystemTime`.
+///
+/// Wall-clock time can jump when the system clock is adjusted; prefer
+/// `InstantClock` unless readings must match timestamps from other sources.
+/// Times before the epoch read as 0.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now_ms(&self) -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elapsed(clock: impl Clock, start_ms: u64) -> u64 {
+        clock.now_ms() - start_ms
+    }
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new(5);
+        assert_eq!(clock.now_ms(), 5);
+        clock.advance(10);
+        assert_eq!(elapsed(&clock, 5), 10);
+        clock.set(u64::MAX - 1);
+        clock.advance(10);
+        assert_eq!(clock.now_ms(), u64::MAX);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_std_clocks() {
+        let clock = InstantClock::new();
+        let start = clock.now_ms();
+        assert!(clock.now_ms() >= start);
+        // Some time after 2020-01-01.
+        assert!(SystemClock.now_ms() > 1_577_836_800_000);
+    }
+}
@@ -0,0 +1,272 @@
+//! Configuration of a gateway built on the crate: its NAME and address, the
+//! PGN filters and address aliases of a `Bridge`, instance labels and sensor
+//! calibrations.
+//!
+//! `GatewayConfig` is plain serde data. With the `toml` feature it is loaded
+//! from and saved to TOML files like:
+//!
+//! ```toml
+//! [device]
+//! name = "0xC0320A0000E01234"
+//! address = 35
+//!
+//! [[filters]]
+//! direction = "AToB"
+//! deny = [126208]
+//!
+//! [[mappings]]
+//! segment = "A"
+//! address = 16
+//! alias = 128
+//!
+//! [[labels]]
+//! source = 35
+//! instance = 1
+//! label = "Fridge"
+//!
+//! [[calibrations]]
+//! source = 16
+//! pgn = 130306
+//! field = "angle"
+//! offset = -0.05
+//! ```
+use crate::address_map::{self, AddressMap, Segment};
+use crate::bridge::Direction;
+use crate::calibration::{self, Calibrations, CorrectionEntry};
+use crate::labels::{self, LabelEntry, LabelRegistry};
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error(transparent)]
+    Address(#[from] address_map::Error),
+    #[error(transparent)]
+    Label(#[from] labels::Error),
+    #[error(transparent)]
+    Calibration(#[from] calibration::Error),
+}
+
+/// NAMEs are written as hex strings; TOML integers don't reach 64 bits.
+mod hex_name {
+    use alloc::format;
+    use alloc::string::String;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(name: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match name {
+            Some(name) => serializer.serialize_str(&format!("0x{:016X}", name)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u64>, D::Error> {
+        let Some(name) = Option::<String>::deserialize(deserializer)? else {
+            return Ok(None);
+        };
+        let digits = name.strip_prefix("0x").unwrap_or(&name);
+        u64::from_str_radix(digits, 16)
+            .map(Some)
+            .map_err(|_| D::Error::custom(format!("invalid NAME {:?}", name)))
+    }
+}
+
+/// The gateway's own identity on the bus.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceConfig {
+    /// 64-bit ISO NAME.
+    #[serde(default, with = "hex_name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<u64>,
+    /// Preferred source address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<u8>,
+}
+
+/// PGNs forwarded in `direction`: those in `allow`, or all if it is empty,
+/// except those in `deny`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Filter {
+    pub direction: Direction,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<u32>,
+}
+
+impl Filter {
+    pub fn allows(&self, pgn: u32) -> bool {
+        (self.allow.is_empty() || self.allow.contains(&pgn)) && !self.deny.contains(&pgn)
+    }
+}
+
+/// Node at `address` on `segment` appearing as `alias` on the other, see
+/// `AddressMap`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mapping {
+    pub segment: Segment,
+    pub address: u8,
+    pub alias: u8,
+}
+
+/// ## Example:
+///
+/// ```
+/// use nmea::bridge::Direction;
+/// use nmea::config::{Filter, GatewayConfig};
+///
+/// let config = GatewayConfig {
+///     filters: vec![Filter {
+///         direction: Direction::AToB,
+///         allow: vec![],
+///         deny: vec![126208],
+///     }],
+///     ..Default::default()
+/// };
+/// assert!(config.allows(Direction::AToB, 127250));
+/// assert!(!config.allows(Direction::AToB, 126208));
+/// assert!(config.allows(Direction::BToA, 126208));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GatewayConfig {
+    #[serde(default)]
+    pub device: DeviceConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<Filter>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mappings: Vec<Mapping>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<LabelEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub calibrations: Vec<CorrectionEntry>,
+}
+
+impl GatewayConfig {
+    /// Whether every filter for `direction` forwards `pgn`.
+    pub fn allows(&self, direction: Direction, pgn: u32) -> bool {
+        self.filters
+            .iter()
+            .filter(|f| f.direction == direction)
+            .all(|f| f.allows(pgn))
+    }
+
+    pub fn address_map<const M: usize>(&self) -> Result<AddressMap<M>, Error> {
+        let mut map = AddressMap::new();
+        for mapping in &self.mappings {
+            map.insert(mapping.segment, mapping.address, mapping.alias)?;
+        }
+        Ok(map)
+    }
+
+    pub fn label_registry<const N: usize, const L: usize>(
+        &self,
+    ) -> Result<LabelRegistry<N, L>, Error> {
+        Ok(LabelRegistry::from_entries(&self.labels)?)
+    }
+
+    pub fn calibrations<const N: usize>(&self) -> Result<Calibrations<N>, Error> {
+        Ok(Calibrations::from_entries(&self.calibrations)?)
+    }
+}
+
+#[cfg(any(test, feature = "toml"))]
+impl GatewayConfig {
+    pub fn from_toml(s: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(s)
+    }
+
+    pub fn to_toml(&self) -> Result<alloc::string::String, toml::ser::Error> {
+        toml::to_string(self)
+    }
+
+    pub fn load(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let s = std::fs::read_to_string(path)?;
+        Self::from_toml(&s).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let s = self
+            .to_toml()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::can_id::CanId;
+    use crate::labels::InstanceKey;
+
+    const CONFIG: &str = r#"[device]
+name = "0xC0320A0000E01234"
+address = 35
+
+[[filters]]
+direction = "AToB"
+allow = [127250, 129025]
+
+[[filters]]
+direction = "BToA"
+deny = [126208]
+
+[[mappings]]
+segment = "A"
+address = 16
+alias = 128
+
+[[labels]]
+source = 35
+instance = 1
+label = "Fridge"
+
+[[calibrations]]
+source = 16
+instance = 0
+pgn = 130306
+field = "angle"
+offset = -0.5
+scale = 1.0
+"#;
+
+    #[test]
+    fn test_toml() {
+        let config = GatewayConfig::from_toml(CONFIG).unwrap();
+        assert_eq!(config.device.name, Some(0xC032_0A00_00E0_1234));
+        assert_eq!(config.device.address, Some(35));
+        assert!(config.allows(Direction::AToB, 129025));
+        assert!(!config.allows(Direction::AToB, 126208));
+        assert!(config.allows(Direction::BToA, 129025));
+        assert!(!config.allows(Direction::BToA, 126208));
+
+        let map: AddressMap<4> = config.address_map().unwrap();
+        let heading = CanId::new(2, 127250, 16, 255).unwrap();
+        assert_eq!(map.to_other(Segment::A, heading).source(), 128);
+        let labels: LabelRegistry<4, 16> = config.label_registry().unwrap();
+        let fridge = InstanceKey {
+            source: 35,
+            instance: 1,
+        };
+        assert_eq!(labels.get(&fridge), Some("Fridge"));
+        let calibrations: Calibrations<4> = config.calibrations().unwrap();
+        assert_eq!(calibrations.len(), 1);
+
+        assert_eq!(config.to_toml().unwrap(), CONFIG);
+        assert_eq!(
+            GatewayConfig::from_toml("").unwrap(),
+            GatewayConfig::default()
+        );
+        assert!(GatewayConfig::from_toml("[device]\nname = \"0xZZ\"").is_err());
+    }
+
+    #[test]
+    fn test_load_save() {
+        let path = std::env::temp_dir().join("nmea-gateway-config-test.toml");
+        let config = GatewayConfig::from_toml(CONFIG).unwrap();
+        config.save(&path).unwrap();
+        assert_eq!(GatewayConfig::load(&path).unwrap(), config);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
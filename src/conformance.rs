@@ -0,0 +1,250 @@
+//! Conformance checks for a device's outgoing traffic, run against frames
+//! captured from the bus: priorities, heartbeat, transmit rates and
+//! Fast-Packet framing.
+use crate::can_id::standard_priority;
+use crate::frame_queue::RxFrame;
+use crate::nmea_message::FirstFramePolicy;
+use crate::rate_limiter::{self, RateLimiter};
+use crate::reassembler::{self, Reassembler};
+
+pub const HEARTBEAT_PGN: u32 = 126993;
+/// Default heartbeat interval; a device may announce a longer one.
+pub const HEARTBEAT_INTERVAL_MS: u64 = 60_000;
+
+/// A departure from the standard found by `Checker`.
+#[derive(Debug, PartialEq)]
+pub enum Finding {
+    /// `pgn` sent at `priority` instead of its standard default priority.
+    WrongPriority {
+        pgn: u32,
+        priority: u8,
+        expected: u8,
+    },
+    /// No heartbeat for longer than the interval. `last_ms` is the time of
+    /// the previous one, if any.
+    MissingHeartbeat { last_ms: Option<u64> },
+    /// `pgn` sent more often than the checker's limit within a second.
+    ExcessiveRate { pgn: u32 },
+    /// A Fast-Packet frame of `pgn` out of sequence, or a new message
+    /// started before the previous one was complete.
+    FastPacket { pgn: u32, error: reassembler::Error },
+    /// More distinct PGNs or concurrent messages than the checker can track.
+    FullTable,
+}
+
+/// Checks the frames sent by one device in order of their timestamps,
+/// reporting each `Finding` as it is found.
+///
+/// `N` bounds the distinct PGNs tracked for rates and the Fast-Packet
+/// messages in progress; `fast_packet` tells which PGNs use Fast-Packet
+/// framing.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::can_id::CanId;
+/// use nmea::conformance::{Checker, Finding};
+/// use nmea::frame_queue::RxFrame;
+///
+/// let mut checker: Checker<8> = Checker::new(10, |_| false);
+/// let mut findings = Vec::new();
+/// // Position rapid update sent at priority 6 instead of 2.
+/// let frame = RxFrame { id: CanId::new(6, 129025, 0x23, 255).unwrap(), data: [0; 8] };
+/// checker.check(0, &frame, |f| findings.push(f));
+/// checker.finish(61_000, |f| findings.push(f));
+/// assert_eq!(
+///     findings,
+///     [
+///         Finding::WrongPriority { pgn: 129025, priority: 6, expected: 2 },
+///         Finding::MissingHeartbeat { last_ms: None },
+///     ]
+/// );
+/// ```
+pub struct Checker<const N: usize> {
+    rates: RateLimiter<N>,
+    sessions: Reassembler<N>,
+    fast_packet: fn(u32) -> bool,
+    heartbeat_interval_ms: u64,
+    /// Time of the last heartbeat, or of the first frame before one.
+    since_ms: Option<u64>,
+    heartbeat_seen: bool,
+    heartbeat_reported: bool,
+}
+
+impl<const N: usize> Checker<N> {
+    /// Creates a checker allowing up to `max_per_second` messages of each
+    /// PGN.
+    pub const fn new(max_per_second: u16, fast_packet: fn(u32) -> bool) -> Self {
+        Self {
+            rates: RateLimiter::new(max_per_second),
+            sessions: Reassembler::with_policy(FirstFramePolicy::Error),
+            fast_packet,
+            heartbeat_interval_ms: HEARTBEAT_INTERVAL_MS,
+            since_ms: None,
+            heartbeat_seen: false,
+            heartbeat_reported: false,
+        }
+    }
+
+    /// Expects heartbeats every `interval_ms` instead of the default.
+    pub const fn with_heartbeat_interval(mut self, interval_ms: u64) -> Self {
+        self.heartbeat_interval_ms = interval_ms;
+        self
+    }
+
+    /// Checks `frame`, sent at `timestamp_ms`.
+    pub fn check(&mut self, timestamp_ms: u64, frame: &RxFrame, mut report: impl FnMut(Finding)) {
+        let pgn = frame.id.pgn();
+        self.check_heartbeat(timestamp_ms, &mut report);
+        if pgn == HEARTBEAT_PGN {
+            self.since_ms = Some(timestamp_ms);
+            self.heartbeat_seen = true;
+            self.heartbeat_reported = false;
+        }
+
+        if let Some(expected) = standard_priority(pgn) {
+            if frame.id.priority() != expected {
+                report(Finding::WrongPriority {
+                    pgn,
+                    priority: frame.id.priority(),
+                    expected,
+                });
+            }
+        }
+
+        let complete = if (self.fast_packet)(pgn) {
+            let source = frame.id.source();
+            match self.sessions.add_frame(source, pgn, &frame.data) {
+                Ok(true) => {
+                    self.sessions.abort(source, pgn);
+                    true
+                }
+                Ok(false) => false,
+                Err(reassembler::Error::FullTable) => {
+                    report(Finding::FullTable);
+                    false
+                }
+                Err(error) => {
+                    report(Finding::FastPacket { pgn, error });
+                    false
+                }
+            }
+        } else {
+            true
+        };
+        if complete {
+            match self.rates.allow(pgn, timestamp_ms) {
+                Ok(true) => {}
+                Ok(false) => report(Finding::ExcessiveRate { pgn }),
+                Err(rate_limiter::Error::FullTable) => report(Finding::FullTable),
+            }
+        }
+    }
+
+    /// Checks for a heartbeat overdue at `now_ms`, the end of the capture.
+    pub fn finish(&mut self, now_ms: u64, mut report: impl FnMut(Finding)) {
+        self.check_heartbeat(now_ms, &mut report);
+    }
+
+    /// Reports a missing heartbeat once per gap.
+    fn check_heartbeat(&mut self, now_ms: u64, report: &mut impl FnMut(Finding)) {
+        let since_ms = *self.since_ms.get_or_insert(now_ms);
+        if !self.heartbeat_reported && now_ms.saturating_sub(since_ms) > self.heartbeat_interval_ms
+        {
+            self.heartbeat_reported = true;
+            report(Finding::MissingHeartbeat {
+                last_ms: self.heartbeat_seen.then_some(since_ms),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::can_id::{default_priority, CanId};
+    use crate::nmea_message::{self, Message};
+    use std::vec::Vec;
+
+    const PRODUCT_INFO: u32 = 126996;
+
+    fn frame(pgn: u32, data: [u8; 8]) -> RxFrame {
+        RxFrame {
+            id: CanId::for_pgn(pgn, 0x23, 255).unwrap(),
+            data,
+        }
+    }
+
+    fn run(frames: &[(u64, RxFrame)], end_ms: u64) -> Vec<Finding> {
+        let mut checker: Checker<4> = Checker::new(2, |pgn| pgn == PRODUCT_INFO);
+        let mut findings = Vec::new();
+        for (t, f) in frames {
+            checker.check(*t, f, |finding| findings.push(finding));
+        }
+        checker.finish(end_ms, |finding| findings.push(finding));
+        findings
+    }
+
+    fn fast_packet_frames(sequence_counter: u8) -> Vec<[u8; 8]> {
+        let mut msg = Message::from_payload(&[0x42; 20], sequence_counter).unwrap();
+        let mut frames = Vec::new();
+        while let Some(frame) = msg.pop_frame() {
+            frames.push(frame.bytes);
+        }
+        frames
+    }
+
+    #[test]
+    fn test_heartbeat() {
+        let heartbeat = frame(HEARTBEAT_PGN, [0; 8]);
+        assert_eq!(default_priority(HEARTBEAT_PGN), 7);
+        let frames = [(0, heartbeat), (60_000, heartbeat), (100_000, heartbeat)];
+        assert_eq!(run(&frames, 150_000), []);
+        let frames = [(0, heartbeat), (70_000, heartbeat)];
+        assert_eq!(
+            run(&frames, 200_000),
+            [
+                Finding::MissingHeartbeat { last_ms: Some(0) },
+                Finding::MissingHeartbeat {
+                    last_ms: Some(70_000)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rate() {
+        let heading = frame(127250, [0; 8]);
+        let frames = [
+            (0, heading),
+            (100, heading),
+            (200, heading),
+            (1000, heading),
+        ];
+        assert_eq!(run(&frames, 1000), [Finding::ExcessiveRate { pgn: 127250 }]);
+    }
+
+    #[test]
+    fn test_fast_packet() {
+        let first = fast_packet_frames(0);
+        let second = fast_packet_frames(1);
+        let frames: Vec<(u64, RxFrame)> = first
+            .iter()
+            .chain(&second[..1])
+            .chain(&first[1..2])
+            .map(|data| (0, frame(PRODUCT_INFO, *data)))
+            .collect();
+        // The complete message passes; the truncated one is interrupted by a
+        // stray frame of the first.
+        let findings = run(&frames, 0);
+        assert_eq!(findings.len(), 1);
+        assert!(matches!(
+            findings[0],
+            Finding::FastPacket {
+                pgn: PRODUCT_INFO,
+                error: reassembler::Error::Message(nmea_message::Error::SequenceMismatch { .. })
+                    | reassembler::Error::Message(nmea_message::Error::SequenceCountError { .. })
+            }
+        ));
+    }
+}
@@ -0,0 +1,253 @@
+//! CSV export of decoded messages.
+use crate::pgn::distance_log::DistanceLog;
+use crate::pgn::environment::{
+    Humidity, HumiditySource, Pressure, PressureSource, Temperature, TemperatureSource,
+};
+use crate::pgn::fluid_level::{FluidLevel, FluidType};
+use crate::pgn::gnss::{
+    CogSogRapidUpdate, DirectionReference, GnssMethod, GnssPositionData, PositionRapidUpdate,
+};
+use crate::pgn::heading::{HeadingReference, MagneticVariation, VariationSource, VesselHeading};
+use crate::pgn::info;
+use crate::pgn::speed::{Leeway, SpeedComponents};
+use crate::pgn::wind::{WindData, WindReference};
+use std::io::{self, Write};
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+/// A value written into a CSV cell. Not available values are left empty and
+/// lookups are written as their variant name.
+pub trait Cell {
+    fn cell(&self) -> String;
+}
+
+macro_rules! display_cell {
+    ($($ty:ty),*) => {
+        $(impl Cell for $ty {
+            fn cell(&self) -> String {
+                self.to_string()
+            }
+        })*
+    };
+}
+
+macro_rules! debug_cell {
+    ($($ty:ty),*) => {
+        $(impl Cell for $ty {
+            fn cell(&self) -> String {
+                std::format!("{:?}", self)
+            }
+        })*
+    };
+}
+
+display_cell!(u8, u16, u32, f32, f64);
+debug_cell!(
+    HeadingReference,
+    VariationSource,
+    FluidType,
+    DirectionReference,
+    GnssMethod,
+    WindReference,
+    TemperatureSource,
+    HumiditySource,
+    PressureSource
+);
+
+impl<T: Cell> Cell for Option<T> {
+    fn cell(&self) -> String {
+        self.as_ref().map_or_else(String::new, Cell::cell)
+    }
+}
+
+/// A decoded PGN that can be flattened into named columns.
+pub trait Fields {
+    /// Calls `f` with the name and value of each field, in declaration order.
+    fn fields(&self, f: &mut dyn FnMut(&'static str, String));
+}
+
+macro_rules! fields {
+    ($($ty:ty { $($field:ident),* })*) => {
+        $(impl Fields for $ty {
+            fn fields(&self, f: &mut dyn FnMut(&'static str, String)) {
+                $(f(stringify!($field), self.$field.cell());)*
+            }
+        })*
+    };
+}
+
+fields! {
+    VesselHeading { sid, heading, deviation, variation, reference }
+    MagneticVariation { sid, source, age_of_service, variation }
+    FluidLevel { instance, fluid_type, level, capacity }
+    Leeway { sid, leeway }
+    DistanceLog { date, time, log, trip_log }
+    PositionRapidUpdate { latitude, longitude }
+    CogSogRapidUpdate { sid, cog_reference, cog, sog }
+    GnssPositionData {
+        sid, date, time, latitude, longitude, altitude, gnss_type, method, integrity,
+        satellites, hdop, pdop, geoidal_separation
+    }
+    WindData { sid, speed, angle, reference }
+    Temperature { sid, instance, source, temperature, set_temperature }
+    Humidity { sid, instance, source, humidity, set_humidity }
+    Pressure { sid, instance, source, pressure }
+    SpeedComponents {
+        longitudinal_water, transverse_water, longitudinal_ground, transverse_ground,
+        stern_water, stern_ground
+    }
+}
+
+fn write_cell(out: &mut impl Write, value: &str) -> io::Result<()> {
+    if value.contains([',', '"', '\n']) {
+        write!(out, ",\"{}\"", value.replace('"', "\"\""))
+    } else {
+        write!(out, ",{}", value)
+    }
+}
+
+/// Writes decoded messages as CSV rows of `timestamp_ms,source,pgn`
+/// followed by field columns.
+///
+/// Only PGNs added with `select` are written, each with the chosen fields.
+/// The field columns are the union of the selected fields, in order of
+/// selection, so fields of the same name share a column and the cells of
+/// fields a PGN doesn't have are left empty. The header is written with the
+/// first row, after which the selection can't change.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::csv::CsvWriter;
+/// use nmea::pgn::wind::WindData;
+///
+/// let mut writer = CsvWriter::new(Vec::new());
+/// writer.select(WindData::PGN, &["speed", "angle"]);
+///
+/// let wind = WindData::from_payload(&[0x01, 0xF4, 0x01, 0x00, 0x00, 0x02, 0xFF, 0xFF]).unwrap();
+/// writer.write(1000, 0x23, WindData::PGN, &wind).unwrap();
+/// assert_eq!(
+///     String::from_utf8(writer.into_inner()).unwrap(),
+///     "timestamp_ms,source,pgn,speed,angle\n1000,35,130306,5,0\n"
+/// );
+/// ```
+pub struct CsvWriter<W: Write> {
+    out: W,
+    selections: Vec<(u32, Vec<&'static str>)>,
+    columns: Vec<&'static str>,
+    header_written: bool,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            selections: Vec::new(),
+            columns: Vec::new(),
+            header_written: false,
+        }
+    }
+
+    /// Writes messages of `pgn` with the given fields, or with the fields
+    /// listed in `pgn::info` if `fields` is empty. Returns `false`, changing
+    /// nothing, once the header has been written.
+    pub fn select(&mut self, pgn: u32, fields: &[&'static str]) -> bool {
+        if self.header_written {
+            return false;
+        }
+        let fields: Vec<&'static str> = if fields.is_empty() {
+            info::find(pgn)
+                .map(|info| info.fields.iter().map(|field| field.name).collect())
+                .unwrap_or_default()
+        } else {
+            fields.to_vec()
+        };
+        for field in &fields {
+            if !self.columns.contains(field) {
+                self.columns.push(field);
+            }
+        }
+        self.selections.retain(|(p, _)| *p != pgn);
+        self.selections.push((pgn, fields));
+        true
+    }
+
+    /// Writes a row for `decoded` if its PGN is selected. Returns whether a
+    /// row was written.
+    pub fn write(
+        &mut self,
+        timestamp_ms: u64,
+        source: u8,
+        pgn: u32,
+        decoded: &impl Fields,
+    ) -> io::Result<bool> {
+        let Some((_, selected)) = self.selections.iter().find(|(p, _)| *p == pgn) else {
+            return Ok(false);
+        };
+        let mut cells: Vec<(&'static str, String)> = Vec::new();
+        decoded.fields(&mut |name, value| {
+            if selected.contains(&name) {
+                cells.push((name, value));
+            }
+        });
+
+        if !self.header_written {
+            write!(self.out, "timestamp_ms,source,pgn")?;
+            for column in &self.columns {
+                write_cell(&mut self.out, column)?;
+            }
+            writeln!(self.out)?;
+            self.header_written = true;
+        }
+
+        write!(self.out, "{},{},{}", timestamp_ms, source, pgn)?;
+        for column in &self.columns {
+            let value = cells.iter().find(|(name, _)| name == column);
+            write_cell(&mut self.out, value.map_or("", |(_, value)| value.as_str()))?;
+        }
+        writeln!(self.out)?;
+        Ok(true)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows() {
+        let heading =
+            VesselHeading::from_payload(&[0x01, 0x10, 0x27, 0xFF, 0x7F, 0xFF, 0x7F, 0xFD]).unwrap();
+        let wind =
+            WindData::from_payload(&[0x02, 0xF4, 0x01, 0x00, 0x00, 0x02, 0xFF, 0xFF]).unwrap();
+
+        let mut writer = CsvWriter::new(Vec::new());
+        writer.select(VesselHeading::PGN, &["sid", "heading", "reference"]);
+        writer.select(WindData::PGN, &[]);
+        assert!(writer.write(10, 1, VesselHeading::PGN, &heading).unwrap());
+        assert!(writer.write(20, 2, WindData::PGN, &wind).unwrap());
+        assert!(!writer.write(30, 2, 130312, &wind).unwrap());
+        assert!(!writer.select(130312, &[]));
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "timestamp_ms,source,pgn,sid,heading,reference,speed,angle\n\
+             10,1,127250,1,1,Magnetic,,\n\
+             20,2,130306,2,,Apparent,5,0\n"
+        );
+    }
+
+    #[test]
+    fn test_cells() {
+        assert_eq!(None::<u8>.cell(), "");
+        assert_eq!(Some(1.5f32).cell(), "1.5");
+        assert_eq!(FluidType::Unknown(9).cell(), "Unknown(9)");
+        let mut out = Vec::new();
+        write_cell(&mut out, "a,\"b\"").unwrap();
+        assert_eq!(out, b",\"a,\"\"b\"\"\"");
+    }
+}
@@ -0,0 +1,155 @@
+//! Set and drift of the current, derived from the difference between the
+//! vessel's motion over ground and through the water.
+use crate::bridge::FrameSink;
+use crate::device::{self, Destination, N2kDevice};
+use crate::nav_state::{NavState, Timestamped};
+use crate::pgn::gnss::DirectionReference;
+use crate::pgn::normalize_angle;
+use crate::pgn::speed::{SetDrift, WaterSpeed};
+use crate::pgn::Error;
+
+/// The current from true heading and speed through water against course
+/// and speed over ground, all in radians and m/s. Returns the set, the
+/// direction the current flows towards, and the drift. Leeway is taken as
+/// part of the current.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::current::set_and_drift;
+///
+/// // Heading north at 5 knots through the water, making 4 knots over ground.
+/// let (set, drift) = set_and_drift(0.0, 2.572, 0.0, 2.058);
+/// assert!((set.to_degrees() - 180.0).abs() < 1e-3);
+/// assert!((drift - 0.514).abs() < 1e-3);
+/// ```
+pub fn set_and_drift(heading: f32, stw: f32, cog: f32, sog: f32) -> (f32, f32) {
+    let north = sog * libm::cosf(cog) - stw * libm::cosf(heading);
+    let east = sog * libm::sinf(cog) - stw * libm::sinf(heading);
+    (
+        normalize_angle(libm::atan2f(east, north)),
+        libm::hypotf(north, east),
+    )
+}
+
+/// Tracks heading, COG/SOG and speed through water to publish set and drift
+/// (PGN 129291) as a gateway does.
+///
+/// Inputs older than `max_age_ms` are not used.
+pub struct Current {
+    nav: NavState,
+    stw: Option<Timestamped<f32>>,
+    max_age_ms: u64,
+    sid: u8,
+}
+
+impl Current {
+    pub const fn new(max_age_ms: u64) -> Self {
+        Self {
+            nav: NavState::new(),
+            stw: None,
+            max_age_ms,
+            sid: 0,
+        }
+    }
+
+    /// Updates the inputs from a decoded payload. Returns `false` if `pgn` is
+    /// not one of 128259 or those taken by `NavState::ingest`.
+    pub fn ingest(&mut self, pgn: u32, payload: &[u8], now_ms: u64) -> Result<bool, Error> {
+        if pgn != WaterSpeed::PGN {
+            return self.nav.ingest(pgn, payload, now_ms);
+        }
+        if let Some(water) = WaterSpeed::from_payload(payload)?.water {
+            self.stw = Some(Timestamped {
+                value: water,
+                timestamp_ms: now_ms,
+            });
+        }
+        Ok(true)
+    }
+
+    /// The current at `now_ms`, if all inputs are fresh.
+    pub fn set_drift(&self, now_ms: u64) -> Option<SetDrift> {
+        let heading = self.nav.heading()?.fresh(now_ms, self.max_age_ms)?;
+        let velocity = self.nav.velocity()?.fresh(now_ms, self.max_age_ms)?;
+        let stw = self.stw?.fresh(now_ms, self.max_age_ms)?;
+        let (set, drift) = set_and_drift(heading, stw, velocity.cog, velocity.sog);
+        Some(SetDrift {
+            sid: Some(self.sid),
+            set_reference: DirectionReference::True,
+            set: Some(set),
+            drift: Some(drift),
+        })
+    }
+
+    /// Broadcasts the current at `now_ms` through `device`. Returns whether
+    /// there was one to send.
+    pub fn publish<K: FrameSink, const N: usize>(
+        &mut self,
+        device: &mut N2kDevice<K, N>,
+        now_ms: u64,
+    ) -> Result<bool, device::Error> {
+        let Some(set_drift) = self.set_drift(now_ms) else {
+            return Ok(false);
+        };
+        device.send(&set_drift, Destination::Broadcast, None)?;
+        self.sid = (self.sid + 1) % 0xFD;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge;
+    use crate::frame_queue::RxFrame;
+    use std::vec::Vec;
+
+    struct Bus(Vec<RxFrame>);
+
+    impl FrameSink for Bus {
+        fn transmit(&mut self, frame: &RxFrame) -> Result<(), bridge::Error> {
+            self.0.push(*frame);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_set_and_drift() {
+        // Crabbing: heading north-east, tracking east at the same speed.
+        let (set, drift) = set_and_drift(
+            core::f32::consts::FRAC_PI_4,
+            2.0,
+            core::f32::consts::FRAC_PI_2,
+            2.0,
+        );
+        assert!((set.to_degrees() - 157.5).abs() < 1e-3);
+        assert!((drift - 1.531).abs() < 1e-3);
+        assert_eq!(set_and_drift(1.0, 3.0, 1.0, 3.0).1, 0.0);
+    }
+
+    #[test]
+    fn test_publish() {
+        let mut current = Current::new(2000);
+        let mut device: N2kDevice<Bus, 4> = N2kDevice::new(Bus(Vec::new()), 0x23);
+        // True heading 0, COG 0 at 2 m/s.
+        let heading: [u8; 8] = [0x00, 0x00, 0x00, 0xFF, 0x7F, 0xFF, 0x7F, 0xFC];
+        let cog_sog: [u8; 8] = [0x00, 0xFC, 0x00, 0x00, 0xC8, 0x00, 0xFF, 0xFF];
+        assert!(current.ingest(127250, &heading, 0).unwrap());
+        assert!(current.ingest(129026, &cog_sog, 0).unwrap());
+        assert!(!current.publish(&mut device, 0).unwrap());
+
+        // 2.5 m/s through the water.
+        let speed: [u8; 8] = [0x00, 0xFA, 0x00, 0xFF, 0xFF, 0x00, 0xFF, 0xFF];
+        assert!(current.ingest(WaterSpeed::PGN, &speed, 500).unwrap());
+        assert!(current.publish(&mut device, 1000).unwrap());
+        let frame = device.sink().0[0];
+        assert_eq!(frame.id.pgn(), SetDrift::PGN);
+        let set_drift = SetDrift::from_payload(&frame.data).unwrap();
+        assert!((set_drift.set.unwrap().to_degrees() - 180.0).abs() < 0.01);
+        assert!((set_drift.drift.unwrap() - 0.5).abs() < 1e-6);
+
+        // Heading and COG/SOG have gone stale.
+        assert!(!current.publish(&mut device, 2001).unwrap());
+    }
+}
@@ -0,0 +1,114 @@
+//! Per-source queues of completed messages.
+use fixed_queue::{LinearMap, VecDeque};
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Source table is full")]
+    FullTable,
+    #[error("Queue for source {0} is full")]
+    FullQueue(u8),
+}
+
+/// Splits a stream of completed messages, of any type `T`, by source
+/// address so that each device's traffic can be processed on its own, e.g.
+/// to compare two GPS receivers.
+///
+/// Up to `S` sources are tracked, each with a queue of `Q` messages.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::demux::Demux;
+///
+/// let mut demux: Demux<(u32, u8), 2, 4> = Demux::new();
+/// demux.push(0x01, (129025, 1)).unwrap();
+/// demux.push(0x02, (129025, 2)).unwrap();
+/// demux.push(0x01, (129025, 3)).unwrap();
+///
+/// assert!(demux.drain(0x01).eq([(129025, 1), (129025, 3)]));
+/// assert_eq!(demux.pop(0x02), Some((129025, 2)));
+/// ```
+pub struct Demux<T, const S: usize, const Q: usize> {
+    queues: LinearMap<u8, VecDeque<T, Q>, S>,
+}
+
+impl<T, const S: usize, const Q: usize> Default for Demux<T, S, Q> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const S: usize, const Q: usize> Demux<T, S, Q> {
+    pub const fn new() -> Self {
+        Self {
+            queues: LinearMap::new(),
+        }
+    }
+
+    /// Queues a message completed by `source`. A full queue leaves the
+    /// message to the caller to drop or retry after draining.
+    pub fn push(&mut self, source: u8, message: T) -> Result<(), Error> {
+        if self.queues.get(&source).is_none()
+            && self.queues.insert(source, VecDeque::new()).is_err()
+        {
+            return Err(Error::FullTable);
+        }
+        self.queues
+            .get_mut(&source)
+            .unwrap()
+            .push_back(message)
+            .map_err(|_| Error::FullQueue(source))
+    }
+
+    pub fn pop(&mut self, source: u8) -> Option<T> {
+        self.queues.get_mut(&source)?.pop_front()
+    }
+
+    /// Takes the queued messages of `source`, oldest first.
+    pub fn drain(&mut self, source: u8) -> impl Iterator<Item = T> + '_ {
+        let mut queue = self.queues.get_mut(&source);
+        core::iter::from_fn(move || queue.as_mut()?.pop_front())
+    }
+
+    /// Number of messages queued for `source`.
+    pub fn len(&self, source: u8) -> usize {
+        self.queues.get(&source).map_or(0, |queue| queue.len())
+    }
+
+    /// The sources seen so far, in order of their first message.
+    pub fn sources(&self) -> impl Iterator<Item = u8> + '_ {
+        self.queues.iter().map(|(source, _)| *source)
+    }
+
+    /// Stops tracking `source`, dropping its queued messages and freeing its
+    /// slot.
+    pub fn remove(&mut self, source: u8) {
+        self.queues.remove(&source);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push() {
+        let mut demux: Demux<u32, 2, 2> = Demux::new();
+        demux.push(0x10, 1).unwrap();
+        demux.push(0x20, 2).unwrap();
+        demux.push(0x10, 3).unwrap();
+        assert_eq!(demux.push(0x10, 4), Err(Error::FullQueue(0x10)));
+        assert_eq!(demux.push(0x30, 5), Err(Error::FullTable));
+        assert!(demux.sources().eq([0x10, 0x20]));
+        assert_eq!(demux.len(0x10), 2);
+
+        assert!(demux.drain(0x10).eq([1, 3]));
+        assert_eq!(demux.len(0x10), 0);
+        assert_eq!(demux.drain(0x30).next(), None);
+
+        demux.remove(0x20);
+        assert_eq!(demux.pop(0x20), None);
+        demux.push(0x30, 5).unwrap();
+    }
+}
@@ -0,0 +1,176 @@
+//! Smoothed depth below the keel with a shallow water alarm.
+use crate::pgn::depth::WaterDepth;
+use crate::pgn::Error;
+use fixed_queue::VecDeque;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepthAlarm {
+    /// The filtered depth fell below the alarm threshold.
+    Shallow { depth: f32 },
+    /// The filtered depth recovered past the threshold and hysteresis.
+    Cleared { depth: f32 },
+}
+
+/// Median of the last `N` depth soundings, which rejects the spikes and
+/// dropouts of a transducer in aerated water or over weed.
+///
+/// Depths are reported below the keel: the sounding below the transducer
+/// less `keel_offset`, the transducer's height above the keel. Sounding
+/// offsets in the PGN are ignored, since they are often unset or refer to
+/// the waterline.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::depth::{DepthAlarm, DepthFilter};
+///
+/// let mut filter: DepthFilter<5> = DepthFilter::new()
+///     .with_keel_offset(0.5)
+///     .with_shallow_alarm(2.0, 0.5);
+/// for sounding in [3.0, 3.1, 0.0, 3.0] {
+///     assert_eq!(filter.push(sounding), None);
+/// }
+/// // The 0 m dropout doesn't sound the alarm.
+/// assert_eq!(filter.depth(), Some(2.5));
+/// let alarms: Vec<_> = [2.2, 2.1, 2.0].iter().filter_map(|d| filter.push(*d)).collect();
+/// assert!(matches!(alarms[..], [DepthAlarm::Shallow { .. }]));
+/// assert!(filter.is_shallow());
+/// ```
+pub struct DepthFilter<const N: usize> {
+    soundings: VecDeque<f32, N>,
+    keel_offset: f32,
+    /// Alarm threshold and hysteresis.
+    shallow: Option<(f32, f32)>,
+    alarm: bool,
+}
+
+impl<const N: usize> Default for DepthFilter<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> DepthFilter<N> {
+    pub const fn new() -> Self {
+        Self {
+            soundings: VecDeque::new(),
+            keel_offset: 0.0,
+            shallow: None,
+            alarm: false,
+        }
+    }
+
+    /// Sets the transducer's height above the keel in metres.
+    pub const fn with_keel_offset(mut self, keel_offset: f32) -> Self {
+        self.keel_offset = keel_offset;
+        self
+    }
+
+    /// Alarms when the depth below the keel falls below `threshold` metres,
+    /// until it recovers to `threshold + hysteresis`.
+    pub const fn with_shallow_alarm(mut self, threshold: f32, hysteresis: f32) -> Self {
+        self.shallow = Some((threshold, hysteresis));
+        self
+    }
+
+    /// Adds a Water Depth payload, see `push`.
+    pub fn ingest(&mut self, payload: &[u8]) -> Result<Option<DepthAlarm>, Error> {
+        Ok(WaterDepth::from_payload(payload)?
+            .depth
+            .and_then(|depth| self.push(depth)))
+    }
+
+    /// Adds a sounding below the transducer in metres. Returns an alarm
+    /// raised or cleared by it.
+    pub fn push(&mut self, depth: f32) -> Option<DepthAlarm> {
+        if self.soundings.is_full() {
+            self.soundings.pop_front();
+        }
+        let _ = self.soundings.push_back(depth);
+        let (threshold, hysteresis) = self.shallow?;
+        let depth = self.depth()?;
+        if !self.alarm && depth < threshold {
+            self.alarm = true;
+            Some(DepthAlarm::Shallow { depth })
+        } else if self.alarm && depth >= threshold + hysteresis {
+            self.alarm = false;
+            Some(DepthAlarm::Cleared { depth })
+        } else {
+            None
+        }
+    }
+
+    /// Median depth below the keel in metres over the recent soundings.
+    pub fn depth(&self) -> Option<f32> {
+        let len = self.soundings.len();
+        if len == 0 {
+            return None;
+        }
+        let mut sorted = [0.0; N];
+        let (a, b) = self.soundings.as_slices();
+        sorted[..a.len()].copy_from_slice(a);
+        sorted[a.len()..len].copy_from_slice(b);
+        let sorted = &mut sorted[..len];
+        sorted.sort_unstable_by(f32::total_cmp);
+        let median = if len % 2 == 1 {
+            sorted[len / 2]
+        } else {
+            (sorted[len / 2 - 1] + sorted[len / 2]) / 2.0
+        };
+        Some(median - self.keel_offset)
+    }
+
+    pub fn is_shallow(&self) -> bool {
+        self.alarm
+    }
+
+    /// Forgets the soundings, e.g. after moving the boat.
+    pub fn clear(&mut self) {
+        self.soundings.clear();
+        self.alarm = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(depth: f32) -> [u8; 8] {
+        WaterDepth {
+            sid: None,
+            depth: Some(depth),
+            offset: None,
+            range: None,
+        }
+        .to_payload()
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut filter: DepthFilter<3> = DepthFilter::new().with_shallow_alarm(5.0, 1.0);
+        assert_eq!(filter.depth(), None);
+        assert_eq!(filter.ingest(&payload(6.0)), Ok(None));
+        assert_eq!(filter.ingest(&payload(4.0)), Ok(None));
+        // Median of 6, 4, 4.
+        assert_eq!(
+            filter.ingest(&payload(4.0)),
+            Ok(Some(DepthAlarm::Shallow { depth: 4.0 }))
+        );
+        assert!(filter.is_shallow());
+        // Oldest soundings drop out: median of 4, 5.5, 5.5 within hysteresis.
+        assert_eq!(filter.ingest(&payload(5.5)), Ok(None));
+        assert_eq!(filter.ingest(&payload(5.5)), Ok(None));
+        assert_eq!(filter.depth(), Some(5.5));
+        assert_eq!(filter.push(30.0), None);
+        assert_eq!(filter.push(30.0), Some(DepthAlarm::Cleared { depth: 30.0 }));
+        // Not available depths are skipped.
+        let mut unknown = payload(0.0);
+        unknown[1..5].copy_from_slice(&[0xFF; 4]);
+        assert_eq!(filter.ingest(&unknown), Ok(None));
+        assert_eq!(filter.ingest(&unknown[..4]), Err(Error::InvalidLength));
+
+        filter.clear();
+        assert_eq!(filter.depth(), None);
+        assert!(!filter.is_shallow());
+    }
+}
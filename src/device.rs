@@ -0,0 +1,502 @@
+//! Transmitting messages as a node on the bus.
+use crate::bridge::FrameSink;
+use crate::can_id::{self, default_priority, AddressFilter, CanId, BROADCAST};
+use crate::frame_queue::RxFrame;
+use crate::nmea_message::{self, Message, MAX_NMEA_PACKET_SIZE};
+use crate::pgn::Encode;
+use fixed_queue::LinearMap;
+use thiserror_no_std::Error;
+
+/// ISO Address Claim, carrying the 64-bit NAME of the claiming device.
+pub const ADDRESS_CLAIM_PGN: u32 = 60928;
+/// Source address of a device that failed to claim one.
+pub const NULL_ADDRESS: u8 = 254;
+/// ISO Transport Protocol, Data Transfer.
+pub const TP_DT_PGN: u32 = 60160;
+/// ISO Transport Protocol, Connection Management.
+pub const TP_CM_PGN: u32 = 60416;
+/// Longest payload the ISO Transport Protocol carries.
+pub const MAX_TP_SIZE: usize = 1785;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("No address claimed for NAME {0:#018x}")]
+    UnknownName(u64),
+    #[error("Frame could not be transmitted")]
+    Transmit,
+    #[error("Payload is too long")]
+    TooLong,
+    #[error("Only broadcast transfers are supported")]
+    AddressedTransfer,
+    #[error(transparent)]
+    Id(#[from] can_id::Error),
+    #[error(transparent)]
+    Message(#[from] nmea_message::Error),
+}
+
+/// Where a message is sent. Ignored for broadcast (PDU2) PGNs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Destination {
+    Broadcast,
+    Address(u8),
+    /// The device with this NAME, at whatever address it has claimed.
+    Name(u64),
+}
+
+/// Addresses claimed by up to `N` devices, by NAME, as seen in ISO Address
+/// Claims. Claims of further devices are ignored.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::can_id::CanId;
+/// use nmea::device::{DeviceRegistry, ADDRESS_CLAIM_PGN};
+///
+/// let mut registry: DeviceRegistry<8> = DeviceRegistry::new();
+/// let name: u64 = 0xC0_32_0A_00_00_E0_12_34;
+/// let claim = CanId::new(6, ADDRESS_CLAIM_PGN, 0x23, 255).unwrap();
+/// registry.observe(claim, &name.to_le_bytes());
+/// assert_eq!(registry.address_of(name), Some(0x23));
+/// assert_eq!(registry.name_of(0x23), Some(name));
+/// ```
+pub struct DeviceRegistry<const N: usize> {
+    addresses: LinearMap<u64, u8, N>,
+}
+
+impl<const N: usize> Default for DeviceRegistry<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> DeviceRegistry<N> {
+    pub const fn new() -> Self {
+        Self {
+            addresses: LinearMap::new(),
+        }
+    }
+
+    /// Updates the registry from an Address Claim; other frames are
+    /// ignored. A device claiming an address takes it over from any other
+    /// and gives up its previous one.
+    pub fn observe(&mut self, id: CanId, data: &[u8; 8]) {
+        if id.pgn() != ADDRESS_CLAIM_PGN {
+            return;
+        }
+        let name = u64::from_le_bytes(*data);
+        let address = id.source();
+        if let Some(other) = self.name_of(address) {
+            self.addresses.remove(&other);
+        }
+        self.addresses.remove(&name);
+        if address != NULL_ADDRESS {
+            let _ = self.addresses.insert(name, address);
+        }
+    }
+
+    pub fn address_of(&self, name: u64) -> Option<u8> {
+        self.addresses.get(&name).copied()
+    }
+
+    pub fn name_of(&self, address: u8) -> Option<u64> {
+        self.addresses
+            .iter()
+            .find(|(_, a)| *a == address)
+            .map(|(name, _)| *name)
+    }
+
+    /// The address to send to for `destination`.
+    pub fn resolve(&self, destination: Destination) -> Result<u8, Error> {
+        match destination {
+            Destination::Broadcast => Ok(BROADCAST),
+            Destination::Address(address) => Ok(address),
+            Destination::Name(name) => self.address_of(name).ok_or(Error::UnknownName(name)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+}
+
+/// A node at `address` transmitting through a `FrameSink`, tracking the
+/// addresses of up to `N` other devices so that messages can be sent to a
+/// device by NAME across address changes.
+///
+/// Every received frame is passed to `observe`, which tells whether it is
+/// for this node: a broadcast or addressed to it, or any frame in
+/// promiscuous mode.
+pub struct N2kDevice<K: FrameSink, const N: usize> {
+    sink: K,
+    address: u8,
+    promiscuous: bool,
+    registry: DeviceRegistry<N>,
+    sequence_counter: u8,
+}
+
+impl<K: FrameSink, const N: usize> N2kDevice<K, N> {
+    pub const fn new(sink: K, address: u8) -> Self {
+        Self {
+            sink,
+            address,
+            promiscuous: false,
+            registry: DeviceRegistry::new(),
+            sequence_counter: 0,
+        }
+    }
+
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    pub fn set_address(&mut self, address: u8) {
+        self.address = address;
+    }
+
+    /// Processes frames addressed to other nodes too, as an analyzer would.
+    pub fn set_promiscuous(&mut self, promiscuous: bool) {
+        self.promiscuous = promiscuous;
+    }
+
+    /// The filter `observe` applies, following address changes. Add it to
+    /// a `Pipeline` to drop frames for other nodes before reassembly.
+    pub fn filter(&self) -> AddressFilter {
+        AddressFilter {
+            address: self.address,
+            promiscuous: self.promiscuous,
+        }
+    }
+
+    pub fn registry(&self) -> &DeviceRegistry<N> {
+        &self.registry
+    }
+
+    pub fn sink(&self) -> &K {
+        &self.sink
+    }
+
+    /// Tracks address claims, which are broadcast, and returns whether the
+    /// frame passes `filter`.
+    pub fn observe(&mut self, frame: &RxFrame) -> bool {
+        self.registry.observe(frame.id, &frame.data);
+        self.filter().accepts(frame.id)
+    }
+
+    fn transmit(&mut self, id: CanId, data: [u8; 8]) -> Result<(), Error> {
+        self.sink
+            .transmit(&RxFrame { id, data })
+            .map_err(|_| Error::Transmit)
+    }
+
+    fn id(&self, priority: u8, pgn: u32, destination: Destination) -> Result<CanId, Error> {
+        let destination = match destination {
+            // Broadcast PGNs don't carry a destination.
+            Destination::Name(_) if CanId::new(priority, pgn, 0, 0)?.is_broadcast() => BROADCAST,
+            destination => self.registry.resolve(destination)?,
+        };
+        Ok(CanId::new(priority, pgn, self.address, destination)?)
+    }
+
+    /// Sends a single-frame PGN. Payloads shorter than 8 bytes are padded
+    /// with `0xFF`; longer ones fail with `Error::TooLong`.
+    pub fn send_single(
+        &mut self,
+        priority: u8,
+        pgn: u32,
+        destination: Destination,
+        payload: &[u8],
+    ) -> Result<(), Error> {
+        if payload.len() > 8 {
+            return Err(Error::TooLong);
+        }
+        let id = self.id(priority, pgn, destination)?;
+        let mut data = [0xFF; 8];
+        data[..payload.len()].copy_from_slice(payload);
+        self.transmit(id, data)
+    }
+
+    /// Sends `payload` as a Fast-Packet message. Returns the number of
+    /// frames transmitted.
+    pub fn send_fast_packet(
+        &mut self,
+        priority: u8,
+        pgn: u32,
+        destination: Destination,
+        payload: &[u8],
+    ) -> Result<usize, Error> {
+        let id = self.id(priority, pgn, destination)?;
+        let mut msg = Message::from_payload(payload, self.sequence_counter)?;
+        self.sequence_counter = (self.sequence_counter + 1) & 0x07;
+        let mut transmitted = 0;
+        while let Some(frame) = msg.pop_frame() {
+            self.transmit(id, frame.bytes)?;
+            transmitted += 1;
+        }
+        Ok(transmitted)
+    }
+
+    /// Sends `payload` with the ISO Transport Protocol as a Broadcast
+    /// Announce Message. Returns the number of frames transmitted.
+    ///
+    /// The frames are handed to the sink all at once; the sink has to space
+    /// the data transfers 50 to 200 ms apart. Addressed transfers, which
+    /// need a handshake with the receiver, aren't supported.
+    pub fn send_iso_tp(
+        &mut self,
+        priority: u8,
+        pgn: u32,
+        destination: Destination,
+        payload: &[u8],
+    ) -> Result<usize, Error> {
+        if self.id(priority, pgn, destination)?.destination() != BROADCAST {
+            return Err(Error::AddressedTransfer);
+        }
+        if payload.len() > MAX_TP_SIZE {
+            return Err(Error::TooLong);
+        }
+        let size = (payload.len() as u16).to_le_bytes();
+        let packets = payload.len().div_ceil(7) as u8;
+        let pgn_bytes = pgn.to_le_bytes();
+        let announce = [
+            0x20,
+            size[0],
+            size[1],
+            packets,
+            0xFF,
+            pgn_bytes[0],
+            pgn_bytes[1],
+            pgn_bytes[2],
+        ];
+        let tp_priority = default_priority(TP_CM_PGN);
+        self.transmit(
+            CanId::new(tp_priority, TP_CM_PGN, self.address, BROADCAST)?,
+            announce,
+        )?;
+        let id = CanId::new(tp_priority, TP_DT_PGN, self.address, BROADCAST)?;
+        for (i, chunk) in payload.chunks(7).enumerate() {
+            let mut data = [0xFF; 8];
+            data[0] = i as u8 + 1;
+            data[1..=chunk.len()].copy_from_slice(chunk);
+            self.transmit(id, data)?;
+        }
+        Ok(1 + packets as usize)
+    }
+
+    /// Encodes `message` and sends it as a single frame, a Fast-Packet
+    /// message or, if it doesn't fit in either, with the ISO Transport
+    /// Protocol. `priority` defaults to the PGN's, see
+    /// `PgnMessage::DEFAULT_PRIORITY`.
+    /// Returns the number of frames transmitted.
+    ///
+    /// ## Example:
+    ///
+    /// ```
+    /// use nmea::bridge::{self, FrameSink};
+    /// use nmea::device::{Destination, N2kDevice};
+    /// use nmea::frame_queue::RxFrame;
+    /// use nmea::pgn::wind::{WindData, WindReference};
+    ///
+    /// struct Bus(Vec<RxFrame>);
+    ///
+    /// impl FrameSink for Bus {
+    ///     fn transmit(&mut self, frame: &RxFrame) -> Result<(), bridge::Error> {
+    ///         self.0.push(*frame);
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mut device: N2kDevice<Bus, 4> = N2kDevice::new(Bus(Vec::new()), 0x23);
+    /// let wind = WindData {
+    ///     sid: None,
+    ///     speed: Some(5.0),
+    ///     angle: Some(0.0),
+    ///     reference: WindReference::Apparent,
+    /// };
+    /// assert_eq!(device.send(&wind, Destination::Broadcast, None), Ok(1));
+    /// assert_eq!(u32::from(device.sink().0[0].id), 0x09FD0223);
+    /// ```
+    pub fn send<T: Encode>(
+        &mut self,
+        message: &T,
+        destination: Destination,
+        priority: Option<u8>,
+    ) -> Result<usize, Error> {
+        let mut buf = [0xFF; MAX_NMEA_PACKET_SIZE];
+        let len = message.encode(&mut buf);
+        let payload = &buf[..len];
+        let priority = priority.unwrap_or(T::DEFAULT_PRIORITY);
+        if T::IS_FAST_PACKET {
+            self.send_fast_packet(priority, T::PGN, destination, payload)
+        } else if len <= 8 {
+            self.send_single(priority, T::PGN, destination, payload)
+                .map(|()| 1)
+        } else {
+            self.send_iso_tp(priority, T::PGN, destination, payload)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge;
+    use fixed_queue::VecDeque;
+
+    struct Bus(VecDeque<RxFrame, 8>);
+
+    impl FrameSink for Bus {
+        fn transmit(&mut self, frame: &RxFrame) -> Result<(), bridge::Error> {
+            self.0
+                .push_back(*frame)
+                .map_err(|_| bridge::Error::Transmit)
+        }
+    }
+
+    fn claim(address: u8, name: u64) -> RxFrame {
+        RxFrame {
+            id: CanId::new(6, ADDRESS_CLAIM_PGN, address, BROADCAST).unwrap(),
+            data: name.to_le_bytes(),
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry: DeviceRegistry<2> = DeviceRegistry::new();
+        let claim_a = claim(0x10, 1);
+        registry.observe(claim_a.id, &claim_a.data);
+        // NAME 1 moves to 0x11, NAME 2 takes 0x10.
+        let moved = claim(0x11, 1);
+        registry.observe(moved.id, &moved.data);
+        let claim_b = claim(0x10, 2);
+        registry.observe(claim_b.id, &claim_b.data);
+        assert_eq!(registry.address_of(1), Some(0x11));
+        assert_eq!(registry.name_of(0x10), Some(2));
+        // NAME 2 loses its address.
+        let lost = claim(NULL_ADDRESS, 2);
+        registry.observe(lost.id, &lost.data);
+        assert_eq!(registry.len(), 1);
+        assert_eq!(
+            registry.resolve(Destination::Name(2)),
+            Err(Error::UnknownName(2))
+        );
+    }
+
+    #[test]
+    fn test_observe() {
+        let mut device: N2kDevice<Bus, 4> = N2kDevice::new(Bus(VecDeque::new()), 0x20);
+        let request = |destination| RxFrame {
+            id: CanId::new(6, 59904, 0x42, destination).unwrap(),
+            data: [0x14, 0xF0, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
+        };
+        assert!(device.observe(&claim(0x42, 7)));
+        assert!(device.observe(&request(0x20)));
+        assert!(!device.observe(&request(0x21)));
+        device.set_address(0x21);
+        assert!(device.observe(&request(0x21)));
+        assert_eq!(device.filter(), AddressFilter::new(0x21));
+        device.set_promiscuous(true);
+        assert!(device.observe(&request(0x20)));
+        assert_eq!(device.registry().address_of(7), Some(0x42));
+    }
+
+    #[test]
+    fn test_send_to_name() {
+        let mut device: N2kDevice<Bus, 4> = N2kDevice::new(Bus(VecDeque::new()), 0x20);
+        let payload = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        // 126208 is addressed; 129029 is broadcast whatever the destination.
+        assert_eq!(
+            device.send_fast_packet(3, 126208, Destination::Name(7), &payload),
+            Err(Error::UnknownName(7))
+        );
+        assert_eq!(
+            device.send_fast_packet(3, 129029, Destination::Name(7), &payload),
+            Ok(2)
+        );
+
+        device.observe(&claim(0x42, 7));
+        assert_eq!(
+            device.send_fast_packet(3, 126208, Destination::Name(7), &payload),
+            Ok(2)
+        );
+        device.observe(&claim(0x43, 7));
+        device
+            .send_single(6, 59904, Destination::Name(7), &[0x00, 0xEE, 0x00])
+            .unwrap();
+        assert_eq!(
+            device.send_single(6, 59904, Destination::Name(7), &payload),
+            Err(Error::TooLong)
+        );
+
+        let frames = &device.sink().0;
+        assert_eq!(frames[1].data, [0x01, 7, 8, 9, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert!(frames[1].id.is_broadcast());
+        assert_eq!(frames[2].id, CanId::new(3, 126208, 0x20, 0x42).unwrap());
+        assert_eq!(frames[2].data[0], 0x20);
+        assert_eq!(frames[4].id, CanId::new(6, 59904, 0x20, 0x43).unwrap());
+        assert_eq!(
+            frames[4].data,
+            [0x00, 0xEE, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+    }
+
+    #[test]
+    fn test_send() {
+        use crate::pgn::iso_request::IsoRequest;
+        use crate::pgn::speed::SpeedComponents;
+
+        let mut device: N2kDevice<Bus, 4> = N2kDevice::new(Bus(VecDeque::new()), 0x20);
+        device.observe(&claim(0x42, 7));
+        let request = IsoRequest {
+            destination: 0,
+            pgn: 126996,
+        };
+        assert_eq!(device.send(&request, Destination::Name(7), None), Ok(1));
+        let speed = SpeedComponents::from_payload(&[0xFF; 12]).unwrap();
+        assert_eq!(device.send(&speed, Destination::Broadcast, Some(3)), Ok(2));
+
+        let frames = &device.sink().0;
+        assert_eq!(frames[0].id, CanId::new(6, 59904, 0x20, 0x42).unwrap());
+        assert_eq!(frames[0].data[..3], [0x14, 0xF0, 0x01]);
+        assert_eq!(
+            frames[1].id,
+            CanId::new(3, 130578, 0x20, BROADCAST).unwrap()
+        );
+        assert_eq!(frames[1].data[..2], [0x00, 12]);
+    }
+
+    #[test]
+    fn test_iso_tp() {
+        let mut device: N2kDevice<Bus, 4> = N2kDevice::new(Bus(VecDeque::new()), 0x20);
+        let payload = [0xAA; 15];
+        assert_eq!(
+            device.send_iso_tp(6, 59904, Destination::Address(0x42), &payload),
+            Err(Error::AddressedTransfer)
+        );
+        assert_eq!(
+            device.send_iso_tp(6, 126464, Destination::Broadcast, &payload),
+            Ok(4)
+        );
+        let frames = &device.sink().0;
+        assert_eq!(
+            frames[0].id,
+            CanId::new(7, TP_CM_PGN, 0x20, BROADCAST).unwrap()
+        );
+        assert_eq!(frames[0].data, [0x20, 15, 0, 3, 0xFF, 0x00, 0xEE, 0x01]);
+        assert_eq!(
+            frames[1].id,
+            CanId::new(7, TP_DT_PGN, 0x20, BROADCAST).unwrap()
+        );
+        assert_eq!(
+            frames[1].data,
+            [1, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA]
+        );
+        assert_eq!(
+            frames[3].data,
+            [3, 0xAA, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+    }
+}
@@ -0,0 +1,72 @@
+//! Field-level differences between two decoded messages of the same PGN,
+//! for change-only logging and alerting.
+use crate::csv::Fields;
+use std::string::String;
+use std::vec::Vec;
+
+/// A field whose value differs, formatted as in `csv::Cell`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+/// The fields of `after` whose values differ from `before`, in declaration
+/// order.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::diff::{diff, Change};
+/// use nmea::pgn::wind::WindData;
+///
+/// let before = WindData::from_payload(&[0x01, 0xF4, 0x01, 0x00, 0x00, 0x02, 0xFF, 0xFF]).unwrap();
+/// let after = WindData::from_payload(&[0x02, 0x58, 0x02, 0x00, 0x00, 0x02, 0xFF, 0xFF]).unwrap();
+/// let mut changes = diff(&before, &after);
+/// // Sequence ids change with every message.
+/// changes.retain(|change| change.field != "sid");
+/// assert_eq!(
+///     changes,
+///     [Change { field: "speed", before: "5".into(), after: "6".into() }]
+/// );
+/// ```
+pub fn diff<T: Fields>(before: &T, after: &T) -> Vec<Change> {
+    let mut values: Vec<String> = Vec::new();
+    before.fields(&mut |_, value| values.push(value));
+    let mut values = values.into_iter();
+    let mut changes = Vec::new();
+    after.fields(&mut |field, value| {
+        let before = values.next().unwrap_or_default();
+        if before != value {
+            changes.push(Change {
+                field,
+                before,
+                after: value,
+            });
+        }
+    });
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::heading::VesselHeading;
+
+    #[test]
+    fn test_diff() {
+        let heading =
+            VesselHeading::from_payload(&[0x01, 0x10, 0x27, 0xFF, 0x7F, 0xFF, 0x7F, 0xFD]).unwrap();
+        assert_eq!(diff(&heading, &heading), []);
+
+        // Variation becomes available.
+        let changed =
+            VesselHeading::from_payload(&[0x01, 0x10, 0x27, 0xFF, 0x7F, 0x10, 0x00, 0xFD]).unwrap();
+        let changes = diff(&heading, &changed);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "variation");
+        assert_eq!(changes[0].before, "");
+        assert!(!changes[0].after.is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+//! Engine hours and fuel used, totalled from the engine parameter PGNs for
+//! maintenance tracking.
+use crate::pgn;
+use crate::pgn::engine::{EngineDynamic, EngineRapid};
+use fixed_queue::LinearMap;
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Too many engines")]
+    FullTable,
+    #[error(transparent)]
+    Pgn(#[from] pgn::Error),
+}
+
+/// Running totals for one engine.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct EngineTotals {
+    /// Time spent with the engine turning, in milliseconds.
+    pub running_ms: u64,
+    /// Fuel used in litres.
+    pub fuel_used: f64,
+}
+
+impl EngineTotals {
+    pub fn hours(&self) -> f64 {
+        self.running_ms as f64 / 3_600_000.0
+    }
+}
+
+struct Engine {
+    totals: EngineTotals,
+    /// Time and speed of the last rapid update.
+    speed: Option<(u64, f32)>,
+    /// Time and rate of the last fuel rate.
+    fuel_rate: Option<(u64, f32)>,
+}
+
+/// Integrates engine speed (PGN 127488) into running time and fuel rate
+/// (PGN 127489) into fuel used, per engine instance.
+///
+/// The engine counts as running between two speed updates if it was turning
+/// at the first. Fuel rate is integrated with the trapezoid rule. A gap of
+/// more than `max_gap_ms` between updates, such as the bus being switched
+/// off, is not counted.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::engine_hours::Totalizer;
+/// use nmea::pgn::engine::EngineRapid;
+///
+/// let mut totalizer: Totalizer<2> = Totalizer::new(5000);
+/// let rapid = EngineRapid { instance: 0, speed: Some(1800.0), boost: None, tilt_trim: None };
+/// for second in 0..=60 {
+///     totalizer
+///         .ingest(EngineRapid::PGN, &rapid.to_payload(), second * 1000)
+///         .unwrap();
+/// }
+/// assert_eq!(totalizer.totals(0).unwrap().running_ms, 60_000);
+/// ```
+pub struct Totalizer<const N: usize> {
+    engines: LinearMap<u8, Engine, N>,
+    max_gap_ms: u64,
+}
+
+impl<const N: usize> Totalizer<N> {
+    pub const fn new(max_gap_ms: u64) -> Self {
+        Self {
+            engines: LinearMap::new(),
+            max_gap_ms,
+        }
+    }
+
+    /// Updates the totals from a payload. Returns `false` if `pgn` is not an
+    /// engine parameter PGN.
+    pub fn ingest(&mut self, pgn: u32, payload: &[u8], now_ms: u64) -> Result<bool, Error> {
+        match pgn {
+            EngineRapid::PGN => {
+                let rapid = EngineRapid::from_payload(payload)?;
+                let max_gap_ms = self.max_gap_ms;
+                let engine = self.engine(rapid.instance)?;
+                if let Some((last_ms, speed)) = engine.speed {
+                    let elapsed = now_ms.saturating_sub(last_ms);
+                    if speed > 0.0 && elapsed <= max_gap_ms {
+                        engine.totals.running_ms += elapsed;
+                    }
+                }
+                engine.speed = rapid.speed.map(|speed| (now_ms, speed));
+            }
+            EngineDynamic::PGN => {
+                let dynamic = EngineDynamic::from_payload(payload)?;
+                let max_gap_ms = self.max_gap_ms;
+                let engine = self.engine(dynamic.instance)?;
+                if let (Some((last_ms, last_rate)), Some(rate)) =
+                    (engine.fuel_rate, dynamic.fuel_rate)
+                {
+                    let elapsed = now_ms.saturating_sub(last_ms);
+                    if elapsed <= max_gap_ms {
+                        let hours = elapsed as f64 / 3_600_000.0;
+                        engine.totals.fuel_used += (last_rate + rate) as f64 / 2.0 * hours;
+                    }
+                }
+                engine.fuel_rate = dynamic.fuel_rate.map(|rate| (now_ms, rate));
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn engine(&mut self, instance: u8) -> Result<&mut Engine, Error> {
+        if self.engines.get(&instance).is_none() {
+            let engine = Engine {
+                totals: EngineTotals::default(),
+                speed: None,
+                fuel_rate: None,
+            };
+            self.engines
+                .insert(instance, engine)
+                .map_err(|_| Error::FullTable)?;
+        }
+        Ok(self.engines.get_mut(&instance).unwrap())
+    }
+
+    pub fn totals(&self, instance: u8) -> Option<EngineTotals> {
+        self.engines.get(&instance).map(|engine| engine.totals)
+    }
+
+    /// Sets the totals of an engine, e.g. restored from storage or read off
+    /// the engine's own hour meter.
+    pub fn set_totals(&mut self, instance: u8, totals: EngineTotals) -> Result<(), Error> {
+        self.engine(instance)?.totals = totals;
+        Ok(())
+    }
+
+    /// Zeroes the totals of an engine, e.g. after a service.
+    pub fn reset(&mut self, instance: u8) {
+        if let Some(engine) = self.engines.get_mut(&instance) {
+            engine.totals = EngineTotals::default();
+        }
+    }
+
+    /// Iterates the engines seen and their totals.
+    pub fn iter(&self) -> impl Iterator<Item = (u8, EngineTotals)> + '_ {
+        self.engines
+            .iter()
+            .map(|(instance, engine)| (*instance, engine.totals))
+    }
+}
+
+/// One engine's totals in the form used for persistence.
+#[cfg(any(test, feature = "serde"))]
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TotalsEntry {
+    pub instance: u8,
+    #[serde(flatten)]
+    pub totals: EngineTotals,
+}
+
+#[cfg(any(test, feature = "serde"))]
+impl<const N: usize> Totalizer<N> {
+    /// Returns the entries to serialize.
+    pub fn to_entries(&self) -> alloc::vec::Vec<TotalsEntry> {
+        self.iter()
+            .map(|(instance, totals)| TotalsEntry { instance, totals })
+            .collect()
+    }
+
+    /// Builds a totalizer from deserialized entries.
+    pub fn from_entries(entries: &[TotalsEntry], max_gap_ms: u64) -> Result<Self, Error> {
+        let mut totalizer = Self::new(max_gap_ms);
+        for entry in entries {
+            totalizer.set_totals(entry.instance, entry.totals)?;
+        }
+        Ok(totalizer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rapid(instance: u8, speed: f32) -> [u8; 8] {
+        EngineRapid {
+            instance,
+            speed: Some(speed),
+            boost: None,
+            tilt_trim: None,
+        }
+        .to_payload()
+    }
+
+    fn dynamic(instance: u8, fuel_rate: f32) -> [u8; 26] {
+        EngineDynamic {
+            instance,
+            oil_pressure: None,
+            oil_temperature: None,
+            temperature: None,
+            alternator_potential: None,
+            fuel_rate: Some(fuel_rate),
+            total_hours: None,
+            coolant_pressure: None,
+            fuel_pressure: None,
+        }
+        .to_payload()
+    }
+
+    #[test]
+    fn test_totalizer() {
+        let mut totalizer: Totalizer<1> = Totalizer::new(2000);
+        assert!(totalizer
+            .ingest(EngineRapid::PGN, &rapid(0, 0.0), 0)
+            .unwrap());
+        // Stopped until the first update at speed.
+        assert!(totalizer
+            .ingest(EngineRapid::PGN, &rapid(0, 800.0), 1000)
+            .unwrap());
+        totalizer
+            .ingest(EngineRapid::PGN, &rapid(0, 800.0), 2000)
+            .unwrap();
+        // Bus off for a minute.
+        totalizer
+            .ingest(EngineRapid::PGN, &rapid(0, 800.0), 62_000)
+            .unwrap();
+        totalizer
+            .ingest(EngineRapid::PGN, &rapid(0, 0.0), 63_000)
+            .unwrap();
+        totalizer
+            .ingest(EngineRapid::PGN, &rapid(0, 0.0), 64_000)
+            .unwrap();
+        assert_eq!(totalizer.totals(0).unwrap().running_ms, 2000);
+
+        // 10 then 20 L/h, 1.8 s apart.
+        totalizer
+            .ingest(EngineDynamic::PGN, &dynamic(0, 10.0), 0)
+            .unwrap();
+        totalizer
+            .ingest(EngineDynamic::PGN, &dynamic(0, 20.0), 1800)
+            .unwrap();
+        let fuel_used = totalizer.totals(0).unwrap().fuel_used;
+        assert!((fuel_used - 15.0 * 0.0005).abs() < 1e-9);
+
+        assert!(!totalizer.ingest(129025, &[0; 8], 0).unwrap());
+        assert_eq!(
+            totalizer.ingest(EngineRapid::PGN, &rapid(1, 800.0), 0),
+            Err(Error::FullTable)
+        );
+        assert_eq!(
+            totalizer.ingest(EngineRapid::PGN, &[0; 2], 0),
+            Err(Error::Pgn(pgn::Error::InvalidLength))
+        );
+
+        totalizer.reset(0);
+        assert_eq!(totalizer.totals(0), Some(EngineTotals::default()));
+    }
+
+    #[test]
+    fn test_entries() {
+        let mut totalizer: Totalizer<2> = Totalizer::new(2000);
+        let totals = EngineTotals {
+            running_ms: 3_600_000 * 250,
+            fuel_used: 1200.5,
+        };
+        totalizer.set_totals(1, totals).unwrap();
+        let json = serde_json::to_string(&totalizer.to_entries()).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"instance":1,"running_ms":900000000,"fuel_used":1200.5}]"#
+        );
+
+        let entries: alloc::vec::Vec<TotalsEntry> = serde_json::from_str(&json).unwrap();
+        let restored: Totalizer<2> = Totalizer::from_entries(&entries, 2000).unwrap();
+        assert_eq!(restored.totals(1).unwrap().hours(), 250.0);
+    }
+}
@@ -0,0 +1,453 @@
+//! ISO 11783-3 Extended Transport Protocol, for addressed transfers longer
+//! than the 1785 bytes of the Transport Protocol.
+//!
+//! The sender announces the transfer with a Request to Send; the receiver
+//! asks for windows of packets with Clear to Send, and the sender answers
+//! each with a Data Packet Offset, giving the 24-bit number of packets
+//! before the window, followed by the packets. The receiver acknowledges the
+//! complete message with End of Message.
+//!
+//! Both sides are driven by the frames they receive and transmit through a
+//! `FrameSink`. Timeouts are left to the caller, who abandons a session with
+//! `abort`.
+use crate::bridge::FrameSink;
+use crate::can_id::{self, default_priority, CanId};
+use crate::frame_queue::RxFrame;
+use thiserror_no_std::Error;
+
+/// Extended Transport Protocol, Connection Management.
+pub const ETP_CM_PGN: u32 = 51456;
+/// Extended Transport Protocol, Data Transfer.
+pub const ETP_DT_PGN: u32 = 50944;
+/// Longest payload the Extended Transport Protocol carries.
+pub const MAX_ETP_SIZE: usize = 0xFF_FFFF * 7;
+
+const RTS: u8 = 0x14;
+const CTS: u8 = 0x15;
+const DPO: u8 = 0x16;
+const EOMA: u8 = 0x17;
+const ABORT: u8 = 0xFF;
+
+/// Abort reason: already in a session.
+pub const ABORT_BUSY: u8 = 1;
+/// Abort reason: not enough resources, e.g. a message too long to buffer.
+pub const ABORT_RESOURCES: u8 = 2;
+/// Abort reason: timeout.
+pub const ABORT_TIMEOUT: u8 = 3;
+/// Abort reason: unexpected data packet offset or sequence number.
+pub const ABORT_SEQUENCE: u8 = 8;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Payload is too long")]
+    TooLong,
+    #[error("Transfer aborted, reason {0}")]
+    Aborted(u8),
+    #[error("Frame could not be transmitted")]
+    Transmit,
+    #[error(transparent)]
+    Id(#[from] can_id::Error),
+}
+
+fn u24(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0])
+}
+
+fn cm(control: u8, body: [u8; 4], pgn: u32) -> [u8; 8] {
+    let pgn = pgn.to_le_bytes();
+    [
+        control, body[0], body[1], body[2], body[3], pgn[0], pgn[1], pgn[2],
+    ]
+}
+
+fn send(sink: &mut impl FrameSink, id: CanId, data: [u8; 8]) -> Result<(), Error> {
+    sink.transmit(&RxFrame { id, data })
+        .map_err(|_| Error::Transmit)
+}
+
+/// Sending side of a transfer of `payload` on `pgn` from `source` to
+/// `destination`.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::bridge::{self, FrameSink};
+/// use nmea::etp::{EtpReceiver, EtpSender};
+/// use nmea::frame_queue::RxFrame;
+///
+/// struct Bus(Vec<RxFrame>);
+///
+/// impl FrameSink for Bus {
+///     fn transmit(&mut self, frame: &RxFrame) -> Result<(), bridge::Error> {
+///         self.0.push(*frame);
+///         Ok(())
+///     }
+/// }
+///
+/// let payload: Vec<u8> = (0..4000).map(|i| i as u8).collect();
+/// let mut sender = EtpSender::new(0xEF00, 0x10, 0x20, &payload).unwrap();
+/// let mut receiver: EtpReceiver<8192> = EtpReceiver::new(0x20, 64);
+/// let (mut to_receiver, mut to_sender) = (Bus(Vec::new()), Bus(Vec::new()));
+/// sender.start(&mut to_receiver).unwrap();
+/// let mut received = None;
+/// while !sender.is_done() {
+///     for frame in to_receiver.0.drain(..) {
+///         if let Some(message) = receiver.handle(&frame, &mut to_sender).unwrap() {
+///             received = Some(message.to_vec());
+///         }
+///     }
+///     for frame in to_sender.0.drain(..) {
+///         sender.handle(&frame, &mut to_receiver).unwrap();
+///     }
+/// }
+/// assert_eq!(received, Some(payload));
+/// ```
+pub struct EtpSender<'a> {
+    pgn: u32,
+    source: u8,
+    destination: u8,
+    payload: &'a [u8],
+    done: bool,
+}
+
+impl<'a> EtpSender<'a> {
+    pub fn new(pgn: u32, source: u8, destination: u8, payload: &'a [u8]) -> Result<Self, Error> {
+        if payload.len() > MAX_ETP_SIZE {
+            return Err(Error::TooLong);
+        }
+        Ok(Self {
+            pgn,
+            source,
+            destination,
+            payload,
+            done: false,
+        })
+    }
+
+    fn id(&self, pgn: u32) -> Result<CanId, Error> {
+        Ok(CanId::new(
+            default_priority(pgn),
+            pgn,
+            self.source,
+            self.destination,
+        )?)
+    }
+
+    /// Sends the Request to Send.
+    pub fn start(&mut self, sink: &mut impl FrameSink) -> Result<(), Error> {
+        let size = (self.payload.len() as u32).to_le_bytes();
+        send(sink, self.id(ETP_CM_PGN)?, cm(RTS, size, self.pgn))
+    }
+
+    /// Handles a frame from the receiver, sending the packets it asks for.
+    /// Frames of other sessions are ignored.
+    pub fn handle(&mut self, frame: &RxFrame, sink: &mut impl FrameSink) -> Result<(), Error> {
+        let id = frame.id;
+        let data = &frame.data;
+        if id.pgn() != ETP_CM_PGN
+            || id.source() != self.destination
+            || id.destination() != self.source
+            || u24(&data[5..]) != self.pgn
+        {
+            return Ok(());
+        }
+        match data[0] {
+            CTS => {
+                // A window of 0 packets holds the transfer.
+                let count = data[1] as u32;
+                let next = u24(&data[2..5]);
+                let packets = self.payload.len().div_ceil(7) as u32;
+                if count == 0 || next == 0 || next > packets {
+                    return Ok(());
+                }
+                let count = count.min(packets - next + 1);
+                let offset = (next - 1).to_le_bytes();
+                let body = [count as u8, offset[0], offset[1], offset[2]];
+                send(sink, self.id(ETP_CM_PGN)?, cm(DPO, body, self.pgn))?;
+                let dt = self.id(ETP_DT_PGN)?;
+                let start = (next - 1) as usize * 7;
+                let end = self.payload.len().min(start + count as usize * 7);
+                for (i, chunk) in self.payload[start..end].chunks(7).enumerate() {
+                    let mut data = [0xFF; 8];
+                    data[0] = i as u8 + 1;
+                    data[1..=chunk.len()].copy_from_slice(chunk);
+                    send(sink, dt, data)?;
+                }
+                Ok(())
+            }
+            EOMA => {
+                self.done = true;
+                Ok(())
+            }
+            ABORT => Err(Error::Aborted(data[1])),
+            _ => Ok(()),
+        }
+    }
+
+    /// Abandons the transfer, telling the receiver why.
+    pub fn abort(&mut self, reason: u8, sink: &mut impl FrameSink) -> Result<(), Error> {
+        send(
+            sink,
+            self.id(ETP_CM_PGN)?,
+            cm(ABORT, [reason, 0xFF, 0xFF, 0xFF], self.pgn),
+        )
+    }
+
+    /// Whether the receiver acknowledged the whole message.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Session {
+    source: u8,
+    pgn: u32,
+    size: u32,
+    /// Packets received in order so far.
+    received: u32,
+    /// Offset and length of the current window.
+    window: Option<(u32, u8)>,
+}
+
+/// Receiving side of transfers to `address`, buffering messages of up to
+/// `SIZE` bytes. One session is open at a time; requests from other senders
+/// meanwhile are aborted. Windows are `window` packets long.
+pub struct EtpReceiver<const SIZE: usize> {
+    address: u8,
+    window: u8,
+    session: Option<Session>,
+    buf: [u8; SIZE],
+}
+
+impl<const SIZE: usize> EtpReceiver<SIZE> {
+    pub const fn new(address: u8, window: u8) -> Self {
+        Self {
+            address,
+            window,
+            session: None,
+            buf: [0; SIZE],
+        }
+    }
+
+    fn reply(
+        &self,
+        sink: &mut impl FrameSink,
+        to: u8,
+        control: u8,
+        body: [u8; 4],
+        pgn: u32,
+    ) -> Result<(), Error> {
+        let id = CanId::new(default_priority(ETP_CM_PGN), ETP_CM_PGN, self.address, to)?;
+        send(sink, id, cm(control, body, pgn))
+    }
+
+    fn clear_to_send(&mut self, sink: &mut impl FrameSink) -> Result<(), Error> {
+        let Some(session) = self.session.as_mut() else {
+            return Ok(());
+        };
+        let next = (session.received + 1).to_le_bytes();
+        let body = [self.window.max(1), next[0], next[1], next[2]];
+        session.window = None;
+        let (source, pgn) = (session.source, session.pgn);
+        self.reply(sink, source, CTS, body, pgn)
+    }
+
+    /// Handles a frame addressed to us, answering the sender through `sink`.
+    /// Returns the message once all of it has arrived; it stays valid until
+    /// the next call.
+    pub fn handle(
+        &mut self,
+        frame: &RxFrame,
+        sink: &mut impl FrameSink,
+    ) -> Result<Option<&[u8]>, Error> {
+        let id = frame.id;
+        let data = &frame.data;
+        if id.destination() != self.address {
+            return Ok(None);
+        }
+        let source = id.source();
+        let current = self.session.filter(|s| s.source == source);
+        match (id.pgn(), data[0]) {
+            (ETP_CM_PGN, RTS) => {
+                let size = u32::from_le_bytes([data[1], data[2], data[3], data[4]]);
+                let pgn = u24(&data[5..]);
+                if self.session.is_some() && current.is_none() {
+                    let body = [ABORT_BUSY, 0xFF, 0xFF, 0xFF];
+                    self.reply(sink, source, ABORT, body, pgn)?;
+                    return Ok(None);
+                }
+                if size as usize > SIZE {
+                    self.session = None;
+                    let body = [ABORT_RESOURCES, 0xFF, 0xFF, 0xFF];
+                    self.reply(sink, source, ABORT, body, pgn)?;
+                    return Ok(None);
+                }
+                self.session = Some(Session {
+                    source,
+                    pgn,
+                    size,
+                    received: 0,
+                    window: None,
+                });
+                self.clear_to_send(sink)?;
+            }
+            (ETP_CM_PGN, DPO) => {
+                let Some(session) = self.session.as_mut().filter(|_| current.is_some()) else {
+                    return Ok(None);
+                };
+                let offset = u24(&data[2..5]);
+                if offset != session.received {
+                    return self.fail(sink, ABORT_SEQUENCE);
+                }
+                session.window = Some((offset, data[1]));
+            }
+            (ETP_CM_PGN, ABORT) if current.is_some() => {
+                self.session = None;
+                return Err(Error::Aborted(data[1]));
+            }
+            (ETP_DT_PGN, sequence) => {
+                let Some(session) = self.session.as_mut().filter(|_| current.is_some()) else {
+                    return Ok(None);
+                };
+                let Some((offset, count)) = session.window else {
+                    return Ok(None);
+                };
+                if sequence as u32 != session.received - offset + 1 || sequence > count {
+                    return self.fail(sink, ABORT_SEQUENCE);
+                }
+                let start = session.received as usize * 7;
+                let end = (session.size as usize).min(start + 7);
+                self.buf[start..end].copy_from_slice(&data[1..1 + end - start]);
+                session.received += 1;
+                let (size, pgn) = (session.size, session.pgn);
+                if end == size as usize {
+                    self.session = None;
+                    self.reply(sink, source, EOMA, size.to_le_bytes(), pgn)?;
+                    return Ok(Some(&self.buf[..end]));
+                }
+                if sequence == count {
+                    self.clear_to_send(sink)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn fail(&mut self, sink: &mut impl FrameSink, reason: u8) -> Result<Option<&[u8]>, Error> {
+        self.abort(reason, sink)?;
+        Err(Error::Aborted(reason))
+    }
+
+    /// Abandons the session in progress, telling the sender why.
+    pub fn abort(&mut self, reason: u8, sink: &mut impl FrameSink) -> Result<(), Error> {
+        if let Some(session) = self.session.take() {
+            let body = [reason, 0xFF, 0xFF, 0xFF];
+            self.reply(sink, session.source, ABORT, body, session.pgn)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_receiving(&self) -> bool {
+        self.session.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge;
+    use std::vec::Vec;
+
+    struct Bus(Vec<RxFrame>);
+
+    impl FrameSink for Bus {
+        fn transmit(&mut self, frame: &RxFrame) -> Result<(), bridge::Error> {
+            self.0.push(*frame);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_transfer() {
+        let payload: Vec<u8> = (0..2000).map(|i| i as u8).collect();
+        let mut sender = EtpSender::new(0xEF00, 0x10, 0x20, &payload).unwrap();
+        let mut receiver: EtpReceiver<2048> = EtpReceiver::new(0x20, 255);
+        let mut to_sender = Bus(Vec::new());
+        let mut to_receiver = Bus(Vec::new());
+
+        sender.start(&mut to_receiver).unwrap();
+        assert_eq!(
+            to_receiver.0[0].data,
+            [RTS, 0xD0, 0x07, 0, 0, 0x00, 0xEF, 0x00]
+        );
+        receiver.handle(&to_receiver.0[0], &mut to_sender).unwrap();
+        assert_eq!(to_sender.0[0].data, [CTS, 255, 1, 0, 0, 0x00, 0xEF, 0x00]);
+        assert_eq!(to_sender.0[0].id.destination(), 0x10);
+
+        sender.handle(&to_sender.0[0], &mut to_receiver).unwrap();
+        // RTS, DPO and 255 packets.
+        assert_eq!(to_receiver.0.len(), 257);
+        assert_eq!(to_receiver.0[1].data, [DPO, 255, 0, 0, 0, 0x00, 0xEF, 0x00]);
+        for frame in &to_receiver.0[1..] {
+            assert_eq!(receiver.handle(frame, &mut to_sender).unwrap(), None);
+        }
+        // The second window starts at packet 256.
+        assert_eq!(to_sender.0[1].data, [CTS, 255, 0, 1, 0, 0x00, 0xEF, 0x00]);
+        to_receiver.0.clear();
+        sender.handle(&to_sender.0[1], &mut to_receiver).unwrap();
+        // DPO and the remaining 31 packets.
+        assert_eq!(to_receiver.0.len(), 32);
+        assert_eq!(
+            to_receiver.0[0].data,
+            [DPO, 31, 255, 0, 0, 0x00, 0xEF, 0x00]
+        );
+        let mut received = None;
+        for frame in &to_receiver.0 {
+            if let Some(message) = receiver.handle(frame, &mut to_sender).unwrap() {
+                received = Some(message.to_vec());
+            }
+        }
+        assert_eq!(received.as_deref(), Some(&payload[..]));
+        assert_eq!(
+            to_sender.0[2].data,
+            [EOMA, 0xD0, 0x07, 0, 0, 0x00, 0xEF, 0x00]
+        );
+        sender.handle(&to_sender.0[2], &mut to_receiver).unwrap();
+        assert!(sender.is_done());
+        assert!(!receiver.is_receiving());
+    }
+
+    #[test]
+    fn test_abort() {
+        let payload = [0; 100];
+        let mut sender = EtpSender::new(0xEF00, 0x10, 0x20, &payload).unwrap();
+        let mut receiver: EtpReceiver<64> = EtpReceiver::new(0x20, 16);
+        let mut to_sender = Bus(Vec::new());
+        let mut to_receiver = Bus(Vec::new());
+        sender.start(&mut to_receiver).unwrap();
+        receiver.handle(&to_receiver.0[0], &mut to_sender).unwrap();
+        assert_eq!(
+            sender.handle(&to_sender.0[0], &mut to_receiver),
+            Err(Error::Aborted(ABORT_RESOURCES))
+        );
+
+        // A packet out of sequence.
+        let mut receiver: EtpReceiver<128> = EtpReceiver::new(0x20, 16);
+        to_sender.0.clear();
+        receiver.handle(&to_receiver.0[0], &mut to_sender).unwrap();
+        to_receiver.0.clear();
+        sender.handle(&to_sender.0[0], &mut to_receiver).unwrap();
+        receiver.handle(&to_receiver.0[0], &mut to_sender).unwrap();
+        assert_eq!(
+            receiver.handle(&to_receiver.0[2], &mut to_sender),
+            Err(Error::Aborted(ABORT_SEQUENCE))
+        );
+        assert!(!receiver.is_receiving());
+        assert_eq!(
+            to_sender.0.last().unwrap().data[..2],
+            [ABORT, ABORT_SEQUENCE]
+        );
+    }
+}
@@ -0,0 +1,549 @@
+//! Firmware transfer to a device, with messages sent as tunnel blobs (see
+//! `nmea::tunnel`).
+//!
+//! The `Updater` sends a `Start` announcing the image size and CRC-32,
+//! `Segment`s of the image, then `Verify` and `Commit`. The `Target` answers
+//! every message with a `Status` carrying the number of bytes it has
+//! received, which is where the `Updater` continues. A transfer interrupted
+//! by either side is resumed by starting it again with the same image.
+//! Messages that went unanswered are simply sent again.
+use crate::integrity::{crc32, crc32_update};
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Message is empty")]
+    Empty,
+    #[error("Unknown message type {0:#04x}")]
+    UnknownMessage(u8),
+    #[error("Message is truncated")]
+    Truncated,
+    #[error("Buffer is too small")]
+    BufferTooSmall,
+    #[error("Storage failed")]
+    Storage,
+}
+
+/// Result of a message reported by the `Target`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    CrcMismatch,
+    TooLarge,
+    Storage,
+    NotStarted,
+    NotVerified,
+    Unknown(u8),
+}
+
+impl From<u8> for Status {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Ok,
+            1 => Self::CrcMismatch,
+            2 => Self::TooLarge,
+            3 => Self::Storage,
+            4 => Self::NotStarted,
+            5 => Self::NotVerified,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<Status> for u8 {
+    fn from(value: Status) -> Self {
+        match value {
+            Status::Ok => 0,
+            Status::CrcMismatch => 1,
+            Status::TooLarge => 2,
+            Status::Storage => 3,
+            Status::NotStarted => 4,
+            Status::NotVerified => 5,
+            Status::Unknown(v) => v,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Message<'a> {
+    Start {
+        size: u32,
+        crc: u32,
+    },
+    Segment {
+        offset: u32,
+        data: &'a [u8],
+    },
+    Verify,
+    Commit,
+    /// Reply of the `Target`, with the number of bytes received.
+    Status {
+        status: Status,
+        offset: u32,
+    },
+}
+
+const START: u8 = 0x01;
+const SEGMENT: u8 = 0x02;
+const VERIFY: u8 = 0x03;
+const COMMIT: u8 = 0x04;
+const STATUS: u8 = 0x80;
+
+fn read_u32(bytes: &[u8], at: usize) -> Result<u32, Error> {
+    let bytes = bytes.get(at..at + 4).ok_or(Error::Truncated)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+impl<'a> Message<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, Error> {
+        match *bytes.first().ok_or(Error::Empty)? {
+            START => Ok(Self::Start {
+                size: read_u32(bytes, 1)?,
+                crc: read_u32(bytes, 5)?,
+            }),
+            SEGMENT => Ok(Self::Segment {
+                offset: read_u32(bytes, 1)?,
+                data: &bytes[5..],
+            }),
+            VERIFY => Ok(Self::Verify),
+            COMMIT => Ok(Self::Commit),
+            STATUS => Ok(Self::Status {
+                status: (*bytes.get(1).ok_or(Error::Truncated)?).into(),
+                offset: read_u32(bytes, 2)?,
+            }),
+            other => Err(Error::UnknownMessage(other)),
+        }
+    }
+
+    /// Writes the message into `buf`. Returns the length written.
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut header = [0; 9];
+        let (len, data): (usize, &[u8]) = match *self {
+            Self::Start { size, crc } => {
+                header[0] = START;
+                header[1..5].copy_from_slice(&size.to_le_bytes());
+                header[5..9].copy_from_slice(&crc.to_le_bytes());
+                (9, &[])
+            }
+            Self::Segment { offset, data } => {
+                header[0] = SEGMENT;
+                header[1..5].copy_from_slice(&offset.to_le_bytes());
+                (5, data)
+            }
+            Self::Verify => {
+                header[0] = VERIFY;
+                (1, &[])
+            }
+            Self::Commit => {
+                header[0] = COMMIT;
+                (1, &[])
+            }
+            Self::Status { status, offset } => {
+                header[0] = STATUS;
+                header[1] = status.into();
+                header[2..6].copy_from_slice(&offset.to_le_bytes());
+                (6, &[])
+            }
+        };
+        let total = len + data.len();
+        if buf.len() < total {
+            return Err(Error::BufferTooSmall);
+        }
+        buf[..len].copy_from_slice(&header[..len]);
+        buf[len..total].copy_from_slice(data);
+        Ok(total)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Step {
+    Start,
+    Transfer,
+    Verify,
+    Commit,
+    Done,
+    Failed(Status),
+}
+
+/// Sending side of a transfer of `image` in segments of `segment_len` bytes.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::firmware::{Error, Message, Storage, Target, Updater};
+///
+/// struct Flash(Vec<u8>);
+///
+/// impl Storage for Flash {
+///     fn capacity(&self) -> u32 {
+///         4096
+///     }
+///
+///     fn write(&mut self, offset: u32, data: &[u8]) -> Result<(), Error> {
+///         self.0.truncate(offset as usize);
+///         self.0.extend_from_slice(data);
+///         Ok(())
+///     }
+///
+///     fn commit(&mut self, _size: u32) -> Result<(), Error> {
+///         Ok(())
+///     }
+/// }
+///
+/// let image: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+/// let mut updater = Updater::new(&image, 200);
+/// let mut target = Target::new(Flash(Vec::new()));
+/// let (mut request, mut reply) = ([0; 256], [0; 16]);
+/// while let Some(message) = updater.poll() {
+///     // Each side sends its messages with `Tunnel::send`.
+///     let len = message.encode(&mut request).unwrap();
+///     let answer = target.handle(&Message::parse(&request[..len]).unwrap()).unwrap();
+///     let len = answer.encode(&mut reply).unwrap();
+///     updater.handle(&Message::parse(&reply[..len]).unwrap());
+/// }
+/// assert!(updater.is_done());
+/// assert_eq!(target.storage().0, image);
+/// ```
+pub struct Updater<'a> {
+    image: &'a [u8],
+    crc: u32,
+    segment_len: usize,
+    offset: usize,
+    step: Step,
+}
+
+impl<'a> Updater<'a> {
+    pub fn new(image: &'a [u8], segment_len: usize) -> Self {
+        Self {
+            image,
+            crc: crc32(image),
+            segment_len: segment_len.max(1),
+            offset: 0,
+            step: Step::Start,
+        }
+    }
+
+    /// The message to send next, until the transfer is done or has failed.
+    /// It is sent again if no `Status` arrives in time.
+    pub fn poll(&self) -> Option<Message<'a>> {
+        match self.step {
+            Step::Start => Some(Message::Start {
+                size: self.image.len() as u32,
+                crc: self.crc,
+            }),
+            Step::Transfer => {
+                let end = self.image.len().min(self.offset + self.segment_len);
+                Some(Message::Segment {
+                    offset: self.offset as u32,
+                    data: &self.image[self.offset..end],
+                })
+            }
+            Step::Verify => Some(Message::Verify),
+            Step::Commit => Some(Message::Commit),
+            Step::Done | Step::Failed(_) => None,
+        }
+    }
+
+    /// Handles a message from the `Target`. Messages other than `Status`
+    /// are ignored.
+    pub fn handle(&mut self, message: &Message) {
+        let Message::Status { status, offset } = *message else {
+            return;
+        };
+        if matches!(self.step, Step::Done | Step::Failed(_)) {
+            return;
+        }
+        if status != Status::Ok {
+            self.step = Step::Failed(status);
+            return;
+        }
+        self.offset = (offset as usize).min(self.image.len());
+        self.step = match self.step {
+            Step::Start | Step::Transfer | Step::Verify if self.offset < self.image.len() => {
+                Step::Transfer
+            }
+            Step::Start | Step::Transfer => Step::Verify,
+            Step::Verify => Step::Commit,
+            _ => Step::Done,
+        };
+    }
+
+    /// Bytes of the image the `Target` has confirmed.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.step == Step::Done
+    }
+
+    /// The `Status` the transfer failed with, if it did.
+    pub fn failure(&self) -> Option<Status> {
+        match self.step {
+            Step::Failed(status) => Some(status),
+            _ => None,
+        }
+    }
+}
+
+/// Where a `Target` writes the image, e.g. the inactive flash bank.
+pub trait Storage {
+    /// Largest image that fits.
+    fn capacity(&self) -> u32;
+
+    /// Writes `data` at `offset`. Segments are written in order; a
+    /// transfer started over writes from offset 0 again.
+    fn write(&mut self, offset: u32, data: &[u8]) -> Result<(), Error>;
+
+    /// Activates the verified image of `size` bytes.
+    fn commit(&mut self, size: u32) -> Result<(), Error>;
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Image {
+    size: u32,
+    crc: u32,
+    received: u32,
+    received_crc: u32,
+    verified: bool,
+}
+
+/// Receiving side of a transfer, writing the image to `Storage`.
+pub struct Target<S: Storage> {
+    storage: S,
+    image: Option<Image>,
+}
+
+impl<S: Storage> Target<S> {
+    pub const fn new(storage: S) -> Self {
+        Self {
+            storage,
+            image: None,
+        }
+    }
+
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Bytes of the current image received so far.
+    pub fn received(&self) -> Option<u32> {
+        self.image.map(|image| image.received)
+    }
+
+    /// Handles a message from the `Updater`. Returns the `Status` to send
+    /// back, or `None` for a `Status`.
+    pub fn handle(&mut self, message: &Message) -> Option<Message<'static>> {
+        let (status, offset) = match *message {
+            Message::Start { size, crc } => self.start(size, crc),
+            Message::Segment { offset, data } => self.segment(offset, data),
+            Message::Verify => self.verify(),
+            Message::Commit => self.commit(),
+            Message::Status { .. } => return None,
+        };
+        Some(Message::Status { status, offset })
+    }
+
+    fn start(&mut self, size: u32, crc: u32) -> (Status, u32) {
+        if let Some(image) = self.image.filter(|i| (i.size, i.crc) == (size, crc)) {
+            return (Status::Ok, image.received);
+        }
+        self.image = None;
+        if size > self.storage.capacity() {
+            return (Status::TooLarge, 0);
+        }
+        self.image = Some(Image {
+            size,
+            crc,
+            received: 0,
+            received_crc: 0,
+            verified: false,
+        });
+        (Status::Ok, 0)
+    }
+
+    fn segment(&mut self, offset: u32, data: &[u8]) -> (Status, u32) {
+        let Some(image) = self.image.as_mut() else {
+            return (Status::NotStarted, 0);
+        };
+        // A repeated or skipped segment; the `Updater` continues from here.
+        if offset != image.received {
+            return (Status::Ok, image.received);
+        }
+        if image.size - image.received < data.len() as u32 {
+            return (Status::TooLarge, image.received);
+        }
+        if self.storage.write(offset, data).is_err() {
+            return (Status::Storage, image.received);
+        }
+        image.received += data.len() as u32;
+        image.received_crc = crc32_update(image.received_crc, data);
+        (Status::Ok, image.received)
+    }
+
+    fn verify(&mut self) -> (Status, u32) {
+        let Some(image) = self.image.as_mut() else {
+            return (Status::NotStarted, 0);
+        };
+        if image.received < image.size {
+            return (Status::Ok, image.received);
+        }
+        if image.received_crc != image.crc {
+            // Start over on the next transfer.
+            self.image = None;
+            return (Status::CrcMismatch, 0);
+        }
+        image.verified = true;
+        (Status::Ok, image.received)
+    }
+
+    fn commit(&mut self) -> (Status, u32) {
+        let Some(image) = self.image else {
+            return (Status::NotStarted, 0);
+        };
+        if !image.verified {
+            return (Status::NotVerified, image.received);
+        }
+        if self.storage.commit(image.size).is_err() {
+            return (Status::Storage, image.received);
+        }
+        self.image = None;
+        (Status::Ok, image.size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    struct Flash {
+        data: Vec<u8>,
+        committed: Option<u32>,
+    }
+
+    impl Storage for Flash {
+        fn capacity(&self) -> u32 {
+            1024
+        }
+
+        fn write(&mut self, offset: u32, data: &[u8]) -> Result<(), Error> {
+            self.data.truncate(offset as usize);
+            self.data.extend_from_slice(data);
+            Ok(())
+        }
+
+        fn commit(&mut self, size: u32) -> Result<(), Error> {
+            self.committed = Some(size);
+            Ok(())
+        }
+    }
+
+    fn target() -> Target<Flash> {
+        Target::new(Flash {
+            data: Vec::new(),
+            committed: None,
+        })
+    }
+
+    /// Passes `count` messages from `updater` to `target` and back.
+    fn exchange(updater: &mut Updater, target: &mut Target<Flash>, count: usize) {
+        for _ in 0..count {
+            let Some(message) = updater.poll() else {
+                return;
+            };
+            let mut buf = [0; 64];
+            let len = message.encode(&mut buf).unwrap();
+            let reply = target.handle(&Message::parse(&buf[..len]).unwrap());
+            updater.handle(&reply.unwrap());
+        }
+    }
+
+    #[test]
+    fn test_messages() {
+        let mut buf = [0; 16];
+        let start = Message::Start {
+            size: 1000,
+            crc: 0xCBF4_3926,
+        };
+        let len = start.encode(&mut buf).unwrap();
+        assert_eq!(buf[..len], [0x01, 0xE8, 0x03, 0, 0, 0x26, 0x39, 0xF4, 0xCB]);
+        assert_eq!(Message::parse(&buf[..len]), Ok(start));
+        let status = Message::Status {
+            status: Status::Unknown(9),
+            offset: 7,
+        };
+        let len = status.encode(&mut buf).unwrap();
+        assert_eq!(Message::parse(&buf[..len]), Ok(status));
+        let segment = Message::Segment {
+            offset: 0,
+            data: &[0; 12],
+        };
+        assert_eq!(segment.encode(&mut buf), Err(Error::BufferTooSmall));
+
+        assert_eq!(Message::parse(&[]), Err(Error::Empty));
+        assert_eq!(Message::parse(&[0x01, 0, 0]), Err(Error::Truncated));
+        assert_eq!(Message::parse(&[0x7F]), Err(Error::UnknownMessage(0x7F)));
+    }
+
+    #[test]
+    fn test_resume() {
+        let image: Vec<u8> = (0..100).collect();
+        let mut target = target();
+        // The sender restarts after three segments.
+        let mut updater = Updater::new(&image, 16);
+        exchange(&mut updater, &mut target, 4);
+        assert_eq!(target.received(), Some(48));
+        let mut updater = Updater::new(&image, 16);
+        exchange(&mut updater, &mut target, 1);
+        assert_eq!(updater.offset(), 48);
+        // A lost reply makes the sender repeat a segment.
+        let segment = updater.poll().unwrap();
+        target.handle(&segment);
+        exchange(&mut updater, &mut target, 100);
+
+        assert!(updater.is_done());
+        assert_eq!(target.storage().data, image);
+        assert_eq!(target.storage().committed, Some(100));
+        assert_eq!(target.received(), None);
+    }
+
+    #[test]
+    fn test_failures() {
+        let mut target = target();
+        assert_eq!(
+            target.handle(&Message::Commit),
+            Some(Message::Status {
+                status: Status::NotStarted,
+                offset: 0
+            })
+        );
+        let large = [0; 2000];
+        let mut updater = Updater::new(&large, 16);
+        exchange(&mut updater, &mut target, 1);
+        assert_eq!(updater.failure(), Some(Status::TooLarge));
+        assert_eq!(updater.poll(), None);
+
+        // An image announced with the wrong CRC.
+        target.handle(&Message::Start { size: 4, crc: 0 });
+        target.handle(&Message::Segment {
+            offset: 0,
+            data: &[1, 2, 3, 4],
+        });
+        assert_eq!(
+            target.handle(&Message::Commit),
+            Some(Message::Status {
+                status: Status::NotVerified,
+                offset: 4
+            })
+        );
+        assert_eq!(
+            target.handle(&Message::Verify),
+            Some(Message::Status {
+                status: Status::CrcMismatch,
+                offset: 0
+            })
+        );
+        assert_eq!(target.received(), None);
+    }
+}
@@ -0,0 +1,255 @@
+//! Capturing the traffic around intermittent bus faults: the frames before
+//! a trigger are kept in RAM and dumped along with those that follow it.
+use crate::alert::{AlertEvent, Transition};
+use crate::analyzer::CapturedFrame;
+use fixed_queue::VecDeque;
+
+/// What fired a capture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// Requested by the application, e.g. from a button or a remote command.
+    Manual,
+    /// A reassembly, decoding or transmit error.
+    Error,
+    /// An alert raised by rule `rule` of an `AlertEngine`.
+    Alert { rule: usize, source: u8 },
+    /// Expression `index` of a `TriggerSet` matched.
+    Expression(usize),
+}
+
+impl Trigger {
+    /// The trigger for a raised alert; cleared alerts don't fire.
+    pub fn from_alert(event: &AlertEvent) -> Option<Self> {
+        (event.transition == Transition::Raised).then_some(Self::Alert {
+            rule: event.rule,
+            source: event.source,
+        })
+    }
+}
+
+/// Where captures are written, such as a file or a binary log on an SD
+/// card.
+pub trait CaptureSink {
+    /// A capture starts, fired by `trigger` at `timestamp_ms`.
+    fn begin(&mut self, trigger: Trigger, timestamp_ms: u64);
+    fn frame(&mut self, frame: &CapturedFrame);
+    /// The capture is complete.
+    fn end(&mut self);
+}
+
+/// Keeps the frames of the last `pre_ms` in a ring buffer of up to `CAP`
+/// frames. When triggered, writes them to the sink followed by every frame
+/// of the next `post_ms`.
+///
+/// A trigger during a capture extends it to `post_ms` after the new
+/// trigger. If the buffer fills before `pre_ms` have passed, the oldest
+/// frames are dropped, so `CAP` should cover the busiest bus expected.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::analyzer::{CapturedFrame, Direction};
+/// use nmea::can_id::CanId;
+/// use nmea::flight_recorder::{CaptureSink, FlightRecorder, Trigger};
+///
+/// #[derive(Default)]
+/// struct Dump(Vec<u64>);
+///
+/// impl CaptureSink for Dump {
+///     fn begin(&mut self, _: Trigger, _: u64) {}
+///     fn frame(&mut self, frame: &CapturedFrame) {
+///         self.0.push(frame.timestamp_ms);
+///     }
+///     fn end(&mut self) {}
+/// }
+///
+/// let mut recorder: FlightRecorder<Dump, 64> = FlightRecorder::new(Dump::default(), 2000, 1000);
+/// for t in (0..10_000).step_by(500) {
+///     let frame = CapturedFrame {
+///         timestamp_ms: t,
+///         id: CanId::new(2, 127250, 0x10, 255).unwrap(),
+///         data: [0; 8],
+///         direction: Direction::Rx,
+///     };
+///     recorder.record(&frame);
+///     if t == 5000 {
+///         recorder.trigger(Trigger::Manual, t);
+///     }
+/// }
+/// assert_eq!(recorder.sink().0, [3000, 3500, 4000, 4500, 5000, 5500, 6000]);
+/// ```
+pub struct FlightRecorder<K: CaptureSink, const CAP: usize> {
+    sink: K,
+    pre_ms: u64,
+    post_ms: u64,
+    buffer: VecDeque<CapturedFrame, CAP>,
+    /// End of the capture in progress.
+    until_ms: Option<u64>,
+    captures: u32,
+}
+
+impl<K: CaptureSink, const CAP: usize> FlightRecorder<K, CAP> {
+    pub const fn new(sink: K, pre_ms: u64, post_ms: u64) -> Self {
+        Self {
+            sink,
+            pre_ms,
+            post_ms,
+            buffer: VecDeque::new(),
+            until_ms: None,
+            captures: 0,
+        }
+    }
+
+    pub fn sink(&self) -> &K {
+        &self.sink
+    }
+
+    pub fn sink_mut(&mut self) -> &mut K {
+        &mut self.sink
+    }
+
+    /// Whether a capture is in progress.
+    pub fn is_capturing(&self) -> bool {
+        self.until_ms.is_some()
+    }
+
+    /// Number of captures started.
+    pub fn captures(&self) -> u32 {
+        self.captures
+    }
+
+    /// Records a frame, writing it to the sink if a capture is in progress.
+    pub fn record(&mut self, frame: &CapturedFrame) {
+        self.poll(frame.timestamp_ms);
+        if self.until_ms.is_some() {
+            self.sink.frame(frame);
+        }
+        let oldest_ms = frame.timestamp_ms.saturating_sub(self.pre_ms);
+        while self
+            .buffer
+            .get(0)
+            .is_some_and(|f| f.timestamp_ms < oldest_ms)
+            || self.buffer.is_full()
+        {
+            self.buffer.pop_front();
+        }
+        let _ = self.buffer.push_back(*frame);
+    }
+
+    /// Fires a capture at `now_ms`, writing the buffered frames of the last
+    /// `pre_ms` to the sink. Returns `false` if a capture was already in
+    /// progress, which is extended instead.
+    pub fn trigger(&mut self, trigger: Trigger, now_ms: u64) -> bool {
+        let until_ms = now_ms.saturating_add(self.post_ms);
+        if let Some(until) = self.until_ms.as_mut() {
+            *until = until_ms.max(*until);
+            return false;
+        }
+        self.sink.begin(trigger, now_ms);
+        let oldest_ms = now_ms.saturating_sub(self.pre_ms);
+        let (front, back) = self.buffer.as_slices();
+        for frame in front.iter().chain(back) {
+            if frame.timestamp_ms >= oldest_ms {
+                self.sink.frame(frame);
+            }
+        }
+        self.until_ms = Some(until_ms);
+        self.captures += 1;
+        true
+    }
+
+    /// Ends the capture in progress if `post_ms` have passed at `now_ms`.
+    /// Call this periodically so a capture ends on a quiet bus.
+    pub fn poll(&mut self, now_ms: u64) {
+        if self.until_ms.is_some_and(|until| now_ms > until) {
+            self.until_ms = None;
+            self.sink.end();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::Direction;
+    use crate::can_id::CanId;
+    use std::vec::Vec;
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Dump {
+        events: Vec<(Option<Trigger>, u64)>,
+    }
+
+    impl CaptureSink for Dump {
+        fn begin(&mut self, trigger: Trigger, timestamp_ms: u64) {
+            self.events.push((Some(trigger), timestamp_ms));
+        }
+
+        fn frame(&mut self, frame: &CapturedFrame) {
+            self.events.push((None, frame.timestamp_ms));
+        }
+
+        fn end(&mut self) {
+            self.events.push((None, u64::MAX));
+        }
+    }
+
+    fn frame(timestamp_ms: u64) -> CapturedFrame {
+        CapturedFrame {
+            timestamp_ms,
+            id: CanId::new(2, 127250, 0x10, 255).unwrap(),
+            data: [0; 8],
+            direction: Direction::Rx,
+        }
+    }
+
+    #[test]
+    fn test_capture() {
+        let mut recorder: FlightRecorder<Dump, 3> = FlightRecorder::new(Dump::default(), 1000, 500);
+        for t in [0, 100, 200, 300, 400] {
+            recorder.record(&frame(t));
+        }
+        // Only the last 3 frames fit.
+        assert!(recorder.trigger(Trigger::Error, 450));
+        recorder.record(&frame(600));
+        // Extends the capture to 1300.
+        assert!(!recorder.trigger(Trigger::Manual, 800));
+        recorder.record(&frame(1200));
+        recorder.poll(1301);
+        assert!(!recorder.is_capturing());
+        recorder.record(&frame(1400));
+        assert_eq!(
+            recorder.sink().events,
+            [
+                (Some(Trigger::Error), 450),
+                (None, 200),
+                (None, 300),
+                (None, 400),
+                (None, 600),
+                (None, 1200),
+                (None, u64::MAX),
+            ]
+        );
+        assert_eq!(recorder.captures(), 1);
+
+        let event = AlertEvent {
+            rule: 2,
+            timestamp_ms: 0,
+            source: 0x23,
+            transition: Transition::Raised,
+            value: 0.0,
+        };
+        assert_eq!(
+            Trigger::from_alert(&event),
+            Some(Trigger::Alert {
+                rule: 2,
+                source: 0x23
+            })
+        );
+        let cleared = AlertEvent {
+            transition: Transition::Cleared,
+            ..event
+        };
+        assert_eq!(Trigger::from_alert(&cleared), None);
+    }
+}
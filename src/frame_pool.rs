@@ -0,0 +1,507 @@
+//! A fixed-capacity slab of frames shared by Fast-Packet sessions.
+//!
+//! Every `FastPacketMessage` reserves room for the 32 frames of the largest
+//! payload, so a `Reassembler<32>` holds 1024 frames although most buses
+//! only ever have a handful of sessions in progress, most of them a few
+//! frames long. `PooledReassembler` instead keeps the frames of all its
+//! sessions in one `FramePool` of `F` frames, sized for the expected load.
+use crate::nmea_frame::{FastPacketFrame, FastPacketSpec, Nmea2000};
+use crate::nmea_message::{self, FastPacketMessage, FirstFramePolicy};
+use crate::reassembler::{
+    check_first_frame, check_next_frame, complete_session, SessionFrames, SessionKey,
+};
+use fixed_queue::LinearMap;
+use thiserror_no_std::Error;
+
+const NONE: u16 = u16::MAX;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Too many concurrent sessions")]
+    FullTable,
+    #[error("No session in progress")]
+    NoSession,
+    #[error("Frame pool is exhausted")]
+    PoolExhausted,
+    #[error(transparent)]
+    Message(#[from] nmea_message::Error),
+}
+
+/// Frames borrowed from a `FramePool`, linked in the order they were added.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameChain {
+    head: u16,
+    tail: u16,
+    len: u8,
+}
+
+impl FrameChain {
+    pub const fn new() -> Self {
+        Self {
+            head: NONE,
+            tail: NONE,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Default for FrameChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A slab of `F` frames, at most 65535, lent out to `FrameChain`s.
+pub struct FramePool<const F: usize> {
+    frames: [[u8; 8]; F],
+    /// The next frame of each chain, or of the free list.
+    next: [u16; F],
+    free: u16,
+    available: usize,
+}
+
+impl<const F: usize> Default for FramePool<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const F: usize> FramePool<F> {
+    pub const fn new() -> Self {
+        assert!(F < NONE as usize);
+        let mut next = [NONE; F];
+        let mut i = 0;
+        while i + 1 < F {
+            next[i] = (i + 1) as u16;
+            i += 1;
+        }
+        Self {
+            frames: [[0; 8]; F],
+            next,
+            free: if F == 0 { NONE } else { 0 },
+            available: F,
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        F
+    }
+
+    /// Frames not lent to any chain.
+    pub fn available(&self) -> usize {
+        self.available
+    }
+
+    /// Appends `frame` to `chain`, failing if the pool is exhausted.
+    pub fn push(&mut self, chain: &mut FrameChain, frame: &[u8; 8]) -> Result<(), Error> {
+        let slot = self.free;
+        if slot == NONE {
+            return Err(Error::PoolExhausted);
+        }
+        self.free = self.next[slot as usize];
+        self.available -= 1;
+        self.frames[slot as usize] = *frame;
+        self.next[slot as usize] = NONE;
+        if chain.tail == NONE {
+            chain.head = slot;
+        } else {
+            self.next[chain.tail as usize] = slot;
+        }
+        chain.tail = slot;
+        chain.len += 1;
+        Ok(())
+    }
+
+    /// The frames of `chain`, oldest first.
+    pub fn frames<'a>(&'a self, chain: &FrameChain) -> impl Iterator<Item = &'a [u8; 8]> + 'a {
+        let mut slot = chain.head;
+        core::iter::from_fn(move || {
+            let frame = self.frames.get(slot as usize)?;
+            slot = self.next[slot as usize];
+            Some(frame)
+        })
+    }
+
+    /// Returns the frames of `chain` to the pool and empties it.
+    pub fn release(&mut self, chain: &mut FrameChain) {
+        if chain.tail != NONE {
+            self.next[chain.tail as usize] = self.free;
+            self.free = chain.head;
+            self.available += chain.len as usize;
+        }
+        *chain = FrameChain::new();
+    }
+}
+
+struct Session {
+    frames: FrameChain,
+    num_frames: u8,
+    data_len: u8,
+    sequence_counter: u8,
+    started: u32,
+}
+
+impl SessionFrames for Session {
+    fn received(&self) -> usize {
+        self.frames.len()
+    }
+
+    fn num_frames(&self) -> u8 {
+        self.num_frames
+    }
+
+    fn sequence_counter(&self) -> u8 {
+        self.sequence_counter
+    }
+
+    fn started(&self) -> u32 {
+        self.started
+    }
+}
+
+/// A `Reassembler` of up to `N` concurrent sessions whose frames come from
+/// a shared pool of `F`.
+///
+/// A first frame is refused with `Error::PoolExhausted` when the pool is
+/// empty, and a session that runs out of frames part way is dropped with the
+/// same error. Sessions that can't start for lack of a slot or of frames are
+/// counted as rejected. There is no eviction.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::frame_pool::PooledReassembler;
+///
+/// // 32 sessions sharing 96 frames.
+/// let mut reassembler: PooledReassembler<32, 96> = PooledReassembler::new();
+/// reassembler.add_frame(1, 129029, &[0x00, 0x09, 1, 2, 3, 4, 5, 6]).unwrap();
+/// assert!(reassembler.add_frame(1, 129029, &[0x01, 7, 8, 9, 0xFF, 0xFF, 0xFF, 0xFF]).unwrap());
+/// assert_eq!(reassembler.pool().available(), 94);
+/// let mut buf = [0; 223];
+/// assert_eq!(reassembler.get_payload(1, 129029, &mut buf), Ok(9));
+/// assert_eq!(buf[..9], [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+/// assert_eq!(reassembler.pool().available(), 96);
+/// ```
+pub struct PooledReassembler<const N: usize, const F: usize, S: FastPacketSpec = Nmea2000> {
+    sessions: LinearMap<SessionKey, Session, N>,
+    pool: FramePool<F>,
+    policy: FirstFramePolicy,
+    /// Increments with every first frame, ordering complete sessions.
+    clock: u32,
+    rejected: u32,
+    spec: core::marker::PhantomData<fn() -> S>,
+}
+
+impl<const N: usize, const F: usize, S: FastPacketSpec> Default for PooledReassembler<N, F, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const F: usize, S: FastPacketSpec> PooledReassembler<N, F, S> {
+    pub const fn new() -> Self {
+        Self::with_policy(FirstFramePolicy::Restart)
+    }
+
+    pub const fn with_policy(policy: FirstFramePolicy) -> Self {
+        Self {
+            sessions: LinearMap::new(),
+            pool: FramePool::new(),
+            policy,
+            clock: 0,
+            rejected: 0,
+            spec: core::marker::PhantomData,
+        }
+    }
+
+    pub fn pool(&self) -> &FramePool<F> {
+        &self.pool
+    }
+
+    /// Sessions refused because the table or the pool was full.
+    pub fn rejected(&self) -> u32 {
+        self.rejected
+    }
+
+    fn key(&self, source: u8, pgn: u32, sequence_counter: u8) -> SessionKey {
+        SessionKey {
+            source,
+            pgn,
+            sequence_counter: match self.policy {
+                FirstFramePolicy::Concurrent => sequence_counter,
+                _ => 0,
+            },
+        }
+    }
+
+    fn remove(&mut self, key: &SessionKey) -> bool {
+        match self.sessions.remove(key) {
+            Some(mut session) => {
+                self.pool.release(&mut session.frames);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Adds a frame received from `source` carrying `pgn`, as
+    /// `Reassembler::add_frame` does. Returns `true` once the session is
+    /// complete.
+    pub fn add_frame(&mut self, source: u8, pgn: u32, payload: &[u8; 8]) -> Result<bool, Error> {
+        let frame = FastPacketFrame::<S>::from_bytes(payload);
+        let key = self.key(source, pgn, frame.sequence_counter());
+        if frame.is_first_frame() {
+            if let Err(e) = check_first_frame::<S>(self.policy, self.sessions.get(&key), payload) {
+                debug!("pgn {} from {}: {}", pgn, source, e);
+                return Err(e.into());
+            }
+            self.remove(&key);
+            self.clock = self.clock.wrapping_add(1);
+            let mut frames = FrameChain::new();
+            if let Err(e) = self.pool.push(&mut frames, payload) {
+                self.rejected += 1;
+                return Err(e);
+            }
+            let data_len = frame.data_len().unwrap_or(0);
+            let session = Session {
+                frames,
+                num_frames: FastPacketMessage::<S>::expected_frames_for_len(data_len),
+                data_len,
+                sequence_counter: frame.sequence_counter(),
+                started: self.clock,
+            };
+            let complete = session.is_complete();
+            if let Err((_, mut session)) = self.sessions.insert(key, session) {
+                self.pool.release(&mut session.frames);
+                self.rejected += 1;
+                debug!("pgn {} from {}: no free session", pgn, source);
+                return Err(Error::FullTable);
+            }
+            return Ok(complete);
+        }
+        let session = self.sessions.get_mut(&key).ok_or(Error::NoSession)?;
+        let error = match check_next_frame::<S>(&*session, payload) {
+            Ok(()) => match self.pool.push(&mut session.frames, payload) {
+                Ok(()) => return Ok(session.is_complete()),
+                Err(e) => e,
+            },
+            // A stray frame leaves a complete message to be taken.
+            Err(e) if session.is_complete() => return Err(e.into()),
+            Err(e) => e.into(),
+        };
+        debug!("pgn {} from {}: {}", pgn, source, error);
+        // A broken sequence can't be recovered; wait for a new first frame.
+        self.remove(&key);
+        Err(error)
+    }
+
+    /// Copies the payload of a complete session into `buf` and ends the
+    /// session, returning the pooled frames, as `Reassembler::get_payload`
+    /// does. Under `FirstFramePolicy::Concurrent` the oldest complete session
+    /// for `source` and `pgn` is taken. A session still in progress fails with
+    /// `nmea_message::Error::IncompleteMessage`, and a `buf` shorter than the
+    /// payload with `nmea_message::Error::BufferTooSmall`; both keep the
+    /// session.
+    pub fn get_payload(&mut self, source: u8, pgn: u32, buf: &mut [u8]) -> Result<usize, Error> {
+        let key = complete_session(&self.sessions, source, pgn, Error::NoSession)?;
+        let data_len = self.sessions.get(&key).ok_or(Error::NoSession)?.data_len as usize;
+        if buf.len() < data_len {
+            return Err(nmea_message::Error::BufferTooSmall.into());
+        }
+        let mut session = self.sessions.remove(&key).ok_or(Error::NoSession)?;
+        buf.fill(0xFF);
+        let mut len = 0;
+        for (i, frame) in self.pool.frames(&session.frames).enumerate() {
+            let data = if i == 0 { &frame[2..] } else { &frame[1..] };
+            let n = data.len().min(data_len - len);
+            buf[len..len + n].copy_from_slice(&data[..n]);
+            len += n;
+        }
+        self.pool.release(&mut session.frames);
+        Ok(data_len)
+    }
+
+    /// Abandons the sessions for `source` and `pgn`. Returns `true` if any
+    /// was in progress.
+    pub fn abort(&mut self, source: u8, pgn: u32) -> bool {
+        let mut aborted = false;
+        while let Some(key) = self
+            .sessions
+            .iter()
+            .find(|(k, _)| k.source == source && k.pgn == pgn)
+            .map(|(k, _)| *k)
+        {
+            aborted |= self.remove(&key);
+        }
+        aborted
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.sessions = LinearMap::new();
+        self.pool = FramePool::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nmea_message::{Message, MAX_NMEA_PACKET_SIZE};
+    use crate::reassembler::Reassembler;
+
+    const BUF_1: [u8; 8] = [0x00, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D];
+    const BUF_2: [u8; 8] = [0x01, 0x31, 0xF3, 0xD0, 0xAC, 0xF2, 0x23, 0x1A];
+    const BUF_3: [u8; 8] = [0x02, 0x03, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00];
+    const BUF_4: [u8; 8] = [0x03, 0x20, 0xFF, 0xFF, 0x00, 0x70, 0xFF, 0xFF];
+
+    #[test]
+    fn test_pool() {
+        let mut pool: FramePool<3> = FramePool::new();
+        let (mut a, mut b) = (FrameChain::new(), FrameChain::new());
+        pool.push(&mut a, &[1; 8]).unwrap();
+        pool.push(&mut b, &[2; 8]).unwrap();
+        pool.push(&mut a, &[3; 8]).unwrap();
+        assert_eq!(pool.push(&mut b, &[4; 8]), Err(Error::PoolExhausted));
+        assert!(pool.frames(&a).eq([&[1; 8], &[3; 8]]));
+        pool.release(&mut a);
+        assert!(a.is_empty());
+        assert_eq!(pool.available(), 2);
+        pool.push(&mut b, &[5; 8]).unwrap();
+        pool.push(&mut b, &[6; 8]).unwrap();
+        assert!(pool.frames(&b).eq([&[2; 8], &[5; 8], &[6; 8]]));
+    }
+
+    #[test]
+    fn test_reassemble() {
+        let mut reassembler: PooledReassembler<8, 6> = PooledReassembler::new();
+        let mut buf = [0; MAX_NMEA_PACKET_SIZE];
+        assert!(!reassembler.add_frame(1, 129029, &BUF_1).unwrap());
+        assert!(!reassembler.add_frame(2, 129029, &BUF_1).unwrap());
+        assert!(!reassembler.add_frame(1, 129029, &BUF_2).unwrap());
+        assert!(!reassembler.add_frame(2, 129029, &BUF_2).unwrap());
+        assert!(!reassembler.add_frame(1, 129029, &BUF_3).unwrap());
+        assert!(reassembler.add_frame(1, 129029, &BUF_4).unwrap());
+        assert_eq!(
+            reassembler.add_frame(1, 129029, &BUF_2),
+            Err(Error::Message(nmea_message::Error::FullQueue))
+        );
+        // Source 2 can't go on while source 1 holds four frames.
+        assert_eq!(
+            reassembler.add_frame(2, 129029, &BUF_3),
+            Err(Error::PoolExhausted)
+        );
+        assert_eq!(
+            reassembler.get_payload(1, 129029, &mut buf[..24]),
+            Err(Error::Message(nmea_message::Error::BufferTooSmall))
+        );
+        assert_eq!(reassembler.get_payload(1, 129029, &mut buf), Ok(25));
+        assert_eq!(buf[..6], BUF_1[2..]);
+        assert_eq!(buf[25], 0xFF);
+        assert_eq!(reassembler.pool().available(), 6);
+        assert!(reassembler.is_empty());
+
+        // Sequence errors end the session and return its frames.
+        reassembler.add_frame(3, 129029, &BUF_1).unwrap();
+        assert!(matches!(
+            reassembler.add_frame(3, 129029, &BUF_3),
+            Err(Error::Message(nmea_message::Error::SequenceMismatch { .. }))
+        ));
+        assert_eq!(reassembler.pool().available(), 6);
+        assert_eq!(
+            reassembler.get_payload(3, 129029, &mut buf),
+            Err(Error::NoSession)
+        );
+
+        // Single frame messages complete at once.
+        assert!(reassembler
+            .add_frame(4, 126996, &[0x00, 0x02, 7, 8, 0xFF, 0xFF, 0xFF, 0xFF])
+            .unwrap());
+        assert_eq!(reassembler.get_payload(4, 126996, &mut buf), Ok(2));
+    }
+
+    #[test]
+    fn test_concurrent() {
+        let mut reassembler: PooledReassembler<4, 8> =
+            PooledReassembler::with_policy(FirstFramePolicy::Concurrent);
+        let mut buf = [0; MAX_NMEA_PACKET_SIZE];
+        // Complete messages are taken oldest first, even after another
+        // session was removed from the table.
+        let first = Message::from_payload(&[1, 2, 3], 0).unwrap().pop_frame();
+        let second = Message::from_payload(&[4, 5, 6], 1).unwrap().pop_frame();
+        assert!(!reassembler.add_frame(2, 129029, &BUF_1).unwrap());
+        assert!(reassembler
+            .add_frame(1, 129029, &first.unwrap().bytes)
+            .unwrap());
+        assert!(reassembler
+            .add_frame(1, 129029, &second.unwrap().bytes)
+            .unwrap());
+        assert!(reassembler.abort(2, 129029));
+        assert_eq!(reassembler.get_payload(1, 129029, &mut buf), Ok(3));
+        assert_eq!(buf[..3], [1, 2, 3]);
+        assert_eq!(reassembler.get_payload(1, 129029, &mut buf), Ok(3));
+        assert_eq!(buf[..3], [4, 5, 6]);
+        assert!(reassembler.is_empty());
+    }
+
+    impl From<crate::reassembler::Error> for Error {
+        fn from(e: crate::reassembler::Error) -> Self {
+            match e {
+                crate::reassembler::Error::FullTable => Error::FullTable,
+                crate::reassembler::Error::NoSession => Error::NoSession,
+                crate::reassembler::Error::Message(e) => Error::Message(e),
+                crate::reassembler::Error::Corrupted => unreachable!(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_reassembler() {
+        let seq_1: [u8; 8] = [0x20, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D];
+        let too_long: [u8; 8] = [0x40, 0xE0, 0, 0, 0, 0, 0, 0];
+        let script = [
+            BUF_1, BUF_2, seq_1, BUF_2, BUF_3, too_long, BUF_4, BUF_2, BUF_1, BUF_3, BUF_1,
+        ];
+        for policy in [
+            FirstFramePolicy::Restart,
+            FirstFramePolicy::Error,
+            FirstFramePolicy::Concurrent,
+        ] {
+            let mut pooled: PooledReassembler<4, 16> = PooledReassembler::with_policy(policy);
+            let mut reassembler: Reassembler<4> = Reassembler::with_policy(policy);
+            for frame in &script {
+                let expected = reassembler.add_frame(1, 129029, frame).map_err(Error::from);
+                assert_eq!(pooled.add_frame(1, 129029, frame), expected);
+                let (mut a, mut b) = ([0; MAX_NMEA_PACKET_SIZE], [0; MAX_NMEA_PACKET_SIZE]);
+                let expected = reassembler
+                    .get_payload(1, 129029, &mut a)
+                    .map_err(Error::from);
+                assert_eq!(pooled.get_payload(1, 129029, &mut b), expected);
+                assert_eq!(a, b);
+                assert_eq!(pooled.len(), reassembler.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_footprint() {
+        assert!(
+            core::mem::size_of::<PooledReassembler<32, 128>>()
+                < core::mem::size_of::<Reassembler<32>>() / 4
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! Lock-free single-producer single-consumer queue of raw CAN frames.
+//!
+//! The typical embedded deployment receives frames in the CAN RX interrupt
+//! and reassembles them in the main loop. `FrameQueue` connects the two
+//! without a critical section: the interrupt holds the `Producer`, the main
+//! loop the `Consumer`. Only atomic loads and stores are used, so it also
+//! works on cores without compare-and-swap (e.g. Cortex-M0).
+//!
+//! ## Example:
+//!
+//! ```
+//! use nmea::can_id::CanId;
+//! use nmea::frame_queue::{FrameQueue, RxFrame};
+//! use nmea::reassembler::Reassembler;
+//!
+//! let mut queue: FrameQueue<16> = FrameQueue::new();
+//! let (mut producer, mut consumer) = queue.split();
+//!
+//! // In the RX interrupt:
+//! let id = CanId::new(3, 129029, 10, 255).unwrap();
+//! producer.enqueue(RxFrame { id, data: [0x20, 0x09, 1, 2, 3, 4, 5, 6] }).unwrap();
+//!
+//! // In the main loop:
+//! let mut reassembler: Reassembler<4> = Reassembler::new();
+//! while let Some(frame) = consumer.dequeue() {
+//!     reassembler.add_frame(frame.id.source(), frame.id.pgn(), &frame.data).unwrap();
+//! }
+//! ```
+use crate::can_id::CanId;
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Frame queue is full")]
+    Full,
+}
+
+/// A frame as received from the CAN controller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RxFrame {
+    pub id: CanId,
+    pub data: [u8; 8],
+}
+
+/// Ring buffer of up to `N` frames.
+///
+/// `head` and `tail` count modulo `2 * N`, which tells a full queue apart
+/// from an empty one without giving up a slot.
+pub struct FrameQueue<const N: usize> {
+    buffer: UnsafeCell<[MaybeUninit<RxFrame>; N]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    overflows: AtomicU32,
+}
+
+// SAFETY: slots are only written by the single `Producer` and only read by
+// the single `Consumer`, and `split` takes `&mut self` so at most one of each
+// exists. Ownership of a slot is handed over through `tail` (release/acquire)
+// and handed back through `head`.
+unsafe impl<const N: usize> Sync for FrameQueue<N> {}
+
+impl<const N: usize> Default for FrameQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> FrameQueue<N> {
+    pub const fn new() -> Self {
+        Self {
+            buffer: UnsafeCell::new([const { MaybeUninit::uninit() }; N]),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            overflows: AtomicU32::new(0),
+        }
+    }
+
+    pub fn split(&mut self) -> (Producer<'_, N>, Consumer<'_, N>) {
+        (Producer { queue: self }, Consumer { queue: self })
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        (tail + 2 * N - head) % (2 * N)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of frames dropped because the queue was full.
+    pub fn overflows(&self) -> u32 {
+        self.overflows.load(Ordering::Relaxed)
+    }
+}
+
+/// The writing half of a `FrameQueue`, for the interrupt handler.
+pub struct Producer<'a, const N: usize> {
+    queue: &'a FrameQueue<N>,
+}
+
+impl<const N: usize> Producer<'_, N> {
+    /// Pushes a frame. When the queue is full the frame is dropped and the
+    /// overflow counter incremented.
+    pub fn enqueue(&mut self, frame: RxFrame) -> Result<(), Error> {
+        let queue = self.queue;
+        let tail = queue.tail.load(Ordering::Relaxed);
+        let head = queue.head.load(Ordering::Acquire);
+        if (tail + 2 * N - head) % (2 * N) == N {
+            // Only the producer writes the counter, so no read-modify-write
+            // instruction is needed.
+            let overflows = queue.overflows.load(Ordering::Relaxed);
+            queue
+                .overflows
+                .store(overflows.wrapping_add(1), Ordering::Relaxed);
+            return Err(Error::Full);
+        }
+        // SAFETY: the slot at `tail` is outside the consumer's range until
+        // `tail` is published below.
+        unsafe {
+            (*queue.buffer.get())[tail % N].write(frame);
+        }
+        queue.tail.store((tail + 1) % (2 * N), Ordering::Release);
+        Ok(())
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.queue.len() == N
+    }
+}
+
+/// The reading half of a `FrameQueue`, for the main loop.
+pub struct Consumer<'a, const N: usize> {
+    queue: &'a FrameQueue<N>,
+}
+
+impl<const N: usize> Consumer<'_, N> {
+    pub fn dequeue(&mut self) -> Option<RxFrame> {
+        let queue = self.queue;
+        let head = queue.head.load(Ordering::Relaxed);
+        let tail = queue.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        // SAFETY: slots between `head` and `tail` were initialized by the
+        // producer before it published `tail`.
+        let frame = unsafe { (*queue.buffer.get())[head % N].assume_init() };
+        queue.head.store((head + 1) % (2 * N), Ordering::Release);
+        Some(frame)
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Number of frames dropped because the queue was full.
+    pub fn overflows(&self) -> u32 {
+        self.queue.overflows()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(n: u8) -> RxFrame {
+        RxFrame {
+            id: CanId::new(2, 127250, n, 255).unwrap(),
+            data: [n; 8],
+        }
+    }
+
+    #[test]
+    fn test_fifo_and_overflow() {
+        let mut queue: FrameQueue<3> = FrameQueue::new();
+        let (mut producer, mut consumer) = queue.split();
+        assert_eq!(consumer.dequeue(), None);
+
+        // Wrap around the buffer several times.
+        for round in 0..5u8 {
+            for n in 0..3 {
+                producer.enqueue(frame(round * 3 + n)).unwrap();
+            }
+            assert!(producer.is_full());
+            assert_eq!(producer.enqueue(frame(0xFF)), Err(Error::Full));
+            assert_eq!(consumer.len(), 3);
+            for n in 0..3 {
+                assert_eq!(consumer.dequeue(), Some(frame(round * 3 + n)));
+            }
+            assert!(consumer.is_empty());
+        }
+        assert_eq!(consumer.overflows(), 5);
+    }
+
+    #[test]
+    fn test_threads() {
+        extern crate std;
+
+        let mut queue: FrameQueue<8> = FrameQueue::new();
+        let (mut producer, mut consumer) = queue.split();
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                for n in 0..=250u8 {
+                    while producer.enqueue(frame(n)).is_err() {
+                        std::thread::yield_now();
+                    }
+                }
+            });
+            for n in 0..=250u8 {
+                let received = loop {
+                    match consumer.dequeue() {
+                        Some(f) => break f,
+                        None => std::thread::yield_now(),
+                    }
+                };
+                assert_eq!(received, frame(n));
+            }
+        });
+    }
+}
@@ -0,0 +1,228 @@
+//! GPX track export of GNSS positions.
+use crate::pgn::gnss::{GnssPositionData, PositionRapidUpdate};
+use std::io::{self, Write};
+
+/// Writes positions from PGN 129029 and 129025 as points of a single GPX
+/// track segment.
+///
+/// 129029 carries UTC date and time, which become the point times. 129025
+/// carries none, so its points are timed relative to the latest 129029 using
+/// the log timestamps passed along with each message; those before the first
+/// 129029 have no time. Messages without a position are skipped.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::gpx::GpxWriter;
+/// use nmea::pgn::gnss::PositionRapidUpdate;
+///
+/// let mut writer = GpxWriter::new(Vec::new(), "Log").unwrap();
+/// let position = PositionRapidUpdate {
+///     latitude: Some(52.5),
+///     longitude: Some(-1.25),
+/// };
+/// assert!(writer.write_rapid_update(1000, &position).unwrap());
+/// let gpx = String::from_utf8(writer.finish().unwrap()).unwrap();
+/// assert!(gpx.contains("<trkpt lat=\"52.5000000\" lon=\"-1.2500000\"></trkpt>"));
+/// ```
+pub struct GpxWriter<W: Write> {
+    out: W,
+    /// Log timestamp and UTC milliseconds of the latest timed fix.
+    anchor: Option<(u64, u64)>,
+}
+
+impl<W: Write> GpxWriter<W> {
+    /// Writes the GPX header, naming the track `name`.
+    pub fn new(mut out: W, name: &str) -> io::Result<Self> {
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(
+            out,
+            "<gpx version=\"1.1\" creator=\"nmea\" xmlns=\"http://www.topografix.com/GPX/1/1\">"
+        )?;
+        write!(out, "<trk><name>")?;
+        write_escaped(&mut out, name)?;
+        writeln!(out, "</name><trkseg>")?;
+        Ok(Self { out, anchor: None })
+    }
+
+    /// Writes a point for `position` received at `timestamp_ms`. Returns
+    /// whether a point was written.
+    pub fn write_position(
+        &mut self,
+        timestamp_ms: u64,
+        position: &GnssPositionData,
+    ) -> io::Result<bool> {
+        let utc_ms = match (position.date, position.time) {
+            (Some(date), Some(time)) => Some(date as u64 * 86_400_000 + (time * 1000.0) as u64),
+            _ => None,
+        };
+        if let Some(utc_ms) = utc_ms {
+            self.anchor = Some((timestamp_ms, utc_ms));
+        }
+        self.write_point(
+            position.latitude,
+            position.longitude,
+            position.altitude,
+            utc_ms,
+        )
+    }
+
+    /// Writes a point for `position` received at `timestamp_ms`. Returns
+    /// whether a point was written.
+    pub fn write_rapid_update(
+        &mut self,
+        timestamp_ms: u64,
+        position: &PositionRapidUpdate,
+    ) -> io::Result<bool> {
+        let utc_ms = self
+            .anchor
+            .filter(|(log_ms, _)| timestamp_ms >= *log_ms)
+            .map(|(log_ms, utc_ms)| utc_ms + (timestamp_ms - log_ms));
+        self.write_point(position.latitude, position.longitude, None, utc_ms)
+    }
+
+    /// Closes the track and returns the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        writeln!(self.out, "</trkseg></trk>")?;
+        writeln!(self.out, "</gpx>")?;
+        Ok(self.out)
+    }
+
+    fn write_point(
+        &mut self,
+        latitude: Option<f64>,
+        longitude: Option<f64>,
+        altitude: Option<f64>,
+        utc_ms: Option<u64>,
+    ) -> io::Result<bool> {
+        let (Some(latitude), Some(longitude)) = (latitude, longitude) else {
+            return Ok(false);
+        };
+        write!(
+            self.out,
+            "<trkpt lat=\"{:.7}\" lon=\"{:.7}\">",
+            latitude, longitude
+        )?;
+        if let Some(altitude) = altitude {
+            write!(self.out, "<ele>{:.2}</ele>", altitude)?;
+        }
+        if let Some(utc_ms) = utc_ms {
+            write!(self.out, "<time>")?;
+            write_time(&mut self.out, utc_ms)?;
+            write!(self.out, "</time>")?;
+        }
+        writeln!(self.out, "</trkpt>")?;
+        Ok(true)
+    }
+}
+
+fn write_escaped(out: &mut impl Write, s: &str) -> io::Result<()> {
+    for c in s.chars() {
+        match c {
+            '&' => write!(out, "&amp;")?,
+            '<' => write!(out, "&lt;")?,
+            '>' => write!(out, "&gt;")?,
+            '"' => write!(out, "&quot;")?,
+            c => write!(out, "{}", c)?,
+        }
+    }
+    Ok(())
+}
+
+/// Writes milliseconds since the epoch as an ISO 8601 UTC time.
+fn write_time(out: &mut impl Write, utc_ms: u64) -> io::Result<()> {
+    let days = utc_ms / 86_400_000;
+    let ms = utc_ms % 86_400_000;
+    // Civil date from days since 1970-01-01, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    write!(
+        out,
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::gnss::GnssMethod;
+    use std::string::String;
+    use std::vec::Vec;
+
+    fn time(utc_ms: u64) -> String {
+        let mut out = Vec::new();
+        write_time(&mut out, utc_ms).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_time() {
+        assert_eq!(time(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(time(951_782_400_000), "2000-02-29T00:00:00.000Z");
+        assert_eq!(time(1_700_000_000_123), "2023-11-14T22:13:20.123Z");
+    }
+
+    #[test]
+    fn test_track() {
+        let fix = GnssPositionData {
+            sid: None,
+            date: Some(19_675),
+            time: Some(43_200.5),
+            latitude: Some(50.1),
+            longitude: Some(-4.2),
+            altitude: Some(12.0),
+            gnss_type: 0,
+            method: GnssMethod::GnssFix,
+            integrity: 0,
+            satellites: Some(9),
+            hdop: None,
+            pdop: None,
+            geoidal_separation: None,
+        };
+        let rapid = PositionRapidUpdate {
+            latitude: Some(50.2),
+            longitude: Some(-4.3),
+        };
+
+        let mut writer = GpxWriter::new(Vec::new(), "a<b").unwrap();
+        assert!(writer.write_rapid_update(500, &rapid).unwrap());
+        assert!(writer.write_position(1000, &fix).unwrap());
+        assert!(writer.write_rapid_update(1250, &rapid).unwrap());
+        let no_fix = PositionRapidUpdate {
+            latitude: None,
+            longitude: Some(-4.3),
+        };
+        assert!(!writer.write_rapid_update(1300, &no_fix).unwrap());
+
+        let gpx = String::from_utf8(writer.finish().unwrap()).unwrap();
+        let lines: Vec<&str> = gpx.lines().collect();
+        assert_eq!(lines[2], "<trk><name>a&lt;b</name><trkseg>");
+        assert_eq!(
+            lines[3..],
+            [
+                "<trkpt lat=\"50.2000000\" lon=\"-4.3000000\"></trkpt>",
+                "<trkpt lat=\"50.1000000\" lon=\"-4.2000000\"><ele>12.00</ele>\
+                 <time>2023-11-14T12:00:00.500Z</time></trkpt>",
+                "<trkpt lat=\"50.2000000\" lon=\"-4.3000000\">\
+                 <time>2023-11-14T12:00:00.750Z</time></trkpt>",
+                "</trkseg></trk>",
+                "</gpx>",
+            ]
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Detection of two devices sending the same instance of a PGN, a common
+//! installation error: two tank senders both left at instance 0 show up as
+//! one tank whose level jumps between the two.
+use crate::device::DeviceRegistry;
+use crate::labels::InstanceKey;
+use crate::pgn::charger::{ChargerConfiguration, ChargerStatus, InverterStatus};
+use crate::pgn::engine::{EngineDynamic, EngineRapid};
+use crate::pgn::fluid_level::FluidLevel;
+use crate::pgn::switching::SwitchBank;
+use fixed_queue::LinearMap;
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Too many instances")]
+    FullTable,
+}
+
+/// Reads the instance field of an instanced PGN: the environmental PGNs
+/// (see `InstanceKey::from_payload`), fluid level, engine parameters,
+/// switch bank status and charger and inverter status. Returns `None` for
+/// other PGNs and truncated payloads.
+pub fn instance_of(pgn: u32, payload: &[u8]) -> Option<u8> {
+    if let Some(key) = InstanceKey::from_payload(0, pgn, payload) {
+        return Some(key.instance);
+    }
+    match pgn {
+        FluidLevel::PGN => Some(payload.first()? & 0x0F),
+        EngineRapid::PGN
+        | EngineDynamic::PGN
+        | SwitchBank::STATUS_PGN
+        | ChargerStatus::PGN
+        | InverterStatus::PGN
+        | ChargerConfiguration::PGN => payload.first().copied(),
+        _ => None,
+    }
+}
+
+/// A device at `address`, and its NAME if it has claimed the address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sender {
+    pub address: u8,
+    pub name: Option<u64>,
+}
+
+/// Two devices sending the same `instance` of `pgn`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    pub pgn: u32,
+    pub instance: u8,
+    /// The device that was sending the instance first.
+    pub first: Sender,
+    pub second: Sender,
+}
+
+struct Owner {
+    address: u8,
+    /// NAME of the device at `address` when it started sending.
+    name: Option<u64>,
+    last_ms: u64,
+    /// The other address seen sending, once reported.
+    conflict: Option<u8>,
+}
+
+/// Tracks which device sends each instance of up to `N` PGN/instance pairs
+/// and reports a `Conflict` when a second device sends the same one.
+///
+/// An instance passes to a new address without a conflict once the old one
+/// has been quiet for `timeout_ms`, or if both addresses were claimed by the
+/// same NAME. Each conflicting address is reported once.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::device::DeviceRegistry;
+/// use nmea::instance_conflict::ConflictDetector;
+///
+/// let registry: DeviceRegistry<8> = DeviceRegistry::new();
+/// let mut detector: ConflictDetector<16> = ConflictDetector::new(5000);
+/// let mut conflicts = Vec::new();
+/// // Fuel tanks, both instance 0.
+/// let tank = [0x00, 0x00, 0x32, 0x64, 0x00, 0x00, 0xFF, 0xFF];
+/// detector.observe(&registry, 0x30, 127505, &tank, 0, |c| conflicts.push(c)).unwrap();
+/// detector.observe(&registry, 0x31, 127505, &tank, 500, |c| conflicts.push(c)).unwrap();
+/// assert_eq!(conflicts.len(), 1);
+/// assert_eq!(conflicts[0].second.address, 0x31);
+/// ```
+pub struct ConflictDetector<const N: usize> {
+    owners: LinearMap<(u32, u8), Owner, N>,
+    timeout_ms: u64,
+}
+
+impl<const N: usize> ConflictDetector<N> {
+    pub const fn new(timeout_ms: u64) -> Self {
+        Self {
+            owners: LinearMap::new(),
+            timeout_ms,
+        }
+    }
+
+    /// Checks a message from `source`, looking up NAMEs in `registry`.
+    /// Messages of PGNs without an instance are ignored.
+    pub fn observe<const M: usize>(
+        &mut self,
+        registry: &DeviceRegistry<M>,
+        source: u8,
+        pgn: u32,
+        payload: &[u8],
+        now_ms: u64,
+        mut report: impl FnMut(Conflict),
+    ) -> Result<(), Error> {
+        let Some(instance) = instance_of(pgn, payload) else {
+            return Ok(());
+        };
+        let key = (pgn, instance);
+        let name = registry.name_of(source);
+        let Some(owner) = self.owners.get_mut(&key) else {
+            let owner = Owner {
+                address: source,
+                name,
+                last_ms: now_ms,
+                conflict: None,
+            };
+            self.owners
+                .insert(key, owner)
+                .map_err(|_| Error::FullTable)?;
+            return Ok(());
+        };
+        if owner.address == source {
+            owner.last_ms = now_ms;
+            return Ok(());
+        }
+        let first = Sender {
+            address: owner.address,
+            name: owner.name,
+        };
+        let second = Sender {
+            address: source,
+            name,
+        };
+        let same_device = first.name.is_some() && first.name == second.name;
+        if same_device || now_ms.saturating_sub(owner.last_ms) > self.timeout_ms {
+            *owner = Owner {
+                address: source,
+                name,
+                last_ms: now_ms,
+                conflict: None,
+            };
+        } else if owner.conflict != Some(source) {
+            owner.conflict = Some(source);
+            report(Conflict {
+                pgn,
+                instance,
+                first,
+                second,
+            });
+        }
+        Ok(())
+    }
+
+    /// Forgets all instances, e.g. after the installation was fixed.
+    pub fn clear(&mut self) {
+        self.owners = LinearMap::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::can_id::CanId;
+    use crate::device::ADDRESS_CLAIM_PGN;
+    use std::vec::Vec;
+
+    fn claim(registry: &mut DeviceRegistry<4>, name: u64, address: u8) {
+        let id = CanId::new(6, ADDRESS_CLAIM_PGN, address, 255).unwrap();
+        registry.observe(id, &name.to_le_bytes());
+    }
+
+    #[test]
+    fn test_instance_of() {
+        assert_eq!(instance_of(127505, &[0x21, 0x00]), Some(1));
+        assert_eq!(instance_of(130312, &[0x00, 0x03, 0x02]), Some(3));
+        assert_eq!(instance_of(127488, &[0x01; 8]), Some(1));
+        assert_eq!(instance_of(127488, &[]), None);
+        assert_eq!(instance_of(129025, &[0x01; 8]), None);
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mut registry: DeviceRegistry<4> = DeviceRegistry::new();
+        claim(&mut registry, 0xAAAA, 0x40);
+        claim(&mut registry, 0xBBBB, 0x41);
+        let mut detector: ConflictDetector<2> = ConflictDetector::new(2000);
+        let mut conflicts = Vec::new();
+        let engine = [0x00, 0x70, 0x17, 0xFF, 0xFF, 0x7F, 0xFF, 0xFF];
+        let mut observe = |detector: &mut ConflictDetector<2>, source, now_ms| {
+            detector
+                .observe(&registry, source, 127488, &engine, now_ms, |c| {
+                    conflicts.push(c)
+                })
+                .unwrap()
+        };
+        observe(&mut detector, 0x40, 0);
+        observe(&mut detector, 0x41, 100);
+        observe(&mut detector, 0x40, 200);
+        observe(&mut detector, 0x41, 300);
+        // The first engine gateway is switched off.
+        observe(&mut detector, 0x41, 2500);
+        observe(&mut detector, 0x40, 2600);
+        assert_eq!(
+            conflicts,
+            [
+                Conflict {
+                    pgn: 127488,
+                    instance: 0,
+                    first: Sender {
+                        address: 0x40,
+                        name: Some(0xAAAA)
+                    },
+                    second: Sender {
+                        address: 0x41,
+                        name: Some(0xBBBB)
+                    },
+                },
+                Conflict {
+                    pgn: 127488,
+                    instance: 0,
+                    first: Sender {
+                        address: 0x41,
+                        name: Some(0xBBBB)
+                    },
+                    second: Sender {
+                        address: 0x40,
+                        name: Some(0xAAAA)
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_readdressed() {
+        let mut registry: DeviceRegistry<4> = DeviceRegistry::new();
+        let mut detector: ConflictDetector<1> = ConflictDetector::new(2000);
+        let tank = [0x00, 0x00, 0x32, 0x64, 0x00, 0x00, 0xFF, 0xFF];
+        claim(&mut registry, 0xAAAA, 0x40);
+        detector
+            .observe(&registry, 0x40, 127505, &tank, 0, |_| panic!())
+            .unwrap();
+        // The sender lost its address to another device and claimed a new one.
+        claim(&mut registry, 0xAAAA, 0x42);
+        claim(&mut registry, 0xCCCC, 0x40);
+        detector
+            .observe(&registry, 0x42, 127505, &tank, 100, |_| panic!())
+            .unwrap();
+        assert_eq!(
+            detector.observe(&registry, 0x40, 130312, &[0, 1, 2], 200, |_| {}),
+            Err(Error::FullTable)
+        );
+    }
+}
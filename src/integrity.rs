@@ -0,0 +1,211 @@
+//! Integrity tags for application data tunneled over proprietary PGNs.
+//!
+//! CAN checks each frame, but nothing covers a Fast-Packet message as a
+//! whole or guards against a misbehaving node. `Wrapper` appends a tag to
+//! the payload on the sending side and checks and strips it on the
+//! receiving side.
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Buffer is too small")]
+    BufferTooSmall,
+    #[error("Payload is shorter than its tag")]
+    TooShort,
+    #[error("Tag does not match")]
+    Mismatch,
+}
+
+/// Longest tag a `Wrapper` can verify.
+pub const MAX_TAG_LEN: usize = 32;
+
+/// A tag computed over a payload.
+pub trait Tag {
+    /// Length of the tag in bytes, at most `MAX_TAG_LEN`; `Wrapper::verify`
+    /// fails to compile for longer tags.
+    ///
+    /// ```compile_fail
+    /// use nmea::integrity::{Tag, Wrapper};
+    ///
+    /// struct Long;
+    ///
+    /// impl Tag for Long {
+    ///     const LEN: usize = 33;
+    ///
+    ///     fn compute(&self, _payload: &[u8], tag: &mut [u8]) {
+    ///         tag.fill(0);
+    ///     }
+    /// }
+    ///
+    /// let _ = Wrapper(Long).verify(&[0; 40]);
+    /// ```
+    const LEN: usize;
+
+    /// Writes the tag of `payload` into `tag`, which is `LEN` bytes long.
+    fn compute(&self, payload: &[u8], tag: &mut [u8]);
+}
+
+/// CRC-32 (IEEE 802.3), guarding against corruption but not tampering.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Crc32;
+
+/// The CRC-32 of `data`.
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0, data)
+}
+
+/// Continues `crc`, the CRC-32 of the data so far, over `data`.
+pub fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+impl Tag for Crc32 {
+    const LEN: usize = 4;
+
+    fn compute(&self, payload: &[u8], tag: &mut [u8]) {
+        tag.copy_from_slice(&crc32(payload).to_le_bytes());
+    }
+}
+
+/// HMAC-SHA256 with a shared key, truncated to 16 bytes to leave room for
+/// data in a Fast-Packet message.
+#[cfg(feature = "hmac")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HmacSha256<'a> {
+    pub key: &'a [u8],
+}
+
+#[cfg(feature = "hmac")]
+impl Tag for HmacSha256<'_> {
+    const LEN: usize = 16;
+
+    fn compute(&self, payload: &[u8], tag: &mut [u8]) {
+        use hmac::{Hmac, Mac};
+
+        // HMAC accepts keys of any length.
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(self.key).unwrap();
+        mac.update(payload);
+        tag.copy_from_slice(&mac.finalize().into_bytes()[..Self::LEN]);
+    }
+}
+
+/// Appends and verifies a `Tag`.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::integrity::{Crc32, Error, Wrapper};
+///
+/// let wrapper = Wrapper(Crc32);
+/// let mut buf = [0; 16];
+/// let len = wrapper.encode(b"hello", &mut buf).unwrap();
+/// assert_eq!(wrapper.verify(&buf[..len]), Ok(&b"hello"[..]));
+///
+/// buf[0] ^= 1;
+/// assert_eq!(wrapper.verify(&buf[..len]), Err(Error::Mismatch));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Wrapper<T: Tag>(pub T);
+
+impl<T: Tag> Wrapper<T> {
+    /// Writes `payload` followed by its tag into `buf`. Returns the length
+    /// written.
+    pub fn encode(&self, payload: &[u8], buf: &mut [u8]) -> Result<usize, Error> {
+        let len = payload.len() + T::LEN;
+        if buf.len() < len {
+            return Err(Error::BufferTooSmall);
+        }
+        buf[..payload.len()].copy_from_slice(payload);
+        self.0.compute(payload, &mut buf[payload.len()..len]);
+        Ok(len)
+    }
+
+    /// Checks the tag at the end of `wrapped` and returns the payload
+    /// before it.
+    pub fn verify<'a>(&self, wrapped: &'a [u8]) -> Result<&'a [u8], Error> {
+        const { assert!(T::LEN <= MAX_TAG_LEN) };
+        let split = wrapped.len().checked_sub(T::LEN).ok_or(Error::TooShort)?;
+        let (payload, tag) = wrapped.split_at(split);
+        let mut expected = [0; MAX_TAG_LEN];
+        self.0.compute(payload, &mut expected[..T::LEN]);
+        // Compare in constant time so the tag can't be guessed byte by byte.
+        let diff = tag
+            .iter()
+            .zip(&expected)
+            .fold(0, |diff, (a, b)| diff | (a ^ b));
+        if diff != 0 {
+            return Err(Error::Mismatch);
+        }
+        Ok(payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32_update(crc32(b"1234"), b"56789"), 0xCBF4_3926);
+
+        let wrapper = Wrapper(Crc32);
+        let mut buf = [0; 8];
+        assert_eq!(
+            wrapper.encode(b"hello", &mut buf),
+            Err(Error::BufferTooSmall)
+        );
+        assert_eq!(wrapper.verify(&[1, 2, 3]), Err(Error::TooShort));
+        let len = wrapper.encode(b"", &mut buf).unwrap();
+        assert_eq!(wrapper.verify(&buf[..len]), Ok(&[][..]));
+    }
+
+    #[test]
+    fn test_longest_tag() {
+        struct Repeat;
+
+        impl Tag for Repeat {
+            const LEN: usize = MAX_TAG_LEN;
+
+            fn compute(&self, payload: &[u8], tag: &mut [u8]) {
+                tag.fill(payload.len() as u8);
+            }
+        }
+
+        let wrapper = Wrapper(Repeat);
+        let mut buf = [0; 40];
+        let len = wrapper.encode(b"abc", &mut buf).unwrap();
+        assert_eq!(len, 35);
+        assert_eq!(wrapper.verify(&buf[..len]), Ok(&b"abc"[..]));
+        buf[34] = 0;
+        assert_eq!(wrapper.verify(&buf[..len]), Err(Error::Mismatch));
+    }
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn test_hmac() {
+        let wrapper = Wrapper(HmacSha256 { key: b"key" });
+        let mut buf = [0; 64];
+        let message = b"The quick brown fox jumps over the lazy dog";
+        let len = wrapper.encode(message, &mut buf).unwrap();
+        // Well-known HMAC-SHA256 example, truncated.
+        assert_eq!(
+            buf[message.len()..len],
+            [
+                0xF7, 0xBC, 0x83, 0xF4, 0x30, 0x53, 0x84, 0x24, 0xB1, 0x32, 0x98, 0xE6, 0xAA, 0x6F,
+                0xB1, 0x43
+            ]
+        );
+        assert_eq!(wrapper.verify(&buf[..len]), Ok(&message[..]));
+        let other = Wrapper(HmacSha256 { key: b"other" });
+        assert_eq!(other.verify(&buf[..len]), Err(Error::Mismatch));
+    }
+}
@@ -0,0 +1,310 @@
+//! Decoders for common SAE J1939 engine PGNs.
+//!
+//! Marine engines frequently expose J1939 on their own bus and are bridged to
+//! NMEA2000. Values follow the J1939 conventions: raw values above the valid
+//! range (0xFB.. for one byte, 0xFB00.. for two bytes) mean "error" or "not
+//! available" and decode to `None`.
+use crate::pgn::Opt;
+use core::fmt;
+use thiserror_no_std::Error;
+
+pub const PGN_EEC1: u32 = 61444;
+pub const PGN_LFE: u32 = 65266;
+pub const PGN_DM1: u32 = 65226;
+pub const PGN_DM2: u32 = 65227;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Payload is too short")]
+    InvalidLength,
+}
+
+fn u8_field(payload: &[u8], offset: usize) -> Option<u8> {
+    payload.get(offset).copied().filter(|v| *v <= 0xFA)
+}
+
+fn u16_field(payload: &[u8], offset: usize) -> Option<u16> {
+    let bytes = payload.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]])).filter(|v| *v <= 0xFAFF)
+}
+
+fn check_len(payload: &[u8], len: usize) -> Result<(), Error> {
+    if payload.len() < len {
+        return Err(Error::InvalidLength);
+    }
+    Ok(())
+}
+
+/// Electronic Engine Controller 1 (PGN 61444).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Eec1 {
+    /// Engine torque mode (SPN 899).
+    pub torque_mode: Option<u8>,
+    /// Driver's demand engine percent torque (SPN 512), in %.
+    pub driver_demand_torque: Option<i16>,
+    /// Actual engine percent torque (SPN 513), in %.
+    pub actual_torque: Option<i16>,
+    /// Engine speed (SPN 190), in rpm.
+    pub engine_speed: Option<f32>,
+    /// Source address of the device controlling the engine (SPN 1483).
+    pub controlling_source: Option<u8>,
+    /// Engine demand percent torque (SPN 2432), in %.
+    pub engine_demand_torque: Option<i16>,
+}
+
+impl Eec1 {
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 8)?;
+        let torque_mode = payload[0] & 0x0F;
+        Ok(Self {
+            torque_mode: (torque_mode != 0x0F).then_some(torque_mode),
+            driver_demand_torque: u8_field(payload, 1).map(|v| v as i16 - 125),
+            actual_torque: u8_field(payload, 2).map(|v| v as i16 - 125),
+            engine_speed: u16_field(payload, 3).map(|v| v as f32 * 0.125),
+            controlling_source: payload.get(5).copied().filter(|v| *v != 0xFF),
+            engine_demand_torque: u8_field(payload, 7).map(|v| v as i16 - 125),
+        })
+    }
+}
+
+/// Fuel Economy (Liquid) (PGN 65266).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FuelEconomy {
+    /// Engine fuel rate (SPN 183), in L/h.
+    pub fuel_rate: Option<f32>,
+    /// Instantaneous fuel economy (SPN 184), in km/L.
+    pub instantaneous_economy: Option<f32>,
+    /// Average fuel economy (SPN 185), in km/L.
+    pub average_economy: Option<f32>,
+    /// Accelerator pedal / throttle valve position (SPN 51), in %.
+    pub throttle_position: Option<f32>,
+}
+
+impl FuelEconomy {
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 7)?;
+        Ok(Self {
+            fuel_rate: u16_field(payload, 0).map(|v| v as f32 * 0.05),
+            instantaneous_economy: u16_field(payload, 2).map(|v| v as f32 / 512.0),
+            average_economy: u16_field(payload, 4).map(|v| v as f32 / 512.0),
+            throttle_position: u8_field(payload, 6).map(|v| v as f32 * 0.4),
+        })
+    }
+}
+
+/// State of one of the four DM1 indicator lamps. `None` when the sender
+/// reports the lamp as not available.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LampStatus {
+    pub malfunction_indicator: Option<bool>,
+    pub red_stop: Option<bool>,
+    pub amber_warning: Option<bool>,
+    pub protect: Option<bool>,
+}
+
+impl LampStatus {
+    pub fn from_byte(byte: u8) -> Self {
+        let lamp = |shift: u8| match (byte >> shift) & 0x03 {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        };
+        Self {
+            malfunction_indicator: lamp(6),
+            red_stop: lamp(4),
+            amber_warning: lamp(2),
+            protect: lamp(0),
+        }
+    }
+}
+
+/// Diagnostic trouble code: suspect parameter, failure mode and occurrence count.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dtc {
+    pub spn: u32,
+    pub fmi: u8,
+    pub occurrence_count: u8,
+    /// SPN conversion method bit. Set by senders using the pre-2000 SPN byte
+    /// order, in which case `spn` should be treated with caution.
+    pub conversion_method: bool,
+}
+
+impl Dtc {
+    pub fn from_bytes(bytes: &[u8; 4]) -> Self {
+        Self {
+            spn: bytes[0] as u32 | (bytes[1] as u32) << 8 | ((bytes[2] as u32) >> 5) << 16,
+            fmi: bytes[2] & 0x1F,
+            occurrence_count: bytes[3] & 0x7F,
+            conversion_method: bytes[3] & 0x80 != 0,
+        }
+    }
+}
+
+/// Active (DM1, PGN 65226) or previously active (DM2, PGN 65227) diagnostic
+/// trouble codes.
+///
+/// A single trouble code fits in one 8-byte frame; longer lists are sent as
+/// multi-packet messages whose reassembled payload can be passed here whole.
+/// Trouble codes are read lazily from the borrowed payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiagnosticMessage<'a> {
+    pub lamps: LampStatus,
+    dtc_bytes: &'a [u8],
+}
+
+pub type Dm1<'a> = DiagnosticMessage<'a>;
+pub type Dm2<'a> = DiagnosticMessage<'a>;
+
+impl<'a> DiagnosticMessage<'a> {
+    pub fn from_payload(payload: &'a [u8]) -> Result<Self, Error> {
+        check_len(payload, 6)?;
+        Ok(Self {
+            lamps: LampStatus::from_byte(payload[0]),
+            dtc_bytes: &payload[2..],
+        })
+    }
+
+    /// Iterates over the reported trouble codes, skipping the all-zero
+    /// placeholder sent when no code is active and trailing padding.
+    pub fn dtcs(&self) -> impl Iterator<Item = Dtc> + 'a {
+        self.dtc_bytes
+            .chunks_exact(4)
+            .filter(|b| b.iter().any(|v| *v != 0x00) && b.iter().any(|v| *v != 0xFF))
+            .map(|b| Dtc::from_bytes(b.try_into().unwrap()))
+    }
+
+    pub fn dtc_count(&self) -> usize {
+        self.dtcs().count()
+    }
+}
+
+impl fmt::Display for Eec1 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "eec1 {:.0}rpm torque={}% demand={}%",
+            Opt(self.engine_speed),
+            Opt(self.actual_torque),
+            Opt(self.driver_demand_torque)
+        )
+    }
+}
+
+impl fmt::Display for FuelEconomy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "fuel rate={:.2}L/h economy={:.2}km/L throttle={:.1}%",
+            Opt(self.fuel_rate),
+            Opt(self.instantaneous_economy),
+            Opt(self.throttle_position)
+        )
+    }
+}
+
+impl fmt::Display for Dtc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "spn={} fmi={} oc={}",
+            self.spn, self.fmi, self.occurrence_count
+        )
+    }
+}
+
+impl fmt::Display for DiagnosticMessage<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "dm dtcs={}", self.dtc_count())?;
+        for dtc in self.dtcs() {
+            write!(f, " [{}]", dtc)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eec1() {
+        let payload: [u8; 8] = [0xF1, 0x7D, 0x91, 0x40, 0x2F, 0x00, 0xFF, 0xFF];
+        let eec1 = Eec1::from_payload(&payload).unwrap();
+        assert_eq!(eec1.torque_mode, Some(1));
+        assert_eq!(eec1.driver_demand_torque, Some(0));
+        assert_eq!(eec1.actual_torque, Some(20));
+        assert_eq!(eec1.engine_speed, Some(1512.0));
+        assert_eq!(eec1.controlling_source, Some(0));
+        assert_eq!(eec1.engine_demand_torque, None);
+
+        assert_eq!(Eec1::from_payload(&payload[..7]), Err(Error::InvalidLength));
+    }
+
+    #[test]
+    fn test_fuel_economy() {
+        let payload: [u8; 8] = [0xE8, 0x03, 0x00, 0x0A, 0xFF, 0xFF, 0x7D, 0xFF];
+        let lfe = FuelEconomy::from_payload(&payload).unwrap();
+        assert_eq!(lfe.fuel_rate, Some(50.0));
+        assert_eq!(lfe.instantaneous_economy, Some(5.0));
+        assert_eq!(lfe.average_economy, None);
+        assert_eq!(lfe.throttle_position, Some(50.0));
+    }
+
+    #[test]
+    fn test_dm1_single_frame() {
+        // Amber warning lamp on, SPN 110 (coolant temperature) FMI 16, seen 3 times.
+        let payload: [u8; 8] = [0x04, 0xFF, 0x6E, 0x00, 0x10, 0x03, 0xFF, 0xFF];
+        let dm1 = Dm1::from_payload(&payload).unwrap();
+        assert_eq!(dm1.lamps.amber_warning, Some(true));
+        assert_eq!(dm1.lamps.red_stop, Some(false));
+        let mut dtcs = dm1.dtcs();
+        assert_eq!(
+            dtcs.next(),
+            Some(Dtc {
+                spn: 110,
+                fmi: 16,
+                occurrence_count: 3,
+                conversion_method: false,
+            })
+        );
+        assert_eq!(dtcs.next(), None);
+
+        let payload: [u8; 8] = [0x00, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF];
+        assert_eq!(Dm1::from_payload(&payload).unwrap().dtc_count(), 0);
+        assert_eq!(Dm1::from_payload(&payload[..5]), Err(Error::InvalidLength));
+    }
+
+    #[test]
+    fn test_dm2_multi_packet() {
+        // Reassembled multi-packet payload with three previously active codes:
+        // SPN 100 FMI 1, SPN 524287 FMI 31 and SPN 3251 FMI 0.
+        let payload: [u8; 14] = [
+            0x40, 0xFF, 0x64, 0x00, 0x01, 0x01, 0xFF, 0xFF, 0xFF, 0x7E, 0xB3, 0x0C, 0x00, 0x82,
+        ];
+        let dm2 = Dm2::from_payload(&payload).unwrap();
+        assert_eq!(dm2.lamps.malfunction_indicator, Some(true));
+        assert_eq!(dm2.dtc_count(), 3);
+
+        let dtcs: [Dtc; 3] = {
+            let mut it = dm2.dtcs();
+            [it.next().unwrap(), it.next().unwrap(), it.next().unwrap()]
+        };
+        assert_eq!(
+            (dtcs[0].spn, dtcs[0].fmi, dtcs[0].occurrence_count),
+            (100, 1, 1)
+        );
+        assert_eq!(
+            (dtcs[1].spn, dtcs[1].fmi, dtcs[1].occurrence_count),
+            (524287, 31, 126)
+        );
+        assert_eq!(
+            (dtcs[2].spn, dtcs[2].fmi, dtcs[2].occurrence_count),
+            (3251, 0, 2)
+        );
+        assert!(dtcs[2].conversion_method);
+        assert_eq!(
+            alloc::format!("{}", dm2),
+            "dm dtcs=3 [spn=100 fmi=1 oc=1] [spn=524287 fmi=31 oc=126] [spn=3251 fmi=0 oc=2]"
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! User labels for the instances of environmental sensors.
+//!
+//! Temperature, humidity and pressure PGNs identify a sensor only by the
+//! sender's address and an instance number. `LabelRegistry` maps those pairs
+//! to names such as "Fridge" or "Engine room" so applications can present
+//! multi-sensor installs consistently.
+use crate::pgn::environment::{Humidity, Pressure, Temperature};
+use fixed_queue::LinearMap;
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Label table is full")]
+    FullTable,
+    #[error("Label is too long")]
+    LabelTooLong,
+}
+
+/// A sensor on the bus: the sender's source address and the instance field
+/// of its PGNs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct InstanceKey {
+    pub source: u8,
+    pub instance: u8,
+}
+
+impl InstanceKey {
+    /// Reads the instance of a temperature (130312, 130316), humidity
+    /// (130313) or pressure (130314, 130315) payload. Returns `None` for
+    /// other PGNs and truncated payloads.
+    pub fn from_payload(source: u8, pgn: u32, payload: &[u8]) -> Option<Self> {
+        match pgn {
+            Temperature::PGN
+            | Temperature::EXTENDED_PGN
+            | Humidity::PGN
+            | Pressure::ACTUAL_PGN
+            | Pressure::SET_PGN => Some(Self {
+                source,
+                instance: *payload.get(1)?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Label<const L: usize> {
+    bytes: [u8; L],
+    len: usize,
+}
+
+impl<const L: usize> Label<L> {
+    fn new(label: &str) -> Result<Self, Error> {
+        if label.len() > L {
+            return Err(Error::LabelTooLong);
+        }
+        let mut bytes = [0; L];
+        bytes[..label.len()].copy_from_slice(label.as_bytes());
+        Ok(Self {
+            bytes,
+            len: label.len(),
+        })
+    }
+
+    fn as_str(&self) -> &str {
+        // Only ever built from a `&str`.
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap()
+    }
+}
+
+/// Up to `N` labels of at most `L` bytes each.
+pub struct LabelRegistry<const N: usize, const L: usize> {
+    labels: LinearMap<InstanceKey, Label<L>, N>,
+}
+
+impl<const N: usize, const L: usize> Default for LabelRegistry<N, L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const L: usize> LabelRegistry<N, L> {
+    pub const fn new() -> Self {
+        Self {
+            labels: LinearMap::new(),
+        }
+    }
+
+    /// Sets the label of `key`, replacing any previous label.
+    pub fn insert(&mut self, key: InstanceKey, label: &str) -> Result<(), Error> {
+        let label = Label::new(label)?;
+        if let Some(existing) = self.labels.get_mut(&key) {
+            *existing = label;
+            return Ok(());
+        }
+        self.labels
+            .insert(key, label)
+            .map_err(|_| Error::FullTable)?;
+        Ok(())
+    }
+
+    pub fn get(&self, key: &InstanceKey) -> Option<&str> {
+        self.labels.get(key).map(Label::as_str)
+    }
+
+    /// Looks up the label of the sensor that sent `payload`.
+    pub fn label_for(&self, source: u8, pgn: u32, payload: &[u8]) -> Option<&str> {
+        self.get(&InstanceKey::from_payload(source, pgn, payload)?)
+    }
+
+    pub fn remove(&mut self, key: &InstanceKey) -> bool {
+        self.labels.remove(key).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (InstanceKey, &str)> {
+        self.labels.iter().map(|(k, v)| (*k, v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
+
+/// One registry entry in the form used for persistence.
+#[cfg(any(test, feature = "serde"))]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LabelEntry {
+    #[serde(flatten)]
+    pub key: InstanceKey,
+    pub label: alloc::string::String,
+}
+
+#[cfg(any(test, feature = "serde"))]
+impl<const N: usize, const L: usize> LabelRegistry<N, L> {
+    /// Returns the entries to serialize, in insertion order.
+    pub fn to_entries(&self) -> alloc::vec::Vec<LabelEntry> {
+        self.iter()
+            .map(|(key, label)| LabelEntry {
+                key,
+                label: label.into(),
+            })
+            .collect()
+    }
+
+    /// Builds a registry from deserialized entries.
+    pub fn from_entries(entries: &[LabelEntry]) -> Result<Self, Error> {
+        let mut registry = Self::new();
+        for entry in entries {
+            registry.insert(entry.key, &entry.label)?;
+        }
+        Ok(registry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRIDGE: InstanceKey = InstanceKey {
+        source: 35,
+        instance: 1,
+    };
+
+    #[test]
+    fn test_registry() {
+        let mut registry: LabelRegistry<2, 12> = LabelRegistry::new();
+        registry.insert(FRIDGE, "Fridge").unwrap();
+        registry
+            .insert(
+                InstanceKey {
+                    source: 35,
+                    instance: 2,
+                },
+                "Freezer",
+            )
+            .unwrap();
+        assert_eq!(
+            registry.insert(
+                InstanceKey {
+                    source: 36,
+                    instance: 1
+                },
+                "Cabin"
+            ),
+            Err(Error::FullTable)
+        );
+        assert_eq!(
+            registry.insert(FRIDGE, "Galley refrigerator"),
+            Err(Error::LabelTooLong)
+        );
+        registry.insert(FRIDGE, "Galley").unwrap();
+        assert_eq!(registry.get(&FRIDGE), Some("Galley"));
+
+        let temperature: [u8; 8] = [0xFF, 0x01, 0x07, 0x43, 0x6C, 0xFF, 0xFF, 0xFF];
+        assert_eq!(registry.label_for(35, 130312, &temperature), Some("Galley"));
+        assert_eq!(registry.label_for(36, 130312, &temperature), None);
+        assert_eq!(registry.label_for(35, 130306, &temperature), None);
+
+        assert!(registry.remove(&FRIDGE));
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn test_persistence() {
+        let mut registry: LabelRegistry<4, 16> = LabelRegistry::new();
+        registry.insert(FRIDGE, "Fridge").unwrap();
+        let json = serde_json::to_string(&registry.to_entries()).unwrap();
+        assert_eq!(json, r#"[{"source":35,"instance":1,"label":"Fridge"}]"#);
+
+        let entries: alloc::vec::Vec<LabelEntry> = serde_json::from_str(&json).unwrap();
+        let restored: LabelRegistry<4, 16> = LabelRegistry::from_entries(&entries).unwrap();
+        assert_eq!(restored.get(&FRIDGE), Some("Fridge"));
+    }
+}
@@ -1,6 +1,85 @@
-#![cfg_attr(not(feature = "pyo3"), no_std)]
+#![cfg_attr(not(any(feature = "pyo3", feature = "std")), no_std)]
 
+#[cfg(any(test, feature = "alloc"))]
+extern crate alloc;
+#[cfg(test)]
+extern crate std;
+
+#[macro_use]
+mod logging;
+
+pub mod address_claim;
+pub mod address_map;
+pub mod alert;
+pub mod analyzer;
+pub mod anchor_watch;
+pub mod arbitration;
 #[cfg(feature = "pyo3")]
 pub mod binding;
+pub mod binlog;
+pub mod bridge;
+#[cfg(any(test, feature = "std"))]
+pub mod bulk;
+pub mod calibration;
+pub mod can_id;
+pub mod candump;
+pub mod canfd;
+pub mod clock;
+#[cfg(any(test, feature = "serde"))]
+pub mod config;
+pub mod conformance;
+#[cfg(any(test, feature = "csv"))]
+pub mod csv;
+pub mod current;
+pub mod demux;
+pub mod depth;
+pub mod device;
+#[cfg(any(test, feature = "csv"))]
+pub mod diff;
+pub mod engine_hours;
+#[cfg(any(test, feature = "etp"))]
+pub mod etp;
+pub mod firmware;
+pub mod flight_recorder;
+pub mod frame_pool;
+pub mod frame_queue;
+#[cfg(any(test, feature = "std"))]
+pub mod gpx;
+pub mod instance_conflict;
+pub mod integrity;
+#[cfg(any(test, feature = "j1939"))]
+pub mod j1939;
+pub mod labels;
+#[cfg(any(test, feature = "testing"))]
+pub mod lossy;
+#[cfg(any(test, feature = "manufacturers"))]
+pub mod manufacturers;
+#[cfg(any(test, feature = "mqtt"))]
+pub mod mqtt;
+pub mod name;
+pub mod nav_state;
 pub mod nmea_frame;
 pub mod nmea_message;
+pub mod pgn;
+pub mod pipeline;
+pub mod rate_limiter;
+pub mod reassembler;
+#[cfg(any(test, feature = "alloc"))]
+pub mod reference;
+pub mod requester;
+#[cfg(any(test, feature = "std"))]
+pub mod resample;
+mod rng;
+pub mod serial;
+#[cfg(any(test, feature = "sim"))]
+pub mod sim;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod stats;
+#[cfg(any(test, feature = "alloc"))]
+pub mod template;
+#[cfg(any(test, feature = "test-vectors"))]
+pub mod test_vectors;
+pub mod trigger_expr;
+pub mod trip;
+pub mod tunnel;
@@ -0,0 +1,16 @@
+//! Internal logging macros. They forward to the `log` crate when the `log`
+//! feature is enabled and expand to nothing otherwise.
+
+macro_rules! trace {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::trace!($($arg)+);
+    };
+}
+
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::debug!($($arg)+);
+    };
+}
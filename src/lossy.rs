@@ -0,0 +1,265 @@
+//! Fault injection for resilience tests.
+use crate::bridge::{Error, FrameSink, FrameSource};
+use crate::frame_queue::RxFrame;
+use crate::rng::Rng;
+use fixed_queue::Vec;
+
+/// Probabilities, between 0 and 1, of each fault being applied to a frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Faults {
+    pub drop: f32,
+    pub duplicate: f32,
+    /// Flips one random data bit.
+    pub bit_flip: f32,
+}
+
+/// Number of frames each fault was applied to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FaultCounts {
+    pub dropped: u32,
+    pub duplicated: u32,
+    pub corrupted: u32,
+}
+
+/// Wraps a `FrameSource` or `FrameSink` and drops, duplicates, corrupts and
+/// reorders the frames passing through it.
+///
+/// Frames are held in a window of `W` and released in random order once it
+/// is full, so `W = 1` disables reordering. A duplicate directly follows its
+/// original. The same seed always yields the same faults for the same
+/// traffic.
+pub struct LossyTransport<T, const W: usize> {
+    inner: T,
+    faults: Faults,
+    rng: Rng,
+    window: Vec<RxFrame, W>,
+    duplicate: Option<RxFrame>,
+    counts: FaultCounts,
+}
+
+impl<T, const W: usize> LossyTransport<T, W> {
+    pub fn new(inner: T, faults: Faults, seed: u64) -> Self {
+        Self {
+            inner,
+            faults,
+            rng: Rng::new(seed),
+            window: Vec::new(),
+            duplicate: None,
+            counts: FaultCounts::default(),
+        }
+    }
+
+    pub fn counts(&self) -> FaultCounts {
+        self.counts
+    }
+
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Applies drop and bit flip faults and adds the frame to the window.
+    fn admit(&mut self, mut frame: RxFrame) {
+        if self.rng.chance(self.faults.drop) {
+            self.counts.dropped += 1;
+            return;
+        }
+        if self.rng.chance(self.faults.bit_flip) {
+            let bit = self.rng.below(64);
+            frame.data[bit / 8] ^= 1 << (bit % 8);
+            self.counts.corrupted += 1;
+        }
+        let _ = self.window.push(frame);
+    }
+
+    /// Takes a random frame out of the window if it is full, or if `flush` is
+    /// set and it isn't empty.
+    fn release(&mut self, flush: bool) -> Option<RxFrame> {
+        if let Some(frame) = self.duplicate.take() {
+            return Some(frame);
+        }
+        let len = self.window.len();
+        if len == 0 || (len < W && !flush) {
+            return None;
+        }
+        let frame = self.window.swap_remove(self.rng.below(len));
+        if self.rng.chance(self.faults.duplicate) {
+            self.duplicate = Some(frame);
+            self.counts.duplicated += 1;
+        }
+        Some(frame)
+    }
+}
+
+impl<T: FrameSource, const W: usize> FrameSource for LossyTransport<T, W> {
+    fn receive(&mut self) -> Option<RxFrame> {
+        loop {
+            if let Some(frame) = self.release(false) {
+                return Some(frame);
+            }
+            match self.inner.receive() {
+                Some(frame) => self.admit(frame),
+                None => return self.release(true),
+            }
+        }
+    }
+}
+
+impl<T: FrameSink, const W: usize> FrameSink for LossyTransport<T, W> {
+    fn transmit(&mut self, frame: &RxFrame) -> Result<(), Error> {
+        self.admit(*frame);
+        while let Some(frame) = self.release(false) {
+            self.inner.transmit(&frame)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: FrameSink, const W: usize> LossyTransport<T, W> {
+    /// Transmits the frames still held for reordering.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        while let Some(frame) = self.release(true) {
+            self.inner.transmit(&frame)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::can_id::CanId;
+    use crate::reassembler::Reassembler;
+    use fixed_queue::VecDeque;
+
+    struct Frames(VecDeque<RxFrame, 64>);
+
+    impl FrameSource for Frames {
+        fn receive(&mut self) -> Option<RxFrame> {
+            self.0.pop_front()
+        }
+    }
+
+    impl FrameSink for Frames {
+        fn transmit(&mut self, frame: &RxFrame) -> Result<(), Error> {
+            self.0.push_back(*frame).map_err(|_| Error::Transmit)
+        }
+    }
+
+    fn frames(count: u8) -> Frames {
+        let mut frames = Frames(VecDeque::new());
+        for n in 0..count {
+            let id = CanId::new(2, 127250, n, 255).unwrap();
+            frames.transmit(&RxFrame { id, data: [n; 8] }).unwrap();
+        }
+        frames
+    }
+
+    fn receive_all<T: FrameSource>(source: &mut T) -> usize {
+        let mut count = 0;
+        while source.receive().is_some() {
+            count += 1;
+        }
+        count
+    }
+
+    #[test]
+    fn test_passthrough() {
+        let mut lossy: LossyTransport<_, 1> = LossyTransport::new(frames(3), Faults::default(), 1);
+        for n in 0..3 {
+            assert_eq!(lossy.receive().unwrap().data, [n; 8]);
+        }
+        assert_eq!(lossy.receive(), None);
+        assert_eq!(lossy.counts(), FaultCounts::default());
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let faults = Faults {
+            drop: 0.2,
+            duplicate: 0.2,
+            bit_flip: 0.2,
+        };
+        let mut first: LossyTransport<_, 4> = LossyTransport::new(frames(50), faults, 7);
+        let mut second: LossyTransport<_, 4> = LossyTransport::new(frames(50), faults, 7);
+        while let Some(frame) = first.receive() {
+            assert_eq!(second.receive(), Some(frame));
+        }
+        assert_eq!(second.receive(), None);
+
+        let counts = first.counts();
+        assert!(counts.dropped > 0 && counts.duplicated > 0 && counts.corrupted > 0);
+        let mut third: LossyTransport<_, 4> = LossyTransport::new(frames(50), faults, 7);
+        assert_eq!(
+            receive_all(&mut third),
+            (50 - counts.dropped + counts.duplicated) as usize
+        );
+    }
+
+    #[test]
+    fn test_reorder_sink() {
+        let mut lossy: LossyTransport<_, 8> =
+            LossyTransport::new(Frames(VecDeque::new()), Faults::default(), 3);
+        let mut source = frames(20);
+        while let Some(frame) = source.receive() {
+            lossy.transmit(&frame).unwrap();
+        }
+        lossy.flush().unwrap();
+        let mut sink = lossy.into_inner();
+        let mut seen = [false; 20];
+        let mut in_order = true;
+        let mut last = None;
+        while let Some(frame) = sink.receive() {
+            let n = frame.data[0];
+            seen[n as usize] = true;
+            in_order &= last.is_none_or(|last| last < n);
+            last = Some(n);
+        }
+        assert!(seen.iter().all(|s| *s));
+        assert!(!in_order);
+    }
+
+    #[test]
+    fn test_reassembler_survives() {
+        // Lost and corrupted frames break sessions but never panic, and
+        // sessions after them are still reassembled.
+        let faults = Faults {
+            drop: 0.1,
+            duplicate: 0.1,
+            bit_flip: 0.1,
+        };
+        let mut bus = Frames(VecDeque::new());
+        let payload = [0x55; 20];
+        for sequence_counter in 0..8 {
+            let mut msg =
+                crate::nmea_message::Message::from_payload(&payload, sequence_counter).unwrap();
+            let id = CanId::new(3, 129029, 1, 255).unwrap();
+            while let Some(frame) = msg.pop_frame() {
+                bus.transmit(&RxFrame {
+                    id,
+                    data: frame.bytes,
+                })
+                .unwrap();
+            }
+        }
+        let mut lossy: LossyTransport<_, 1> = LossyTransport::new(bus, faults, 11);
+        let mut reassembler: Reassembler<2> = Reassembler::new();
+        let mut complete = 0;
+        while let Some(frame) = lossy.receive() {
+            if let Ok(true) = reassembler.add_frame(1, 129029, &frame.data) {
+                let mut buf = [0; 223];
+                if reassembler.get_payload(1, 129029, &mut buf).is_ok() {
+                    complete += 1;
+                }
+            }
+        }
+        assert!(complete > 0);
+    }
+}
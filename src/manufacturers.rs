@@ -0,0 +1,183 @@
+//! NMEA 2000 manufacturer names by manufacturer code.
+//!
+//! Generated by `scripts/gen_manufacturers.py` from
+//! `scripts/manufacturers.csv`; edit those instead.
+
+/// Manufacturer codes and names, sorted by code.
+pub const MANUFACTURERS: &[(u16, &str)] = &[
+    (69, "ARKS Enterprises"),
+    (78, "FW Murphy/Enovation Controls"),
+    (80, "Twin Disc"),
+    (85, "Kohler Power Systems"),
+    (88, "Hemisphere GPS"),
+    (116, "BEP Marine"),
+    (135, "Airmar"),
+    (137, "Maretron"),
+    (140, "Lowrance"),
+    (144, "Mercury Marine"),
+    (147, "Nautibus Electronic"),
+    (148, "Blue Water Data"),
+    (154, "Westerbeke"),
+    (161, "Offshore Systems"),
+    (163, "Evinrude/BRP"),
+    (165, "CPAC Systems"),
+    (168, "Xantrex Technology"),
+    (172, "Yanmar Marine"),
+    (174, "Volvo Penta"),
+    (176, "Carling Technologies"),
+    (185, "Beede Instruments"),
+    (192, "Floscan Instrument"),
+    (193, "Nobletec"),
+    (198, "Mystic Valley Communications"),
+    (199, "Actia"),
+    (211, "Digital Switching Systems"),
+    (215, "Aetna Engineering/Fireboy-Xintex"),
+    (224, "EMMI Network"),
+    (228, "ZF Marine"),
+    (229, "Garmin"),
+    (233, "Yacht Monitoring Solutions"),
+    (235, "Sailormade Marine Telemetry"),
+    (243, "Eride"),
+    (257, "Honda Motor"),
+    (272, "Groco"),
+    (273, "Actisense"),
+    (274, "Amphenol LTW Technology"),
+    (275, "Navico"),
+    (283, "Hamilton Jet"),
+    (285, "Sea Recovery"),
+    (286, "Coelmo SRL"),
+    (295, "BEP Marine"),
+    (304, "Empir Bus"),
+    (305, "NovAtel"),
+    (306, "Sleipner Motor"),
+    (315, "ICOM"),
+    (328, "Qwerty"),
+    (341, "Böning"),
+    (351, "Thrane and Thrane"),
+    (355, "Mastervolt"),
+    (356, "Fischer Panda"),
+    (358, "Victron Energy"),
+    (370, "Rolls Royce Marine"),
+    (373, "Electronic Design"),
+    (374, "Northern Lights"),
+    (378, "Glendinning"),
+    (381, "B&G"),
+    (384, "Rose Point Navigation Systems"),
+    (385, "Johnson Outdoors Marine Electronics"),
+    (394, "Capi 2"),
+    (396, "Beyond Measure"),
+    (400, "Livorsi Marine"),
+    (404, "ComNav"),
+    (409, "Chetco"),
+    (419, "Fusion Electronics"),
+    (421, "Standard Horizon"),
+    (422, "True Heading"),
+    (426, "Egersund Marine Electronics"),
+    (427, "Em-Trak Marine Electronics"),
+    (431, "Tohatsu"),
+    (437, "Digital Yacht"),
+    (438, "Comar Systems"),
+    (440, "Cummins"),
+    (443, "VDO"),
+    (451, "Parker Hannifin"),
+    (459, "Alltek Marine Electronics"),
+    (460, "San Giorgio S.E.I.N."),
+    (466, "Veethree Electronics & Marine"),
+    (467, "Humminbird Marine Electronics"),
+    (470, "SI-TEX Marine Electronics"),
+    (471, "Sea Cross Marine"),
+    (475, "GME"),
+    (478, "Ocean Sat"),
+    (481, "Chetco Digital Instruments"),
+    (493, "Watcheye"),
+    (499, "LCJ Capteurs"),
+    (502, "Attwood Marine"),
+    (503, "Naviop"),
+    (504, "Vesper Marine"),
+    (510, "Marinesoft"),
+    (517, "NoLand Engineering"),
+    (518, "Transas USA"),
+    (529, "National Instruments Korea"),
+    (532, "Onwa Marine"),
+    (573, "McMurdo Group"),
+    (578, "Advansea"),
+    (579, "KVH"),
+    (580, "San Jose Technology"),
+    (583, "Yacht Control"),
+    (586, "Suzuki Motor"),
+    (591, "US Coast Guard"),
+    (595, "Ship Module"),
+    (600, "Aquatic AV"),
+    (605, "Aventics"),
+    (606, "Intellian"),
+    (612, "SamwonIT"),
+    (614, "Arlt Tecnologies"),
+    (637, "Bavaria Yachts"),
+    (641, "Diverse Yacht Services"),
+    (644, "Wema U.S.A"),
+    (645, "Garmin"),
+    (658, "Shenzhen Jiuzhou Himunication"),
+    (688, "Rockford"),
+    (704, "JL Audio"),
+    (715, "Autonnic"),
+    (717, "Yacht Devices"),
+    (734, "REAP Systems"),
+    (735, "Au Electronics Group"),
+    (739, "LxNav"),
+    (743, "DaeMyung"),
+    (744, "Woosung"),
+    (773, "Clarion US"),
+    (776, "HMI Systems"),
+    (777, "Ocean Signal"),
+    (778, "Seekeeper"),
+    (781, "Poly Planar"),
+    (785, "Fischer Panda DE"),
+    (795, "Broyda Industries"),
+    (796, "Canadian Automotive"),
+    (797, "Tides Marine"),
+    (798, "Lumishore"),
+    (799, "Still Water Designs and Audio"),
+    (802, "BJ Technologies (Beneteau)"),
+    (803, "Gill Sensors"),
+    (811, "Blue Water Desalination"),
+    (815, "FLIR"),
+    (824, "Undheim Systems"),
+    (838, "TeamSurv"),
+    (844, "Fell Marine"),
+    (847, "Oceanvolt"),
+    (862, "Prospec"),
+    (868, "Data Panel"),
+    (890, "L3 Technologies"),
+    (894, "Rhodan Marine Systems"),
+    (896, "Nexfour Solutions"),
+    (905, "ASA Electronics"),
+    (909, "Marines Co (South Korea)"),
+    (911, "Nautic-on"),
+    (930, "Ecotronix"),
+    (962, "Timbolier Industries"),
+    (963, "TJC Micro"),
+    (968, "Cox Powertrain"),
+    (969, "Blue Seas"),
+    (1850, "Teleflex Marine (SeaStar Solutions)"),
+    (1851, "Raymarine"),
+    (1852, "Navionics"),
+    (1853, "Japan Radio"),
+    (1854, "Northstar Technologies"),
+    (1855, "Furuno"),
+    (1856, "Trimble"),
+    (1857, "Simrad"),
+    (1858, "Litton"),
+    (1859, "Kvasar"),
+    (1860, "MMP"),
+    (1861, "Vector Cantech"),
+    (1862, "Yamaha Marine"),
+    (1863, "Faria Instruments"),
+];
+
+/// The name of the manufacturer with NMEA code `code`, if known.
+pub fn name(code: u16) -> Option<&'static str> {
+    MANUFACTURERS
+        .binary_search_by_key(&code, |(c, _)| *c)
+        .ok()
+        .map(|i| MANUFACTURERS[i].1)
+}
@@ -0,0 +1,311 @@
+//! Publishing decoded messages as JSON over MQTT.
+use crate::csv::Fields;
+use crate::pgn::info;
+use crate::template::{Context, Template};
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::string::String;
+use std::vec::Vec;
+
+/// An MQTT connection messages can be published on. Implemented by
+/// `TcpClient`; implement it for other clients to use their connection
+/// handling and QoS.
+pub trait Publish {
+    fn publish(&mut self, topic: &str, payload: &[u8]) -> io::Result<()>;
+}
+
+/// A minimal MQTT 3.1.1 client publishing with QoS 0 over plain TCP.
+pub struct TcpClient {
+    stream: TcpStream,
+}
+
+fn encode_len(buf: &mut Vec<u8>, mut len: usize) {
+    loop {
+        let byte = (len % 128) as u8;
+        len /= 128;
+        if len == 0 {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+fn encode_str(buf: &mut Vec<u8>, s: &[u8]) {
+    buf.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    buf.extend_from_slice(s);
+}
+
+fn packet(kind: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(body.len() + 5);
+    packet.push(kind);
+    encode_len(&mut packet, body.len());
+    packet.extend_from_slice(body);
+    packet
+}
+
+impl TcpClient {
+    /// Connects to the broker at `address` with a clean session.
+    pub fn connect(address: impl ToSocketAddrs, client_id: &str) -> io::Result<Self> {
+        let mut stream = TcpStream::connect(address)?;
+        let mut body = Vec::new();
+        encode_str(&mut body, b"MQTT");
+        // Protocol level 4, clean session, 60 s keep alive.
+        body.extend_from_slice(&[0x04, 0x02, 0x00, 0x3C]);
+        encode_str(&mut body, client_id.as_bytes());
+        stream.write_all(&packet(0x10, &body))?;
+
+        let mut connack = [0; 4];
+        stream.read_exact(&mut connack)?;
+        if connack[0] != 0x20 || connack[3] != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                "MQTT connection refused",
+            ));
+        }
+        Ok(Self { stream })
+    }
+}
+
+impl Publish for TcpClient {
+    fn publish(&mut self, topic: &str, payload: &[u8]) -> io::Result<()> {
+        let mut body = Vec::with_capacity(topic.len() + payload.len() + 2);
+        encode_str(&mut body, topic.as_bytes());
+        body.extend_from_slice(payload);
+        self.stream.write_all(&packet(0x30, &body))
+    }
+}
+
+fn write_json_str(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+/// Formats `decoded` as a JSON object of its fields. Unavailable values are
+/// `null` and lookups are strings holding the variant name.
+pub fn to_json(source: u8, pgn: u32, decoded: &impl Fields) -> String {
+    let mut json = std::format!("{{\"source\":{},\"pgn\":{}", source, pgn);
+    decoded.fields(&mut |name, value| {
+        json.push(',');
+        write_json_str(&mut json, name);
+        json.push(':');
+        write_json_value(&mut json, &value);
+    });
+    json.push('}');
+    json
+}
+
+fn write_json_value(json: &mut String, value: &str) {
+    if value.is_empty() {
+        json.push_str("null");
+    } else if value.parse::<f64>().is_ok_and(f64::is_finite) {
+        json.push_str(value);
+    } else {
+        write_json_str(json, value);
+    }
+}
+
+/// The snake case name of `pgn` in `pgn::info`, e.g. `wind_data`.
+fn topic_name(pgn: u32) -> Option<String> {
+    let name = info::find(pgn)?.name;
+    let mut topic = String::with_capacity(name.len());
+    for word in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        if word.is_empty() {
+            continue;
+        }
+        if !topic.is_empty() {
+            topic.push('_');
+        }
+        topic.extend(word.chars().map(|c| c.to_ascii_lowercase()));
+    }
+    Some(topic)
+}
+
+/// Publishes decoded messages on topics rendered from a `Template`.
+///
+/// `new` publishes to `{prefix}/{pgn}/{instance}/{name}`, e.g.
+/// `n2k/127505/0/fluid_level`, with the JSON from `to_json` as payload.
+/// Templates with a `{field}` placeholder publish each field on its own
+/// topic instead, with the bare JSON value as payload.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::mqtt::{Publish, Publisher};
+/// use nmea::pgn::fluid_level::FluidLevel;
+/// use nmea::template::Template;
+///
+/// struct Topics(Vec<String>);
+///
+/// impl Publish for Topics {
+///     fn publish(&mut self, topic: &str, _payload: &[u8]) -> std::io::Result<()> {
+///         self.0.push(topic.to_string());
+///         Ok(())
+///     }
+/// }
+///
+/// let level = FluidLevel::from_payload(&[0x00, 0x20, 0x4E, 0xE8, 0x03, 0x00, 0x00, 0xFF]).unwrap();
+///
+/// let mut publisher = Publisher::new(Topics(Vec::new()), "n2k");
+/// publisher.publish(0x10, FluidLevel::PGN, &level).unwrap();
+/// assert_eq!(publisher.client().0, ["n2k/127505/0/fluid_level"]);
+///
+/// let template = Template::parse("tanks/{instance}/{field}").unwrap();
+/// let mut publisher = Publisher::with_template(Topics(Vec::new()), template);
+/// publisher.publish(0x10, FluidLevel::PGN, &level).unwrap();
+/// assert_eq!(
+///     publisher.client().0,
+///     ["tanks/0/instance", "tanks/0/fluid_type", "tanks/0/level", "tanks/0/capacity"]
+/// );
+/// ```
+pub struct Publisher<P: Publish> {
+    client: P,
+    template: Template,
+}
+
+impl<P: Publish> Publisher<P> {
+    pub fn new(client: P, prefix: &str) -> Self {
+        let prefix = prefix.replace('{', "{{").replace('}', "}}");
+        let template = std::format!("{}/{{pgn}}/{{instance}}/{{name}}", prefix);
+        // Placeholders are fixed and braces in the prefix escaped.
+        Self::with_template(client, Template::parse(&template).unwrap())
+    }
+
+    pub fn with_template(client: P, template: Template) -> Self {
+        Self { client, template }
+    }
+
+    pub fn client(&self) -> &P {
+        &self.client
+    }
+
+    /// Publishes `decoded`. PGNs missing from `pgn::info` are named by
+    /// their number.
+    pub fn publish(&mut self, source: u8, pgn: u32, decoded: &impl Fields) -> io::Result<()> {
+        let mut fields: Vec<(&'static str, String)> = Vec::new();
+        decoded.fields(&mut |name, value| fields.push((name, value)));
+        let name = topic_name(pgn).unwrap_or_else(|| std::format!("{}", pgn));
+        let mut context = Context {
+            pgn,
+            source,
+            instance: fields
+                .iter()
+                .find(|(field, _)| *field == "instance")
+                .map(|(_, value)| value.as_str()),
+            name: &name,
+            field: None,
+        };
+
+        if !self.template.has_field() {
+            let topic = self.template.render(&context);
+            return self
+                .client
+                .publish(&topic, to_json(source, pgn, decoded).as_bytes());
+        }
+        for (field, value) in &fields {
+            context.field = Some(field);
+            let mut payload = String::new();
+            write_json_value(&mut payload, value);
+            self.client
+                .publish(&self.template.render(&context), payload.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::wind::WindData;
+    use std::net::TcpListener;
+
+    struct Messages(Vec<(String, String)>);
+
+    impl Publish for Messages {
+        fn publish(&mut self, topic: &str, payload: &[u8]) -> io::Result<()> {
+            let payload = String::from_utf8(payload.to_vec()).unwrap();
+            self.0.push((topic.into(), payload));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_publish() {
+        let wind =
+            WindData::from_payload(&[0x01, 0xF4, 0x01, 0xFF, 0xFF, 0x02, 0xFF, 0xFF]).unwrap();
+        let mut publisher = Publisher::new(Messages(Vec::new()), "boat");
+        publisher.publish(0x23, WindData::PGN, &wind).unwrap();
+        assert_eq!(
+            publisher.client().0,
+            [(
+                "boat/130306/35/wind_data".into(),
+                "{\"source\":35,\"pgn\":130306,\"sid\":1,\"speed\":5,\"angle\":null,\
+                 \"reference\":\"Apparent\"}"
+                    .into()
+            )]
+        );
+        assert_eq!(topic_name(129026).unwrap(), "cog_sog_rapid_update");
+    }
+
+    #[test]
+    fn test_publish_fields() {
+        let wind =
+            WindData::from_payload(&[0x01, 0xF4, 0x01, 0xFF, 0xFF, 0x02, 0xFF, 0xFF]).unwrap();
+        let template = Template::parse("{name}/{src}/{field}").unwrap();
+        let mut publisher = Publisher::with_template(Messages(Vec::new()), template);
+        publisher.publish(0x23, WindData::PGN, &wind).unwrap();
+        assert_eq!(
+            publisher.client().0[1..],
+            [
+                ("wind_data/35/speed".into(), "5".into()),
+                ("wind_data/35/angle".into(), "null".into()),
+                ("wind_data/35/reference".into(), "\"Apparent\"".into()),
+            ]
+        );
+
+        // Braces in the prefix are taken literally.
+        let mut publisher = Publisher::new(Messages(Vec::new()), "{boat}");
+        publisher.publish(0x23, WindData::PGN, &wind).unwrap();
+        assert_eq!(publisher.client().0[0].0, "{boat}/130306/35/wind_data");
+    }
+
+    #[test]
+    fn test_tcp_client() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let broker = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut connect = [0; 16];
+            stream.read_exact(&mut connect).unwrap();
+            stream.write_all(&[0x20, 0x02, 0x00, 0x00]).unwrap();
+            let mut publish = Vec::new();
+            stream.read_to_end(&mut publish).unwrap();
+            (connect, publish)
+        });
+
+        let mut client = TcpClient::connect(address, "nm").unwrap();
+        client.publish("a/b", b"{}").unwrap();
+        drop(client);
+
+        let (connect, publish) = broker.join().unwrap();
+        assert_eq!(
+            connect,
+            [0x10, 14, 0, 4, b'M', b'Q', b'T', b'T', 4, 0x02, 0, 60, 0, 2, b'n', b'm']
+        );
+        assert_eq!(publish, [0x30, 7, 0, 3, b'a', b'/', b'b', b'{', b'}']);
+    }
+
+    #[test]
+    fn test_encode_len() {
+        let mut buf = Vec::new();
+        encode_len(&mut buf, 321);
+        assert_eq!(buf, [0xC1, 0x02]);
+    }
+}
@@ -0,0 +1,157 @@
+//! ISO 11783 NAMEs, the 64-bit identities devices claim their addresses
+//! with.
+use core::fmt;
+
+/// The fields of a NAME.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NameParts {
+    /// Serial number unique among the manufacturer's devices, 21 bits.
+    pub identity_number: u32,
+    /// NMEA manufacturer code, 11 bits.
+    pub manufacturer_code: u16,
+    /// Lower 3 bits of the device instance.
+    pub ecu_instance: u8,
+    /// Upper 5 bits of the device instance.
+    pub function_instance: u8,
+    pub function: u8,
+    /// Device class, 7 bits.
+    pub device_class: u8,
+    /// System instance, 4 bits.
+    pub system_instance: u8,
+    /// Industry group, 3 bits; 4 is marine.
+    pub industry_group: u8,
+    pub arbitrary_address_capable: bool,
+}
+
+impl NameParts {
+    /// The 8-bit device instance NMEA 2000 tools show.
+    pub fn device_instance(&self) -> u8 {
+        ((self.function_instance & 0x1F) << 3) | (self.ecu_instance & 0x07)
+    }
+}
+
+/// A NAME as sent in an ISO Address Claim.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::name::NameField;
+///
+/// // A Simrad display.
+/// let name = NameField(0xC0F0_8200_E820_04D2);
+/// let parts = name.to_parts();
+/// assert_eq!(parts.manufacturer_code, 1857);
+/// assert_eq!(parts.function, 130);
+/// assert_eq!(parts.device_class, 120);
+/// assert_eq!(NameField::from_parts(&parts), name);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NameField(pub u64);
+
+impl NameField {
+    pub const fn to_parts(self) -> NameParts {
+        let v = self.0;
+        NameParts {
+            identity_number: (v & 0x1F_FFFF) as u32,
+            manufacturer_code: ((v >> 21) & 0x7FF) as u16,
+            ecu_instance: ((v >> 32) & 0x07) as u8,
+            function_instance: ((v >> 35) & 0x1F) as u8,
+            function: (v >> 40) as u8,
+            device_class: ((v >> 49) & 0x7F) as u8,
+            system_instance: ((v >> 56) & 0x0F) as u8,
+            industry_group: ((v >> 60) & 0x07) as u8,
+            arbitrary_address_capable: v >> 63 != 0,
+        }
+    }
+
+    /// Packs `parts`, truncating each field to its width.
+    pub const fn from_parts(parts: &NameParts) -> Self {
+        Self(
+            (parts.identity_number as u64 & 0x1F_FFFF)
+                | (parts.manufacturer_code as u64 & 0x7FF) << 21
+                | (parts.ecu_instance as u64 & 0x07) << 32
+                | (parts.function_instance as u64 & 0x1F) << 35
+                | (parts.function as u64) << 40
+                | (parts.device_class as u64 & 0x7F) << 49
+                | (parts.system_instance as u64 & 0x0F) << 56
+                | (parts.industry_group as u64 & 0x07) << 60
+                | (parts.arbitrary_address_capable as u64) << 63,
+        )
+    }
+}
+
+impl From<u64> for NameField {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<NameField> for u64 {
+    fn from(value: NameField) -> u64 {
+        value.0
+    }
+}
+
+impl fmt::Display for NameField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts = self.to_parts();
+        #[cfg(any(test, feature = "manufacturers"))]
+        if let Some(name) = crate::manufacturers::name(parts.manufacturer_code) {
+            f.write_str(name)?;
+        } else {
+            write!(f, "manufacturer {}", parts.manufacturer_code)?;
+        }
+        #[cfg(not(any(test, feature = "manufacturers")))]
+        write!(f, "manufacturer {}", parts.manufacturer_code)?;
+        write!(
+            f,
+            " class {} function {} instance {} system {} id {}",
+            parts.device_class,
+            parts.function,
+            parts.device_instance(),
+            parts.system_instance,
+            parts.identity_number
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::ToString;
+
+    #[test]
+    fn test_parts() {
+        let parts = NameParts {
+            identity_number: 123456,
+            manufacturer_code: 229,
+            ecu_instance: 1,
+            function_instance: 2,
+            function: 145,
+            device_class: 60,
+            system_instance: 0,
+            industry_group: 4,
+            arbitrary_address_capable: true,
+        };
+        let name = NameField::from_parts(&parts);
+        assert_eq!(name.to_parts(), parts);
+        assert_eq!(parts.device_instance(), 17);
+        assert_eq!(
+            name.to_string(),
+            "Garmin class 60 function 145 instance 17 system 0 id 123456"
+        );
+        let unknown = NameParts {
+            manufacturer_code: 2000,
+            ..parts
+        };
+        assert!(NameField::from_parts(&unknown)
+            .to_string()
+            .starts_with("manufacturer 2000 class 60"));
+        // Fields are truncated to their width.
+        let wide = NameParts {
+            identity_number: 0xFFFF_FFFF,
+            ..NameParts::default()
+        };
+        assert_eq!(NameField::from_parts(&wide).0, 0x1F_FFFF);
+    }
+}
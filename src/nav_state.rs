@@ -0,0 +1,378 @@
+//! Latest vessel position, course, speed and heading, gathered from the
+//! navigation PGNs.
+use crate::clock::Clock;
+use crate::pgn::gnss::{
+    self, CogSogRapidUpdate, DirectionReference, GnssPositionData, PositionRapidUpdate,
+};
+use crate::pgn::heading::{magnetic_to_true, MagneticVariation, VesselHeading};
+use crate::pgn::Error;
+
+/// A value together with the time it was last updated, in caller-supplied
+/// monotonic milliseconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timestamped<T> {
+    pub value: T,
+    pub timestamp_ms: u64,
+}
+
+impl<T> Timestamped<T> {
+    pub fn age_ms(&self, now_ms: u64) -> u64 {
+        now_ms.saturating_sub(self.timestamp_ms)
+    }
+
+    pub fn age(&self, clock: &impl Clock) -> u64 {
+        self.age_ms(clock.now_ms())
+    }
+
+    /// Whether the value is older than `max_age_ms` at `now_ms`.
+    pub fn is_stale(&self, now_ms: u64, max_age_ms: u64) -> bool {
+        self.age_ms(now_ms) > max_age_ms
+    }
+
+    /// The value, unless it is older than `max_age_ms` at `now_ms`.
+    pub fn fresh(self, now_ms: u64, max_age_ms: u64) -> Option<T> {
+        (!self.is_stale(now_ms, max_age_ms)).then_some(self.value)
+    }
+}
+
+/// Latitude and longitude in degrees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Position {
+    /// Great-circle distance to `other` in metres.
+    pub fn distance_to(&self, other: &Position) -> f64 {
+        gnss::distance(
+            self.latitude,
+            self.longitude,
+            other.latitude,
+            other.longitude,
+        )
+    }
+
+    /// Initial true bearing to `other` in radians.
+    pub fn bearing_to(&self, other: &Position) -> f64 {
+        gnss::bearing(
+            self.latitude,
+            self.longitude,
+            other.latitude,
+            other.longitude,
+        )
+    }
+}
+
+/// A position and whether it was extrapolated rather than received.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PositionFix {
+    pub position: Position,
+    pub estimated: bool,
+}
+
+/// Course (radians, true) and speed (m/s) over ground.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Velocity {
+    pub cog: f32,
+    pub sog: f32,
+}
+
+/// Latest navigation data assembled from position, COG/SOG, heading and
+/// variation PGNs.
+///
+/// Each value carries the time it was received so consumers can decide how
+/// old is too old. Magnetic headings and courses are converted to true using
+/// the variation in the message or the latest PGN 127258; they are dropped if
+/// no variation is known.
+///
+/// With dead reckoning enabled, `position_at` carries the last position
+/// forward along the last course and speed once position updates stop.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NavState {
+    position: Option<Timestamped<Position>>,
+    velocity: Option<Timestamped<Velocity>>,
+    heading: Option<Timestamped<f32>>,
+    variation: Option<Timestamped<f32>>,
+    /// Position age after which to extrapolate, and after which to give up.
+    dead_reckoning: Option<(u64, u64)>,
+}
+
+impl NavState {
+    pub const fn new() -> Self {
+        Self {
+            position: None,
+            velocity: None,
+            heading: None,
+            variation: None,
+            dead_reckoning: None,
+        }
+    }
+
+    /// Extrapolates positions older than `after_ms` from the last COG/SOG,
+    /// for at most `max_ms` after the last position.
+    pub const fn with_dead_reckoning(mut self, after_ms: u64, max_ms: u64) -> Self {
+        self.dead_reckoning = Some((after_ms, max_ms));
+        self
+    }
+
+    /// Updates the state from a decoded payload. Returns `false` if `pgn` is
+    /// not one of 129025, 129026, 129029, 127250 or 127258.
+    pub fn ingest(&mut self, pgn: u32, payload: &[u8], now_ms: u64) -> Result<bool, Error> {
+        match pgn {
+            PositionRapidUpdate::PGN => {
+                let msg = PositionRapidUpdate::from_payload(payload)?;
+                self.update_position(msg.latitude, msg.longitude, now_ms);
+            }
+            GnssPositionData::PGN => {
+                let msg = GnssPositionData::from_payload(payload)?;
+                self.update_position(msg.latitude, msg.longitude, now_ms);
+            }
+            CogSogRapidUpdate::PGN => {
+                let msg = CogSogRapidUpdate::from_payload(payload)?;
+                let cog = match msg.cog_reference {
+                    DirectionReference::True => msg.cog,
+                    DirectionReference::Magnetic => msg
+                        .cog
+                        .zip(self.variation())
+                        .map(|(cog, variation)| magnetic_to_true(cog, variation)),
+                    DirectionReference::Unknown(_) => None,
+                };
+                if let (Some(cog), Some(sog)) = (cog, msg.sog) {
+                    self.velocity = Some(Timestamped {
+                        value: Velocity { cog, sog },
+                        timestamp_ms: now_ms,
+                    });
+                }
+            }
+            VesselHeading::PGN => {
+                let msg = VesselHeading::from_payload(payload)?;
+                if let Some(heading) = msg.true_heading(self.variation()) {
+                    self.heading = Some(Timestamped {
+                        value: heading,
+                        timestamp_ms: now_ms,
+                    });
+                }
+            }
+            MagneticVariation::PGN => {
+                let msg = MagneticVariation::from_payload(payload)?;
+                if let Some(variation) = msg.variation {
+                    self.variation = Some(Timestamped {
+                        value: variation,
+                        timestamp_ms: now_ms,
+                    });
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Like `ingest`, reading the current time from `clock`.
+    pub fn ingest_now(
+        &mut self,
+        pgn: u32,
+        payload: &[u8],
+        clock: &impl Clock,
+    ) -> Result<bool, Error> {
+        self.ingest(pgn, payload, clock.now_ms())
+    }
+
+    fn update_position(&mut self, latitude: Option<f64>, longitude: Option<f64>, now_ms: u64) {
+        if let (Some(latitude), Some(longitude)) = (latitude, longitude) {
+            self.position = Some(Timestamped {
+                value: Position {
+                    latitude,
+                    longitude,
+                },
+                timestamp_ms: now_ms,
+            });
+        }
+    }
+
+    fn variation(&self) -> Option<f32> {
+        self.variation.map(|v| v.value)
+    }
+
+    pub fn position(&self) -> Option<Timestamped<Position>> {
+        self.position
+    }
+
+    /// The position at `now_ms`: the last one received or, under dead
+    /// reckoning, an estimate once it is older than `after_ms`. Without a
+    /// course and speed to extrapolate from, or past `max_ms`, there is no
+    /// estimate.
+    ///
+    /// ## Example:
+    ///
+    /// ```
+    /// use nmea::nav_state::NavState;
+    ///
+    /// let mut nav = NavState::new().with_dead_reckoning(2000, 30_000);
+    /// let position = [0x94, 0x21, 0x60, 0x1C, 0x84, 0x9B, 0x15, 0xB7];
+    /// // Due north at 5 m/s.
+    /// let cog_sog = [0x00, 0xFC, 0x00, 0x00, 0xF4, 0x01, 0xFF, 0xFF];
+    /// nav.ingest(129025, &position, 0).unwrap();
+    /// nav.ingest(129026, &cog_sog, 0).unwrap();
+    ///
+    /// assert!(!nav.position_at(1000).unwrap().estimated);
+    /// let fix = nav.position_at(10_000).unwrap();
+    /// assert!(fix.estimated);
+    /// let moved = nav.position().unwrap().value.distance_to(&fix.position);
+    /// assert!((moved - 50.0).abs() < 0.01);
+    /// assert_eq!(nav.position_at(31_000), None);
+    /// ```
+    pub fn position_at(&self, now_ms: u64) -> Option<PositionFix> {
+        let last = self.position?;
+        let age_ms = last.age_ms(now_ms);
+        let fix = PositionFix {
+            position: last.value,
+            estimated: false,
+        };
+        let Some((after_ms, max_ms)) = self.dead_reckoning else {
+            return Some(fix);
+        };
+        if age_ms <= after_ms {
+            return Some(fix);
+        }
+        if age_ms > max_ms {
+            return None;
+        }
+        let velocity = self.velocity?.value;
+        let distance = velocity.sog as f64 * age_ms as f64 / 1000.0;
+        let (latitude, longitude) = gnss::destination(
+            last.value.latitude,
+            last.value.longitude,
+            velocity.cog as f64,
+            distance,
+        );
+        Some(PositionFix {
+            position: Position {
+                latitude,
+                longitude,
+            },
+            estimated: true,
+        })
+    }
+
+    pub fn velocity(&self) -> Option<Timestamped<Velocity>> {
+        self.velocity
+    }
+
+    /// True heading in radians.
+    pub fn heading(&self) -> Option<Timestamped<f32>> {
+        self.heading
+    }
+
+    /// Magnetic variation in radians, positive east.
+    pub fn magnetic_variation(&self) -> Option<Timestamped<f32>> {
+        self.variation
+    }
+
+    /// Whether any of position, velocity and heading is missing or older
+    /// than `max_age_ms` at `now_ms`.
+    ///
+    /// ## Example:
+    ///
+    /// ```
+    /// use nmea::nav_state::NavState;
+    ///
+    /// let mut nav = NavState::new();
+    /// assert!(nav.is_stale(0, 1000));
+    /// let position = [0x94, 0x21, 0x60, 0x1C, 0x84, 0x9B, 0x15, 0xB7];
+    /// nav.ingest(129025, &position, 0).unwrap();
+    /// assert!(nav.position().unwrap().fresh(500, 1000).is_some());
+    /// assert_eq!(nav.position().unwrap().fresh(1500, 1000), None);
+    /// ```
+    pub fn is_stale(&self, now_ms: u64, max_age_ms: u64) -> bool {
+        let stale = |timestamp_ms: Option<u64>| {
+            timestamp_ms.is_none_or(|t| now_ms.saturating_sub(t) > max_age_ms)
+        };
+        stale(self.position.map(|v| v.timestamp_ms))
+            || stale(self.velocity.map(|v| v.timestamp_ms))
+            || stale(self.heading.map(|v| v.timestamp_ms))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ingest() {
+        let mut nav = NavState::new();
+        let payload: [u8; 8] = [0x94, 0x21, 0x60, 0x1C, 0x84, 0x9B, 0x15, 0xB7];
+        assert!(nav.ingest(129025, &payload, 100).unwrap());
+        let position = nav.position().unwrap();
+        assert!((position.value.latitude - 47.60621).abs() < 1e-7);
+        assert_eq!(position.timestamp_ms, 100);
+        assert_eq!(position.age_ms(350), 250);
+        assert_eq!(position.age(&crate::clock::ManualClock::new(350)), 250);
+
+        let cog_sog: [u8; 8] = [0x03, 0xFC, 0x10, 0x27, 0xF4, 0x01, 0xFF, 0xFF];
+        assert!(nav.ingest(129026, &cog_sog, 200).unwrap());
+        let velocity = nav.velocity().unwrap().value;
+        assert!((velocity.cog - 1.0).abs() < 1e-6);
+        assert!((velocity.sog - 5.0).abs() < 1e-6);
+
+        assert!(!nav.ingest(130306, &cog_sog, 200).unwrap());
+        assert_eq!(
+            nav.ingest(129025, &payload[..4], 300),
+            Err(Error::InvalidLength)
+        );
+        // Failed updates leave the previous value in place.
+        assert_eq!(nav.position().unwrap().timestamp_ms, 100);
+        assert!(position.is_stale(1101, 1000));
+        assert!(!position.is_stale(1100, 1000));
+        assert_eq!(position.fresh(1101, 1000), None);
+        // No heading yet.
+        assert!(nav.is_stale(200, 1000));
+    }
+
+    #[test]
+    fn test_dead_reckoning() {
+        let position: [u8; 8] = [0x94, 0x21, 0x60, 0x1C, 0x84, 0x9B, 0x15, 0xB7];
+        // East at 10 m/s.
+        let cog_sog: [u8; 8] = [0x00, 0xFC, 0x5C, 0x3D, 0xE8, 0x03, 0xFF, 0xFF];
+
+        let mut nav = NavState::new();
+        nav.ingest(129025, &position, 0).unwrap();
+        nav.ingest(129026, &cog_sog, 0).unwrap();
+        // Disabled: the last position, however old.
+        assert!(!nav.position_at(100_000).unwrap().estimated);
+
+        let mut nav = NavState::new().with_dead_reckoning(1000, 10_000);
+        nav.ingest(129025, &position, 0).unwrap();
+        assert!(!nav.position_at(1000).unwrap().estimated);
+        // No course and speed to go on.
+        assert_eq!(nav.position_at(1001), None);
+
+        nav.ingest(129026, &cog_sog, 500).unwrap();
+        let start = nav.position().unwrap().value;
+        let fix = nav.position_at(5000).unwrap();
+        assert!(fix.estimated);
+        assert!((start.distance_to(&fix.position) - 50.0).abs() < 0.01);
+        assert!((start.bearing_to(&fix.position) - core::f64::consts::FRAC_PI_2).abs() < 1e-3);
+        assert_eq!(nav.position_at(10_001), None);
+
+        // A new position ends the estimate.
+        nav.ingest(129025, &position, 12_000).unwrap();
+        assert_eq!(nav.position_at(12_000).unwrap().position, start);
+    }
+
+    #[test]
+    fn test_magnetic_heading_uses_latest_variation() {
+        let mut nav = NavState::new();
+        // Magnetic heading 1.0 rad without variation in the message.
+        let heading: [u8; 8] = [0x00, 0x10, 0x27, 0xFF, 0x7F, 0xFF, 0x7F, 0xFD];
+        assert!(nav.ingest(127250, &heading, 0).unwrap());
+        assert_eq!(nav.heading(), None);
+
+        let variation: [u8; 6] = [0x00, 0xF8, 0x7B, 0x4D, 0xD0, 0x07];
+        assert!(nav.ingest(127258, &variation, 10).unwrap());
+        assert!(nav.ingest(127250, &heading, 20).unwrap());
+        let heading = nav.heading().unwrap();
+        assert!((heading.value - 1.2).abs() < 1e-3);
+        assert_eq!(heading.timestamp_ms, 20);
+    }
+}
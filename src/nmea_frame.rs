@@ -1,11 +1,81 @@
+use core::fmt;
+use core::marker::PhantomData;
 use thiserror_no_std::Error;
 
-#[derive(Debug, Error)]
+#[derive(Debug, Error, PartialEq)]
 pub enum Error {
     #[error("Invalid input parameter")]
     InvalidParameter,
 }
 
+/// Effective data length of a Fast-Packet message, validated to `1..=223` at
+/// construction so that lengths can never be silently truncated into the
+/// single length byte of the first frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PayloadLen(u8);
+
+impl PayloadLen {
+    pub const MIN: u8 = 1;
+    pub const MAX: u8 = 223;
+
+    pub const fn new(len: u8) -> Result<Self, Error> {
+        if len < Self::MIN || len > Self::MAX {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self(len))
+    }
+
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+}
+
+impl TryFrom<usize> for PayloadLen {
+    type Error = Error;
+
+    fn try_from(len: usize) -> Result<Self, Error> {
+        if len > Self::MAX as usize {
+            return Err(Error::InvalidParameter);
+        }
+        Self::new(len as u8)
+    }
+}
+
+impl From<PayloadLen> for u8 {
+    fn from(len: PayloadLen) -> u8 {
+        len.0
+    }
+}
+
+/// Describes the header layout of a Fast-Packet style protocol.
+///
+/// The first byte of every frame carries a sequence counter in its high bits
+/// and a frame counter in its low bits. Protocols built on J1939 that reuse
+/// the scheme differ in how that byte is split and in the largest payload they
+/// allow, so both are parameters of the framing layer.
+///
+/// `MAX_LEN` may not exceed 223 bytes (32 frames), and must fit in the frames
+/// addressable by `FRAME_COUNTER_BITS`.
+pub trait FastPacketSpec {
+    /// Number of low bits of the first byte carrying the frame counter.
+    const FRAME_COUNTER_BITS: u8;
+    /// Largest effective payload length of a message.
+    const MAX_LEN: u8;
+
+    const FRAME_COUNTER_MASK: u8 = ((1u16 << Self::FRAME_COUNTER_BITS) - 1) as u8;
+    const MAX_SEQUENCE_COUNTER: u8 = (0xFFu16 >> Self::FRAME_COUNTER_BITS) as u8;
+}
+
+/// NMEA2000 Fast-Packet: 3-bit sequence counter, 5-bit frame counter and up
+/// to 223 bytes of payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Nmea2000;
+
+impl FastPacketSpec for Nmea2000 {
+    const FRAME_COUNTER_BITS: u8 = 5;
+    const MAX_LEN: u8 = 223;
+}
+
 /// Represents a single CAN frame in an NMEA2000 Fast-Packet message sequence.
 ///
 /// NMEA2000 messages are split across multiple CAN frames. Each frame contains:
@@ -23,18 +93,25 @@ pub enum Error {
 ///
 /// The maximum data length is 223 effective data bytes (6 bytes in the first frame,
 /// 7 bytes in each consecutive frame).
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Frame {
+///
+/// Other header layouts are supported through the `S` parameter, see
+/// `FastPacketSpec`; `Frame` is the NMEA2000 instantiation.
+pub struct FastPacketFrame<S: FastPacketSpec> {
     pub bytes: [u8; 8],
+    // The spec is a marker only; `fn() -> S` keeps frames `Send + Sync`
+    // whatever `S` is.
+    spec: PhantomData<fn() -> S>,
 }
 
-impl Frame {
-    pub fn first_frame(bytes: &[u8; 6], len: u8, sequence_counter: u8) -> Self {
+pub type Frame = FastPacketFrame<Nmea2000>;
+
+impl<S: FastPacketSpec> FastPacketFrame<S> {
+    pub fn first_frame(bytes: &[u8; 6], len: PayloadLen, sequence_counter: u8) -> Self {
         let mut buf: [u8; 8] = [0xFF; 8];
-        buf[0] = sequence_counter << 5;
-        buf[1] = len;
+        buf[0] = sequence_counter << S::FRAME_COUNTER_BITS;
+        buf[1] = len.get();
         buf[2..].copy_from_slice(bytes);
-        Self { bytes: buf }
+        Self::new(buf)
     }
 
     pub fn consecutive_frame(
@@ -43,29 +120,34 @@ impl Frame {
         frame_counter: u8,
     ) -> Result<Self, Error> {
         let mut buf: [u8; 8] = [0xFF; 8];
-        if sequence_counter > 7 || frame_counter > 31 {
+        if sequence_counter > S::MAX_SEQUENCE_COUNTER || frame_counter > S::FRAME_COUNTER_MASK {
             return Err(Error::InvalidParameter);
         }
-        buf[0] = (sequence_counter << 5) | frame_counter;
+        buf[0] = (sequence_counter << S::FRAME_COUNTER_BITS) | frame_counter;
         buf[1..].copy_from_slice(bytes);
-        Ok(Self { bytes: buf })
+        Ok(Self::new(buf))
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Self {
         let mut buf = [0; 8];
-        for (a, b) in bytes.iter().zip(&mut buf) {
-            *b = *a;
-        }
+        let len = bytes.len().min(8);
+        buf[..len].copy_from_slice(&bytes[..len]);
+        Self::new(buf)
+    }
 
-        Self { bytes: buf }
+    fn new(bytes: [u8; 8]) -> Self {
+        Self {
+            bytes,
+            spec: PhantomData,
+        }
     }
 
     pub fn sequence_counter(&self) -> u8 {
-        return (self.bytes[0] & 0xE0) >> 5;
+        self.bytes[0] >> S::FRAME_COUNTER_BITS
     }
 
     pub fn frame_counter(&self) -> u8 {
-        return self.bytes[0] & 0x1F;
+        self.bytes[0] & S::FRAME_COUNTER_MASK
     }
 
     pub fn data_len(&self) -> Option<u8> {
@@ -85,11 +167,100 @@ impl Frame {
     }
 
     pub fn is_first_frame(&self) -> bool {
-        return self.frame_counter() == 0;
+        self.frame_counter() == 0
     }
 }
 
-impl AsRef<[u8]> for Frame {
+/// Little-endian field accessors for single-frame PGNs, whose payload is all
+/// 8 bytes of the frame rather than `payload()`. They return `None` when the
+/// field doesn't fit at `offset`.
+impl<S: FastPacketSpec> FastPacketFrame<S> {
+    pub fn payload_bytes<const N: usize>(&self, offset: usize) -> Option<[u8; N]> {
+        self.bytes
+            .get(offset..offset.checked_add(N)?)?
+            .try_into()
+            .ok()
+    }
+
+    pub fn payload_u16_le(&self, offset: usize) -> Option<u16> {
+        self.payload_bytes(offset).map(u16::from_le_bytes)
+    }
+
+    pub fn payload_i16_le(&self, offset: usize) -> Option<i16> {
+        self.payload_bytes(offset).map(i16::from_le_bytes)
+    }
+
+    pub fn payload_u32_le(&self, offset: usize) -> Option<u32> {
+        self.payload_bytes(offset).map(u32::from_le_bytes)
+    }
+
+    pub fn payload_i32_le(&self, offset: usize) -> Option<i32> {
+        self.payload_bytes(offset).map(i32::from_le_bytes)
+    }
+
+    pub fn payload_u64_le(&self, offset: usize) -> Option<u64> {
+        self.payload_bytes(offset).map(u64::from_le_bytes)
+    }
+
+    pub fn payload_i64_le(&self, offset: usize) -> Option<i64> {
+        self.payload_bytes(offset).map(i64::from_le_bytes)
+    }
+}
+
+/// Mutators for producing malformed frames in fault-injection tests and fuzzers.
+///
+/// No consistency checks are performed beyond masking counters to their bit
+/// widths, e.g. `set_data_len` overwrites byte 1 even on consecutive frames.
+#[cfg(feature = "testing")]
+impl<S: FastPacketSpec> FastPacketFrame<S> {
+    pub fn set_sequence_counter(&mut self, sequence_counter: u8) {
+        self.bytes[0] = (self.bytes[0] & S::FRAME_COUNTER_MASK)
+            | ((sequence_counter & S::MAX_SEQUENCE_COUNTER) << S::FRAME_COUNTER_BITS);
+    }
+
+    pub fn set_frame_counter(&mut self, frame_counter: u8) {
+        self.bytes[0] =
+            (self.bytes[0] & !S::FRAME_COUNTER_MASK) | (frame_counter & S::FRAME_COUNTER_MASK);
+    }
+
+    pub fn set_data_len(&mut self, len: u8) {
+        self.bytes[1] = len;
+    }
+
+    /// Overwrites byte `index` of `payload()`.
+    pub fn set_payload_byte(&mut self, index: usize, value: u8) -> Result<(), Error> {
+        let offset = if self.is_first_frame() { 2 } else { 1 };
+        match self.bytes.get_mut(offset + index) {
+            Some(b) => {
+                *b = value;
+                Ok(())
+            }
+            None => Err(Error::InvalidParameter),
+        }
+    }
+}
+
+impl<S: FastPacketSpec> Clone for FastPacketFrame<S> {
+    fn clone(&self) -> Self {
+        Self::new(self.bytes)
+    }
+}
+
+impl<S: FastPacketSpec> PartialEq for FastPacketFrame<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl<S: FastPacketSpec> Eq for FastPacketFrame<S> {}
+
+impl<S: FastPacketSpec> fmt::Debug for FastPacketFrame<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Frame").field("bytes", &self.bytes).finish()
+    }
+}
+
+impl<S: FastPacketSpec> AsRef<[u8]> for FastPacketFrame<S> {
     fn as_ref(&self) -> &[u8] {
         &self.bytes
     }
@@ -109,7 +280,8 @@ mod tests {
         assert_eq!(test_frame.payload(), [0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D]);
 
         let first_frame_payload: [u8; 6] = [0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D];
-        let test_first_frame = Frame::first_frame(&first_frame_payload, 27, 0);
+        let test_first_frame =
+            Frame::first_frame(&first_frame_payload, PayloadLen::new(27).unwrap(), 0);
         assert_eq!(test_first_frame.sequence_counter(), 0);
         assert_eq!(test_first_frame.frame_counter(), 0);
         assert_eq!(test_first_frame.data_len(), Some(27));
@@ -119,6 +291,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_payload_fields() {
+        // Vessel Heading (PGN 127250): heading 1.0 rad, deviation not
+        // available, variation -0.5 rad.
+        let frame = Frame::from_bytes(&[0x01, 0x10, 0x27, 0xFF, 0x7F, 0x78, 0xEC, 0xFD]);
+        assert_eq!(frame.payload_u16_le(1), Some(10000));
+        assert_eq!(frame.payload_i16_le(3), Some(i16::MAX));
+        assert_eq!(frame.payload_i16_le(5), Some(-5000));
+        assert_eq!(frame.payload_u32_le(4), Some(0xFDEC_787F));
+        assert_eq!(frame.payload_u16_le(7), None);
+        assert_eq!(frame.payload_u64_le(0), Some(0xFDEC_787F_FF27_1001));
+        assert_eq!(frame.payload_i64_le(1), None);
+    }
+
     #[test]
     fn test_consecutive_frame() {
         let frame_counter = 3;
@@ -134,4 +320,70 @@ mod tests {
             [0x20, 0xFF, 0xFF, 0x00, 0x70, 0xFE, 0xFF]
         );
     }
+
+    #[derive(Debug)]
+    struct NarrowSpec;
+
+    impl FastPacketSpec for NarrowSpec {
+        const FRAME_COUNTER_BITS: u8 = 4;
+        const MAX_LEN: u8 = 111;
+    }
+
+    #[test]
+    fn test_custom_spec() {
+        let payload: [u8; 7] = [0x20, 0xFF, 0xFF, 0x00, 0x70, 0xFE, 0xFF];
+        let frame: FastPacketFrame<NarrowSpec> =
+            FastPacketFrame::consecutive_frame(&payload, 9, 15).unwrap();
+        assert_eq!(frame.bytes[0], 0x9F);
+        assert_eq!(frame.sequence_counter(), 9);
+        assert_eq!(frame.frame_counter(), 15);
+        assert!(FastPacketFrame::<NarrowSpec>::consecutive_frame(&payload, 16, 1).is_err());
+        assert!(FastPacketFrame::<NarrowSpec>::consecutive_frame(&payload, 1, 16).is_err());
+
+        // The same byte reads differently under the NMEA2000 layout.
+        let frame = Frame::from_bytes(&frame.bytes);
+        assert_eq!(frame.sequence_counter(), 4);
+        assert_eq!(frame.frame_counter(), 31);
+    }
+
+    #[test]
+    fn test_payload_len() {
+        assert_eq!(PayloadLen::new(1).unwrap().get(), 1);
+        assert_eq!(PayloadLen::new(223).unwrap().get(), 223);
+        assert_eq!(PayloadLen::new(0), Err(Error::InvalidParameter));
+        assert_eq!(PayloadLen::new(224), Err(Error::InvalidParameter));
+        assert_eq!(PayloadLen::try_from(27usize).unwrap().get(), 27);
+        // Lengths that would wrap around a u8 are rejected rather than truncated.
+        assert_eq!(
+            PayloadLen::try_from(256 + 27usize),
+            Err(Error::InvalidParameter)
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_frame_mutation() {
+        let payload: [u8; 7] = [0x20, 0xFF, 0xFF, 0x00, 0x70, 0xFE, 0xFF];
+        let mut frame = Frame::consecutive_frame(&payload, 1, 3).unwrap();
+        frame.set_sequence_counter(6);
+        frame.set_frame_counter(17);
+        assert_eq!(frame.sequence_counter(), 6);
+        assert_eq!(frame.frame_counter(), 17);
+
+        frame.set_payload_byte(6, 0x42).unwrap();
+        assert_eq!(frame.payload()[6], 0x42);
+        assert_eq!(
+            frame.set_payload_byte(7, 0x42),
+            Err(Error::InvalidParameter)
+        );
+
+        // Turning a consecutive frame into a first frame re-interprets byte 1.
+        frame.set_frame_counter(0);
+        frame.set_data_len(250);
+        assert_eq!(frame.data_len(), Some(250));
+        assert_eq!(
+            frame.set_payload_byte(6, 0x00),
+            Err(Error::InvalidParameter)
+        );
+    }
 }
@@ -1,24 +1,51 @@
-use crate::nmea_frame::Frame;
+use crate::nmea_frame::{FastPacketFrame, FastPacketSpec, Nmea2000, PayloadLen};
+use core::fmt;
 use core::result::Result;
 use core::result::Result::Err;
-use thiserror_no_std::Error;
 use fixed_queue::VecDeque;
+use thiserror_no_std::Error;
 
 pub const MAX_NMEA_PACKET_SIZE: usize = 223;
+/// Number of frames needed for the largest Fast-Packet payload.
+pub const MAX_FRAMES: usize = 32;
+#[cfg(any(test, feature = "pyo3"))]
+const STATE_HEADER_LEN: usize = 7;
+/// Maximum length of a message encoded with `FastPacketMessage::to_state`.
+#[cfg(any(test, feature = "pyo3"))]
+pub(crate) const STATE_LEN: usize = STATE_HEADER_LEN + 8 * MAX_FRAMES;
 
-#[derive(PartialEq)]
-enum MessageType {
+/// Whether a message fits in a single frame or spans consecutive frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageType {
     Single,
     Consecutive,
+    /// No first frame has been received yet.
     Unknown,
 }
 
-#[derive(PartialEq)]
-enum TransmissionType {
+/// Whether a message is being received (assembled from frames) or transmitted
+/// (split into frames).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransmissionType {
     Rx,
     Tx,
 }
 
+/// What to do when a first frame arrives while a message is still being
+/// assembled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FirstFramePolicy {
+    /// Discard the message in progress and start over with the new one.
+    #[default]
+    Restart,
+    /// Reject the new first frame and keep the message in progress.
+    Error,
+    /// Assemble both, keyed by sequence counter. Only `Reassembler` can hold
+    /// several messages; a single `FastPacketMessage` treats this as
+    /// `Restart`.
+    Concurrent,
+}
+
 #[derive(Debug, Error, PartialEq)]
 pub enum Error {
     #[error("Message queue is empty")]
@@ -27,33 +54,244 @@ pub enum Error {
     FullQueue,
     #[error("Wrong transmission type")]
     TransmissionTypeMismatch,
-    #[error("Wrong sequence counter")]
-    SequenceCountError,
-    #[error("Frame is out of sequence")]
-    SequenceMismatch,
+    #[error("Message is incomplete")]
+    IncompleteMessage,
+    #[error("Invalid message state")]
+    InvalidState,
+    /// A consecutive frame belongs to a different message than the one in
+    /// progress. `frame` holds the offending frame.
+    #[error("Wrong sequence counter (expected {expected}, received {received})")]
+    SequenceCountError {
+        expected: u8,
+        received: u8,
+        frame: [u8; 8],
+    },
+    /// A consecutive frame arrived out of order or without a first frame.
+    /// `frame` holds the offending frame.
+    #[error("Frame is out of sequence (expected frame {expected}, received {received})")]
+    SequenceMismatch {
+        expected: u8,
+        received: u8,
+        frame: [u8; 8],
+    },
+    #[error("Payload length is out of range")]
+    InvalidPayloadLength,
+    #[error("Buffer is too small for the payload")]
+    BufferTooSmall,
+    /// A first frame arrived while another message was being assembled and
+    /// the policy is `FirstFramePolicy::Error`.
+    #[error("First frame received while a message is in progress")]
+    UnexpectedFirstFrame { frame: [u8; 8] },
 }
 
-pub struct Message {
-    queue: VecDeque<Frame, 31>,
+/// A Fast-Packet message being assembled from (Rx) or split into (Tx) frames.
+///
+/// `Message` is the NMEA2000 instantiation; see `FastPacketSpec` for other
+/// header layouts.
+pub struct FastPacketMessage<S: FastPacketSpec> {
+    queue: VecDeque<FastPacketFrame<S>, MAX_FRAMES>,
     message_type: MessageType,
     transmission_type: TransmissionType,
     pub num_frames: u8,
     pub data_len: u8,
     pub sequence_counter: u8,
     cur_frame_counter: u8,
+    first_frame_policy: FirstFramePolicy,
 }
 
-impl Message {
-    pub fn new() -> Self {
-        let queue = VecDeque::new();
+pub type Message = FastPacketMessage<Nmea2000>;
+
+impl<S: FastPacketSpec> Default for FastPacketMessage<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: FastPacketSpec> Clone for FastPacketMessage<S> {
+    fn clone(&self) -> Self {
+        let mut queue = VecDeque::new();
+        for frame in self.frames() {
+            let _ = queue.push_back(frame.clone());
+        }
         Self {
             queue,
+            message_type: self.message_type,
+            transmission_type: self.transmission_type,
+            num_frames: self.num_frames,
+            data_len: self.data_len,
+            sequence_counter: self.sequence_counter,
+            cur_frame_counter: self.cur_frame_counter,
+            first_frame_policy: self.first_frame_policy,
+        }
+    }
+}
+
+impl<S: FastPacketSpec> PartialEq for FastPacketMessage<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.message_type == other.message_type
+            && self.transmission_type == other.transmission_type
+            && self.num_frames == other.num_frames
+            && self.data_len == other.data_len
+            && self.sequence_counter == other.sequence_counter
+            && self.cur_frame_counter == other.cur_frame_counter
+            && self.first_frame_policy == other.first_frame_policy
+            && self.frames().eq(other.frames())
+    }
+}
+
+impl<S: FastPacketSpec> fmt::Debug for FastPacketMessage<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Message")
+            .field("message_type", &self.message_type)
+            .field("transmission_type", &self.transmission_type)
+            .field("num_frames", &self.num_frames)
+            .field("data_len", &self.data_len)
+            .field("sequence_counter", &self.sequence_counter)
+            .field("frames", &self.queue)
+            .finish()
+    }
+}
+
+/// An immutable copy of a message's frames and metadata, taken with
+/// `FastPacketMessage::snapshot`. It outlives the message, which can be
+/// cleared and reused for the next session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AssembledMessage {
+    frames: [[u8; 8]; MAX_FRAMES],
+    frame_count: usize,
+    message_type: MessageType,
+    transmission_type: TransmissionType,
+    data_len: u8,
+    sequence_counter: u8,
+}
+
+impl AssembledMessage {
+    /// The raw frames, in the order they were received or will be sent.
+    pub fn frames(&self) -> &[[u8; 8]] {
+        &self.frames[..self.frame_count]
+    }
+
+    pub fn kind(&self) -> MessageType {
+        self.message_type
+    }
+
+    pub fn direction(&self) -> TransmissionType {
+        self.transmission_type
+    }
+
+    pub fn data_len(&self) -> u8 {
+        self.data_len
+    }
+
+    pub fn sequence_counter(&self) -> u8 {
+        self.sequence_counter
+    }
+
+    /// The payload byte at `offset`, `None` past `data_len`.
+    pub fn payload_byte(&self, offset: usize) -> Option<u8> {
+        if offset >= self.data_len as usize {
+            return None;
+        }
+        let (frame, index) = if offset < 6 {
+            (0, offset + 2)
+        } else {
+            (1 + (offset - 6) / 7, 1 + (offset - 6) % 7)
+        };
+        Some(self.frames().get(frame)?[index])
+    }
+
+    /// `N` payload bytes starting at `offset`, `None` if they extend past
+    /// `data_len`. The `payload_*_le` accessors decode them as little-endian
+    /// fields, as used by NMEA2000.
+    pub fn payload_bytes<const N: usize>(&self, offset: usize) -> Option<[u8; N]> {
+        let mut bytes = [0; N];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = self.payload_byte(offset.checked_add(i)?)?;
+        }
+        Some(bytes)
+    }
+
+    pub fn payload_u16_le(&self, offset: usize) -> Option<u16> {
+        self.payload_bytes(offset).map(u16::from_le_bytes)
+    }
+
+    pub fn payload_i16_le(&self, offset: usize) -> Option<i16> {
+        self.payload_bytes(offset).map(i16::from_le_bytes)
+    }
+
+    pub fn payload_u32_le(&self, offset: usize) -> Option<u32> {
+        self.payload_bytes(offset).map(u32::from_le_bytes)
+    }
+
+    pub fn payload_i32_le(&self, offset: usize) -> Option<i32> {
+        self.payload_bytes(offset).map(i32::from_le_bytes)
+    }
+
+    pub fn payload_u64_le(&self, offset: usize) -> Option<u64> {
+        self.payload_bytes(offset).map(u64::from_le_bytes)
+    }
+
+    pub fn payload_i64_le(&self, offset: usize) -> Option<i64> {
+        self.payload_bytes(offset).map(i64::from_le_bytes)
+    }
+
+    /// The frames as seen on the wire, back to back, padding included, for
+    /// re-emitting them byte for byte.
+    pub fn raw_frames(&self) -> &[u8] {
+        self.frames().as_flattened()
+    }
+
+    /// The data bytes of each frame, after the frame headers.
+    fn data(&self) -> impl Iterator<Item = &[u8]> {
+        self.frames()
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| if i == 0 { &frame[2..] } else { &frame[1..] })
+    }
+
+    /// Copies the payload carried by the frames into `buf` and returns the
+    /// number of bytes written, at most `data_len`.
+    pub fn payload(&self, buf: &mut [u8]) -> usize {
+        let mut len = 0;
+        for data in self.data() {
+            let n = data
+                .len()
+                .min(self.data_len as usize - len)
+                .min(buf.len() - len);
+            buf[len..len + n].copy_from_slice(&data[..n]);
+            len += n;
+        }
+        len
+    }
+
+    /// Like `payload`, but keeps the padding after `data_len` as it was
+    /// received instead of trimming it. Returns the number of bytes written.
+    pub fn raw_padded_payload(&self, buf: &mut [u8]) -> usize {
+        let mut len = 0;
+        for data in self.data() {
+            let n = data.len().min(buf.len() - len);
+            buf[len..len + n].copy_from_slice(&data[..n]);
+            len += n;
+        }
+        len
+    }
+}
+
+impl<S: FastPacketSpec> FastPacketMessage<S> {
+    pub const fn new() -> Self {
+        Self::with_policy(FirstFramePolicy::Restart)
+    }
+
+    pub const fn with_policy(first_frame_policy: FirstFramePolicy) -> Self {
+        Self {
+            queue: VecDeque::new(),
             message_type: MessageType::Unknown,
             num_frames: 0,
             transmission_type: TransmissionType::Rx,
             data_len: 0,
             sequence_counter: 0,
             cur_frame_counter: 0,
+            first_frame_policy,
         }
     }
 
@@ -64,23 +302,53 @@ impl Message {
         if !self.queue.is_empty() && self.queue.len() as u8 == self.num_frames {
             return Err(Error::FullQueue);
         }
-        let frame = Frame::from_bytes(payload);
+        let frame = FastPacketFrame::<S>::from_bytes(payload);
         if frame.is_first_frame() {
-            if frame.data_len().unwrap() <= 6 {
-                self.num_frames = 1
-            } else {
-                self.num_frames = num_integer::div_floor(frame.data_len().unwrap(), 7) + 1;
+            if frame.data_len().unwrap() > S::MAX_LEN {
+                return Err(Error::InvalidPayloadLength);
+            }
+            if !self.queue.is_empty() {
+                if self.first_frame_policy == FirstFramePolicy::Error {
+                    return Err(Error::UnexpectedFirstFrame { frame: *payload });
+                }
+                self.queue.clear();
             }
+            self.num_frames = Self::expected_frames_for_len(frame.data_len().unwrap());
+            self.message_type = if self.num_frames == 1 {
+                MessageType::Single
+            } else {
+                MessageType::Consecutive
+            };
             self.sequence_counter = frame.sequence_counter();
             self.data_len = frame.data_len().unwrap();
             let _ = self.queue.push_back(frame);
             self.cur_frame_counter = 0;
+            if self.num_frames == 1 {
+                // Single frame message is complete.
+                return Ok(true);
+            }
         } else {
+            if self.queue.is_empty() {
+                // No session in progress to continue.
+                return Err(Error::SequenceMismatch {
+                    expected: 0,
+                    received: frame.frame_counter(),
+                    frame: *payload,
+                });
+            }
             if self.sequence_counter != frame.sequence_counter() {
-                return Err(Error::SequenceCountError);
+                return Err(Error::SequenceCountError {
+                    expected: self.sequence_counter,
+                    received: frame.sequence_counter(),
+                    frame: *payload,
+                });
             }
             if self.cur_frame_counter + 1 != frame.frame_counter() {
-                return Err(Error::SequenceMismatch);
+                return Err(Error::SequenceMismatch {
+                    expected: self.cur_frame_counter + 1,
+                    received: frame.frame_counter(),
+                    frame: *payload,
+                });
             }
             let frame_counter = frame.frame_counter();
             if frame_counter >= self.num_frames - 1 {
@@ -91,42 +359,43 @@ impl Message {
             }
             self.cur_frame_counter = frame_counter;
         }
-        return Ok(false);
+        Ok(false)
     }
 
-    pub fn from_payload(payload: &[u8], sequence_counter: u8) -> Self {
+    pub fn from_payload(payload: &[u8], sequence_counter: u8) -> Result<Self, Error> {
+        let len = PayloadLen::try_from(payload.len()).map_err(|_| Error::InvalidPayloadLength)?;
+        if len.get() > S::MAX_LEN {
+            return Err(Error::InvalidPayloadLength);
+        }
         let mut queue = VecDeque::new();
         if payload.len() <= 6 {
             let mut padded_payload: [u8; 6] = [0xFF; 6];
             padded_payload[..payload.len()].copy_from_slice(payload);
-            let first_frame =
-                Frame::first_frame(&padded_payload, payload.len() as u8, sequence_counter);
+            let first_frame = FastPacketFrame::first_frame(&padded_payload, len, sequence_counter);
             let _ = queue.push_back(first_frame);
             // We can contain in a single frame.
-            return Self {
+            return Ok(Self {
                 queue,
                 message_type: MessageType::Single,
                 num_frames: 1,
                 transmission_type: TransmissionType::Tx,
-                data_len: payload.len() as u8,
+                data_len: len.get(),
                 sequence_counter: 0,
                 cur_frame_counter: 0,
-            };
+                first_frame_policy: FirstFramePolicy::default(),
+            });
         }
         // Process first frame.
-        let first_frame = Frame::first_frame(
-            payload[..6].try_into().unwrap(),
-            payload.len() as u8,
-            sequence_counter,
-        );
+        let first_frame =
+            FastPacketFrame::first_frame(payload[..6].try_into().unwrap(), len, sequence_counter);
         let _ = queue.push_back(first_frame);
 
         // Process consecutive frames.
-        let num_chunks: u8 = num_integer::div_floor(payload.len() as u8 - 6, 7);
-        let remaining_bytes = (payload.len() as u8 - 6) - 7 * num_chunks;
+        let num_chunks: u8 = num_integer::div_floor(len.get() - 6, 7);
+        let remaining_bytes = (len.get() - 6) - 7 * num_chunks;
         let mut frame_counter: u8 = 1; // First frame is already processed.
         for i in 0..num_chunks {
-            let frame = Frame::consecutive_frame(
+            let frame = FastPacketFrame::consecutive_frame(
                 &payload[(6 + 7 * (i as usize))..(6 + 7 * (i as usize) + 7)]
                     .try_into()
                     .unwrap(),
@@ -147,42 +416,253 @@ impl Message {
                 &payload[6 + (num_chunks as usize) * 7
                     ..6 + (num_chunks as usize) * 7 + remaining_bytes as usize],
             );
-            let last_frame =
-                Frame::consecutive_frame(&padded_payload, sequence_counter, frame_counter);
+            let last_frame = FastPacketFrame::consecutive_frame(
+                &padded_payload,
+                sequence_counter,
+                frame_counter,
+            );
             let _ = match last_frame {
                 Ok(f) => queue.push_back(f),
                 Err(_e) => panic!("Error creating last consecutive frame"),
             };
         }
-        return Self {
+        Ok(Self {
             queue,
             message_type: MessageType::Consecutive,
-            num_frames: 0,
+            num_frames: Self::expected_frames_for_len(len.get()),
             transmission_type: TransmissionType::Tx,
-            data_len: payload.len() as u8,
+            data_len: len.get(),
             sequence_counter,
             cur_frame_counter: 0,
+            first_frame_policy: FirstFramePolicy::default(),
+        })
+    }
+
+    /// Number of frames needed to carry a payload of `len` bytes: the first
+    /// frame holds 6 bytes and every consecutive frame 7.
+    pub const fn expected_frames_for_len(len: u8) -> u8 {
+        if len <= 6 {
+            1
+        } else {
+            1 + (len - 6).div_ceil(7)
+        }
+    }
+
+    /// Payload bytes still to be received (Rx) or still queued for
+    /// transmission (Tx). 0 before the first frame of a received message.
+    pub fn bytes_remaining(&self) -> usize {
+        let carried = |frames: usize| match frames {
+            0 => 0,
+            n => (6 + 7 * (n - 1)).min(self.data_len as usize),
+        };
+        let done = match self.transmission_type {
+            TransmissionType::Rx => carried(self.queue.len()),
+            TransmissionType::Tx => carried(self.num_frames as usize - self.queue.len()),
         };
+        self.data_len as usize - done
+    }
+
+    /// The frames received or still to be sent, in order, borrowed in place.
+    pub fn frames(&self) -> impl Iterator<Item = &FastPacketFrame<S>> {
+        let (front, back) = self.queue.as_slices();
+        front.iter().chain(back)
+    }
+
+    /// The payload carried by each frame, without the frame headers or the
+    /// padding after `data_len`, borrowed in place.
+    pub fn payload_chunks(&self) -> impl Iterator<Item = &[u8]> {
+        let mut remaining = self.data_len as usize;
+        self.frames().map(move |frame| {
+            let data = frame.payload();
+            let n = data.len().min(remaining);
+            remaining -= n;
+            &data[..n]
+        })
+    }
+
+    /// Copies the payload received so far into `buf`, leaving the message
+    /// untouched. Returns the number of bytes written.
+    pub fn copy_payload(&self, buf: &mut [u8]) -> usize {
+        let mut len = 0;
+        for chunk in self.payload_chunks() {
+            let n = chunk.len().min(buf.len() - len);
+            buf[len..len + n].copy_from_slice(&chunk[..n]);
+            len += n;
+        }
+        len
+    }
+
+    /// Copies the message's frames and metadata into an `AssembledMessage`.
+    pub fn snapshot(&self) -> AssembledMessage {
+        let mut frames = [[0xFF; 8]; MAX_FRAMES];
+        let mut frame_count = 0;
+        for (slot, frame) in frames.iter_mut().zip(self.frames()) {
+            *slot = frame.bytes;
+            frame_count += 1;
+        }
+        AssembledMessage {
+            frames,
+            frame_count,
+            message_type: self.message_type,
+            transmission_type: self.transmission_type,
+            data_len: self.data_len,
+            sequence_counter: self.sequence_counter,
+        }
+    }
+
+    /// Splits the payload of a complete received message into a new Tx
+    /// message under `sequence_counter`, for forwarding it onto another bus.
+    /// All `data_len` bytes are carried over as received; only the padding
+    /// after them is regenerated. The message itself is left untouched.
+    pub fn refragment(&self, sequence_counter: u8) -> Result<Self, Error> {
+        if self.transmission_type == TransmissionType::Tx {
+            return Err(Error::TransmissionTypeMismatch);
+        }
+        if !self.is_complete() {
+            return Err(Error::IncompleteMessage);
+        }
+        let mut payload = [0xFF; MAX_NMEA_PACKET_SIZE];
+        let len = self.snapshot().payload(&mut payload);
+        let mut msg = Self::from_payload(&payload[..len], sequence_counter)?;
+        msg.first_frame_policy = self.first_frame_policy;
+        Ok(msg)
+    }
+
+    /// Encodes the complete state of the message, including partially
+    /// assembled or transmitted ones, into `buf` for `from_state`. Returns
+    /// the encoded length.
+    #[cfg(any(test, feature = "pyo3"))]
+    pub(crate) fn to_state(&self, buf: &mut [u8; STATE_LEN]) -> usize {
+        buf[..STATE_HEADER_LEN].copy_from_slice(&[
+            self.message_type as u8,
+            self.transmission_type as u8,
+            self.num_frames,
+            self.data_len,
+            self.sequence_counter,
+            self.cur_frame_counter,
+            self.first_frame_policy as u8,
+        ]);
+        let mut len = STATE_HEADER_LEN;
+        for frame in self.frames() {
+            buf[len..len + 8].copy_from_slice(&frame.bytes);
+            len += 8;
+        }
+        len
+    }
+
+    #[cfg(any(test, feature = "pyo3"))]
+    pub(crate) fn from_state(state: &[u8]) -> Result<Self, Error> {
+        if state.len() < STATE_HEADER_LEN
+            || state.len() > STATE_LEN
+            || !(state.len() - STATE_HEADER_LEN).is_multiple_of(8)
+        {
+            return Err(Error::InvalidState);
+        }
+        let message_type = match state[0] {
+            0 => MessageType::Single,
+            1 => MessageType::Consecutive,
+            2 => MessageType::Unknown,
+            _ => return Err(Error::InvalidState),
+        };
+        let transmission_type = match state[1] {
+            0 => TransmissionType::Rx,
+            1 => TransmissionType::Tx,
+            _ => return Err(Error::InvalidState),
+        };
+        let first_frame_policy = match state[6] {
+            0 => FirstFramePolicy::Restart,
+            1 => FirstFramePolicy::Error,
+            2 => FirstFramePolicy::Concurrent,
+            _ => return Err(Error::InvalidState),
+        };
+        let (num_frames, data_len, cur_frame_counter) = (state[2], state[3], state[5]);
+        let frame_count = (state.len() - STATE_HEADER_LEN) / 8;
+        // Only a new or cleared message has no frame count.
+        let consistent = if num_frames == 0 {
+            data_len == 0 && frame_count == 0
+        } else {
+            data_len <= S::MAX_LEN && num_frames == Self::expected_frames_for_len(data_len)
+        };
+        if !consistent
+            || frame_count > num_frames as usize
+            || cur_frame_counter >= num_frames.max(1)
+        {
+            return Err(Error::InvalidState);
+        }
+        let mut queue = VecDeque::new();
+        for bytes in state[STATE_HEADER_LEN..].chunks_exact(8) {
+            let _ = queue.push_back(FastPacketFrame::from_bytes(bytes));
+        }
+        Ok(Self {
+            queue,
+            message_type,
+            transmission_type,
+            num_frames,
+            data_len,
+            sequence_counter: state[4],
+            cur_frame_counter,
+            first_frame_policy,
+        })
+    }
+
+    /// Whether every frame of a received message has arrived.
+    pub fn is_complete(&self) -> bool {
+        self.transmission_type == TransmissionType::Rx
+            && !self.queue.is_empty()
+            && self.queue.len() as u8 == self.num_frames
     }
 
-    pub fn pop_frame(&mut self) -> Option<Frame> {
+    pub fn pop_frame(&mut self) -> Option<FastPacketFrame<S>> {
         self.queue.pop_front()
     }
 
-    pub fn get_payload(&mut self, buf: &mut [u8]) -> usize {
-        buf.fill(0xFF);
-        let mut i = 0;
-        while !self.queue.is_empty() {
-            let frame = self.pop_frame().unwrap();
-            if frame.is_first_frame() {
-                buf[0..6].copy_from_slice(frame.payload());
-                i += 6
-            } else {
-                buf[i..i + 7].copy_from_slice(frame.payload());
-                i += 7
-            }
+    pub fn kind(&self) -> MessageType {
+        self.message_type
+    }
+
+    pub fn direction(&self) -> TransmissionType {
+        self.transmission_type
+    }
+
+    /// Appends the payload received so far to `out`, growing it once, and
+    /// returns the number of bytes appended. Offline tools assembling large
+    /// logs can reuse one buffer for every message.
+    #[cfg(any(test, feature = "alloc"))]
+    pub fn assemble_into(&self, out: &mut alloc::vec::Vec<u8>) -> usize {
+        let start = out.len();
+        out.reserve(self.data_len as usize);
+        for chunk in self.payload_chunks() {
+            out.extend_from_slice(chunk);
         }
-        return self.data_len as usize;
+        out.len() - start
+    }
+
+    /// Drains the received frames into `buf` and returns the payload length.
+    /// Bytes of `buf` past the payload are set to 0xFF. Fails for Tx
+    /// messages, whose frames are meant to be popped instead, and with
+    /// `Error::BufferTooSmall`, leaving the message untouched, if `buf` is
+    /// shorter than the payload.
+    /// Outside the crate, payloads are read from a `Complete` message.
+    #[cfg(any(test, feature = "pyo3"))]
+    pub(crate) fn get_payload(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if self.transmission_type == TransmissionType::Tx {
+            return Err(Error::TransmissionTypeMismatch);
+        }
+        if buf.len() < self.data_len as usize {
+            return Err(Error::BufferTooSmall);
+        }
+        let len = self.copy_payload(buf);
+        buf[len..].fill(0xFF);
+        self.queue.clear();
+        Ok(self.data_len as usize)
+    }
+
+    /// Abandons the message, dropping any partially assembled or untransmitted
+    /// frames. Returns `true` if there were frames to discard.
+    pub fn abort(&mut self) -> bool {
+        let had_frames = !self.queue.is_empty();
+        self.clear();
+        had_frames
     }
 
     pub fn clear(&mut self) {
@@ -196,6 +676,133 @@ impl Message {
     }
 }
 
+/// A received message still missing frames. Together with `Complete` it
+/// encodes the receive sequence in types: frames can only be added while
+/// assembling, and the payload can only be read once the message is
+/// complete.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::nmea_message::{Assembling, Message, Progress};
+///
+/// let payload: Vec<u8> = (0..20).collect();
+/// let mut tx = Message::from_payload(&payload, 0).unwrap();
+/// let mut msg: Assembling = Assembling::new();
+/// let complete = loop {
+///     let frame = tx.pop_frame().unwrap();
+///     match msg.add_frame(&frame.bytes).unwrap() {
+///         Progress::Assembling(next) => msg = next,
+///         Progress::Complete(complete) => break complete,
+///     }
+/// };
+/// let mut buf = [0; 32];
+/// assert_eq!(complete.get_payload(&mut buf), Ok(20));
+/// assert_eq!(buf[..20], payload[..]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Assembling<S: FastPacketSpec = Nmea2000>(FastPacketMessage<S>);
+
+/// A received message with every frame, taken from `Assembling::add_frame`.
+///
+/// Reading a partial payload doesn't compile:
+///
+/// ```compile_fail
+/// use nmea::nmea_message::Assembling;
+///
+/// let msg: Assembling = Assembling::new();
+/// msg.get_payload(&mut [0; 8]);
+/// ```
+///
+/// nor does reading it from the message underneath:
+///
+/// ```compile_fail
+/// use nmea::nmea_message::Message;
+///
+/// Message::new().get_payload(&mut [0; 8]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Complete<S: FastPacketSpec = Nmea2000>(FastPacketMessage<S>);
+
+/// The state of a message after a frame was added.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Progress<S: FastPacketSpec = Nmea2000> {
+    Assembling(Assembling<S>),
+    Complete(Complete<S>),
+}
+
+impl<S: FastPacketSpec> Default for Assembling<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: FastPacketSpec> Assembling<S> {
+    pub const fn new() -> Self {
+        Self::with_policy(FirstFramePolicy::Restart)
+    }
+
+    pub const fn with_policy(first_frame_policy: FirstFramePolicy) -> Self {
+        Self(FastPacketMessage::with_policy(first_frame_policy))
+    }
+
+    /// Adds a frame, moving to `Complete` with the last one. On error the
+    /// message is handed back as `FastPacketMessage::add_frame` left it, so
+    /// under `FirstFramePolicy::Error` an unexpected first frame keeps the
+    /// message in progress.
+    // The Ok variant is as large, and boxing would need alloc.
+    #[allow(clippy::result_large_err)]
+    pub fn add_frame(mut self, payload: &[u8; 8]) -> Result<Progress<S>, (Self, Error)> {
+        match self.0.add_frame(payload) {
+            Ok(true) => Ok(Progress::Complete(Complete(self.0))),
+            Ok(false) => Ok(Progress::Assembling(self)),
+            Err(e) => Err((self, e)),
+        }
+    }
+
+    /// The message assembled so far.
+    pub fn message(&self) -> &FastPacketMessage<S> {
+        &self.0
+    }
+}
+
+impl<S: FastPacketSpec> TryFrom<FastPacketMessage<S>> for Complete<S> {
+    type Error = Error;
+
+    /// Fails with `Error::IncompleteMessage` unless the message is complete.
+    fn try_from(msg: FastPacketMessage<S>) -> Result<Self, Error> {
+        match msg.is_complete() {
+            true => Ok(Self(msg)),
+            false => Err(Error::IncompleteMessage),
+        }
+    }
+}
+
+impl<S: FastPacketSpec> Complete<S> {
+    /// Copies the payload into `buf` and returns its length. Bytes of `buf`
+    /// past the payload are set to 0xFF. Fails with `Error::BufferTooSmall`
+    /// if `buf` is shorter than the payload.
+    pub fn get_payload(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        if buf.len() < self.0.data_len as usize {
+            return Err(Error::BufferTooSmall);
+        }
+        let len = self.0.copy_payload(buf);
+        buf[len..].fill(0xFF);
+        Ok(len)
+    }
+
+    /// The complete message, for its frames and `payload_chunks`.
+    pub fn message(&self) -> &FastPacketMessage<S> {
+        &self.0
+    }
+
+    /// Clears the message to assemble the next one with the same policy.
+    pub fn reset(mut self) -> Assembling<S> {
+        self.0.clear();
+        Assembling(self.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -207,18 +814,29 @@ mod tests {
         let buf_2: [u8; 8] = [0x01, 0x31, 0xF3, 0xD0, 0xAC, 0xF2, 0x23, 0x1A];
         let buf_3: [u8; 8] = [0x02, 0x03, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00];
         let buf_4: [u8; 8] = [0x03, 0x20, 0xFF, 0xFF, 0x00, 0x70, 0xFF, 0xFF];
-        assert_eq!(msg.add_frame(&buf_1).unwrap(), false);
-        assert_eq!(msg.add_frame(&buf_2).unwrap(), false);
-        assert_eq!(msg.add_frame(&buf_3).unwrap(), false);
-        assert_eq!(msg.add_frame(&buf_4).unwrap(), true);
+        assert!(!msg.add_frame(&buf_1).unwrap());
+        assert!(!msg.add_frame(&buf_2).unwrap());
+        assert!(!msg.add_frame(&buf_3).unwrap());
+        assert!(msg.add_frame(&buf_4).unwrap());
         assert_eq!(msg.num_frames, 4);
         assert_eq!(msg.sequence_counter, 0);
 
         let error_kind: Error = msg.add_frame(&buf_1).unwrap_err();
         assert_eq!(error_kind, Error::FullQueue);
 
-        let mut buf: [u8; 223] = [0xFF; 223];
-        msg.get_payload(&mut buf);
+        let chunks: std::vec::Vec<usize> = msg.payload_chunks().map(|chunk| chunk.len()).collect();
+        assert_eq!(chunks, [6, 7, 7, 5]);
+        let mut short = [0; 10];
+        assert_eq!(msg.copy_payload(&mut short), 10);
+        assert_eq!(short[..6], buf_1[2..]);
+        // A short buffer fails without draining the message.
+        assert_eq!(msg.get_payload(&mut short), Err(Error::BufferTooSmall));
+        assert!(msg.is_complete());
+
+        let mut buf: [u8; 223] = [0x00; 223];
+        msg.get_payload(&mut buf).unwrap();
+        assert_eq!(buf[25], 0xFF);
+        assert!(msg.frames().next().is_none());
         let expected_payload: [u8; 25] = [
             0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D, 0x31, 0xF3, 0xD0, 0xAC, 0xF2, 0x23, 0x1A, 0x03,
             0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x20, 0xFF, 0xFF, 0x00, 0x70,
@@ -233,7 +851,7 @@ mod tests {
             0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D, 0x31, 0xF3, 0xD0, 0xAC, 0xF2, 0x23, 0x1A, 0x03,
             0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x20, 0xFF, 0xFF, 0x00, 0x70,
         ];
-        let mut msg = Message::from_payload(&received_packet, 0);
+        let mut msg = Message::from_payload(&received_packet, 0).unwrap();
         let buf_1: [u8; 8] = [0x00, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D];
         let buf_2: [u8; 8] = [0x01, 0x31, 0xF3, 0xD0, 0xAC, 0xF2, 0x23, 0x1A];
         let buf_3: [u8; 8] = [0x02, 0x03, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00];
@@ -249,7 +867,7 @@ mod tests {
         rand::fill(&mut original_payload[..payload_length]);
 
         // Create message for encoding
-        let mut msg_encode = Message::from_payload(&original_payload[..payload_length], 0);
+        let mut msg_encode = Message::from_payload(&original_payload[..payload_length], 0).unwrap();
         let mut msg_decode = Message::new();
 
         // Process all frames
@@ -259,7 +877,7 @@ mod tests {
 
         // Get decoded payload
         let mut decoded_payload = [0xFF; MAX_NMEA_PACKET_SIZE];
-        let decoded_len = msg_decode.get_payload(&mut decoded_payload);
+        let decoded_len = msg_decode.get_payload(&mut decoded_payload).unwrap();
 
         // Compare original and decoded payloads
         assert_eq!(
@@ -271,16 +889,464 @@ mod tests {
     #[test]
     fn test_fuzzing() {
         // Test up to 6-byte payload size which can be fit into a single chunk.
-        for payload_length in 0..6 {
+        for payload_length in 1..6 {
             // Generate random payload length between 1 and 100 bytess
             test_for_payload_size(payload_length);
         }
 
-        // Test for variable lengths.
-        for payload_length in 6..=200 {
+        // Test for variable lengths, up to the largest payload, which needs
+        // every one of the 32 frames.
+        for payload_length in 6..=MAX_NMEA_PACKET_SIZE {
             test_for_payload_size(payload_length);
         }
+    }
+
+    #[test]
+    fn test_expected_frames() {
+        assert_eq!(Message::expected_frames_for_len(1), 1);
+        assert_eq!(Message::expected_frames_for_len(6), 1);
+        assert_eq!(Message::expected_frames_for_len(7), 2);
+        assert_eq!(Message::expected_frames_for_len(13), 2);
+        assert_eq!(Message::expected_frames_for_len(14), 3);
+        assert_eq!(Message::expected_frames_for_len(216), 31);
+        assert_eq!(Message::expected_frames_for_len(223), 32);
+
+        for len in 1..=MAX_NMEA_PACKET_SIZE {
+            let payload = [0u8; MAX_NMEA_PACKET_SIZE];
+            let mut msg = Message::from_payload(&payload[..len], 0).unwrap();
+            let expected = Message::expected_frames_for_len(len as u8);
+            assert_eq!(msg.num_frames, expected);
+            assert_eq!(msg.bytes_remaining(), len);
+            let mut popped = 0;
+            while msg.pop_frame().is_some() {
+                popped += 1;
+            }
+            assert_eq!(popped, expected);
+            assert_eq!(msg.bytes_remaining(), 0);
+        }
+    }
+
+    #[test]
+    fn test_bytes_remaining_rx() {
+        let mut msg = Message::new();
+        assert_eq!(msg.bytes_remaining(), 0);
+        let buf_1: [u8; 8] = [0x00, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D];
+        let buf_2: [u8; 8] = [0x01, 0x31, 0xF3, 0xD0, 0xAC, 0xF2, 0x23, 0x1A];
+        assert!(!msg.add_frame(&buf_1).unwrap());
+        assert_eq!(msg.bytes_remaining(), 19);
+        assert!(!msg.add_frame(&buf_2).unwrap());
+        assert_eq!(msg.bytes_remaining(), 12);
+    }
+
+    #[test]
+    fn test_invalid_payload_length() {
+        assert_eq!(
+            Message::from_payload(&[], 0).err(),
+            Some(Error::InvalidPayloadLength)
+        );
+        let oversized = [0u8; MAX_NMEA_PACKET_SIZE + 1];
+        assert_eq!(
+            Message::from_payload(&oversized, 0).err(),
+            Some(Error::InvalidPayloadLength)
+        );
+    }
+
+    #[test]
+    fn test_single_frame_rx() {
+        let mut msg = Message::new();
+        let buf: [u8; 8] = [0x40, 0x03, 0x01, 0x02, 0x03, 0xFF, 0xFF, 0xFF];
+        assert!(msg.add_frame(&buf).unwrap());
+        assert_eq!(msg.num_frames, 1);
+        assert_eq!(msg.sequence_counter, 2);
+
+        let mut payload = [0xFF; MAX_NMEA_PACKET_SIZE];
+        assert_eq!(msg.get_payload(&mut payload).unwrap(), 3);
+        assert_eq!(payload[..3], [0x01, 0x02, 0x03]);
+    }
+
+    #[test]
+    fn test_abort() {
+        let mut msg = Message::new();
+        let buf_1: [u8; 8] = [0x00, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D];
+        let buf_2: [u8; 8] = [0x01, 0x31, 0xF3, 0xD0, 0xAC, 0xF2, 0x23, 0x1A];
+        assert!(!msg.add_frame(&buf_1).unwrap());
+        assert!(msg.abort());
+        assert_eq!(msg.num_frames, 0);
+        assert_eq!(msg.data_len, 0);
+        assert!(!msg.abort());
+
+        // The aborted session's continuation frames are no longer accepted.
+        assert_eq!(
+            msg.add_frame(&buf_2).unwrap_err(),
+            Error::SequenceMismatch {
+                expected: 0,
+                received: 1,
+                frame: buf_2,
+            }
+        );
+
+        let mut tx = Message::from_payload(&[0u8; 25], 0).unwrap();
+        assert!(tx.pop_frame().is_some());
+        assert!(tx.abort());
+        assert!(tx.pop_frame().is_none());
+    }
+
+    #[test]
+    fn test_kind_and_direction() {
+        let mut msg = Message::new();
+        assert_eq!(msg.kind(), MessageType::Unknown);
+        assert_eq!(msg.direction(), TransmissionType::Rx);
+        let buf_1: [u8; 8] = [0x00, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D];
+        msg.add_frame(&buf_1).unwrap();
+        assert_eq!(msg.kind(), MessageType::Consecutive);
+
+        let mut tx = Message::from_payload(&[0x01, 0x02], 0).unwrap();
+        assert_eq!(tx.kind(), MessageType::Single);
+        assert_eq!(tx.direction(), TransmissionType::Tx);
+        assert_eq!(
+            tx.add_frame(&buf_1).unwrap_err(),
+            Error::TransmissionTypeMismatch
+        );
+        let mut buf = [0xFF; MAX_NMEA_PACKET_SIZE];
+        assert_eq!(
+            tx.get_payload(&mut buf).unwrap_err(),
+            Error::TransmissionTypeMismatch
+        );
+        // Frames are left in place for transmission.
+        assert!(tx.pop_frame().is_some());
+    }
+
+    #[test]
+    fn test_oversized_first_frame() {
+        let mut msg = Message::new();
+        let buf: [u8; 8] = [0x00, 0xF0, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D];
+        assert_eq!(
+            msg.add_frame(&buf).unwrap_err(),
+            Error::InvalidPayloadLength
+        );
+    }
+
+    #[test]
+    fn test_first_frame_mid_session() {
+        let buf_1: [u8; 8] = [0x00, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D];
+        let buf_2: [u8; 8] = [0x01, 0x31, 0xF3, 0xD0, 0xAC, 0xF2, 0x23, 0x1A];
+        let single: [u8; 8] = [0x40, 0x03, 0x01, 0x02, 0x03, 0xFF, 0xFF, 0xFF];
+
+        let mut msg = Message::new();
+        assert!(!msg.add_frame(&buf_1).unwrap());
+        assert!(!msg.add_frame(&buf_2).unwrap());
+        assert!(msg.add_frame(&single).unwrap());
+        assert!(msg.is_complete());
+        let mut payload = [0xFF; MAX_NMEA_PACKET_SIZE];
+        assert_eq!(msg.get_payload(&mut payload).unwrap(), 3);
+        assert_eq!(payload[..3], [0x01, 0x02, 0x03]);
+
+        let mut msg = Message::with_policy(FirstFramePolicy::Error);
+        assert!(!msg.add_frame(&buf_1).unwrap());
+        assert_eq!(
+            msg.add_frame(&single).unwrap_err(),
+            Error::UnexpectedFirstFrame { frame: single }
+        );
+        // The message in progress is kept.
+        assert!(!msg.add_frame(&buf_2).unwrap());
+        assert!(!msg.is_complete());
+    }
+
+    #[test]
+    fn test_clone_and_snapshot() {
+        let buf_1: [u8; 8] = [0x00, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D];
+        let buf_2: [u8; 8] = [0x01, 0x31, 0xF3, 0xD0, 0xAC, 0xF2, 0x23, 0x1A];
+        let buf_3: [u8; 8] = [0x02, 0x03, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00];
+        let buf_4: [u8; 8] = [0x03, 0x20, 0xFF, 0xFF, 0x00, 0x70, 0xFF, 0xFF];
+
+        let mut msg = Message::new();
+        assert!(!msg.add_frame(&buf_1).unwrap());
+        assert!(!msg.add_frame(&buf_2).unwrap());
+        let partial = msg.clone();
+        assert_eq!(partial, msg);
+        assert!(!msg.add_frame(&buf_3).unwrap());
+        assert_ne!(partial, msg);
+        assert!(msg.add_frame(&buf_4).unwrap());
+
+        let snapshot = msg.snapshot();
+        let mut expected = [0xFF; MAX_NMEA_PACKET_SIZE];
+        assert_eq!(msg.get_payload(&mut expected).unwrap(), 25);
+        msg.clear();
+
+        assert_eq!(snapshot.frames(), [buf_1, buf_2, buf_3, buf_4]);
+        assert_eq!(snapshot.kind(), MessageType::Consecutive);
+        assert_eq!(snapshot.data_len(), 25);
+        let mut payload = [0xFF; MAX_NMEA_PACKET_SIZE];
+        assert_eq!(snapshot.payload(&mut payload), 25);
+        assert_eq!(payload, expected);
+        assert_eq!(snapshot.raw_frames(), [buf_1, buf_2, buf_3, buf_4].concat());
+        // The padding after the payload is kept.
+        assert_eq!(snapshot.raw_padded_payload(&mut payload), 27);
+        assert_eq!(payload[..25], expected[..25]);
+        assert_eq!(payload[23..27], [0x00, 0x70, 0xFF, 0xFF]);
+
+        assert_eq!(snapshot.payload_u16_le(0), Some(0x7C12));
+        // Fields spanning frames are read across the frame headers.
+        assert_eq!(snapshot.payload_u32_le(4), Some(0xF331_3D12));
+        assert_eq!(snapshot.payload_i64_le(17), Some(0x7000_FFFF_2000_0000));
+        assert_eq!(snapshot.payload_i64_le(18), None);
+        assert_eq!(snapshot.payload_byte(24), Some(0x70));
+        assert_eq!(snapshot.payload_byte(25), None);
+        assert_eq!(snapshot.payload_u16_le(usize::MAX), None);
+    }
+
+    #[test]
+    fn test_assemble_into() {
+        let buf_1: [u8; 8] = [0x00, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D];
+        let buf_2: [u8; 8] = [0x01, 0x31, 0xF3, 0xD0, 0xAC, 0xF2, 0x23, 0x1A];
+        let buf_3: [u8; 8] = [0x02, 0x03, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00];
+        let buf_4: [u8; 8] = [0x03, 0x20, 0xFF, 0xFF, 0x00, 0x70, 0xFF, 0xFF];
+
+        let mut msg = Message::new();
+        let mut out = alloc::vec![0xAA];
+        assert_eq!(msg.assemble_into(&mut out), 0);
+        msg.add_frame(&buf_1).unwrap();
+        msg.add_frame(&buf_2).unwrap();
+        // The payload received so far, appended after what `out` held.
+        assert_eq!(msg.assemble_into(&mut out), 13);
+        assert_eq!(out[..7], [0xAA, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D]);
+        assert_eq!(out[7..], buf_2[1..]);
+
+        msg.add_frame(&buf_3).unwrap();
+        msg.add_frame(&buf_4).unwrap();
+        out.clear();
+        assert_eq!(msg.assemble_into(&mut out), 25);
+        let mut expected = [0xFF; MAX_NMEA_PACKET_SIZE];
+        assert_eq!(msg.copy_payload(&mut expected), 25);
+        assert_eq!(out, expected[..25]);
+        // Padding after the payload isn't appended.
+        assert_eq!(out[20..], [0x20, 0xFF, 0xFF, 0x00, 0x70]);
+    }
+
+    #[test]
+    fn test_raw_frames() {
+        // The sender pads with 0xAA instead of 0xFF, which only the raw
+        // accessors show.
+        let frames: [[u8; 8]; 2] = [
+            [0x40, 0x09, 1, 2, 3, 4, 5, 6],
+            [0x41, 7, 8, 9, 0xAA, 0xAA, 0xAA, 0xAA],
+        ];
+        let mut msg = Message::new();
+        for frame in &frames {
+            msg.add_frame(frame).unwrap();
+        }
+        let snapshot = msg.snapshot();
+        assert_eq!(snapshot.raw_frames().len(), 16);
+        assert_eq!(snapshot.raw_frames()[..8], frames[0]);
+        assert_eq!(snapshot.raw_frames()[8..], frames[1]);
+
+        let mut buf = [0xFF; 16];
+        assert_eq!(snapshot.raw_padded_payload(&mut buf), 13);
+        assert_eq!(
+            buf[..13],
+            [1, 2, 3, 4, 5, 6, 7, 8, 9, 0xAA, 0xAA, 0xAA, 0xAA]
+        );
+        assert_eq!(buf[13..], [0xFF; 3]);
+        let mut trimmed = [0xFF; 16];
+        assert_eq!(snapshot.payload(&mut trimmed), 9);
+        assert_eq!(trimmed[..9], buf[..9]);
+        assert_eq!(trimmed[9..], [0xFF; 7]);
+        // A short buffer takes what fits.
+        assert_eq!(snapshot.raw_padded_payload(&mut buf[..4]), 4);
+
+        // A single frame carries 6 bytes, padding included.
+        let mut single = Message::new();
+        single
+            .add_frame(&[0x20, 0x02, 7, 8, 0xFF, 0xFF, 0xFF, 0xFF])
+            .unwrap();
+        let snapshot = single.snapshot();
+        assert_eq!(
+            snapshot.raw_frames(),
+            [0x20, 0x02, 7, 8, 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+        assert_eq!(snapshot.raw_padded_payload(&mut buf), 6);
+        assert_eq!(buf[..6], [7, 8, 0xFF, 0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn test_refragment() {
+        let frames: [[u8; 8]; 4] = [
+            [0x00, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D],
+            [0x01, 0x31, 0xF3, 0xD0, 0xAC, 0xF2, 0x23, 0x1A],
+            [0x02, 0x03, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00],
+            [0x03, 0x20, 0xFF, 0xFF, 0x00, 0x70, 0xFF, 0xFF],
+        ];
+        let mut rx = Message::new();
+        assert_eq!(rx.refragment(0).unwrap_err(), Error::IncompleteMessage);
+        for frame in &frames[..3] {
+            rx.add_frame(frame).unwrap();
+        }
+        assert_eq!(rx.refragment(0).unwrap_err(), Error::IncompleteMessage);
+        rx.add_frame(&frames[3]).unwrap();
+
+        // Same sequence counter: the frames are reproduced byte for byte.
+        let mut tx = rx.refragment(0).unwrap();
+        assert_eq!(tx.direction(), TransmissionType::Tx);
+        for frame in &frames {
+            assert_eq!(tx.pop_frame().unwrap().bytes, *frame);
+        }
+        assert!(tx.pop_frame().is_none());
+
+        let mut tx = rx.refragment(5).unwrap();
+        assert_eq!(tx.pop_frame().unwrap().bytes[0], 0xA0);
+        assert_eq!(
+            tx.refragment(1).unwrap_err(),
+            Error::TransmissionTypeMismatch
+        );
+        assert!(rx.is_complete());
+    }
+
+    #[test]
+    fn test_state() {
+        let mut rx = Message::with_policy(FirstFramePolicy::Error);
+        rx.add_frame(&[0x40, 0x09, 1, 2, 3, 4, 5, 6]).unwrap();
+        let mut tx = Message::from_payload(&[0x55; 20], 3).unwrap();
+        tx.pop_frame().unwrap();
+        for msg in [Message::new(), rx, tx] {
+            let mut buf = [0; STATE_LEN];
+            let len = msg.to_state(&mut buf);
+            assert_eq!(Message::from_state(&buf[..len]), Ok(msg));
+        }
+
+        // Restored messages carry on where the original left off.
+        let mut restored =
+            Message::from_state(&[1, 0, 2, 9, 2, 0, 1, 0x40, 0x09, 1, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(
+            restored.add_frame(&[0x41, 7, 8, 9, 0xFF, 0xFF, 0xFF, 0xFF]),
+            Ok(true)
+        );
+
+        assert_eq!(Message::from_state(&[1, 0, 2]), Err(Error::InvalidState));
+        assert_eq!(
+            Message::from_state(&[3, 0, 0, 0, 0, 0, 0]),
+            Err(Error::InvalidState)
+        );
+        // Frame counts, lengths and frames that don't agree.
+        for header in [
+            [1, 0, 3, 9, 2, 0, 1],
+            [1, 0, 2, 224, 2, 0, 1],
+            [1, 0, 0, 9, 2, 0, 1],
+            [1, 0, 1, 9, 2, 0, 1],
+            [1, 0, 2, 9, 2, 2, 1],
+        ] {
+            let mut state = header.to_vec();
+            state.extend_from_slice(&[0x40, 0x09, 1, 2, 3, 4, 5, 6]);
+            assert_eq!(Message::from_state(&state), Err(Error::InvalidState));
+        }
+        let mut state = [1, 0, 1, 3, 2, 0, 1].to_vec();
+        state.extend_from_slice(&[0x40, 0x03, 1, 2, 3, 0xFF, 0xFF, 0xFF].repeat(2));
+        assert_eq!(Message::from_state(&state), Err(Error::InvalidState));
+    }
+
+    #[test]
+    fn test_error_context() {
+        use alloc::string::ToString;
+
+        let mut msg = Message::new();
+        let buf_1: [u8; 8] = [0x00, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D];
+        let other_sequence: [u8; 8] = [0x21, 0x31, 0xF3, 0xD0, 0xAC, 0xF2, 0x23, 0x1A];
+        let skipped: [u8; 8] = [0x02, 0x03, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00];
+        assert!(!msg.add_frame(&buf_1).unwrap());
+
+        let err = msg.add_frame(&other_sequence).unwrap_err();
+        assert_eq!(
+            err,
+            Error::SequenceCountError {
+                expected: 0,
+                received: 1,
+                frame: other_sequence,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Wrong sequence counter (expected 0, received 1)"
+        );
+        assert_eq!(
+            msg.add_frame(&skipped).unwrap_err(),
+            Error::SequenceMismatch {
+                expected: 1,
+                received: 2,
+                frame: skipped,
+            }
+        );
+    }
+
+    struct ShortSpec;
+
+    impl FastPacketSpec for ShortSpec {
+        const FRAME_COUNTER_BITS: u8 = 4;
+        const MAX_LEN: u8 = 48;
+    }
+
+    #[test]
+    fn test_custom_spec() {
+        let payload = [0x5A; 48];
+        let mut msg_encode: FastPacketMessage<ShortSpec> =
+            FastPacketMessage::from_payload(&payload, 12).unwrap();
+        let mut msg_decode: FastPacketMessage<ShortSpec> = FastPacketMessage::new();
+        while let Some(frame) = msg_encode.pop_frame() {
+            assert_eq!(frame.bytes[0] >> 4, 12);
+            let _ = msg_decode.add_frame(&frame.bytes);
+        }
+        let mut decoded_payload = [0xFF; MAX_NMEA_PACKET_SIZE];
+        let decoded_len = msg_decode.get_payload(&mut decoded_payload).unwrap();
+        assert_eq!(decoded_payload[..decoded_len], payload);
+
+        assert_eq!(
+            FastPacketMessage::<ShortSpec>::from_payload(&[0x5A; 49], 0).err(),
+            Some(Error::InvalidPayloadLength)
+        );
+    }
+
+    #[test]
+    fn test_typestate() {
+        let payload: [u8; 9] = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let mut tx = Message::from_payload(&payload, 3).unwrap();
+        let first = tx.pop_frame().unwrap().bytes;
+        let second = tx.pop_frame().unwrap().bytes;
+
+        let Progress::Assembling(msg) = Assembling::<Nmea2000>::new().add_frame(&first).unwrap()
+        else {
+            panic!("complete after the first frame");
+        };
+        assert_eq!(
+            Complete::try_from(msg.message().clone()),
+            Err(Error::IncompleteMessage)
+        );
+        let Progress::Complete(complete) = msg.add_frame(&second).unwrap() else {
+            panic!("incomplete after the last frame");
+        };
+        assert_eq!(
+            complete.get_payload(&mut [0; 8]),
+            Err(Error::BufferTooSmall)
+        );
+        let mut buf = [0; 12];
+        assert_eq!(complete.get_payload(&mut buf), Ok(9));
+        assert_eq!(buf, [1, 2, 3, 4, 5, 6, 7, 8, 9, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(
+            Complete::try_from(complete.message().clone()).as_ref(),
+            Ok(&complete)
+        );
 
-        test_for_payload_size(216);
+        let msg = complete.reset();
+        assert_eq!(msg.message().kind(), MessageType::Unknown);
+        assert!(matches!(
+            msg.add_frame(&second),
+            Err((_, Error::SequenceMismatch { .. }))
+        ));
+
+        // An unexpected first frame leaves the message in progress.
+        let msg = Assembling::<Nmea2000>::with_policy(FirstFramePolicy::Error);
+        let Ok(Progress::Assembling(msg)) = msg.add_frame(&first) else {
+            panic!("complete after the first frame");
+        };
+        let (msg, error) = msg.add_frame(&first).unwrap_err();
+        assert_eq!(error, Error::UnexpectedFirstFrame { frame: first });
+        assert!(matches!(msg.add_frame(&second), Ok(Progress::Complete(_))));
     }
 }
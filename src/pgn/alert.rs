@@ -0,0 +1,286 @@
+//! Alert PGNs.
+use crate::pgn::{check_len, Error};
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertType {
+    EmergencyAlarm,
+    Alarm,
+    Warning,
+    Caution,
+    Unknown(u8),
+}
+
+impl From<u8> for AlertType {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::EmergencyAlarm,
+            2 => Self::Alarm,
+            5 => Self::Warning,
+            8 => Self::Caution,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<AlertType> for u8 {
+    fn from(value: AlertType) -> u8 {
+        match value {
+            AlertType::EmergencyAlarm => 1,
+            AlertType::Alarm => 2,
+            AlertType::Warning => 5,
+            AlertType::Caution => 8,
+            AlertType::Unknown(v) => v,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertCategory {
+    Navigational,
+    Technical,
+    Unknown(u8),
+}
+
+impl From<u8> for AlertCategory {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Navigational,
+            1 => Self::Technical,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<AlertCategory> for u8 {
+    fn from(value: AlertCategory) -> u8 {
+        match value {
+            AlertCategory::Navigational => 0,
+            AlertCategory::Technical => 1,
+            AlertCategory::Unknown(v) => v,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriggerCondition {
+    Manual,
+    Auto,
+    Test,
+    Disabled,
+    Unknown(u8),
+}
+
+impl From<u8> for TriggerCondition {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Manual,
+            1 => Self::Auto,
+            2 => Self::Test,
+            3 => Self::Disabled,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<TriggerCondition> for u8 {
+    fn from(value: TriggerCondition) -> u8 {
+        match value {
+            TriggerCondition::Manual => 0,
+            TriggerCondition::Auto => 1,
+            TriggerCondition::Test => 2,
+            TriggerCondition::Disabled => 3,
+            TriggerCondition::Unknown(v) => v,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThresholdStatus {
+    Normal,
+    Exceeded,
+    ExtremeExceeded,
+    LowExceeded,
+    Acknowledged,
+    AwaitingAcknowledge,
+    Unknown(u8),
+}
+
+impl From<u8> for ThresholdStatus {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Normal,
+            1 => Self::Exceeded,
+            2 => Self::ExtremeExceeded,
+            3 => Self::LowExceeded,
+            4 => Self::Acknowledged,
+            5 => Self::AwaitingAcknowledge,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<ThresholdStatus> for u8 {
+    fn from(value: ThresholdStatus) -> u8 {
+        match value {
+            ThresholdStatus::Normal => 0,
+            ThresholdStatus::Exceeded => 1,
+            ThresholdStatus::ExtremeExceeded => 2,
+            ThresholdStatus::LowExceeded => 3,
+            ThresholdStatus::Acknowledged => 4,
+            ThresholdStatus::AwaitingAcknowledge => 5,
+            ThresholdStatus::Unknown(v) => v,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertState {
+    Disabled,
+    Normal,
+    Active,
+    Silenced,
+    Acknowledged,
+    AwaitingAcknowledge,
+    Unknown(u8),
+}
+
+impl From<u8> for AlertState {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Disabled,
+            1 => Self::Normal,
+            2 => Self::Active,
+            3 => Self::Silenced,
+            4 => Self::Acknowledged,
+            5 => Self::AwaitingAcknowledge,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<AlertState> for u8 {
+    fn from(value: AlertState) -> u8 {
+        match value {
+            AlertState::Disabled => 0,
+            AlertState::Normal => 1,
+            AlertState::Active => 2,
+            AlertState::Silenced => 3,
+            AlertState::Acknowledged => 4,
+            AlertState::AwaitingAcknowledge => 5,
+            AlertState::Unknown(v) => v,
+        }
+    }
+}
+
+/// Alert (PGN 126983), sent as a Fast-Packet message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Alert {
+    pub alert_type: AlertType,
+    pub category: AlertCategory,
+    pub system: u8,
+    pub sub_system: u8,
+    pub id: u16,
+    /// NAME of the device whose data raised the alert.
+    pub source_name: u64,
+    pub source_instance: u8,
+    pub source_index: u8,
+    pub occurrence: u8,
+    /// Temporary silence, acknowledge and escalation status from bit 0,
+    /// then support for each of them from bit 3.
+    pub flags: u8,
+    /// NAME of the device that acknowledged the alert.
+    pub acknowledge_name: u64,
+    pub trigger: TriggerCondition,
+    pub threshold_status: ThresholdStatus,
+    pub priority: u8,
+    pub state: AlertState,
+}
+
+impl Alert {
+    pub const PGN: u32 = 126983;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 28)?;
+        Ok(Self {
+            alert_type: AlertType::from(payload[0] & 0x0F),
+            category: AlertCategory::from(payload[0] >> 4),
+            system: payload[1],
+            sub_system: payload[2],
+            id: u16::from_le_bytes([payload[3], payload[4]]),
+            source_name: u64::from_le_bytes(payload[5..13].try_into().unwrap()),
+            source_instance: payload[13],
+            source_index: payload[14],
+            occurrence: payload[15],
+            flags: payload[16] & 0x3F,
+            acknowledge_name: u64::from_le_bytes(payload[17..25].try_into().unwrap()),
+            trigger: TriggerCondition::from(payload[25] & 0x0F),
+            threshold_status: ThresholdStatus::from(payload[25] >> 4),
+            priority: payload[26],
+            state: AlertState::from(payload[27]),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; 28] {
+        let mut buf = [0xFF; 28];
+        buf[0] = u8::from(self.alert_type) & 0x0F | u8::from(self.category) << 4;
+        buf[1] = self.system;
+        buf[2] = self.sub_system;
+        buf[3..5].copy_from_slice(&self.id.to_le_bytes());
+        buf[5..13].copy_from_slice(&self.source_name.to_le_bytes());
+        buf[13] = self.source_instance;
+        buf[14] = self.source_index;
+        buf[15] = self.occurrence;
+        buf[16] = self.flags & 0x3F | 0xC0;
+        buf[17..25].copy_from_slice(&self.acknowledge_name.to_le_bytes());
+        buf[25] = u8::from(self.trigger) & 0x0F | u8::from(self.threshold_status) << 4;
+        buf[26] = self.priority;
+        buf[27] = u8::from(self.state);
+        buf
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "alert #{} {:?} {:?} {:?}",
+            self.id, self.alert_type, self.state, self.threshold_status
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alert() {
+        let alert = Alert {
+            alert_type: AlertType::Warning,
+            category: AlertCategory::Technical,
+            system: 5,
+            sub_system: 0,
+            id: 0x0102,
+            source_name: 0xC032_0A00_00E0_1234,
+            source_instance: 0,
+            source_index: 0,
+            occurrence: 1,
+            flags: 0x10,
+            acknowledge_name: u64::MAX,
+            trigger: TriggerCondition::Auto,
+            threshold_status: ThresholdStatus::LowExceeded,
+            priority: 0,
+            state: AlertState::Active,
+        };
+        let payload = alert.to_payload();
+        assert_eq!(payload[..5], [0x15, 0x05, 0x00, 0x02, 0x01]);
+        assert_eq!(payload[16], 0xD0);
+        assert_eq!(payload[25..], [0x31, 0x00, 0x02]);
+        assert_eq!(Alert::from_payload(&payload), Ok(alert));
+        assert_eq!(
+            Alert::from_payload(&payload[..27]),
+            Err(Error::InvalidLength)
+        );
+    }
+}
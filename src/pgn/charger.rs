@@ -0,0 +1,397 @@
+//! Charger and inverter PGNs.
+use crate::pgn::{check_len, read_u16, Error, Opt};
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChargerState {
+    NotCharging,
+    Bulk,
+    Absorption,
+    Overcharge,
+    Equalise,
+    Float,
+    NoFloat,
+    ConstantVi,
+    Disabled,
+    Fault,
+    Unknown(u8),
+}
+
+impl From<u8> for ChargerState {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::NotCharging,
+            1 => Self::Bulk,
+            2 => Self::Absorption,
+            3 => Self::Overcharge,
+            4 => Self::Equalise,
+            5 => Self::Float,
+            6 => Self::NoFloat,
+            7 => Self::ConstantVi,
+            8 => Self::Disabled,
+            9 => Self::Fault,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<ChargerState> for u8 {
+    fn from(value: ChargerState) -> u8 {
+        match value {
+            ChargerState::NotCharging => 0,
+            ChargerState::Bulk => 1,
+            ChargerState::Absorption => 2,
+            ChargerState::Overcharge => 3,
+            ChargerState::Equalise => 4,
+            ChargerState::Float => 5,
+            ChargerState::NoFloat => 6,
+            ChargerState::ConstantVi => 7,
+            ChargerState::Disabled => 8,
+            ChargerState::Fault => 9,
+            ChargerState::Unknown(v) => v,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChargerMode {
+    Standalone,
+    Primary,
+    Secondary,
+    Echo,
+    Unknown(u8),
+}
+
+impl From<u8> for ChargerMode {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Standalone,
+            1 => Self::Primary,
+            2 => Self::Secondary,
+            3 => Self::Echo,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<ChargerMode> for u8 {
+    fn from(value: ChargerMode) -> u8 {
+        match value {
+            ChargerMode::Standalone => 0,
+            ChargerMode::Primary => 1,
+            ChargerMode::Secondary => 2,
+            ChargerMode::Echo => 3,
+            ChargerMode::Unknown(v) => v,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InverterState {
+    Invert,
+    AcPassthrough,
+    LoadSense,
+    Fault,
+    Disabled,
+    Unknown(u8),
+}
+
+impl From<u8> for InverterState {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Invert,
+            1 => Self::AcPassthrough,
+            2 => Self::LoadSense,
+            3 => Self::Fault,
+            4 => Self::Disabled,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<InverterState> for u8 {
+    fn from(value: InverterState) -> u8 {
+        match value {
+            InverterState::Invert => 0,
+            InverterState::AcPassthrough => 1,
+            InverterState::LoadSense => 2,
+            InverterState::Fault => 3,
+            InverterState::Disabled => 4,
+            InverterState::Unknown(v) => v,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChargingAlgorithm {
+    Trickle,
+    ConstantVoltageConstantCurrent,
+    TwoStage,
+    ThreeStage,
+    Unknown(u8),
+}
+
+impl From<u8> for ChargingAlgorithm {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Trickle,
+            1 => Self::ConstantVoltageConstantCurrent,
+            2 => Self::TwoStage,
+            3 => Self::ThreeStage,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<ChargingAlgorithm> for u8 {
+    fn from(value: ChargingAlgorithm) -> u8 {
+        match value {
+            ChargingAlgorithm::Trickle => 0,
+            ChargingAlgorithm::ConstantVoltageConstantCurrent => 1,
+            ChargingAlgorithm::TwoStage => 2,
+            ChargingAlgorithm::ThreeStage => 3,
+            ChargingAlgorithm::Unknown(v) => v,
+        }
+    }
+}
+
+/// Reads a two-bit off/on field; error and not available decode to `None`.
+fn read_flag(byte: u8, shift: u8) -> Option<bool> {
+    match (byte >> shift) & 0x03 {
+        0 => Some(false),
+        1 => Some(true),
+        _ => None,
+    }
+}
+
+fn write_flag(flag: Option<bool>, shift: u8) -> u8 {
+    (match flag {
+        Some(false) => 0,
+        Some(true) => 1,
+        None => 3,
+    }) << shift
+}
+
+/// Charger Status (PGN 127507).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChargerStatus {
+    pub instance: u8,
+    pub battery_instance: u8,
+    pub state: ChargerState,
+    pub mode: ChargerMode,
+    pub enabled: Option<bool>,
+    pub equalization_pending: Option<bool>,
+    /// Remaining equalization time in seconds.
+    pub equalization_time_remaining: Option<u16>,
+}
+
+impl ChargerStatus {
+    pub const PGN: u32 = 127507;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 6)?;
+        Ok(Self {
+            instance: payload[0],
+            battery_instance: payload[1],
+            state: ChargerState::from(payload[2] & 0x0F),
+            mode: ChargerMode::from(payload[2] >> 4),
+            enabled: read_flag(payload[3], 0),
+            equalization_pending: read_flag(payload[3], 2),
+            equalization_time_remaining: read_u16(payload, 4),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        let mut buf = [0xFF; 8];
+        buf[0] = self.instance;
+        buf[1] = self.battery_instance;
+        buf[2] = u8::from(self.state) & 0x0F | u8::from(self.mode) << 4;
+        buf[3] = write_flag(self.enabled, 0) | write_flag(self.equalization_pending, 2) | 0xF0;
+        buf[4..6].copy_from_slice(
+            &self
+                .equalization_time_remaining
+                .unwrap_or(0xFFFF)
+                .to_le_bytes(),
+        );
+        buf
+    }
+}
+
+/// Inverter Status (PGN 127509).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InverterStatus {
+    pub instance: u8,
+    pub ac_instance: u8,
+    pub dc_instance: u8,
+    pub state: InverterState,
+    pub enabled: Option<bool>,
+}
+
+impl InverterStatus {
+    pub const PGN: u32 = 127509;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 4)?;
+        Ok(Self {
+            instance: payload[0],
+            ac_instance: payload[1],
+            dc_instance: payload[2],
+            state: InverterState::from(payload[3] & 0x0F),
+            enabled: read_flag(payload[3], 4),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        let mut buf = [0xFF; 8];
+        buf[0] = self.instance;
+        buf[1] = self.ac_instance;
+        buf[2] = self.dc_instance;
+        buf[3] = u8::from(self.state) & 0x0F | write_flag(self.enabled, 4) | 0xC0;
+        buf
+    }
+}
+
+/// Charger Configuration Status (PGN 127510, Fast-Packet).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChargerConfiguration {
+    pub instance: u8,
+    pub battery_instance: u8,
+    pub enabled: Option<bool>,
+    /// Charge current limit in amperes.
+    pub charge_current_limit: Option<f32>,
+    pub algorithm: ChargingAlgorithm,
+    pub mode: ChargerMode,
+    /// 0 cold, 1 warm, 2 hot; the temperature assumed when no sensor is fitted.
+    pub estimated_temperature: u8,
+    pub equalize_one_time: Option<bool>,
+    pub overcharge: Option<bool>,
+    /// Equalization time in seconds.
+    pub equalize_time: Option<u16>,
+}
+
+impl ChargerConfiguration {
+    pub const PGN: u32 = 127510;
+    pub const LEN: usize = 9;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, Self::LEN)?;
+        Ok(Self {
+            instance: payload[0],
+            battery_instance: payload[1],
+            enabled: read_flag(payload[2], 0),
+            charge_current_limit: read_u16(payload, 3).map(|v| v as f32 * 0.1),
+            algorithm: ChargingAlgorithm::from(payload[5] & 0x0F),
+            mode: ChargerMode::from(payload[5] >> 4),
+            estimated_temperature: payload[6] & 0x03,
+            equalize_one_time: read_flag(payload[6], 2),
+            overcharge: read_flag(payload[6], 4),
+            equalize_time: read_u16(payload, 7),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; Self::LEN] {
+        let mut buf = [0xFF; Self::LEN];
+        buf[0] = self.instance;
+        buf[1] = self.battery_instance;
+        buf[2] = write_flag(self.enabled, 0) | 0xFC;
+        let limit = self
+            .charge_current_limit
+            .map_or(0xFFFF, |v| libm::roundf(v / 0.1) as u16);
+        buf[3..5].copy_from_slice(&limit.to_le_bytes());
+        buf[5] = u8::from(self.algorithm) & 0x0F | u8::from(self.mode) << 4;
+        buf[6] = self.estimated_temperature & 0x03
+            | write_flag(self.equalize_one_time, 2)
+            | write_flag(self.overcharge, 4)
+            | 0xC0;
+        buf[7..9].copy_from_slice(&self.equalize_time.unwrap_or(0xFFFF).to_le_bytes());
+        buf
+    }
+}
+
+impl fmt::Display for ChargerStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "charger #{} battery #{} {:?} {:?}",
+            self.instance, self.battery_instance, self.state, self.mode
+        )
+    }
+}
+
+impl fmt::Display for InverterStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "inverter #{} {:?}", self.instance, self.state)
+    }
+}
+
+impl fmt::Display for ChargerConfiguration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "charger #{} config {:?} limit={:.1}A",
+            self.instance,
+            self.algorithm,
+            Opt(self.charge_current_limit)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nmea_message::{Message, MessageType};
+
+    #[test]
+    fn test_charger_status() {
+        let payload: [u8; 8] = [0x00, 0x01, 0x15, 0xF1, 0xFF, 0xFF, 0xFF, 0xFF];
+        let status = ChargerStatus::from_payload(&payload).unwrap();
+        assert_eq!(status.state, ChargerState::Float);
+        assert_eq!(status.mode, ChargerMode::Primary);
+        assert_eq!(status.enabled, Some(true));
+        assert_eq!(status.equalization_pending, Some(false));
+        assert_eq!(status.equalization_time_remaining, None);
+        assert_eq!(status.to_payload(), payload);
+        assert_eq!(
+            ChargerStatus::from_payload(&payload[..5]),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_inverter_status() {
+        let payload: [u8; 8] = [0x00, 0x01, 0x02, 0xD1, 0xFF, 0xFF, 0xFF, 0xFF];
+        let status = InverterStatus::from_payload(&payload).unwrap();
+        assert_eq!(status.state, InverterState::AcPassthrough);
+        assert_eq!(status.enabled, Some(true));
+        assert_eq!(status.to_payload(), payload);
+    }
+
+    #[test]
+    fn test_charger_configuration() {
+        let config = ChargerConfiguration {
+            instance: 0,
+            battery_instance: 1,
+            enabled: Some(true),
+            charge_current_limit: Some(40.0),
+            algorithm: ChargingAlgorithm::ThreeStage,
+            mode: ChargerMode::Standalone,
+            estimated_temperature: 1,
+            equalize_one_time: Some(false),
+            overcharge: None,
+            equalize_time: Some(3600),
+        };
+        let payload = config.to_payload();
+        assert_eq!(
+            payload,
+            [0x00, 0x01, 0xFD, 0x90, 0x01, 0x03, 0xF1, 0x10, 0x0E]
+        );
+        assert_eq!(ChargerConfiguration::from_payload(&payload), Ok(config));
+
+        // Too long for a single frame, so it goes out as a Fast-Packet message.
+        let mut msg = Message::from_payload(&payload, 3).unwrap();
+        assert_eq!(msg.kind(), MessageType::Consecutive);
+        assert!(msg.pop_frame().unwrap().is_first_frame());
+        assert!(msg.pop_frame().is_some());
+        assert!(msg.pop_frame().is_none());
+    }
+}
@@ -0,0 +1,82 @@
+//! Water depth PGN.
+use crate::pgn::{check_len, read_i16, read_u32, read_u8, Error, Opt};
+use core::fmt;
+
+/// Water Depth (PGN 128267).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WaterDepth {
+    pub sid: Option<u8>,
+    /// Depth below the transducer in metres.
+    pub depth: Option<f32>,
+    /// Distance from the transducer to the waterline when positive, or to
+    /// the keel when negative, in metres.
+    pub offset: Option<f32>,
+    /// Maximum range of the sounder in metres.
+    pub range: Option<f32>,
+}
+
+impl WaterDepth {
+    pub const PGN: u32 = 128267;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 7)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            depth: read_u32(payload, 1).map(|v| v as f32 * 0.01),
+            offset: read_i16(payload, 5).map(|v| v as f32 * 0.001),
+            range: read_u8(payload, 7).map(|v| v as f32 * 10.0),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        let mut buf = [0xFF; 8];
+        buf[0] = self.sid.unwrap_or(0xFF);
+        let depth = self
+            .depth
+            .map_or(0xFFFF_FFFF, |v| libm::roundf(v / 0.01) as u32);
+        buf[1..5].copy_from_slice(&depth.to_le_bytes());
+        let offset = self
+            .offset
+            .map_or(0x7FFF, |v| libm::roundf(v / 0.001) as i16);
+        buf[5..7].copy_from_slice(&offset.to_le_bytes());
+        buf[7] = self.range.map_or(0xFF, |v| libm::roundf(v / 10.0) as u8);
+        buf
+    }
+
+    /// Depth below the waterline or the keel, as given by `offset`.
+    pub fn adjusted_depth(&self) -> Option<f32> {
+        Some(self.depth? + self.offset.unwrap_or(0.0))
+    }
+}
+
+impl fmt::Display for WaterDepth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "depth {:.2}m offset={:.3}m",
+            Opt(self.depth),
+            Opt(self.offset)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_water_depth() {
+        // 12.34 m below a transducer 0.5 m above the keel.
+        let payload: [u8; 8] = [0x01, 0xD2, 0x04, 0x00, 0x00, 0x0C, 0xFE, 0x0A];
+        let depth = WaterDepth::from_payload(&payload).unwrap();
+        assert!((depth.depth.unwrap() - 12.34).abs() < 1e-5);
+        assert!((depth.offset.unwrap() + 0.5).abs() < 1e-6);
+        assert_eq!(depth.range, Some(100.0));
+        assert!((depth.adjusted_depth().unwrap() - 11.84).abs() < 1e-5);
+        assert_eq!(depth.to_payload(), payload);
+        assert_eq!(
+            WaterDepth::from_payload(&payload[..6]),
+            Err(Error::InvalidLength)
+        );
+    }
+}
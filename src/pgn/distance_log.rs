@@ -0,0 +1,87 @@
+//! Distance Log (PGN 128275).
+use crate::pgn::{check_len, read_u16, read_u32, Error, Opt};
+use core::fmt;
+
+/// Distance Log (PGN 128275, Fast-Packet).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DistanceLog {
+    /// Days since 1970-01-01.
+    pub date: Option<u16>,
+    /// Seconds since midnight UTC.
+    pub time: Option<f64>,
+    /// Total cumulative distance in metres.
+    pub log: Option<u32>,
+    /// Distance since the last trip reset in metres.
+    pub trip_log: Option<u32>,
+}
+
+impl DistanceLog {
+    pub const PGN: u32 = 128275;
+    pub const LEN: usize = 14;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, Self::LEN)?;
+        Ok(Self {
+            date: read_u16(payload, 0),
+            time: read_u32(payload, 2).map(|v| v as f64 * 1e-4),
+            log: read_u32(payload, 6),
+            trip_log: read_u32(payload, 10),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; Self::LEN] {
+        let mut buf = [0xFF; Self::LEN];
+        buf[0..2].copy_from_slice(&self.date.unwrap_or(0xFFFF).to_le_bytes());
+        let time = self
+            .time
+            .map_or(0xFFFF_FFFF, |v| libm::round(v / 1e-4) as u32);
+        buf[2..6].copy_from_slice(&time.to_le_bytes());
+        buf[6..10].copy_from_slice(&self.log.unwrap_or(0xFFFF_FFFF).to_le_bytes());
+        buf[10..14].copy_from_slice(&self.trip_log.unwrap_or(0xFFFF_FFFF).to_le_bytes());
+        buf
+    }
+}
+
+impl fmt::Display for DistanceLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "log total={}m trip={}m",
+            Opt(self.log),
+            Opt(self.trip_log)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_log() {
+        let payload: [u8; 14] = [
+            0x7B, 0x4D, 0x00, 0xCC, 0xBF, 0x19, 0x40, 0xE2, 0x01, 0x00, 0xD2, 0x04, 0x00, 0x00,
+        ];
+        let log = DistanceLog::from_payload(&payload).unwrap();
+        assert_eq!(log.date, Some(19835));
+        assert_eq!(log.time, Some(43200.0));
+        assert_eq!(log.log, Some(123456));
+        assert_eq!(log.trip_log, Some(1234));
+        assert_eq!(log.to_payload(), payload);
+
+        let unknown = DistanceLog {
+            date: None,
+            time: None,
+            log: None,
+            trip_log: Some(0),
+        };
+        assert_eq!(
+            DistanceLog::from_payload(&unknown.to_payload()).unwrap(),
+            unknown
+        );
+        assert_eq!(
+            DistanceLog::from_payload(&payload[..13]),
+            Err(Error::InvalidLength)
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Engine parameter PGNs.
+use crate::pgn::{celsius, check_len, read_i16, read_u16, read_u32, Error, Opt};
+use core::fmt;
+
+/// Engine Parameters, Rapid Update (PGN 127488).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EngineRapid {
+    /// 0 for a single or the port engine, 1 for the starboard engine.
+    pub instance: u8,
+    /// Engine speed in rpm.
+    pub speed: Option<f32>,
+    /// Boost pressure in Pascal.
+    pub boost: Option<f32>,
+    /// Drive tilt or trim in percent.
+    pub tilt_trim: Option<i8>,
+}
+
+impl EngineRapid {
+    pub const PGN: u32 = 127488;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 3)?;
+        Ok(Self {
+            instance: payload[0],
+            speed: read_u16(payload, 1).map(|v| v as f32 * 0.25),
+            boost: read_u16(payload, 3).map(|v| v as f32 * 100.0),
+            tilt_trim: payload.get(5).map(|v| *v as i8).filter(|v| *v < 0x7E),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        let mut buf = [0xFF; 8];
+        buf[0] = self.instance;
+        let speed = self.speed.map_or(0xFFFF, |v| libm::roundf(v / 0.25) as u16);
+        buf[1..3].copy_from_slice(&speed.to_le_bytes());
+        let boost = self
+            .boost
+            .map_or(0xFFFF, |v| libm::roundf(v / 100.0) as u16);
+        buf[3..5].copy_from_slice(&boost.to_le_bytes());
+        buf[5] = self.tilt_trim.map_or(0x7F, |v| v as u8);
+        buf
+    }
+}
+
+impl fmt::Display for EngineRapid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "engine {} speed={:.0}rpm boost={:.0}Pa trim={}%",
+            self.instance,
+            Opt(self.speed),
+            Opt(self.boost),
+            Opt(self.tilt_trim)
+        )
+    }
+}
+
+/// Engine Parameters, Dynamic (PGN 127489). A Fast-Packet PGN; the discrete
+/// status, load and torque fields are not decoded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EngineDynamic {
+    pub instance: u8,
+    /// Oil pressure in Pascal.
+    pub oil_pressure: Option<f32>,
+    /// Oil temperature in Kelvin.
+    pub oil_temperature: Option<f32>,
+    /// Coolant temperature in Kelvin.
+    pub temperature: Option<f32>,
+    /// Alternator potential in volts.
+    pub alternator_potential: Option<f32>,
+    /// Fuel rate in L/h.
+    pub fuel_rate: Option<f32>,
+    /// Total engine hours as kept by the engine, in seconds.
+    pub total_hours: Option<u32>,
+    /// Coolant pressure in Pascal.
+    pub coolant_pressure: Option<f32>,
+    /// Fuel pressure in Pascal.
+    pub fuel_pressure: Option<f32>,
+}
+
+impl EngineDynamic {
+    pub const PGN: u32 = 127489;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 15)?;
+        Ok(Self {
+            instance: payload[0],
+            oil_pressure: read_u16(payload, 1).map(|v| v as f32 * 100.0),
+            oil_temperature: read_u16(payload, 3).map(|v| v as f32 * 0.1),
+            temperature: read_u16(payload, 5).map(|v| v as f32 * 0.01),
+            alternator_potential: read_i16(payload, 7).map(|v| v as f32 * 0.01),
+            fuel_rate: read_i16(payload, 9).map(|v| v as f32 * 0.1),
+            total_hours: read_u32(payload, 11),
+            coolant_pressure: read_u16(payload, 15).map(|v| v as f32 * 100.0),
+            fuel_pressure: read_u16(payload, 17).map(|v| v as f32 * 1000.0),
+        })
+    }
+
+    /// Encodes the payload with the undecoded fields set to not available.
+    pub fn to_payload(&self) -> [u8; 26] {
+        let mut buf = [0xFF; 26];
+        buf[0] = self.instance;
+        let u16_field = |v: Option<f32>, scale: f32| {
+            v.map_or(0xFFFF, |v| libm::roundf(v / scale) as u16)
+                .to_le_bytes()
+        };
+        let i16_field = |v: Option<f32>, scale: f32| {
+            v.map_or(0x7FFF, |v| libm::roundf(v / scale) as i16)
+                .to_le_bytes()
+        };
+        buf[1..3].copy_from_slice(&u16_field(self.oil_pressure, 100.0));
+        buf[3..5].copy_from_slice(&u16_field(self.oil_temperature, 0.1));
+        buf[5..7].copy_from_slice(&u16_field(self.temperature, 0.01));
+        buf[7..9].copy_from_slice(&i16_field(self.alternator_potential, 0.01));
+        buf[9..11].copy_from_slice(&i16_field(self.fuel_rate, 0.1));
+        buf[11..15].copy_from_slice(&self.total_hours.unwrap_or(0xFFFF_FFFF).to_le_bytes());
+        buf[15..17].copy_from_slice(&u16_field(self.coolant_pressure, 100.0));
+        buf[17..19].copy_from_slice(&u16_field(self.fuel_pressure, 1000.0));
+        buf[24] = 0x7F;
+        buf[25] = 0x7F;
+        buf
+    }
+}
+
+impl fmt::Display for EngineDynamic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "engine {} oil={:.0}Pa/{:.1}°C coolant={:.1}°C alternator={:.2}V fuel={:.1}L/h hours={:.1}",
+            self.instance,
+            Opt(self.oil_pressure),
+            celsius(self.oil_temperature),
+            celsius(self.temperature),
+            Opt(self.alternator_potential),
+            Opt(self.fuel_rate),
+            Opt(self.total_hours.map(|s| s as f32 / 3600.0))
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rapid() {
+        // Port engine at 1500 rpm, 50 kPa boost, trim not available.
+        let payload: [u8; 8] = [0x00, 0x70, 0x17, 0xF4, 0x01, 0x7F, 0xFF, 0xFF];
+        let rapid = EngineRapid::from_payload(&payload).unwrap();
+        assert_eq!(rapid.speed, Some(1500.0));
+        assert_eq!(rapid.boost, Some(50_000.0));
+        assert_eq!(rapid.tilt_trim, None);
+        assert_eq!(rapid.to_payload(), payload);
+        assert_eq!(
+            EngineRapid::from_payload(&payload[..2]),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_dynamic() {
+        let dynamic = EngineDynamic {
+            instance: 1,
+            oil_pressure: Some(350_000.0),
+            oil_temperature: None,
+            temperature: Some(358.15),
+            alternator_potential: Some(14.2),
+            fuel_rate: Some(12.5),
+            total_hours: Some(1234 * 3600),
+            coolant_pressure: None,
+            fuel_pressure: None,
+        };
+        let payload = dynamic.to_payload();
+        assert_eq!(&payload[9..11], &[125, 0]);
+        let decoded = EngineDynamic::from_payload(&payload).unwrap();
+        assert_eq!(decoded.fuel_rate, Some(12.5));
+        assert_eq!(decoded.total_hours, Some(1234 * 3600));
+        assert!((decoded.temperature.unwrap() - 358.15).abs() < 1e-3);
+        // The pressures at the end are optional.
+        assert_eq!(
+            EngineDynamic::from_payload(&payload[..15])
+                .unwrap()
+                .fuel_rate,
+            Some(12.5)
+        );
+        assert_eq!(
+            EngineDynamic::from_payload(&payload[..14]),
+            Err(Error::InvalidLength)
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! Environmental parameter PGNs.
+use crate::pgn::{celsius, check_len, read_i16, read_i32, read_u16, read_u24, read_u8, Error, Opt};
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PressureSource {
+    Atmospheric,
+    Water,
+    Steam,
+    CompressedAir,
+    Hydraulic,
+    Filter,
+    AltimeterSetting,
+    Oil,
+    Fuel,
+    Unknown(u8),
+}
+
+impl From<u8> for PressureSource {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Atmospheric,
+            1 => Self::Water,
+            2 => Self::Steam,
+            3 => Self::CompressedAir,
+            4 => Self::Hydraulic,
+            5 => Self::Filter,
+            6 => Self::AltimeterSetting,
+            7 => Self::Oil,
+            8 => Self::Fuel,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<PressureSource> for u8 {
+    fn from(value: PressureSource) -> u8 {
+        match value {
+            PressureSource::Atmospheric => 0,
+            PressureSource::Water => 1,
+            PressureSource::Steam => 2,
+            PressureSource::CompressedAir => 3,
+            PressureSource::Hydraulic => 4,
+            PressureSource::Filter => 5,
+            PressureSource::AltimeterSetting => 6,
+            PressureSource::Oil => 7,
+            PressureSource::Fuel => 8,
+            PressureSource::Unknown(v) => v,
+        }
+    }
+}
+
+/// Actual Pressure (PGN 130314) and Set Pressure (PGN 130315), which share
+/// the same layout.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pressure {
+    pub sid: Option<u8>,
+    pub instance: u8,
+    pub source: PressureSource,
+    /// Pressure in Pascal, at 0.1 Pa resolution.
+    pub pressure: Option<f64>,
+}
+
+impl Pressure {
+    pub const ACTUAL_PGN: u32 = 130314;
+    pub const SET_PGN: u32 = 130315;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 7)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            instance: payload[1],
+            source: PressureSource::from(payload[2]),
+            pressure: read_i32(payload, 3).map(|v| v as f64 * 0.1),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        let mut buf = [0xFF; 8];
+        buf[0] = self.sid.unwrap_or(0xFF);
+        buf[1] = self.instance;
+        buf[2] = u8::from(self.source);
+        let pressure = self
+            .pressure
+            .map_or(0x7FFF_FFFF, |v| libm::round(v / 0.1) as i32);
+        buf[3..7].copy_from_slice(&pressure.to_le_bytes());
+        buf
+    }
+}
+
+/// A `Pressure` received or sent as Actual Pressure (PGN 130314).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ActualPressure(pub Pressure);
+
+impl ActualPressure {
+    pub const PGN: u32 = Pressure::ACTUAL_PGN;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        Pressure::from_payload(payload).map(Self)
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        self.0.to_payload()
+    }
+}
+
+/// A `Pressure` received or sent as Set Pressure (PGN 130315).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SetPressure(pub Pressure);
+
+impl SetPressure {
+    pub const PGN: u32 = Pressure::SET_PGN;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        Pressure::from_payload(payload).map(Self)
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        self.0.to_payload()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TemperatureSource {
+    Sea,
+    Outside,
+    Inside,
+    EngineRoom,
+    MainCabin,
+    LiveWell,
+    BaitWell,
+    Refrigeration,
+    HeatingSystem,
+    DewPoint,
+    ApparentWindChill,
+    TheoreticalWindChill,
+    HeatIndex,
+    Freezer,
+    ExhaustGas,
+    ShaftSeal,
+    Unknown(u8),
+}
+
+impl From<u8> for TemperatureSource {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Sea,
+            1 => Self::Outside,
+            2 => Self::Inside,
+            3 => Self::EngineRoom,
+            4 => Self::MainCabin,
+            5 => Self::LiveWell,
+            6 => Self::BaitWell,
+            7 => Self::Refrigeration,
+            8 => Self::HeatingSystem,
+            9 => Self::DewPoint,
+            10 => Self::ApparentWindChill,
+            11 => Self::TheoreticalWindChill,
+            12 => Self::HeatIndex,
+            13 => Self::Freezer,
+            14 => Self::ExhaustGas,
+            15 => Self::ShaftSeal,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+/// Temperature (PGN 130312) or Temperature, Extended Range (PGN 130316).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Temperature {
+    pub sid: Option<u8>,
+    pub instance: u8,
+    pub source: TemperatureSource,
+    /// Temperature in Kelvin.
+    pub temperature: Option<f32>,
+    /// Set temperature in Kelvin.
+    pub set_temperature: Option<f32>,
+}
+
+impl Temperature {
+    pub const PGN: u32 = 130312;
+    pub const EXTENDED_PGN: u32 = 130316;
+
+    /// Decodes PGN 130312, at 0.01 K resolution.
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 7)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            instance: payload[1],
+            source: TemperatureSource::from(payload[2]),
+            temperature: read_u16(payload, 3).map(|v| v as f32 * 0.01),
+            set_temperature: read_u16(payload, 5).map(|v| v as f32 * 0.01),
+        })
+    }
+
+    /// Decodes PGN 130316, at 0.001 K resolution.
+    pub fn from_extended_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 8)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            instance: payload[1],
+            source: TemperatureSource::from(payload[2]),
+            temperature: read_u24(payload, 3).map(|v| v as f32 * 0.001),
+            set_temperature: read_u16(payload, 6).map(|v| v as f32 * 0.1),
+        })
+    }
+}
+
+/// A `Temperature` received as Temperature, Extended Range (PGN 130316).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExtendedTemperature(pub Temperature);
+
+impl ExtendedTemperature {
+    pub const PGN: u32 = Temperature::EXTENDED_PGN;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        Temperature::from_extended_payload(payload).map(Self)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HumiditySource {
+    Inside,
+    Outside,
+    Unknown(u8),
+}
+
+impl From<u8> for HumiditySource {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Inside,
+            1 => Self::Outside,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+/// Humidity (PGN 130313).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Humidity {
+    pub sid: Option<u8>,
+    pub instance: u8,
+    pub source: HumiditySource,
+    /// Relative humidity in %.
+    pub humidity: Option<f32>,
+    /// Set humidity in %.
+    pub set_humidity: Option<f32>,
+}
+
+impl Humidity {
+    pub const PGN: u32 = 130313;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 7)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            instance: payload[1],
+            source: HumiditySource::from(payload[2]),
+            humidity: read_i16(payload, 3).map(|v| v as f32 * 0.004),
+            set_humidity: read_i16(payload, 5).map(|v| v as f32 * 0.004),
+        })
+    }
+}
+
+impl fmt::Display for Pressure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pressure #{} {:?} {:.1}hPa",
+            self.instance,
+            self.source,
+            Opt(self.pressure.map(|p| p / 100.0))
+        )
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "temperature #{} {:?} {:.1}C set={:.1}C",
+            self.instance,
+            self.source,
+            celsius(self.temperature),
+            celsius(self.set_temperature)
+        )
+    }
+}
+
+impl fmt::Display for Humidity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "humidity #{} {:?} {:.1}% set={:.1}%",
+            self.instance,
+            self.source,
+            Opt(self.humidity),
+            Opt(self.set_humidity)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pressure() {
+        // 1013.25 hPa atmospheric pressure.
+        let payload: [u8; 8] = [0x00, 0x00, 0x00, 0x02, 0x76, 0x0F, 0x00, 0xFF];
+        let pressure = Pressure::from_payload(&payload).unwrap();
+        assert_eq!(pressure.source, PressureSource::Atmospheric);
+        assert!((pressure.pressure.unwrap() - 101325.0).abs() < 1e-6);
+        assert_eq!(pressure.to_payload(), payload);
+
+        let set = Pressure {
+            sid: None,
+            instance: 2,
+            source: PressureSource::Hydraulic,
+            pressure: Some(-12.3),
+        };
+        assert_eq!(
+            Pressure::from_payload(&set.to_payload()).unwrap().pressure,
+            Some(-12.3)
+        );
+        assert_eq!(
+            Pressure::from_payload(&payload[..6]),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_temperature() {
+        // Refrigeration at 277.15 K (4 °C), no set point.
+        let payload: [u8; 8] = [0xFF, 0x01, 0x07, 0x43, 0x6C, 0xFF, 0xFF, 0xFF];
+        let temperature = Temperature::from_payload(&payload).unwrap();
+        assert_eq!(temperature.source, TemperatureSource::Refrigeration);
+        assert!((temperature.temperature.unwrap() - 277.15).abs() < 1e-3);
+        assert_eq!(temperature.set_temperature, None);
+
+        // Freezer at 255.372 K, set to 255.4 K.
+        let payload: [u8; 8] = [0xFF, 0x00, 0x0D, 0x8C, 0xE5, 0x03, 0xFA, 0x09];
+        let temperature = Temperature::from_extended_payload(&payload).unwrap();
+        assert_eq!(temperature.source, TemperatureSource::Freezer);
+        assert!((temperature.temperature.unwrap() - 255.372).abs() < 1e-3);
+        assert!((temperature.set_temperature.unwrap() - 255.4).abs() < 1e-3);
+        assert_eq!(
+            Temperature::from_extended_payload(&payload[..7]),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_humidity() {
+        let payload: [u8; 8] = [0xFF, 0x00, 0x00, 0xD4, 0x30, 0xFF, 0x7F, 0xFF];
+        let humidity = Humidity::from_payload(&payload).unwrap();
+        assert_eq!(humidity.source, HumiditySource::Inside);
+        assert!((humidity.humidity.unwrap() - 50.0).abs() < 1e-3);
+        assert_eq!(humidity.set_humidity, None);
+    }
+}
@@ -0,0 +1,177 @@
+//! Fluid Level (PGN 127505) and tank sender calibration.
+use crate::pgn::group_function::{self, Acknowledge};
+use crate::pgn::{check_len, read_i16, read_u32, Error, Opt};
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FluidType {
+    Fuel,
+    Water,
+    GrayWater,
+    LiveWell,
+    Oil,
+    BlackWater,
+    Gasoline,
+    Unknown(u8),
+}
+
+impl From<u8> for FluidType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Fuel,
+            1 => Self::Water,
+            2 => Self::GrayWater,
+            3 => Self::LiveWell,
+            4 => Self::Oil,
+            5 => Self::BlackWater,
+            6 => Self::Gasoline,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<FluidType> for u8 {
+    fn from(value: FluidType) -> u8 {
+        match value {
+            FluidType::Fuel => 0,
+            FluidType::Water => 1,
+            FluidType::GrayWater => 2,
+            FluidType::LiveWell => 3,
+            FluidType::Oil => 4,
+            FluidType::BlackWater => 5,
+            FluidType::Gasoline => 6,
+            FluidType::Unknown(v) => v,
+        }
+    }
+}
+
+/// Fluid Level (PGN 127505).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FluidLevel {
+    pub instance: u8,
+    pub fluid_type: FluidType,
+    /// Level in % of capacity.
+    pub level: Option<f32>,
+    /// Tank capacity in litres.
+    pub capacity: Option<f32>,
+}
+
+impl FluidLevel {
+    pub const PGN: u32 = 127505;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 7)?;
+        Ok(Self {
+            instance: payload[0] & 0x0F,
+            fluid_type: FluidType::from(payload[0] >> 4),
+            level: read_i16(payload, 1).map(|v| v as f32 * 0.004),
+            capacity: read_u32(payload, 3).map(|v| v as f32 * 0.1),
+        })
+    }
+}
+
+/// Group function payloads that configure a tank level sender.
+///
+/// The command is addressed to the sender and sets the fluid type (field 2)
+/// and/or the capacity (field 4) it reports in PGN 127505.
+pub struct TankCalibration {
+    pub fluid_type: Option<FluidType>,
+    /// Capacity in litres.
+    pub capacity: Option<f32>,
+}
+
+impl TankCalibration {
+    /// Largest command payload, with both fields set.
+    pub const MAX_LEN: usize = 13;
+
+    /// Encodes the Command group function into `buf` and returns its length.
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let fluid_type = self.fluid_type.map(|t| [u8::from(t)]);
+        let capacity = self
+            .capacity
+            .map(|c| (libm::roundf(c / 0.1) as u32).to_le_bytes());
+        let mut parameters: [(u8, &[u8]); 2] = [(0, &[]); 2];
+        let mut count = 0;
+        if let Some(fluid_type) = &fluid_type {
+            parameters[count] = (2, fluid_type);
+            count += 1;
+        }
+        if let Some(capacity) = &capacity {
+            parameters[count] = (4, capacity);
+            count += 1;
+        }
+        group_function::encode_command(FluidLevel::PGN, &parameters[..count], buf)
+    }
+
+    /// Checks a group function received from the sender in reply. Returns
+    /// `Ok(None)` if `payload` is not an acknowledgement for PGN 127505,
+    /// otherwise whether every field was accepted.
+    pub fn check_acknowledge(payload: &[u8]) -> Result<Option<bool>, Error> {
+        if group_function::function_code(payload)? != group_function::FunctionCode::Acknowledge {
+            return Ok(None);
+        }
+        let ack = Acknowledge::from_payload(payload)?;
+        if ack.pgn != FluidLevel::PGN {
+            return Ok(None);
+        }
+        Ok(Some(ack.is_success()))
+    }
+}
+
+impl fmt::Display for FluidLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "tank #{} {:?} {:.1}% of {:.0}L",
+            self.instance,
+            self.fluid_type,
+            Opt(self.level),
+            Opt(self.capacity)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fluid_level() {
+        // Fresh water tank 1 at 75% of 200 L.
+        let payload: [u8; 8] = [0x11, 0x3E, 0x49, 0xD0, 0x07, 0x00, 0x00, 0xFF];
+        let level = FluidLevel::from_payload(&payload).unwrap();
+        assert_eq!(level.instance, 1);
+        assert_eq!(level.fluid_type, FluidType::Water);
+        assert!((level.level.unwrap() - 75.0).abs() < 1e-3);
+        assert!((level.capacity.unwrap() - 200.0).abs() < 1e-3);
+        assert_eq!(
+            FluidLevel::from_payload(&payload[..6]),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_calibration() {
+        let calibration = TankCalibration {
+            fluid_type: Some(FluidType::BlackWater),
+            capacity: Some(120.0),
+        };
+        let mut buf = [0; TankCalibration::MAX_LEN];
+        let len = calibration.encode(&mut buf).unwrap();
+        assert_eq!(len, TankCalibration::MAX_LEN);
+        assert_eq!(
+            buf,
+            [0x01, 0x11, 0xF2, 0x01, 0xF8, 0x02, 0x02, 0x05, 0x04, 0xB0, 0x04, 0x00, 0x00]
+        );
+
+        let ack = [0x02, 0x11, 0xF2, 0x01, 0x00, 0x02, 0x00, 0xFF];
+        assert_eq!(TankCalibration::check_acknowledge(&ack), Ok(Some(true)));
+        let rejected = [0x02, 0x11, 0xF2, 0x01, 0x00, 0x02, 0x30, 0xFF];
+        assert_eq!(
+            TankCalibration::check_acknowledge(&rejected),
+            Ok(Some(false))
+        );
+        let other_pgn = [0x02, 0x12, 0xF2, 0x01, 0x00, 0x00, 0xFF, 0xFF];
+        assert_eq!(TankCalibration::check_acknowledge(&other_pgn), Ok(None));
+    }
+}
@@ -0,0 +1,356 @@
+//! Fusion stereo messages (proprietary PGN 130820, manufacturer code 419).
+//!
+//! Fusion does not publish these layouts; they follow the community
+//! reverse-engineering and only the messages whose structure is well
+//! established are decoded. Everything else is returned as
+//! `FusionMessage::Other`. Now-playing information is spread over several
+//! messages, which `FusionState` collects into one view.
+use crate::pgn::proprietary::{ProprietaryDecoder, ProprietaryHeader};
+use crate::pgn::{check_len, Error, Opt};
+use core::fmt;
+use fixed_queue::Vec;
+
+pub const MANUFACTURER_CODE: u16 = 419;
+pub const PGN: u32 = 130820;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerState {
+    On,
+    Off,
+    Unknown(u8),
+}
+
+impl From<u8> for PowerState {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::On,
+            2 => Self::Off,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FusionMessage<'a> {
+    /// Name of a source (radio, AUX, Bluetooth, ...) and the active source.
+    SourceName {
+        source_id: u8,
+        current_source_id: u8,
+        name: &'a [u8],
+    },
+    TrackName(&'a [u8]),
+    ArtistName(&'a [u8]),
+    AlbumName(&'a [u8]),
+    /// Volume of zones 1 to 4, `None` for zones the unit doesn't have.
+    ZoneVolume([Option<u8>; 4]),
+    Mute(bool),
+    Power(PowerState),
+    Other {
+        message_id: u8,
+        data: &'a [u8],
+    },
+}
+
+/// Reads a length-prefixed string, dropping any NUL terminator.
+fn read_text(payload: &[u8], offset: usize) -> Result<&[u8], Error> {
+    check_len(payload, offset + 1)?;
+    let len = payload[offset] as usize;
+    check_len(payload, offset + 1 + len)?;
+    let text = &payload[offset + 1..offset + 1 + len];
+    let end = text.iter().position(|b| *b == 0).unwrap_or(text.len());
+    Ok(&text[..end])
+}
+
+impl<'a> ProprietaryDecoder<'a> for FusionMessage<'a> {
+    const MANUFACTURER_CODE: u16 = MANUFACTURER_CODE;
+    const PGNS: &'static [u32] = &[PGN];
+
+    fn decode(_pgn: u32, payload: &'a [u8]) -> Result<Self, Error> {
+        check_len(payload, ProprietaryHeader::LEN + 2)?;
+        let message_id = payload[2];
+        Ok(match message_id {
+            2 => {
+                check_len(payload, 6)?;
+                Self::SourceName {
+                    source_id: payload[4],
+                    current_source_id: payload[5],
+                    name: read_text(payload, 8)?,
+                }
+            }
+            5 => Self::TrackName(read_text(payload, 9)?),
+            6 => Self::ArtistName(read_text(payload, 9)?),
+            7 => Self::AlbumName(read_text(payload, 9)?),
+            23 => {
+                check_len(payload, 5)?;
+                Self::Mute(payload[4] == 1)
+            }
+            29 => {
+                check_len(payload, 8)?;
+                let zone = |v: u8| (v != 0xFF).then_some(v);
+                Self::ZoneVolume([
+                    zone(payload[4]),
+                    zone(payload[5]),
+                    zone(payload[6]),
+                    zone(payload[7]),
+                ])
+            }
+            32 => {
+                check_len(payload, 5)?;
+                Self::Power(PowerState::from(payload[4]))
+            }
+            _ => Self::Other {
+                message_id,
+                data: &payload[3..],
+            },
+        })
+    }
+}
+
+/// State of a Fusion stereo assembled from its status messages. Text fields
+/// hold at most `L` bytes and are truncated beyond that.
+#[derive(Debug)]
+pub struct FusionState<const L: usize> {
+    pub power: Option<PowerState>,
+    pub muted: Option<bool>,
+    pub zone_volume: [Option<u8>; 4],
+    pub current_source_id: Option<u8>,
+    source_name: Vec<u8, L>,
+    track: Vec<u8, L>,
+    artist: Vec<u8, L>,
+    album: Vec<u8, L>,
+}
+
+fn set_text<const L: usize>(field: &mut Vec<u8, L>, text: &[u8]) {
+    field.clear();
+    field.extend_from_slice(&text[..text.len().min(L)]);
+}
+
+impl<const L: usize> Default for FusionState<L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const L: usize> FusionState<L> {
+    pub const fn new() -> Self {
+        Self {
+            power: None,
+            muted: None,
+            zone_volume: [None; 4],
+            current_source_id: None,
+            source_name: Vec::new(),
+            track: Vec::new(),
+            artist: Vec::new(),
+            album: Vec::new(),
+        }
+    }
+
+    /// Decodes `payload` and applies it to the state. Returns `false` if the
+    /// payload is not a Fusion message.
+    pub fn ingest(&mut self, pgn: u32, payload: &[u8]) -> Result<bool, Error> {
+        match crate::pgn::proprietary::decode::<FusionMessage>(pgn, payload)? {
+            Some(msg) => {
+                self.apply(&msg);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub fn apply(&mut self, msg: &FusionMessage) {
+        match *msg {
+            FusionMessage::SourceName {
+                source_id,
+                current_source_id,
+                name,
+            } => {
+                if current_source_id != self.current_source_id.unwrap_or(current_source_id) {
+                    self.clear_track();
+                }
+                self.current_source_id = Some(current_source_id);
+                if source_id == current_source_id {
+                    set_text(&mut self.source_name, name);
+                }
+            }
+            FusionMessage::TrackName(name) => {
+                // A new title means the artist and album that follow belong
+                // to a different track.
+                if name[..name.len().min(L)] != *self.track {
+                    self.clear_track();
+                    set_text(&mut self.track, name);
+                }
+            }
+            FusionMessage::ArtistName(name) => set_text(&mut self.artist, name),
+            FusionMessage::AlbumName(name) => set_text(&mut self.album, name),
+            FusionMessage::ZoneVolume(volume) => self.zone_volume = volume,
+            FusionMessage::Mute(muted) => self.muted = Some(muted),
+            FusionMessage::Power(power) => self.power = Some(power),
+            FusionMessage::Other { .. } => {}
+        }
+    }
+
+    fn clear_track(&mut self) {
+        self.track.clear();
+        self.artist.clear();
+        self.album.clear();
+    }
+
+    pub fn source_name(&self) -> &[u8] {
+        &self.source_name
+    }
+
+    pub fn track(&self) -> &[u8] {
+        &self.track
+    }
+
+    pub fn artist(&self) -> &[u8] {
+        &self.artist
+    }
+
+    pub fn album(&self) -> &[u8] {
+        &self.album
+    }
+}
+
+/// Writes text as UTF-8, or `?` when it isn't valid.
+fn write_text(f: &mut fmt::Formatter<'_>, text: &[u8]) -> fmt::Result {
+    f.write_str(core::str::from_utf8(text).unwrap_or("?"))
+}
+
+impl fmt::Display for FusionMessage<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SourceName {
+                source_id,
+                current_source_id,
+                name,
+            } => {
+                write!(
+                    f,
+                    "fusion source {} (current {}) ",
+                    source_id, current_source_id
+                )?;
+                write_text(f, name)
+            }
+            Self::TrackName(text) => {
+                f.write_str("fusion track ")?;
+                write_text(f, text)
+            }
+            Self::ArtistName(text) => {
+                f.write_str("fusion artist ")?;
+                write_text(f, text)
+            }
+            Self::AlbumName(text) => {
+                f.write_str("fusion album ")?;
+                write_text(f, text)
+            }
+            Self::ZoneVolume(zones) => write!(
+                f,
+                "fusion volume {}/{}/{}/{}",
+                Opt(zones[0]),
+                Opt(zones[1]),
+                Opt(zones[2]),
+                Opt(zones[3])
+            ),
+            Self::Mute(muted) => write!(f, "fusion mute={}", muted),
+            Self::Power(state) => write!(f, "fusion power {:?}", state),
+            Self::Other { message_id, data } => {
+                write!(f, "fusion message {} ({} bytes)", message_id, data.len())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: [u8; 2] = [0xA3, 0x99];
+
+    fn text_message(message_id: u8, text: &[u8], buf: &mut [u8; 32]) -> usize {
+        buf[..2].copy_from_slice(&HEADER);
+        buf[2] = message_id;
+        buf[3..9].copy_from_slice(&[0x80, 0x01, 0x00, 0x00, 0x00, 0x00]);
+        buf[9] = text.len() as u8 + 1;
+        buf[10..10 + text.len()].copy_from_slice(text);
+        buf[10 + text.len()] = 0;
+        11 + text.len()
+    }
+
+    #[test]
+    fn test_decode() {
+        let power = [0xA3, 0x99, 0x20, 0x80, 0x01];
+        assert_eq!(
+            FusionMessage::decode(PGN, &power),
+            Ok(FusionMessage::Power(PowerState::On))
+        );
+        let volume = [0xA3, 0x99, 0x1D, 0x80, 0x0C, 0x18, 0xFF, 0xFF];
+        assert_eq!(
+            FusionMessage::decode(PGN, &volume),
+            Ok(FusionMessage::ZoneVolume([Some(12), Some(24), None, None]))
+        );
+        let source = [
+            0xA3, 0x99, 0x02, 0x80, 0x01, 0x01, 0x00, 0x00, 0x03, b'A', b'U', b'X',
+        ];
+        assert_eq!(
+            FusionMessage::decode(PGN, &source),
+            Ok(FusionMessage::SourceName {
+                source_id: 1,
+                current_source_id: 1,
+                name: b"AUX",
+            })
+        );
+
+        let mut buf = [0; 32];
+        let len = text_message(5, b"Blue Water", &mut buf);
+        assert_eq!(
+            FusionMessage::decode(PGN, &buf[..len]),
+            Ok(FusionMessage::TrackName(b"Blue Water"))
+        );
+        assert_eq!(
+            alloc::format!("{}", FusionMessage::decode(PGN, &buf[..len]).unwrap()),
+            "fusion track Blue Water"
+        );
+        assert_eq!(
+            alloc::format!("{}", FusionMessage::decode(PGN, &volume).unwrap()),
+            "fusion volume 12/24/-/-"
+        );
+        assert_eq!(
+            FusionMessage::decode(PGN, &buf[..len - 2]),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(
+            FusionMessage::decode(PGN, &[0xA3, 0x99, 0x63, 0x01]),
+            Ok(FusionMessage::Other {
+                message_id: 0x63,
+                data: &[0x01],
+            })
+        );
+    }
+
+    #[test]
+    fn test_state() {
+        let mut state: FusionState<8> = FusionState::new();
+        assert!(state.ingest(PGN, &[0xA3, 0x99, 0x17, 0x80, 0x01]).unwrap());
+        assert_eq!(state.muted, Some(true));
+        // Other manufacturers are ignored.
+        assert!(!state.ingest(PGN, &[0x87, 0x98, 0x17, 0x80, 0x02]).unwrap());
+
+        let mut buf = [0; 32];
+        let len = text_message(5, b"Blue Water", &mut buf);
+        state.ingest(PGN, &buf[..len]).unwrap();
+        let len = text_message(6, b"Trio", &mut buf);
+        state.ingest(PGN, &buf[..len]).unwrap();
+        assert_eq!(state.track(), b"Blue Wat");
+        assert_eq!(state.artist(), b"Trio");
+
+        // Repeating the title keeps the artist, a new title clears it.
+        let len = text_message(5, b"Blue Water", &mut buf);
+        state.ingest(PGN, &buf[..len]).unwrap();
+        assert_eq!(state.artist(), b"Trio");
+        let len = text_message(5, b"Ebb", &mut buf);
+        state.ingest(PGN, &buf[..len]).unwrap();
+        assert_eq!(state.track(), b"Ebb");
+        assert_eq!(state.artist(), b"");
+    }
+}
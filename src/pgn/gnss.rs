@@ -0,0 +1,578 @@
+//! GNSS PGNs.
+use crate::pgn::{
+    check_len, deg, read_i16, read_i32, read_i64, read_u16, read_u32, read_u8, Error, Group, Opt,
+    RepeatingGroup,
+};
+use core::fmt;
+
+/// Position Rapid Update (PGN 129025).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PositionRapidUpdate {
+    /// Latitude in degrees, positive north.
+    pub latitude: Option<f64>,
+    /// Longitude in degrees, positive east.
+    pub longitude: Option<f64>,
+}
+
+impl PositionRapidUpdate {
+    pub const PGN: u32 = 129025;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 8)?;
+        Ok(Self {
+            latitude: read_i32(payload, 0).map(|v| v as f64 * 1e-7),
+            longitude: read_i32(payload, 4).map(|v| v as f64 * 1e-7),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DirectionReference {
+    True,
+    Magnetic,
+    Unknown(u8),
+}
+
+impl From<u8> for DirectionReference {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::True,
+            1 => Self::Magnetic,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<DirectionReference> for u8 {
+    fn from(value: DirectionReference) -> u8 {
+        match value {
+            DirectionReference::True => 0,
+            DirectionReference::Magnetic => 1,
+            DirectionReference::Unknown(v) => v & 0x03,
+        }
+    }
+}
+
+/// COG & SOG, Rapid Update (PGN 129026).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CogSogRapidUpdate {
+    pub sid: Option<u8>,
+    pub cog_reference: DirectionReference,
+    /// Course over ground in radians.
+    pub cog: Option<f32>,
+    /// Speed over ground in m/s.
+    pub sog: Option<f32>,
+}
+
+impl CogSogRapidUpdate {
+    pub const PGN: u32 = 129026;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 6)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            cog_reference: DirectionReference::from(payload[1] & 0x03),
+            cog: read_u16(payload, 2).map(|v| v as f32 * 1e-4),
+            sog: read_u16(payload, 4).map(|v| v as f32 * 0.01),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GnssMethod {
+    NoFix,
+    GnssFix,
+    DgnssFix,
+    PreciseGnss,
+    RtkFixed,
+    RtkFloat,
+    Estimated,
+    Manual,
+    Simulated,
+    Unknown(u8),
+}
+
+impl From<u8> for GnssMethod {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::NoFix,
+            1 => Self::GnssFix,
+            2 => Self::DgnssFix,
+            3 => Self::PreciseGnss,
+            4 => Self::RtkFixed,
+            5 => Self::RtkFloat,
+            6 => Self::Estimated,
+            7 => Self::Manual,
+            8 => Self::Simulated,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+/// GNSS Position Data (PGN 129029, Fast-Packet). Reference station records
+/// are not decoded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GnssPositionData {
+    pub sid: Option<u8>,
+    /// Days since 1970-01-01.
+    pub date: Option<u16>,
+    /// Seconds since midnight UTC.
+    pub time: Option<f64>,
+    /// Latitude in degrees, positive north.
+    pub latitude: Option<f64>,
+    /// Longitude in degrees, positive east.
+    pub longitude: Option<f64>,
+    /// Altitude above the WGS-84 ellipsoid in metres.
+    pub altitude: Option<f64>,
+    pub gnss_type: u8,
+    pub method: GnssMethod,
+    pub integrity: u8,
+    pub satellites: Option<u8>,
+    pub hdop: Option<f32>,
+    pub pdop: Option<f32>,
+    /// Geoidal separation in metres.
+    pub geoidal_separation: Option<f32>,
+}
+
+impl GnssPositionData {
+    pub const PGN: u32 = 129029;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 43)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            date: read_u16(payload, 1),
+            time: read_u32(payload, 3).map(|v| v as f64 * 1e-4),
+            latitude: read_i64(payload, 7).map(|v| v as f64 * 1e-16),
+            longitude: read_i64(payload, 15).map(|v| v as f64 * 1e-16),
+            altitude: read_i64(payload, 23).map(|v| v as f64 * 1e-6),
+            gnss_type: payload[31] & 0x0F,
+            method: GnssMethod::from(payload[31] >> 4),
+            integrity: payload[32] & 0x03,
+            satellites: read_u8(payload, 33),
+            hdop: read_i16(payload, 34).map(|v| v as f32 * 0.01),
+            pdop: read_i16(payload, 36).map(|v| v as f32 * 0.01),
+            geoidal_separation: read_i32(payload, 38).map(|v| v as f32 * 0.01),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SatelliteStatus {
+    NotTracked,
+    Tracked,
+    Used,
+    NotTrackedDiff,
+    TrackedDiff,
+    UsedDiff,
+    Unknown(u8),
+}
+
+impl From<u8> for SatelliteStatus {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::NotTracked,
+            1 => Self::Tracked,
+            2 => Self::Used,
+            3 => Self::NotTrackedDiff,
+            4 => Self::TrackedDiff,
+            5 => Self::UsedDiff,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+/// One satellite record of PGN 129540.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Satellite {
+    pub prn: Option<u8>,
+    /// Elevation in radians.
+    pub elevation: Option<f32>,
+    /// Azimuth in radians.
+    pub azimuth: Option<f32>,
+    /// Signal to noise ratio in dB.
+    pub snr: Option<f32>,
+    /// Range residual in metres.
+    pub range_residual: Option<f32>,
+    pub status: SatelliteStatus,
+}
+
+impl Group for Satellite {
+    const SIZE: usize = 12;
+
+    fn decode(bytes: &[u8]) -> Self {
+        Self {
+            prn: read_u8(bytes, 0),
+            elevation: read_i16(bytes, 1).map(|v| v as f32 * 1e-4),
+            azimuth: read_u16(bytes, 3).map(|v| v as f32 * 1e-4),
+            snr: read_u16(bytes, 5).map(|v| v as f32 * 0.01),
+            range_residual: read_i32(bytes, 7).map(|v| v as f32 * 1e-5),
+            status: SatelliteStatus::from(bytes[11] & 0x0F),
+        }
+    }
+}
+
+/// GNSS Satellites in View (PGN 129540, Fast-Packet).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GnssSatsInView<'a> {
+    pub sid: Option<u8>,
+    pub range_residual_mode: u8,
+    pub sats_in_view: u8,
+    records: &'a [u8],
+}
+
+impl<'a> GnssSatsInView<'a> {
+    pub const PGN: u32 = 129540;
+
+    pub fn from_payload(payload: &'a [u8]) -> Result<Self, Error> {
+        check_len(payload, 3)?;
+        let sats_in_view = if payload[2] == 0xFF { 0 } else { payload[2] };
+        // Validate up front so that iterating can't fail.
+        RepeatingGroup::<Satellite>::new(&payload[3..], sats_in_view as usize)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            range_residual_mode: payload[1] & 0x03,
+            sats_in_view,
+            records: &payload[3..],
+        })
+    }
+
+    pub fn satellites(&self) -> RepeatingGroup<'a, Satellite> {
+        RepeatingGroup::new(self.records, self.sats_in_view as usize).unwrap()
+    }
+}
+
+/// Mean Earth radius in metres.
+pub const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Great-circle distance in metres between two positions in degrees, by the
+/// haversine formula.
+pub fn distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (lon2 - lon1).to_radians();
+    let a = libm::pow(libm::sin(dlat / 2.0), 2.0)
+        + libm::cos(lat1) * libm::cos(lat2) * libm::pow(libm::sin(dlon / 2.0), 2.0);
+    2.0 * EARTH_RADIUS * libm::asin(libm::sqrt(a).min(1.0))
+}
+
+/// Initial true bearing in radians, in `[0, 2π)`, of the great circle from
+/// the first position to the second, both in degrees.
+pub fn bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let dlon = (lon2 - lon1).to_radians();
+    let y = libm::sin(dlon) * libm::cos(lat2);
+    let x = libm::cos(lat1) * libm::sin(lat2) - libm::sin(lat1) * libm::cos(lat2) * libm::cos(dlon);
+    let bearing = libm::atan2(y, x);
+    if bearing < 0.0 {
+        bearing + 2.0 * core::f64::consts::PI
+    } else {
+        bearing
+    }
+}
+
+/// The position in degrees reached from a position in degrees by
+/// travelling `distance` metres along the great circle with initial true
+/// bearing `bearing` in radians.
+pub fn destination(lat: f64, lon: f64, bearing: f64, distance: f64) -> (f64, f64) {
+    let lat1 = lat.to_radians();
+    let delta = distance / EARTH_RADIUS;
+    let lat2 = libm::asin(
+        libm::sin(lat1) * libm::cos(delta)
+            + libm::cos(lat1) * libm::sin(delta) * libm::cos(bearing),
+    );
+    let dlon = libm::atan2(
+        libm::sin(bearing) * libm::sin(delta) * libm::cos(lat1),
+        libm::cos(delta) - libm::sin(lat1) * libm::sin(lat2),
+    );
+    // Wrap into [-180, 180).
+    let lon2 = lon + dlon.to_degrees();
+    let lon2 = lon2 - 360.0 * libm::floor((lon2 + 180.0) / 360.0);
+    (lat2.to_degrees(), lon2)
+}
+
+/// Displays a latitude or longitude in degrees and decimal minutes, e.g.
+/// `47°36.373'N`. The precision of the minutes defaults to 3 decimals.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::pgn::gnss::DegreesMinutes;
+///
+/// assert_eq!(DegreesMinutes::latitude(47.60621).to_string(), "47°36.373'N");
+/// assert_eq!(format!("{:.1}", DegreesMinutes::longitude(-122.33207)), "122°19.9'W");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DegreesMinutes {
+    value: f64,
+    hemispheres: [char; 2],
+}
+
+impl DegreesMinutes {
+    pub const fn latitude(value: f64) -> Self {
+        Self {
+            value,
+            hemispheres: ['N', 'S'],
+        }
+    }
+
+    pub const fn longitude(value: f64) -> Self {
+        Self {
+            value,
+            hemispheres: ['E', 'W'],
+        }
+    }
+}
+
+impl fmt::Display for DegreesMinutes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(3).min(9);
+        let scale = 10u64.pow(precision as u32);
+        // Round once, in whole units of the last decimal, so that minutes
+        // never round up to 60.
+        let total = libm::round(libm::fabs(self.value) * 60.0 * scale as f64) as u64;
+        let degrees = total / (60 * scale);
+        let minutes = (total % (60 * scale)) as f64 / scale as f64;
+        let hemisphere = self.hemispheres[(self.value < 0.0) as usize];
+        write!(
+            f,
+            "{}°{:0w$.p$}'{}",
+            degrees,
+            minutes,
+            hemisphere,
+            w = if precision == 0 { 2 } else { precision + 3 },
+            p = precision
+        )
+    }
+}
+
+impl fmt::Display for PositionRapidUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "position lat={:.6} lon={:.6}",
+            Opt(self.latitude),
+            Opt(self.longitude)
+        )
+    }
+}
+
+impl fmt::Display for CogSogRapidUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cog/sog cog={:.1}deg ({:?}) sog={:.2}m/s",
+            deg(self.cog),
+            self.cog_reference,
+            Opt(self.sog)
+        )
+    }
+}
+
+impl fmt::Display for GnssPositionData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "gnss lat={:.6} lon={:.6} alt={:.1}m method={:?} sats={} hdop={:.1}",
+            Opt(self.latitude),
+            Opt(self.longitude),
+            Opt(self.altitude),
+            self.method,
+            Opt(self.satellites),
+            Opt(self.hdop)
+        )
+    }
+}
+
+impl fmt::Display for Satellite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "prn={} el={:.0}deg az={:.0}deg snr={:.0}dB {:?}",
+            Opt(self.prn),
+            deg(self.elevation),
+            deg(self.azimuth),
+            Opt(self.snr),
+            self.status
+        )
+    }
+}
+
+impl fmt::Display for GnssSatsInView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sats in view={}", self.sats_in_view)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_rapid_update() {
+        // 47.6062100 N, 122.3320700 W
+        let payload: [u8; 8] = [0x94, 0x21, 0x60, 0x1C, 0x84, 0x9B, 0x15, 0xB7];
+        let position = PositionRapidUpdate::from_payload(&payload).unwrap();
+        assert!((position.latitude.unwrap() - 47.60621).abs() < 1e-7);
+        assert!((position.longitude.unwrap() + 122.33207).abs() < 1e-7);
+    }
+
+    #[test]
+    fn test_distance_bearing() {
+        let degree = distance(0.0, 0.0, 0.0, 1.0);
+        assert!((degree - 111_195.08).abs() < 0.01);
+        assert!((bearing(0.0, 0.0, 0.0, 1.0) - core::f64::consts::FRAC_PI_2).abs() < 1e-12);
+        assert!((bearing(0.0, 0.0, -1.0, 0.0) - core::f64::consts::PI).abs() < 1e-12);
+        assert!((bearing(0.0, 1.0, 0.0, 0.0).to_degrees() - 270.0).abs() < 1e-9);
+        // Seattle to Portland.
+        let d = distance(47.60621, -122.33207, 45.51523, -122.67838);
+        assert!((d - 233_700.0).abs() < 500.0);
+        assert_eq!(distance(47.6, -122.3, 47.6, -122.3), 0.0);
+
+        let south = bearing(47.60621, -122.33207, 45.51523, -122.67838);
+        let (lat, lon) = destination(47.60621, -122.33207, south, d);
+        assert!(distance(lat, lon, 45.51523, -122.67838) < 1.0);
+        let (lat, lon) = destination(0.0, 179.5, core::f64::consts::FRAC_PI_2, degree);
+        assert!(lat.abs() < 1e-9);
+        assert!((lon + 179.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_degrees_minutes() {
+        use std::string::ToString;
+
+        assert_eq!(
+            DegreesMinutes::latitude(-33.8688).to_string(),
+            "33°52.128'S"
+        );
+        assert_eq!(
+            DegreesMinutes::longitude(151.2093).to_string(),
+            "151°12.558'E"
+        );
+        assert_eq!(
+            DegreesMinutes::latitude(0.999_999_9).to_string(),
+            "1°00.000'N"
+        );
+        assert_eq!(
+            std::format!("{:.0}", DegreesMinutes::latitude(5.01)),
+            "5°01'N"
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        use core::fmt::Write;
+
+        struct Buf {
+            data: [u8; 64],
+            len: usize,
+        }
+
+        impl Write for Buf {
+            fn write_str(&mut self, s: &str) -> core::fmt::Result {
+                let end = self.len + s.len();
+                self.data
+                    .get_mut(self.len..end)
+                    .ok_or(core::fmt::Error)?
+                    .copy_from_slice(s.as_bytes());
+                self.len = end;
+                Ok(())
+            }
+        }
+
+        let payload: [u8; 8] = [0x94, 0x21, 0x60, 0x1C, 0xFF, 0xFF, 0xFF, 0x7F];
+        let position = PositionRapidUpdate::from_payload(&payload).unwrap();
+        let mut buf = Buf {
+            data: [0; 64],
+            len: 0,
+        };
+        write!(buf, "{}", position).unwrap();
+        assert_eq!(&buf.data[..buf.len], b"position lat=47.606210 lon=-");
+    }
+
+    #[test]
+    fn test_cog_sog_rapid_update() {
+        let payload: [u8; 8] = [0x03, 0xFC, 0x10, 0x27, 0xF4, 0x01, 0xFF, 0xFF];
+        let cog_sog = CogSogRapidUpdate::from_payload(&payload).unwrap();
+        assert_eq!(cog_sog.sid, Some(3));
+        assert_eq!(cog_sog.cog_reference, DirectionReference::True);
+        assert!((cog_sog.cog.unwrap() - 1.0).abs() < 1e-6);
+        assert!((cog_sog.sog.unwrap() - 5.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_gnss_position_data() {
+        let mut payload = [0xFF; 43];
+        payload[0] = 0x01;
+        payload[1..3].copy_from_slice(&19835u16.to_le_bytes());
+        payload[3..7].copy_from_slice(&432_000_000u32.to_le_bytes());
+        payload[7..15].copy_from_slice(&476_062_100_000_000_000i64.to_le_bytes());
+        payload[15..23].copy_from_slice(&(-1_223_320_700_000_000_000i64).to_le_bytes());
+        payload[23..31].copy_from_slice(&12_500_000i64.to_le_bytes());
+        payload[31] = 0x20;
+        payload[32] = 0xFC;
+        payload[33] = 9;
+        payload[34..36].copy_from_slice(&85i16.to_le_bytes());
+        payload[36..38].copy_from_slice(&150i16.to_le_bytes());
+        payload[38..42].copy_from_slice(&(-1850i32).to_le_bytes());
+        payload[42] = 0;
+
+        let position = GnssPositionData::from_payload(&payload).unwrap();
+        assert_eq!(position.date, Some(19835));
+        assert_eq!(position.time, Some(43200.0));
+        assert!((position.latitude.unwrap() - 47.60621).abs() < 1e-9);
+        assert!((position.longitude.unwrap() + 122.33207).abs() < 1e-9);
+        assert!((position.altitude.unwrap() - 12.5).abs() < 1e-9);
+        assert_eq!(position.gnss_type, 0);
+        assert_eq!(position.method, GnssMethod::DgnssFix);
+        assert_eq!(position.satellites, Some(9));
+        assert!((position.hdop.unwrap() - 0.85).abs() < 1e-6);
+        assert!((position.geoidal_separation.unwrap() + 18.5).abs() < 1e-6);
+
+        assert_eq!(
+            GnssPositionData::from_payload(&payload[..42]),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_sats_in_view() {
+        let payload: [u8; 27] = [
+            0x07, 0xFD, 0x02, // SID 7, range residual mode 1, 2 satellites
+            0x05, 0x10, 0x27, 0x20, 0x4E, 0x2C, 0x01, 0xFF, 0xFF, 0xFF, 0x7F, 0xF2, // PRN 5
+            0x0C, 0xF0, 0xD8, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xF1, // PRN 12
+        ];
+        let sats = GnssSatsInView::from_payload(&payload).unwrap();
+        assert_eq!(sats.sid, Some(7));
+        assert_eq!(sats.range_residual_mode, 1);
+        assert_eq!(sats.sats_in_view, 2);
+
+        let mut it = sats.satellites();
+        assert_eq!(it.len(), 2);
+        let sat = it.next().unwrap();
+        assert_eq!(sat.prn, Some(5));
+        assert!((sat.elevation.unwrap() - 1.0).abs() < 1e-6);
+        assert!((sat.azimuth.unwrap() - 2.0).abs() < 1e-6);
+        assert!((sat.snr.unwrap() - 3.0).abs() < 1e-6);
+        assert_eq!(sat.range_residual, None);
+        assert_eq!(sat.status, SatelliteStatus::Used);
+
+        let sat = it.next().unwrap();
+        assert_eq!(sat.prn, Some(12));
+        assert!((sat.elevation.unwrap() + 1.0).abs() < 1e-6);
+        assert_eq!(sat.azimuth, None);
+        assert_eq!(sat.status, SatelliteStatus::Tracked);
+        assert!(it.next().is_none());
+    }
+
+    #[test]
+    fn test_truncated() {
+        let payload: [u8; 14] = [
+            0x07, 0xFD, 0x02, 0x05, 0x10, 0x27, 0x20, 0x4E, 0x2C, 0x01, 0xFF, 0xFF, 0xFF, 0x7F,
+        ];
+        assert_eq!(
+            GnssSatsInView::from_payload(&payload),
+            Err(Error::InvalidLength)
+        );
+    }
+}
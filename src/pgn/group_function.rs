@@ -0,0 +1,156 @@
+//! NMEA Request/Command/Acknowledge Group Function (PGN 126208, Fast-Packet).
+//!
+//! Group functions read or change fields of another device's PGN. A
+//! Command names the target PGN and a list of (field number, value) pairs,
+//! with field numbers counted from 1 in the PGN's field order and values
+//! in the field's own encoding rounded up to whole bytes. The receiver
+//! answers with an Acknowledge carrying an error code per parameter.
+use crate::pgn::{check_len, Error};
+use core::fmt;
+
+pub const PGN: u32 = 126208;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FunctionCode {
+    Request,
+    Command,
+    Acknowledge,
+    ReadFields,
+    ReadFieldsReply,
+    WriteFields,
+    WriteFieldsReply,
+    Unknown(u8),
+}
+
+impl From<u8> for FunctionCode {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Request,
+            1 => Self::Command,
+            2 => Self::Acknowledge,
+            3 => Self::ReadFields,
+            4 => Self::ReadFieldsReply,
+            5 => Self::WriteFields,
+            6 => Self::WriteFieldsReply,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+/// Returns the function code of a group function payload.
+pub fn function_code(payload: &[u8]) -> Result<FunctionCode, Error> {
+    check_len(payload, 1)?;
+    Ok(FunctionCode::from(payload[0]))
+}
+
+/// Encodes a Command group function setting `parameters` of `pgn` into
+/// `buf`, leaving the transmission priority unchanged. Returns the payload
+/// length.
+pub fn encode_command(
+    pgn: u32,
+    parameters: &[(u8, &[u8])],
+    buf: &mut [u8],
+) -> Result<usize, Error> {
+    let len = 6 + parameters.iter().map(|(_, v)| 1 + v.len()).sum::<usize>();
+    if buf.len() < len {
+        return Err(Error::InvalidLength);
+    }
+    buf[0] = 1;
+    buf[1..4].copy_from_slice(&pgn.to_le_bytes()[..3]);
+    // Priority 8 means "leave unchanged".
+    buf[4] = 0xF8;
+    buf[5] = parameters.len() as u8;
+    let mut offset = 6;
+    for (field, value) in parameters {
+        buf[offset] = *field;
+        buf[offset + 1..offset + 1 + value.len()].copy_from_slice(value);
+        offset += 1 + value.len();
+    }
+    Ok(len)
+}
+
+/// Acknowledge group function, sent in reply to a Request or Command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Acknowledge<'a> {
+    pub pgn: u32,
+    /// 0 acknowledged, 1 PGN not supported, 2 PGN not available, 3 access
+    /// denied, 4 not supported, 5 tag not supported, 6 read or write not
+    /// supported.
+    pub pgn_error: u8,
+    /// Error code for the requested transmission interval or priority.
+    pub priority_error: u8,
+    parameter_count: u8,
+    parameter_errors: &'a [u8],
+}
+
+impl<'a> Acknowledge<'a> {
+    pub fn from_payload(payload: &'a [u8]) -> Result<Self, Error> {
+        check_len(payload, 6)?;
+        let parameter_count = payload[5];
+        check_len(payload, 6 + (parameter_count as usize).div_ceil(2))?;
+        Ok(Self {
+            pgn: u32::from_le_bytes([payload[1], payload[2], payload[3], 0]),
+            pgn_error: payload[4] & 0x0F,
+            priority_error: payload[4] >> 4,
+            parameter_count,
+            parameter_errors: &payload[6..],
+        })
+    }
+
+    /// Error codes of the command's parameters, in order: 0 acknowledged, 1
+    /// invalid field, 2 temporary error, 3 out of range, 4 access denied, 5
+    /// not supported, 6 read or write not supported.
+    pub fn parameter_errors(&self) -> impl Iterator<Item = u8> + 'a {
+        let errors = self.parameter_errors;
+        (0..self.parameter_count as usize).map(move |i| (errors[i / 2] >> (i % 2 * 4)) & 0x0F)
+    }
+
+    /// Whether the PGN and every parameter were accepted.
+    pub fn is_success(&self) -> bool {
+        self.pgn_error == 0 && self.parameter_errors().all(|e| e == 0)
+    }
+}
+
+impl fmt::Display for Acknowledge<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ack pgn={} error={}", self.pgn, self.pgn_error)?;
+        for error in self.parameter_errors() {
+            write!(f, " {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command() {
+        let mut buf = [0; 16];
+        let len = encode_command(127505, &[(2, &[0x01])], &mut buf).unwrap();
+        assert_eq!(buf[..len], [0x01, 0x11, 0xF2, 0x01, 0xF8, 0x01, 0x02, 0x01]);
+        assert_eq!(function_code(&buf[..len]), Ok(FunctionCode::Command));
+        assert_eq!(
+            encode_command(127505, &[(2, &[0x01])], &mut buf[..7]),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_acknowledge() {
+        let payload = [0x02, 0x11, 0xF2, 0x01, 0x00, 0x03, 0x30, 0xF0];
+        let ack = Acknowledge::from_payload(&payload).unwrap();
+        assert_eq!(ack.pgn, 127505);
+        let mut errors = ack.parameter_errors();
+        assert_eq!(errors.next(), Some(0));
+        assert_eq!(errors.next(), Some(3));
+        assert_eq!(errors.next(), Some(0));
+        assert_eq!(errors.next(), None);
+        assert!(!ack.is_success());
+        assert_eq!(
+            Acknowledge::from_payload(&payload[..7]),
+            Err(Error::InvalidLength)
+        );
+    }
+}
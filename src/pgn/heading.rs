@@ -0,0 +1,208 @@
+//! Vessel Heading (PGN 127250), Magnetic Variation (PGN 127258) and
+//! magnetic/true heading conversions.
+//!
+//! Variation is positive when magnetic north lies east of true north, so
+//! `true = magnetic + variation`. All angles are in radians.
+use crate::pgn::{check_len, deg, normalize_angle, read_i16, read_u16, read_u8, Error};
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeadingReference {
+    True,
+    Magnetic,
+    Unknown(u8),
+}
+
+impl From<u8> for HeadingReference {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::True,
+            1 => Self::Magnetic,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+/// Vessel Heading (PGN 127250).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VesselHeading {
+    pub sid: Option<u8>,
+    pub heading: Option<f32>,
+    pub deviation: Option<f32>,
+    pub variation: Option<f32>,
+    pub reference: HeadingReference,
+}
+
+impl VesselHeading {
+    pub const PGN: u32 = 127250;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 8)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            heading: read_u16(payload, 1).map(|v| v as f32 * 1e-4),
+            deviation: read_i16(payload, 3).map(|v| v as f32 * 1e-4),
+            variation: read_i16(payload, 5).map(|v| v as f32 * 1e-4),
+            reference: HeadingReference::from(payload[7] & 0x03),
+        })
+    }
+
+    /// Returns the heading relative to true north. Magnetic headings are
+    /// converted with the variation carried in the message, or with
+    /// `fallback_variation` (e.g. the latest PGN 127258) when it is missing.
+    pub fn true_heading(&self, fallback_variation: Option<f32>) -> Option<f32> {
+        let heading = self.heading?;
+        match self.reference {
+            HeadingReference::True => Some(heading),
+            HeadingReference::Magnetic => {
+                let variation = self.variation.or(fallback_variation)?;
+                Some(magnetic_to_true(heading, variation))
+            }
+            HeadingReference::Unknown(_) => None,
+        }
+    }
+
+    /// Returns the heading relative to magnetic north, see `true_heading`.
+    pub fn magnetic_heading(&self, fallback_variation: Option<f32>) -> Option<f32> {
+        let heading = self.heading?;
+        match self.reference {
+            HeadingReference::Magnetic => Some(heading),
+            HeadingReference::True => {
+                let variation = self.variation.or(fallback_variation)?;
+                Some(true_to_magnetic(heading, variation))
+            }
+            HeadingReference::Unknown(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VariationSource {
+    Manual,
+    Chart,
+    Table,
+    Calculation,
+    Wmm2000,
+    Wmm2005,
+    Wmm2010,
+    Wmm2015,
+    Wmm2020,
+    Unknown(u8),
+}
+
+impl From<u8> for VariationSource {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Manual,
+            1 => Self::Chart,
+            2 => Self::Table,
+            3 => Self::Calculation,
+            4 => Self::Wmm2000,
+            5 => Self::Wmm2005,
+            6 => Self::Wmm2010,
+            7 => Self::Wmm2015,
+            8 => Self::Wmm2020,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+/// Magnetic Variation (PGN 127258).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MagneticVariation {
+    pub sid: Option<u8>,
+    pub source: VariationSource,
+    /// Date the variation model is valid for, in days since 1970-01-01.
+    pub age_of_service: Option<u16>,
+    pub variation: Option<f32>,
+}
+
+impl MagneticVariation {
+    pub const PGN: u32 = 127258;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 6)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            source: VariationSource::from(payload[1] & 0x0F),
+            age_of_service: read_u16(payload, 2),
+            variation: read_i16(payload, 4).map(|v| v as f32 * 1e-4),
+        })
+    }
+}
+
+pub fn magnetic_to_true(heading: f32, variation: f32) -> f32 {
+    normalize_angle(heading + variation)
+}
+
+pub fn true_to_magnetic(heading: f32, variation: f32) -> f32 {
+    normalize_angle(heading - variation)
+}
+
+impl fmt::Display for VesselHeading {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "heading {:.1}deg ({:?}) dev={:.1}deg var={:.1}deg",
+            deg(self.heading),
+            self.reference,
+            deg(self.deviation),
+            deg(self.variation)
+        )
+    }
+}
+
+impl fmt::Display for MagneticVariation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "variation {:.1}deg ({:?})",
+            deg(self.variation),
+            self.source
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_vessel_heading() {
+        // Magnetic heading 1.0 rad, no deviation, variation -0.2 rad.
+        let payload: [u8; 8] = [0x00, 0x10, 0x27, 0xFF, 0x7F, 0x30, 0xF8, 0xFD];
+        let heading = VesselHeading::from_payload(&payload).unwrap();
+        assert_eq!(heading.reference, HeadingReference::Magnetic);
+        assert_eq!(heading.deviation, None);
+        assert_close(heading.variation.unwrap(), -0.2);
+        assert_close(heading.true_heading(None).unwrap(), 0.8);
+        assert_close(heading.magnetic_heading(None).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_fallback_variation() {
+        let payload: [u8; 8] = [0x00, 0x10, 0x27, 0xFF, 0x7F, 0xFF, 0x7F, 0xFD];
+        let heading = VesselHeading::from_payload(&payload).unwrap();
+        assert_eq!(heading.true_heading(None), None);
+
+        let payload: [u8; 6] = [0x00, 0xF8, 0x7B, 0x4D, 0xD0, 0x07];
+        let variation = MagneticVariation::from_payload(&payload).unwrap();
+        assert_eq!(variation.source, VariationSource::Wmm2020);
+        assert_eq!(variation.age_of_service, Some(19835));
+        assert_close(variation.variation.unwrap(), 0.2);
+        assert_close(heading.true_heading(variation.variation).unwrap(), 1.2);
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_close(
+            magnetic_to_true(6.2, 0.2),
+            6.2 + 0.2 - core::f32::consts::TAU,
+        );
+        assert_close(true_to_magnetic(0.1, 0.2), core::f32::consts::TAU - 0.1);
+    }
+}
@@ -0,0 +1,134 @@
+//! HVAC and refrigeration readings picked out of the environmental PGNs.
+//!
+//! Climate control units, fridges and freezers report through the standard
+//! temperature and humidity PGNs, distinguished only by their source field.
+//! `decode` keeps the readings relevant to cabin climate and refrigeration
+//! and drops the rest (sea, engine room, exhaust, ...).
+use crate::pgn::environment::{Humidity, HumiditySource, Temperature, TemperatureSource};
+use crate::pgn::{celsius, Error, Opt};
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HvacZone {
+    /// Inside or main cabin temperature.
+    Cabin,
+    Heating,
+    Refrigeration,
+    Freezer,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HvacReading {
+    /// Temperatures in Kelvin.
+    Temperature {
+        zone: HvacZone,
+        instance: u8,
+        actual: Option<f32>,
+        set: Option<f32>,
+    },
+    /// Inside relative humidity in %.
+    Humidity {
+        instance: u8,
+        actual: Option<f32>,
+        set: Option<f32>,
+    },
+}
+
+fn zone(source: TemperatureSource) -> Option<HvacZone> {
+    match source {
+        TemperatureSource::Inside | TemperatureSource::MainCabin => Some(HvacZone::Cabin),
+        TemperatureSource::HeatingSystem => Some(HvacZone::Heating),
+        TemperatureSource::Refrigeration => Some(HvacZone::Refrigeration),
+        TemperatureSource::Freezer => Some(HvacZone::Freezer),
+        _ => None,
+    }
+}
+
+/// Decodes an HVAC reading from PGN 130312, 130313 or 130316. Returns
+/// `Ok(None)` for other PGNs and for sources unrelated to HVAC.
+pub fn decode(pgn: u32, payload: &[u8]) -> Result<Option<HvacReading>, Error> {
+    let temperature = match pgn {
+        Temperature::PGN => Temperature::from_payload(payload)?,
+        Temperature::EXTENDED_PGN => Temperature::from_extended_payload(payload)?,
+        Humidity::PGN => {
+            let humidity = Humidity::from_payload(payload)?;
+            return Ok((humidity.source == HumiditySource::Inside).then_some(
+                HvacReading::Humidity {
+                    instance: humidity.instance,
+                    actual: humidity.humidity,
+                    set: humidity.set_humidity,
+                },
+            ));
+        }
+        _ => return Ok(None),
+    };
+    Ok(
+        zone(temperature.source).map(|zone| HvacReading::Temperature {
+            zone,
+            instance: temperature.instance,
+            actual: temperature.temperature,
+            set: temperature.set_temperature,
+        }),
+    )
+}
+
+impl fmt::Display for HvacReading {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Temperature {
+                zone,
+                instance,
+                actual,
+                set,
+            } => write!(
+                f,
+                "hvac {:?} #{} {:.1}C set={:.1}C",
+                zone,
+                instance,
+                celsius(*actual),
+                celsius(*set)
+            ),
+            Self::Humidity {
+                instance,
+                actual,
+                set,
+            } => write!(
+                f,
+                "hvac humidity #{} {:.1}% set={:.1}%",
+                instance,
+                Opt(*actual),
+                Opt(*set)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let fridge: [u8; 8] = [0xFF, 0x01, 0x07, 0x43, 0x6C, 0xFF, 0xFF, 0xFF];
+        match decode(130312, &fridge).unwrap() {
+            Some(HvacReading::Temperature { zone, instance, .. }) => {
+                assert_eq!((zone, instance), (HvacZone::Refrigeration, 1))
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+
+        // Sea temperature is not an HVAC reading.
+        let sea: [u8; 8] = [0xFF, 0x00, 0x00, 0x43, 0x6C, 0xFF, 0xFF, 0xFF];
+        assert_eq!(decode(130312, &sea), Ok(None));
+        assert_eq!(decode(130306, &sea), Ok(None));
+
+        let outside: [u8; 8] = [0xFF, 0x00, 0x01, 0xD4, 0x30, 0xFF, 0x7F, 0xFF];
+        assert_eq!(decode(130313, &outside), Ok(None));
+        let inside: [u8; 8] = [0xFF, 0x00, 0x00, 0xD4, 0x30, 0xFF, 0x7F, 0xFF];
+        assert!(matches!(
+            decode(130313, &inside),
+            Ok(Some(HvacReading::Humidity { instance: 0, .. }))
+        ));
+        assert_eq!(decode(130316, &inside[..7]), Err(Error::InvalidLength));
+    }
+}
@@ -0,0 +1,533 @@
+//! Field descriptions of the fixed-layout PGNs decoded by this crate.
+//!
+//! Lookup fields decode to enums, and raw integer fields are multiplied by
+//! `scale` to give a value in `unit`.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U24,
+    U32,
+    I32,
+    U64,
+    I64,
+    /// An enumerated value.
+    Lookup,
+    /// A two-bit off/on state.
+    Flag,
+}
+
+impl FieldType {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::U8 => "u8",
+            Self::I8 => "i8",
+            Self::U16 => "u16",
+            Self::I16 => "i16",
+            Self::U24 => "u24",
+            Self::U32 => "u32",
+            Self::I32 => "i32",
+            Self::U64 => "u64",
+            Self::I64 => "i64",
+            Self::Lookup => "lookup",
+            Self::Flag => "flag",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FieldInfo {
+    /// Name of the field in the decoded struct.
+    pub name: &'static str,
+    pub kind: FieldType,
+    pub scale: f64,
+    pub unit: Option<&'static str>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PgnInfo {
+    pub pgn: u32,
+    pub name: &'static str,
+    pub fields: &'static [FieldInfo],
+}
+
+const fn field(
+    name: &'static str,
+    kind: FieldType,
+    scale: f64,
+    unit: Option<&'static str>,
+) -> FieldInfo {
+    FieldInfo {
+        name,
+        kind,
+        scale,
+        unit,
+    }
+}
+
+const fn lookup(name: &'static str) -> FieldInfo {
+    field(name, FieldType::Lookup, 1.0, None)
+}
+
+const fn flag(name: &'static str) -> FieldInfo {
+    field(name, FieldType::Flag, 1.0, None)
+}
+
+const fn u8_field(name: &'static str) -> FieldInfo {
+    field(name, FieldType::U8, 1.0, None)
+}
+
+const SID: FieldInfo = field("sid", FieldType::U8, 1.0, None);
+const INSTANCE: FieldInfo = field("instance", FieldType::U8, 1.0, None);
+const DATE: FieldInfo = field("date", FieldType::U16, 1.0, Some("d"));
+const TIME: FieldInfo = field("time", FieldType::U32, 1e-4, Some("s"));
+
+/// The 28 two-bit channel states decode to `SwitchState`s.
+const SWITCH_BANK: &[FieldInfo] = &[INSTANCE, lookup("channels")];
+
+const PRESSURE: &[FieldInfo] = &[
+    SID,
+    INSTANCE,
+    lookup("source"),
+    field("pressure", FieldType::I32, 0.1, Some("Pa")),
+];
+
+/// Sorted by PGN.
+pub const PGNS: &[PgnInfo] = &[
+    PgnInfo {
+        pgn: 59904,
+        name: "ISO Request",
+        fields: &[field("pgn", FieldType::U24, 1.0, None)],
+    },
+    PgnInfo {
+        pgn: 126983,
+        name: "Alert",
+        fields: &[
+            lookup("alert_type"),
+            lookup("category"),
+            u8_field("system"),
+            u8_field("sub_system"),
+            field("id", FieldType::U16, 1.0, None),
+            field("source_name", FieldType::U64, 1.0, None),
+            u8_field("source_instance"),
+            u8_field("source_index"),
+            u8_field("occurrence"),
+            u8_field("flags"),
+            field("acknowledge_name", FieldType::U64, 1.0, None),
+            lookup("trigger"),
+            lookup("threshold_status"),
+            u8_field("priority"),
+            lookup("state"),
+        ],
+    },
+    PgnInfo {
+        pgn: 127250,
+        name: "Vessel Heading",
+        fields: &[
+            SID,
+            field("heading", FieldType::U16, 1e-4, Some("rad")),
+            field("deviation", FieldType::I16, 1e-4, Some("rad")),
+            field("variation", FieldType::I16, 1e-4, Some("rad")),
+            lookup("reference"),
+        ],
+    },
+    PgnInfo {
+        pgn: 127258,
+        name: "Magnetic Variation",
+        fields: &[
+            SID,
+            lookup("source"),
+            field("age_of_service", FieldType::U16, 1.0, Some("d")),
+            field("variation", FieldType::I16, 1e-4, Some("rad")),
+        ],
+    },
+    PgnInfo {
+        pgn: 127488,
+        name: "Engine Parameters, Rapid Update",
+        fields: &[
+            INSTANCE,
+            field("speed", FieldType::U16, 0.25, Some("rpm")),
+            field("boost", FieldType::U16, 100.0, Some("Pa")),
+            field("tilt_trim", FieldType::I8, 1.0, Some("%")),
+        ],
+    },
+    PgnInfo {
+        pgn: 127489,
+        name: "Engine Parameters, Dynamic",
+        fields: &[
+            INSTANCE,
+            field("oil_pressure", FieldType::U16, 100.0, Some("Pa")),
+            field("oil_temperature", FieldType::U16, 0.1, Some("K")),
+            field("temperature", FieldType::U16, 0.01, Some("K")),
+            field("alternator_potential", FieldType::I16, 0.01, Some("V")),
+            field("fuel_rate", FieldType::I16, 0.1, Some("L/h")),
+            field("total_hours", FieldType::U32, 1.0, Some("s")),
+            field("coolant_pressure", FieldType::U16, 100.0, Some("Pa")),
+            field("fuel_pressure", FieldType::U16, 1000.0, Some("Pa")),
+        ],
+    },
+    PgnInfo {
+        pgn: 127501,
+        name: "Binary Switch Bank Status",
+        fields: SWITCH_BANK,
+    },
+    PgnInfo {
+        pgn: 127502,
+        name: "Switch Bank Control",
+        fields: SWITCH_BANK,
+    },
+    PgnInfo {
+        pgn: 127505,
+        name: "Fluid Level",
+        fields: &[
+            INSTANCE,
+            lookup("fluid_type"),
+            field("level", FieldType::I16, 0.004, Some("%")),
+            field("capacity", FieldType::U32, 0.1, Some("L")),
+        ],
+    },
+    PgnInfo {
+        pgn: 127507,
+        name: "Charger Status",
+        fields: &[
+            INSTANCE,
+            u8_field("battery_instance"),
+            lookup("state"),
+            lookup("mode"),
+            flag("enabled"),
+            flag("equalization_pending"),
+            field(
+                "equalization_time_remaining",
+                FieldType::U16,
+                1.0,
+                Some("s"),
+            ),
+        ],
+    },
+    PgnInfo {
+        pgn: 127509,
+        name: "Inverter Status",
+        fields: &[
+            INSTANCE,
+            u8_field("ac_instance"),
+            u8_field("dc_instance"),
+            lookup("state"),
+            flag("enabled"),
+        ],
+    },
+    PgnInfo {
+        pgn: 127510,
+        name: "Charger Configuration Status",
+        fields: &[
+            INSTANCE,
+            u8_field("battery_instance"),
+            flag("enabled"),
+            field("charge_current_limit", FieldType::U16, 0.1, Some("A")),
+            lookup("algorithm"),
+            lookup("mode"),
+            lookup("estimated_temperature"),
+            flag("equalize_one_time"),
+            flag("overcharge"),
+            field("equalize_time", FieldType::U16, 1.0, Some("s")),
+        ],
+    },
+    PgnInfo {
+        pgn: 128000,
+        name: "Leeway Angle",
+        fields: &[SID, field("leeway", FieldType::I16, 1e-4, Some("rad"))],
+    },
+    PgnInfo {
+        pgn: 128006,
+        name: "Thruster Control Status",
+        fields: &[
+            SID,
+            u8_field("thruster_id"),
+            lookup("direction"),
+            flag("power_enabled"),
+            lookup("retract"),
+            field("speed", FieldType::U8, 1.0, Some("%")),
+            u8_field("control_events"),
+            field("command_timeout", FieldType::U8, 0.005, Some("s")),
+            field("azimuth", FieldType::U16, 1e-4, Some("rad")),
+        ],
+    },
+    PgnInfo {
+        pgn: 128007,
+        name: "Thruster Information",
+        fields: &[
+            u8_field("thruster_id"),
+            lookup("motor_type"),
+            field("power_rating", FieldType::U16, 1.0, Some("W")),
+            field("max_temperature", FieldType::U16, 0.01, Some("K")),
+            field("max_rotational_speed", FieldType::U16, 0.25, Some("rpm")),
+        ],
+    },
+    PgnInfo {
+        pgn: 128008,
+        name: "Thruster Motor Status",
+        fields: &[
+            SID,
+            u8_field("thruster_id"),
+            u8_field("motor_events"),
+            field("current", FieldType::U8, 1.0, Some("A")),
+            field("temperature", FieldType::U16, 0.01, Some("K")),
+            field("operating_time", FieldType::U16, 1.0, Some("min")),
+        ],
+    },
+    PgnInfo {
+        pgn: 128259,
+        name: "Speed",
+        fields: &[
+            SID,
+            field("water", FieldType::U16, 0.01, Some("m/s")),
+            field("ground", FieldType::U16, 0.01, Some("m/s")),
+        ],
+    },
+    PgnInfo {
+        pgn: 128267,
+        name: "Water Depth",
+        fields: &[
+            SID,
+            field("depth", FieldType::U32, 0.01, Some("m")),
+            field("offset", FieldType::I16, 0.001, Some("m")),
+            field("range", FieldType::U8, 10.0, Some("m")),
+        ],
+    },
+    PgnInfo {
+        pgn: 128275,
+        name: "Distance Log",
+        fields: &[
+            DATE,
+            TIME,
+            field("log", FieldType::U32, 1.0, Some("m")),
+            field("trip_log", FieldType::U32, 1.0, Some("m")),
+        ],
+    },
+    PgnInfo {
+        pgn: 128776,
+        name: "Anchor Windlass Control Status",
+        fields: &[
+            SID,
+            u8_field("windlass_id"),
+            lookup("direction"),
+            flag("anchor_docking"),
+            lookup("speed_control_type"),
+            field("speed_control", FieldType::U8, 1.0, Some("%")),
+            flag("power_enable"),
+            flag("mechanical_lock"),
+            flag("deck_and_anchor_wash"),
+            flag("anchor_light"),
+            field("command_timeout", FieldType::U8, 0.005, Some("s")),
+            u8_field("control_events"),
+        ],
+    },
+    PgnInfo {
+        pgn: 128777,
+        name: "Anchor Windlass Operating Status",
+        fields: &[
+            SID,
+            u8_field("windlass_id"),
+            lookup("direction"),
+            lookup("motion"),
+            lookup("rode_type"),
+            field("rode_counter", FieldType::U16, 0.1, Some("m")),
+            field("line_speed", FieldType::U16, 0.01, Some("m/s")),
+            flag("anchor_docked"),
+            u8_field("operating_events"),
+        ],
+    },
+    PgnInfo {
+        pgn: 128778,
+        name: "Anchor Windlass Monitoring Status",
+        fields: &[
+            SID,
+            u8_field("windlass_id"),
+            u8_field("monitoring_events"),
+            field("controller_voltage", FieldType::U8, 0.2, Some("V")),
+            field("motor_current", FieldType::U8, 1.0, Some("A")),
+            field("total_motor_time", FieldType::U16, 60.0, Some("s")),
+        ],
+    },
+    PgnInfo {
+        pgn: 129025,
+        name: "Position, Rapid Update",
+        fields: &[
+            field("latitude", FieldType::I32, 1e-7, Some("deg")),
+            field("longitude", FieldType::I32, 1e-7, Some("deg")),
+        ],
+    },
+    PgnInfo {
+        pgn: 129026,
+        name: "COG & SOG, Rapid Update",
+        fields: &[
+            SID,
+            lookup("cog_reference"),
+            field("cog", FieldType::U16, 1e-4, Some("rad")),
+            field("sog", FieldType::U16, 0.01, Some("m/s")),
+        ],
+    },
+    PgnInfo {
+        pgn: 129029,
+        name: "GNSS Position Data",
+        fields: &[
+            SID,
+            DATE,
+            TIME,
+            field("latitude", FieldType::I64, 1e-16, Some("deg")),
+            field("longitude", FieldType::I64, 1e-16, Some("deg")),
+            field("altitude", FieldType::I64, 1e-6, Some("m")),
+            lookup("gnss_type"),
+            lookup("method"),
+            lookup("integrity"),
+            field("satellites", FieldType::U8, 1.0, None),
+            field("hdop", FieldType::I16, 0.01, None),
+            field("pdop", FieldType::I16, 0.01, None),
+            field("geoidal_separation", FieldType::I32, 0.01, Some("m")),
+        ],
+    },
+    PgnInfo {
+        pgn: 129291,
+        name: "Set & Drift, Rapid Update",
+        fields: &[
+            SID,
+            lookup("set_reference"),
+            field("set", FieldType::U16, 1e-4, Some("rad")),
+            field("drift", FieldType::U16, 0.01, Some("m/s")),
+        ],
+    },
+    PgnInfo {
+        pgn: 130306,
+        name: "Wind Data",
+        fields: &[
+            SID,
+            field("speed", FieldType::U16, 0.01, Some("m/s")),
+            field("angle", FieldType::U16, 1e-4, Some("rad")),
+            lookup("reference"),
+        ],
+    },
+    PgnInfo {
+        pgn: 130312,
+        name: "Temperature",
+        fields: &[
+            SID,
+            INSTANCE,
+            lookup("source"),
+            field("temperature", FieldType::U16, 0.01, Some("K")),
+            field("set_temperature", FieldType::U16, 0.01, Some("K")),
+        ],
+    },
+    PgnInfo {
+        pgn: 130313,
+        name: "Humidity",
+        fields: &[
+            SID,
+            INSTANCE,
+            lookup("source"),
+            field("humidity", FieldType::I16, 0.004, Some("%")),
+            field("set_humidity", FieldType::I16, 0.004, Some("%")),
+        ],
+    },
+    PgnInfo {
+        pgn: 130314,
+        name: "Actual Pressure",
+        fields: PRESSURE,
+    },
+    PgnInfo {
+        pgn: 130315,
+        name: "Set Pressure",
+        fields: PRESSURE,
+    },
+    PgnInfo {
+        pgn: 130316,
+        name: "Temperature, Extended Range",
+        fields: &[
+            SID,
+            INSTANCE,
+            lookup("source"),
+            field("temperature", FieldType::U24, 0.001, Some("K")),
+            field("set_temperature", FieldType::U16, 0.1, Some("K")),
+        ],
+    },
+    PgnInfo {
+        pgn: 130578,
+        name: "Vessel Speed Components",
+        fields: &[
+            field("longitudinal_water", FieldType::I16, 0.001, Some("m/s")),
+            field("transverse_water", FieldType::I16, 0.001, Some("m/s")),
+            field("longitudinal_ground", FieldType::I16, 0.001, Some("m/s")),
+            field("transverse_ground", FieldType::I16, 0.001, Some("m/s")),
+            field("stern_water", FieldType::I16, 0.001, Some("m/s")),
+            field("stern_ground", FieldType::I16, 0.001, Some("m/s")),
+        ],
+    },
+];
+
+pub fn find(pgn: u32) -> Option<&'static PgnInfo> {
+    PGNS.binary_search_by_key(&pgn, |info| info.pgn)
+        .ok()
+        .map(|i| &PGNS[i])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::*;
+
+    #[test]
+    fn test_lookup() {
+        assert!(PGNS.windows(2).all(|w| w[0].pgn < w[1].pgn));
+        let wind = find(130306).unwrap();
+        assert_eq!(wind.name, "Wind Data");
+        assert_eq!(wind.fields[1].name, "speed");
+        assert_eq!(wind.fields[1].kind.as_str(), "u16");
+        assert_eq!(wind.fields[1].unit, Some("m/s"));
+        assert_eq!(find(130315).unwrap().fields.len(), 4);
+        assert_eq!(find(12345), None);
+    }
+
+    fn listed<T: PgnMessage>() -> bool {
+        find(T::PGN).is_some()
+    }
+
+    #[test]
+    fn test_fixed_layout_pgns_listed() {
+        // Every PgnMessage except GnssSatsInView, whose satellites repeat,
+        // and the variable-length group functions and Fusion messages.
+        assert!(listed::<alert::Alert>());
+        assert!(listed::<charger::ChargerStatus>());
+        assert!(listed::<charger::InverterStatus>());
+        assert!(listed::<charger::ChargerConfiguration>());
+        assert!(listed::<depth::WaterDepth>());
+        assert!(listed::<distance_log::DistanceLog>());
+        assert!(listed::<engine::EngineDynamic>());
+        assert!(listed::<engine::EngineRapid>());
+        assert!(listed::<environment::Humidity>());
+        assert!(listed::<environment::Temperature>());
+        assert!(listed::<fluid_level::FluidLevel>());
+        assert!(listed::<gnss::CogSogRapidUpdate>());
+        assert!(listed::<gnss::GnssPositionData>());
+        assert!(listed::<gnss::PositionRapidUpdate>());
+        assert!(listed::<heading::MagneticVariation>());
+        assert!(listed::<heading::VesselHeading>());
+        assert!(listed::<iso_request::IsoRequest>());
+        assert!(listed::<speed::Leeway>());
+        assert!(listed::<speed::SetDrift>());
+        assert!(listed::<speed::SpeedComponents>());
+        assert!(listed::<speed::WaterSpeed>());
+        assert!(listed::<thruster::ThrusterControl>());
+        assert!(listed::<thruster::ThrusterInformation>());
+        assert!(listed::<thruster::ThrusterMotorStatus>());
+        assert!(listed::<wind::WindData>());
+        assert!(listed::<windlass::WindlassControl>());
+        assert!(listed::<windlass::WindlassMonitoringStatus>());
+        assert!(listed::<windlass::WindlassOperatingStatus>());
+        assert!(listed::<environment::ActualPressure>());
+        assert!(listed::<environment::ExtendedTemperature>());
+        assert!(listed::<environment::SetPressure>());
+        assert!(listed::<switching::SwitchBankControl>());
+        assert!(listed::<switching::SwitchBankStatus>());
+    }
+}
@@ -0,0 +1,67 @@
+//! ISO Request (PGN 59904).
+use crate::can_id::CanId;
+use crate::pgn::{check_len, Error};
+use core::fmt;
+
+/// Asks `destination`, or every node when it is `can_id::BROADCAST`, to send
+/// `pgn`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IsoRequest {
+    pub destination: u8,
+    pub pgn: u32,
+}
+
+impl IsoRequest {
+    pub const PGN: u32 = 59904;
+    pub const LEN: usize = 3;
+    pub const PRIORITY: u8 = 6;
+
+    /// Decodes the payload of a request received with `id`.
+    pub fn from_payload(id: CanId, payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, Self::LEN)?;
+        Ok(Self {
+            destination: id.destination(),
+            pgn: u32::from_le_bytes([payload[0], payload[1], payload[2], 0]),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; Self::LEN] {
+        let bytes = self.pgn.to_le_bytes();
+        [bytes[0], bytes[1], bytes[2]]
+    }
+
+    /// The identifier to send the request with from `source`.
+    pub fn can_id(&self, source: u8) -> CanId {
+        CanId::new(Self::PRIORITY, Self::PGN, source, self.destination).unwrap()
+    }
+}
+
+impl fmt::Display for IsoRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "request pgn={} to {}", self.pgn, self.destination)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let request = IsoRequest {
+            destination: 0x23,
+            pgn: 126996,
+        };
+        assert_eq!(request.to_payload(), [0x14, 0xF0, 0x01]);
+        let id = request.can_id(0x01);
+        assert_eq!(u32::from(id), 0x18EA_2301);
+        assert_eq!(
+            IsoRequest::from_payload(id, &request.to_payload()),
+            Ok(request)
+        );
+        assert_eq!(
+            IsoRequest::from_payload(id, &[0x14, 0xF0]),
+            Err(Error::InvalidLength)
+        );
+    }
+}
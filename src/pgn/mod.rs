@@ -0,0 +1,406 @@
+//! Decoders for NMEA2000 PGN payloads.
+//!
+//! Decoded values are scaled to SI units (radians, metres, m/s, Pascal,
+//! Kelvin). Following the NMEA2000 conventions, a field whose raw value is the
+//! largest representable value ("data not available") or one less ("out of
+//! range") decodes to `None`.
+use crate::can_id::default_priority;
+use crate::nmea_message::MAX_NMEA_PACKET_SIZE;
+use core::f32::consts::TAU;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::slice::ChunksExact;
+use thiserror_no_std::Error;
+
+pub mod alert;
+pub mod charger;
+pub mod depth;
+pub mod distance_log;
+pub mod engine;
+pub mod environment;
+pub mod fluid_level;
+#[cfg(any(test, feature = "fusion"))]
+pub mod fusion;
+pub mod gnss;
+pub mod group_function;
+pub mod heading;
+#[cfg(any(test, feature = "hvac"))]
+pub mod hvac;
+pub mod info;
+pub mod iso_request;
+pub mod proprietary;
+pub mod speed;
+pub mod switching;
+pub mod thruster;
+pub mod wind;
+pub mod windlass;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Payload is too short")]
+    InvalidLength,
+}
+
+pub(crate) fn check_len(payload: &[u8], len: usize) -> Result<(), Error> {
+    if payload.len() < len {
+        return Err(Error::InvalidLength);
+    }
+    Ok(())
+}
+
+pub(crate) fn read_u8(payload: &[u8], offset: usize) -> Option<u8> {
+    payload.get(offset).copied().filter(|v| *v < 0xFE)
+}
+
+pub(crate) fn read_u16(payload: &[u8], offset: usize) -> Option<u16> {
+    let b = payload.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([b[0], b[1]])).filter(|v| *v < 0xFFFE)
+}
+
+pub(crate) fn read_i16(payload: &[u8], offset: usize) -> Option<i16> {
+    let b = payload.get(offset..offset + 2)?;
+    Some(i16::from_le_bytes([b[0], b[1]])).filter(|v| *v < 0x7FFE)
+}
+
+pub(crate) fn read_u24(payload: &[u8], offset: usize) -> Option<u32> {
+    let b = payload.get(offset..offset + 3)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], 0])).filter(|v| *v < 0xFF_FFFE)
+}
+
+pub(crate) fn read_u32(payload: &[u8], offset: usize) -> Option<u32> {
+    let b = payload.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]])).filter(|v| *v < 0xFFFF_FFFE)
+}
+
+pub(crate) fn read_i32(payload: &[u8], offset: usize) -> Option<i32> {
+    let b = payload.get(offset..offset + 4)?;
+    Some(i32::from_le_bytes([b[0], b[1], b[2], b[3]])).filter(|v| *v < 0x7FFF_FFFE)
+}
+
+pub(crate) fn read_i64(payload: &[u8], offset: usize) -> Option<i64> {
+    let b = payload.get(offset..offset + 8)?;
+    Some(i64::from_le_bytes(b.try_into().unwrap())).filter(|v| *v < 0x7FFF_FFFF_FFFF_FFFE)
+}
+
+/// Displays an optional field, or `-` when it is not available. Formatting
+/// options such as precision are passed on to the value.
+pub(crate) struct Opt<T>(pub Option<T>);
+
+impl<T: fmt::Display> fmt::Display for Opt<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(v) => v.fmt(f),
+            None => f.write_str("-"),
+        }
+    }
+}
+
+/// Displays an optional angle in radians as degrees.
+pub(crate) fn deg(angle: Option<f32>) -> Opt<f32> {
+    Opt(angle.map(f32::to_degrees))
+}
+
+/// Displays an optional temperature in Kelvin as degrees Celsius.
+pub(crate) fn celsius(temperature: Option<f32>) -> Opt<f32> {
+    Opt(temperature.map(|t| t - 273.15))
+}
+
+/// Wraps an angle in radians into `[0, 2π)`.
+pub fn normalize_angle(angle: f32) -> f32 {
+    let angle = angle % TAU;
+    if angle < 0.0 {
+        angle + TAU
+    } else {
+        angle
+    }
+}
+
+/// Compile-time metadata of a typed PGN, for generic code such as
+/// `N2kDevice::send` that would otherwise look the PGN up in tables.
+///
+/// Types decoding several PGNs, such as `Pressure` and `SwitchBank`, implement
+/// it through a wrapper per PGN (`ActualPressure`, `SwitchBankStatus`, ...).
+/// `hvac::HvacReading` and `fusion::FusionState` gather readings from several
+/// PGNs and don't implement it.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::pgn::depth::WaterDepth;
+/// use nmea::pgn::engine::EngineDynamic;
+/// use nmea::pgn::PgnMessage;
+///
+/// const fn frames<T: PgnMessage>() -> usize {
+///     if T::IS_FAST_PACKET {
+///         (T::MAX_LEN + 1).div_ceil(7)
+///     } else {
+///         1
+///     }
+/// }
+///
+/// assert_eq!((WaterDepth::PGN, WaterDepth::DEFAULT_PRIORITY), (128267, 3));
+/// assert_eq!(frames::<WaterDepth>(), 1);
+/// assert_eq!(frames::<EngineDynamic>(), 4);
+/// ```
+pub trait PgnMessage {
+    const PGN: u32;
+    const DEFAULT_PRIORITY: u8 = default_priority(Self::PGN);
+    /// Whether the PGN is sent as a Fast-Packet message.
+    const IS_FAST_PACKET: bool;
+    /// Largest payload length in bytes.
+    const MAX_LEN: usize;
+}
+
+macro_rules! pgn_message {
+    ($($ty:ty => $fast_packet:expr, $max_len:expr),* $(,)?) => {
+        $(impl PgnMessage for $ty {
+            const PGN: u32 = <$ty>::PGN;
+            const IS_FAST_PACKET: bool = $fast_packet;
+            const MAX_LEN: usize = $max_len;
+        })*
+    };
+}
+
+pgn_message! {
+    alert::Alert => true, 28,
+    charger::ChargerStatus => false, 8,
+    charger::InverterStatus => false, 8,
+    charger::ChargerConfiguration => true, charger::ChargerConfiguration::LEN,
+    depth::WaterDepth => false, 8,
+    distance_log::DistanceLog => true, distance_log::DistanceLog::LEN,
+    engine::EngineDynamic => true, 26,
+    engine::EngineRapid => false, 8,
+    environment::ActualPressure => false, 8,
+    environment::ExtendedTemperature => false, 8,
+    environment::Humidity => false, 8,
+    environment::SetPressure => false, 8,
+    environment::Temperature => false, 8,
+    fluid_level::FluidLevel => false, 8,
+    gnss::CogSogRapidUpdate => false, 8,
+    gnss::GnssPositionData => true, MAX_NMEA_PACKET_SIZE,
+    gnss::PositionRapidUpdate => false, 8,
+    heading::MagneticVariation => false, 8,
+    heading::VesselHeading => false, 8,
+    iso_request::IsoRequest => false, iso_request::IsoRequest::LEN,
+    speed::Leeway => false, 8,
+    speed::SetDrift => false, 8,
+    speed::SpeedComponents => true, speed::SpeedComponents::LEN,
+    speed::WaterSpeed => false, 8,
+    switching::SwitchBankControl => false, 8,
+    switching::SwitchBankStatus => false, 8,
+    thruster::ThrusterControl => false, 8,
+    thruster::ThrusterInformation => false, 8,
+    thruster::ThrusterMotorStatus => false, 8,
+    wind::WindData => false, 8,
+    windlass::WindlassControl => false, 8,
+    windlass::WindlassMonitoringStatus => false, 8,
+    windlass::WindlassOperatingStatus => false, 8,
+}
+
+impl PgnMessage for gnss::GnssSatsInView<'_> {
+    const PGN: u32 = 129540;
+    const IS_FAST_PACKET: bool = true;
+    const MAX_LEN: usize = MAX_NMEA_PACKET_SIZE;
+}
+
+impl PgnMessage for group_function::Acknowledge<'_> {
+    const PGN: u32 = group_function::PGN;
+    const IS_FAST_PACKET: bool = true;
+    const MAX_LEN: usize = MAX_NMEA_PACKET_SIZE;
+}
+
+impl PgnMessage for fluid_level::TankCalibration {
+    const PGN: u32 = group_function::PGN;
+    const IS_FAST_PACKET: bool = true;
+    const MAX_LEN: usize = fluid_level::TankCalibration::MAX_LEN;
+}
+
+#[cfg(any(test, feature = "fusion"))]
+impl PgnMessage for fusion::FusionMessage<'_> {
+    const PGN: u32 = fusion::PGN;
+    const IS_FAST_PACKET: bool = true;
+    const MAX_LEN: usize = MAX_NMEA_PACKET_SIZE;
+}
+
+/// A decoded PGN that can be encoded for transmission. Only the messages
+/// with a `to_payload` encoder implement it.
+pub trait Encode: PgnMessage {
+    /// Writes the payload into `buf`, which must hold at least
+    /// `MAX_NMEA_PACKET_SIZE` bytes, and returns its length.
+    fn encode(&self, buf: &mut [u8]) -> usize;
+}
+
+macro_rules! encode {
+    ($($ty:ty),* $(,)?) => {
+        $(impl Encode for $ty {
+            fn encode(&self, buf: &mut [u8]) -> usize {
+                let payload = self.to_payload();
+                buf[..payload.len()].copy_from_slice(&payload);
+                payload.len()
+            }
+        })*
+    };
+}
+
+encode! {
+    alert::Alert,
+    charger::ChargerStatus,
+    charger::InverterStatus,
+    charger::ChargerConfiguration,
+    depth::WaterDepth,
+    distance_log::DistanceLog,
+    engine::EngineDynamic,
+    engine::EngineRapid,
+    environment::ActualPressure,
+    environment::SetPressure,
+    iso_request::IsoRequest,
+    speed::Leeway,
+    speed::SetDrift,
+    speed::SpeedComponents,
+    speed::WaterSpeed,
+    switching::SwitchBankControl,
+    switching::SwitchBankStatus,
+    thruster::ThrusterControl,
+    thruster::ThrusterInformation,
+    thruster::ThrusterMotorStatus,
+    wind::WindData,
+    windlass::WindlassControl,
+    windlass::WindlassOperatingStatus,
+}
+
+/// A fixed-size record repeated a variable number of times at the end of a
+/// PGN payload, such as the per-satellite entries of PGN 129540.
+pub trait Group: Sized {
+    /// Encoded size of one record in bytes.
+    const SIZE: usize;
+
+    /// Decodes one record from exactly `SIZE` bytes.
+    fn decode(bytes: &[u8]) -> Self;
+}
+
+/// Iterator over the repeating groups of a payload.
+#[derive(Clone, Debug)]
+pub struct RepeatingGroup<'a, G: Group> {
+    chunks: ChunksExact<'a, u8>,
+    remaining: usize,
+    group: PhantomData<G>,
+}
+
+impl<'a, G: Group> RepeatingGroup<'a, G> {
+    /// Reads `count` records of `G` from `bytes`. Fails if `bytes` holds fewer
+    /// than `count` records; trailing bytes are ignored.
+    pub fn new(bytes: &'a [u8], count: usize) -> Result<Self, Error> {
+        check_len(bytes, count * G::SIZE)?;
+        Ok(Self {
+            chunks: bytes[..count * G::SIZE].chunks_exact(G::SIZE),
+            remaining: count,
+            group: PhantomData,
+        })
+    }
+}
+
+impl<G: Group> Iterator for RepeatingGroup<'_, G> {
+    type Item = G;
+
+    fn next(&mut self) -> Option<G> {
+        let bytes = self.chunks.next()?;
+        self.remaining -= 1;
+        Some(G::decode(bytes))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<G: Group> ExactSizeIterator for RepeatingGroup<'_, G> {}
+
+impl<G: Group> FusedIterator for RepeatingGroup<'_, G> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Pair(u8, u8);
+
+    impl Group for Pair {
+        const SIZE: usize = 2;
+
+        fn decode(bytes: &[u8]) -> Self {
+            Pair(bytes[0], bytes[1])
+        }
+    }
+
+    #[test]
+    fn test_repeating_group() {
+        let bytes = [1, 2, 3, 4, 5, 6, 0xFF];
+        let groups: RepeatingGroup<Pair> = RepeatingGroup::new(&bytes, 3).unwrap();
+        assert_eq!(groups.len(), 3);
+        let sums: [u8; 3] = {
+            let mut it = groups.map(|p| p.0 + p.1);
+            [it.next().unwrap(), it.next().unwrap(), it.next().unwrap()]
+        };
+        assert_eq!(sums, [3, 7, 11]);
+
+        assert!(RepeatingGroup::<Pair>::new(&bytes, 4).is_err());
+    }
+
+    #[test]
+    fn test_pgn_message() {
+        use iso_request::IsoRequest;
+
+        assert_eq!(IsoRequest::DEFAULT_PRIORITY, IsoRequest::PRIORITY);
+        assert_eq!(<gnss::GnssSatsInView as PgnMessage>::DEFAULT_PRIORITY, 6);
+        // The encoded payload never exceeds MAX_LEN.
+        let request = IsoRequest {
+            destination: 0x23,
+            pgn: 60928,
+        };
+        let mut buf = [0xFF; MAX_NMEA_PACKET_SIZE];
+        assert_eq!(request.encode(&mut buf), IsoRequest::MAX_LEN);
+
+        // One wrapper per PGN of a shared layout.
+        let bank = switching::SwitchBank::command(2, 3, switching::SwitchState::On).unwrap();
+        let control = switching::SwitchBankControl(bank);
+        assert_eq!(switching::SwitchBankControl::PGN, 127502);
+        assert_eq!(control.encode(&mut buf), 8);
+        assert_eq!(buf[..8], bank.to_payload());
+        assert_eq!(
+            switching::SwitchBankStatus::from_payload(&buf[..8])
+                .unwrap()
+                .0,
+            bank
+        );
+        assert_eq!(
+            <environment::SetPressure as PgnMessage>::PGN,
+            environment::Pressure::SET_PGN
+        );
+        assert_eq!(environment::ExtendedTemperature::PGN, 130316);
+        assert_eq!(
+            <group_function::Acknowledge as PgnMessage>::DEFAULT_PRIORITY,
+            3
+        );
+        assert_eq!(<fusion::FusionMessage as PgnMessage>::MAX_LEN, 223);
+    }
+
+    #[test]
+    fn test_opt() {
+        use alloc::format;
+
+        assert_eq!(format!("{:.2}", Opt(Some(1.234))), "1.23");
+        assert_eq!(format!("{:.2}", Opt::<f32>(None)), "-");
+        assert_eq!(format!("{:.0}", deg(Some(core::f32::consts::PI))), "180");
+        assert_eq!(format!("{:.1}", celsius(Some(277.15))), "4.0");
+    }
+
+    #[test]
+    fn test_not_available() {
+        let payload = [0xFF, 0xFE, 0xFD, 0xFF, 0x7F];
+        assert_eq!(read_u8(&payload, 0), None);
+        assert_eq!(read_u8(&payload, 1), None);
+        assert_eq!(read_u8(&payload, 2), Some(0xFD));
+        assert_eq!(read_i16(&payload, 3), None);
+        assert_eq!(read_u16(&payload, 4), None);
+    }
+}
@@ -0,0 +1,118 @@
+//! Proprietary PGN header parsing and manufacturer-specific decoders.
+//!
+//! Every proprietary payload starts with a 16-bit header holding the 11-bit
+//! manufacturer code and 3-bit industry code of the sender; the rest of the
+//! layout is defined by the manufacturer. Decoders for a manufacturer's
+//! messages implement `ProprietaryDecoder` and are selected with `decode`,
+//! which filters on PGN and manufacturer code.
+use crate::pgn::{check_len, Error};
+
+pub const INDUSTRY_MARINE: u8 = 4;
+
+/// Returns whether `pgn` lies in one of the proprietary ranges.
+pub fn is_proprietary(pgn: u32) -> bool {
+    matches!(pgn, 61184 | 65280..=65535 | 126720 | 130816..=131071)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProprietaryHeader {
+    pub manufacturer_code: u16,
+    pub industry_code: u8,
+}
+
+impl ProprietaryHeader {
+    pub const LEN: usize = 2;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, Self::LEN)?;
+        let header = u16::from_le_bytes([payload[0], payload[1]]);
+        Ok(Self {
+            manufacturer_code: header & 0x07FF,
+            industry_code: (header >> 13) as u8,
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; 2] {
+        let header =
+            (self.manufacturer_code & 0x07FF) | 0x1800 | ((self.industry_code as u16 & 0x07) << 13);
+        header.to_le_bytes()
+    }
+
+    /// The sender's manufacturer, if its code is known.
+    #[cfg(any(test, feature = "manufacturers"))]
+    pub fn manufacturer_name(&self) -> Option<&'static str> {
+        crate::manufacturers::name(self.manufacturer_code)
+    }
+}
+
+/// A family of proprietary messages from one manufacturer.
+pub trait ProprietaryDecoder<'a>: Sized {
+    const MANUFACTURER_CODE: u16;
+    /// Proprietary PGNs the manufacturer uses for these messages.
+    const PGNS: &'static [u32];
+
+    /// Decodes a payload already known to come from `MANUFACTURER_CODE` on
+    /// one of `PGNS`, including the 2-byte header.
+    fn decode(pgn: u32, payload: &'a [u8]) -> Result<Self, Error>;
+}
+
+/// Decodes `payload` with `D` if it was sent on one of `D`'s PGNs by `D`'s
+/// manufacturer; returns `Ok(None)` for anything else.
+pub fn decode<'a, D: ProprietaryDecoder<'a>>(
+    pgn: u32,
+    payload: &'a [u8],
+) -> Result<Option<D>, Error> {
+    if !D::PGNS.contains(&pgn) {
+        return Ok(None);
+    }
+    let header = ProprietaryHeader::from_payload(payload)?;
+    if header.manufacturer_code != D::MANUFACTURER_CODE || header.industry_code != INDUSTRY_MARINE {
+        return Ok(None);
+    }
+    D::decode(pgn, payload).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Raw<'a>(&'a [u8]);
+
+    impl<'a> ProprietaryDecoder<'a> for Raw<'a> {
+        const MANUFACTURER_CODE: u16 = 419;
+        const PGNS: &'static [u32] = &[130820];
+
+        fn decode(_pgn: u32, payload: &'a [u8]) -> Result<Self, Error> {
+            Ok(Raw(&payload[2..]))
+        }
+    }
+
+    #[test]
+    fn test_header() {
+        let header = ProprietaryHeader::from_payload(&[0xA3, 0x99]).unwrap();
+        assert_eq!(header.manufacturer_code, 419);
+        assert_eq!(header.industry_code, INDUSTRY_MARINE);
+        assert_eq!(header.to_bytes(), [0xA3, 0x99]);
+        assert_eq!(header.manufacturer_name(), Some("Fusion Electronics"));
+        assert_eq!(
+            ProprietaryHeader::from_payload(&[0xA3]),
+            Err(Error::InvalidLength)
+        );
+
+        assert!(is_proprietary(130820));
+        assert!(is_proprietary(65280));
+        assert!(!is_proprietary(130306));
+    }
+
+    #[test]
+    fn test_decode_filters_manufacturer() {
+        let payload = [0xA3, 0x99, 0x20, 0x80, 0x01];
+        let raw = decode::<Raw>(130820, &payload).unwrap().unwrap();
+        assert_eq!(raw.0, [0x20, 0x80, 0x01]);
+
+        assert!(decode::<Raw>(130821, &payload).unwrap().is_none());
+        // Manufacturer code 135 (Airmar).
+        let other = [0x87, 0x98, 0x20, 0x80, 0x01];
+        assert!(decode::<Raw>(130820, &other).unwrap().is_none());
+    }
+}
@@ -0,0 +1,262 @@
+//! Speed, leeway, vessel speed component and set and drift PGNs.
+use crate::pgn::gnss::DirectionReference;
+use crate::pgn::{check_len, deg, read_i16, read_u16, read_u8, Error, Opt};
+use core::fmt;
+
+fn write_i16(buf: &mut [u8], value: Option<f32>, resolution: f32) {
+    let raw = value.map_or(0x7FFF, |v| libm::roundf(v / resolution) as i16);
+    buf.copy_from_slice(&raw.to_le_bytes());
+}
+
+fn write_u16(buf: &mut [u8], value: Option<f32>, resolution: f32) {
+    let raw = value.map_or(0xFFFF, |v| libm::roundf(v / resolution) as u16);
+    buf.copy_from_slice(&raw.to_le_bytes());
+}
+
+/// Nautical Leeway Angle (PGN 128000).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Leeway {
+    pub sid: Option<u8>,
+    /// Angle between heading and course through water in radians, positive
+    /// when the vessel is set to starboard.
+    pub leeway: Option<f32>,
+}
+
+impl Leeway {
+    pub const PGN: u32 = 128000;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 3)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            leeway: read_i16(payload, 1).map(|v| v as f32 * 1e-4),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        let mut buf = [0xFF; 8];
+        buf[0] = self.sid.unwrap_or(0xFF);
+        write_i16(&mut buf[1..3], self.leeway, 1e-4);
+        buf
+    }
+}
+
+/// Speed (PGN 128259). Speeds are in m/s.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WaterSpeed {
+    pub sid: Option<u8>,
+    /// Speed through water.
+    pub water: Option<f32>,
+    /// Speed over ground.
+    pub ground: Option<f32>,
+}
+
+impl WaterSpeed {
+    pub const PGN: u32 = 128259;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 5)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            water: read_u16(payload, 1).map(|v| v as f32 * 0.01),
+            ground: read_u16(payload, 3).map(|v| v as f32 * 0.01),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        let mut buf = [0xFF; 8];
+        buf[0] = self.sid.unwrap_or(0xFF);
+        write_u16(&mut buf[1..3], self.water, 0.01);
+        write_u16(&mut buf[3..5], self.ground, 0.01);
+        buf
+    }
+}
+
+/// Set & Drift, Rapid Update (PGN 129291): the current the vessel is
+/// moving in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SetDrift {
+    pub sid: Option<u8>,
+    pub set_reference: DirectionReference,
+    /// Direction the current flows towards, in radians.
+    pub set: Option<f32>,
+    /// Speed of the current in m/s.
+    pub drift: Option<f32>,
+}
+
+impl SetDrift {
+    pub const PGN: u32 = 129291;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 6)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            set_reference: DirectionReference::from(payload[1] & 0x03),
+            set: read_u16(payload, 2).map(|v| v as f32 * 1e-4),
+            drift: read_u16(payload, 4).map(|v| v as f32 * 0.01),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        let mut buf = [0xFF; 8];
+        buf[0] = self.sid.unwrap_or(0xFF);
+        buf[1] = 0xFC | u8::from(self.set_reference);
+        write_u16(&mut buf[2..4], self.set, 1e-4);
+        write_u16(&mut buf[4..6], self.drift, 0.01);
+        buf
+    }
+}
+
+/// Vessel Speed Components (PGN 130578, Fast-Packet). Speeds are in m/s;
+/// longitudinal speeds are positive forward, transverse and stern speeds
+/// positive to starboard.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpeedComponents {
+    pub longitudinal_water: Option<f32>,
+    pub transverse_water: Option<f32>,
+    pub longitudinal_ground: Option<f32>,
+    pub transverse_ground: Option<f32>,
+    pub stern_water: Option<f32>,
+    pub stern_ground: Option<f32>,
+}
+
+impl SpeedComponents {
+    pub const PGN: u32 = 130578;
+    pub const LEN: usize = 12;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, Self::LEN)?;
+        let speed = |offset| read_i16(payload, offset).map(|v| v as f32 * 0.001);
+        Ok(Self {
+            longitudinal_water: speed(0),
+            transverse_water: speed(2),
+            longitudinal_ground: speed(4),
+            transverse_ground: speed(6),
+            stern_water: speed(8),
+            stern_ground: speed(10),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; Self::LEN] {
+        let mut buf = [0xFF; Self::LEN];
+        let speeds = [
+            self.longitudinal_water,
+            self.transverse_water,
+            self.longitudinal_ground,
+            self.transverse_ground,
+            self.stern_water,
+            self.stern_ground,
+        ];
+        for (chunk, speed) in buf.chunks_exact_mut(2).zip(speeds) {
+            write_i16(chunk, speed, 0.001);
+        }
+        buf
+    }
+}
+
+impl fmt::Display for Leeway {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "leeway {:.1}deg", deg(self.leeway))
+    }
+}
+
+impl fmt::Display for WaterSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "speed water={:.2}m/s ground={:.2}m/s",
+            Opt(self.water),
+            Opt(self.ground)
+        )
+    }
+}
+
+impl fmt::Display for SetDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "set={:.0}deg ({:?}) drift={:.2}m/s",
+            deg(self.set),
+            self.set_reference,
+            Opt(self.drift)
+        )
+    }
+}
+
+impl fmt::Display for SpeedComponents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "speed water={:.2}/{:.2}m/s ground={:.2}/{:.2}m/s",
+            Opt(self.longitudinal_water),
+            Opt(self.transverse_water),
+            Opt(self.longitudinal_ground),
+            Opt(self.transverse_ground)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leeway() {
+        // -3 degrees of leeway.
+        let payload: [u8; 8] = [0x05, 0x8B, 0xFD, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        let leeway = Leeway::from_payload(&payload).unwrap();
+        assert!((leeway.leeway.unwrap() + 0.0629).abs() < 1e-6);
+        assert_eq!(leeway.to_payload(), payload);
+        assert_eq!(
+            Leeway::from_payload(&payload[..2]),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_water_speed() {
+        let payload: [u8; 8] = [0x01, 0x5E, 0x01, 0xFF, 0xFF, 0x00, 0xFF, 0xFF];
+        let speed = WaterSpeed::from_payload(&payload).unwrap();
+        assert!((speed.water.unwrap() - 3.5).abs() < 1e-6);
+        assert_eq!(speed.ground, None);
+        assert_eq!(speed.to_payload()[..5], payload[..5]);
+    }
+
+    #[test]
+    fn test_set_drift() {
+        let set_drift = SetDrift {
+            sid: Some(3),
+            set_reference: DirectionReference::True,
+            set: Some(1.5),
+            drift: Some(0.75),
+        };
+        let payload = set_drift.to_payload();
+        assert_eq!(payload, [0x03, 0xFC, 0x98, 0x3A, 0x4B, 0x00, 0xFF, 0xFF]);
+        assert_eq!(SetDrift::from_payload(&payload), Ok(set_drift));
+    }
+
+    #[test]
+    fn test_speed_components() {
+        let speeds = SpeedComponents {
+            longitudinal_water: Some(3.5),
+            transverse_water: Some(-0.2),
+            longitudinal_ground: Some(4.125),
+            transverse_ground: None,
+            stern_water: Some(0.05),
+            stern_ground: None,
+        };
+        let payload = speeds.to_payload();
+        assert_eq!(
+            payload,
+            [0xAC, 0x0D, 0x38, 0xFF, 0x1D, 0x10, 0xFF, 0x7F, 0x32, 0x00, 0xFF, 0x7F]
+        );
+        let decoded = SpeedComponents::from_payload(&payload).unwrap();
+        assert!((decoded.transverse_water.unwrap() + 0.2).abs() < 1e-6);
+        assert_eq!(decoded.transverse_ground, None);
+        assert_eq!(decoded.to_payload(), payload);
+        assert_eq!(
+            SpeedComponents::from_payload(&payload[..11]),
+            Err(Error::InvalidLength)
+        );
+    }
+}
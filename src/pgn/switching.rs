@@ -0,0 +1,216 @@
+//! Digital switching PGNs.
+use crate::pgn::{check_len, Error};
+use core::fmt;
+
+/// Two-bit state of one switch bank channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwitchState {
+    Off,
+    On,
+    Error,
+    /// Not available in a status message, "leave unchanged" in a control
+    /// message.
+    Unavailable,
+}
+
+impl From<u8> for SwitchState {
+    fn from(value: u8) -> Self {
+        match value & 0x03 {
+            0 => Self::Off,
+            1 => Self::On,
+            2 => Self::Error,
+            _ => Self::Unavailable,
+        }
+    }
+}
+
+impl From<SwitchState> for u8 {
+    fn from(value: SwitchState) -> u8 {
+        match value {
+            SwitchState::Off => 0,
+            SwitchState::On => 1,
+            SwitchState::Error => 2,
+            SwitchState::Unavailable => 3,
+        }
+    }
+}
+
+/// Binary Switch Bank Status (PGN 127501) and Switch Bank Control (PGN
+/// 127502), which share the same layout: a bank instance followed by 28
+/// channels of two bits each. Channels are numbered from 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwitchBank {
+    pub instance: u8,
+    channels: [u8; 7],
+}
+
+impl SwitchBank {
+    pub const STATUS_PGN: u32 = 127501;
+    pub const CONTROL_PGN: u32 = 127502;
+    pub const CHANNELS: usize = 28;
+
+    /// A bank with every channel unavailable / unchanged.
+    pub const fn new(instance: u8) -> Self {
+        Self {
+            instance,
+            channels: [0xFF; 7],
+        }
+    }
+
+    /// A control message switching a single channel and leaving the others
+    /// unchanged. Returns `None` if `channel` is out of range.
+    pub fn command(instance: u8, channel: usize, state: SwitchState) -> Option<Self> {
+        let mut bank = Self::new(instance);
+        bank.set(channel, state)?;
+        Some(bank)
+    }
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 8)?;
+        Ok(Self {
+            instance: payload[0],
+            channels: payload[1..8].try_into().unwrap(),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        let mut buf = [0; 8];
+        buf[0] = self.instance;
+        buf[1..].copy_from_slice(&self.channels);
+        buf
+    }
+
+    pub fn get(&self, channel: usize) -> Option<SwitchState> {
+        if !(1..=Self::CHANNELS).contains(&channel) {
+            return None;
+        }
+        let (byte, shift) = Self::position(channel);
+        Some(SwitchState::from(self.channels[byte] >> shift))
+    }
+
+    /// Sets `channel` to `state` and returns its previous state, or `None` if
+    /// `channel` is out of range.
+    pub fn set(&mut self, channel: usize, state: SwitchState) -> Option<SwitchState> {
+        let previous = self.get(channel)?;
+        let (byte, shift) = Self::position(channel);
+        self.channels[byte] = self.channels[byte] & !(0x03 << shift) | u8::from(state) << shift;
+        Some(previous)
+    }
+
+    /// Applies the channels of a control message that are not "leave
+    /// unchanged", returning the number of channels whose state changed.
+    pub fn apply(&mut self, control: &SwitchBank) -> usize {
+        let mut changed = 0;
+        for channel in 1..=Self::CHANNELS {
+            let state = control.get(channel).unwrap();
+            if state != SwitchState::Unavailable && self.set(channel, state) != Some(state) {
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    /// Iterates over the states of channels 1 to 28.
+    pub fn channels(&self) -> impl Iterator<Item = SwitchState> + '_ {
+        (1..=Self::CHANNELS).map(|channel| self.get(channel).unwrap())
+    }
+
+    fn position(channel: usize) -> (usize, usize) {
+        ((channel - 1) / 4, (channel - 1) % 4 * 2)
+    }
+}
+
+/// A `SwitchBank` received or sent as Binary Switch Bank Status (PGN 127501).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwitchBankStatus(pub SwitchBank);
+
+impl SwitchBankStatus {
+    pub const PGN: u32 = SwitchBank::STATUS_PGN;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        SwitchBank::from_payload(payload).map(Self)
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        self.0.to_payload()
+    }
+}
+
+/// A `SwitchBank` received or sent as Switch Bank Control (PGN 127502).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwitchBankControl(pub SwitchBank);
+
+impl SwitchBankControl {
+    pub const PGN: u32 = SwitchBank::CONTROL_PGN;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        SwitchBank::from_payload(payload).map(Self)
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        self.0.to_payload()
+    }
+}
+
+impl fmt::Display for SwitchBank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "switch bank #{} ", self.instance)?;
+        for state in self.channels() {
+            f.write_str(match state {
+                SwitchState::Off => "0",
+                SwitchState::On => "1",
+                SwitchState::Error => "E",
+                SwitchState::Unavailable => "-",
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status() {
+        // Bank 3: channel 1 on, 2 off, 3 error, 5 on, the rest unavailable.
+        let payload: [u8; 8] = [0x03, 0xE1, 0xFD, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        let bank = SwitchBank::from_payload(&payload).unwrap();
+        assert_eq!(bank.instance, 3);
+        assert_eq!(bank.get(1), Some(SwitchState::On));
+        assert_eq!(bank.get(2), Some(SwitchState::Off));
+        assert_eq!(bank.get(3), Some(SwitchState::Error));
+        assert_eq!(bank.get(4), Some(SwitchState::Unavailable));
+        assert_eq!(bank.get(5), Some(SwitchState::On));
+        assert_eq!(bank.get(0), None);
+        assert_eq!(bank.get(29), None);
+        assert_eq!(bank.channels().count(), 28);
+        assert_eq!(bank.to_payload(), payload);
+        assert_eq!(
+            alloc::format!("{}", bank),
+            "switch bank #3 10E-1-----------------------"
+        );
+        assert_eq!(
+            SwitchBank::from_payload(&payload[..7]),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_control() {
+        let command = SwitchBank::command(3, 28, SwitchState::On).unwrap();
+        assert_eq!(
+            command.to_payload(),
+            [0x03, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F]
+        );
+        assert_eq!(SwitchBank::command(3, 29, SwitchState::On), None);
+
+        let mut bank = SwitchBank::new(3);
+        bank.set(1, SwitchState::Off);
+        bank.set(28, SwitchState::Off);
+        assert_eq!(bank.apply(&command), 1);
+        assert_eq!(bank.get(28), Some(SwitchState::On));
+        assert_eq!(bank.get(1), Some(SwitchState::Off));
+        assert_eq!(bank.apply(&command), 0);
+    }
+}
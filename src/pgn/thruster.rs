@@ -0,0 +1,343 @@
+//! Thruster PGNs.
+use crate::pgn::{celsius, check_len, deg, read_u16, read_u8, Error, Opt};
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThrusterDirection {
+    Off,
+    Ready,
+    ToPort,
+    ToStarboard,
+    Unknown(u8),
+}
+
+impl From<u8> for ThrusterDirection {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Off,
+            1 => Self::Ready,
+            2 => Self::ToPort,
+            3 => Self::ToStarboard,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<ThrusterDirection> for u8 {
+    fn from(value: ThrusterDirection) -> u8 {
+        match value {
+            ThrusterDirection::Off => 0,
+            ThrusterDirection::Ready => 1,
+            ThrusterDirection::ToPort => 2,
+            ThrusterDirection::ToStarboard => 3,
+            ThrusterDirection::Unknown(v) => v,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetractControl {
+    Off,
+    Extend,
+    Retract,
+    Unknown(u8),
+}
+
+impl From<u8> for RetractControl {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Off,
+            1 => Self::Extend,
+            2 => Self::Retract,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<RetractControl> for u8 {
+    fn from(value: RetractControl) -> u8 {
+        match value {
+            RetractControl::Off => 0,
+            RetractControl::Extend => 1,
+            RetractControl::Retract => 2,
+            RetractControl::Unknown(v) => v,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThrusterMotorType {
+    Dc12V,
+    Dc24V,
+    Dc48V,
+    Ac24V,
+    Hydraulic,
+    Unknown(u8),
+}
+
+impl From<u8> for ThrusterMotorType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Dc12V,
+            1 => Self::Dc24V,
+            2 => Self::Dc48V,
+            3 => Self::Ac24V,
+            4 => Self::Hydraulic,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<ThrusterMotorType> for u8 {
+    fn from(value: ThrusterMotorType) -> u8 {
+        match value {
+            ThrusterMotorType::Dc12V => 0,
+            ThrusterMotorType::Dc24V => 1,
+            ThrusterMotorType::Dc48V => 2,
+            ThrusterMotorType::Ac24V => 3,
+            ThrusterMotorType::Hydraulic => 4,
+            ThrusterMotorType::Unknown(v) => v,
+        }
+    }
+}
+
+/// Thruster Control Status (PGN 128006), also sent to command a thruster.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThrusterControl {
+    pub sid: Option<u8>,
+    pub thruster_id: u8,
+    pub direction: ThrusterDirection,
+    pub power_enabled: Option<bool>,
+    pub retract: RetractControl,
+    /// Speed in %.
+    pub speed: Option<u8>,
+    /// Another device is in control (bit 0), boat too fast for the thruster
+    /// (bit 1).
+    pub control_events: u8,
+    /// Time after which the thruster stops without a new command, in seconds.
+    pub command_timeout: Option<f32>,
+    /// Azimuth of steerable thrusters in radians.
+    pub azimuth: Option<f32>,
+}
+
+impl ThrusterControl {
+    pub const PGN: u32 = 128006;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 8)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            thruster_id: payload[1],
+            direction: ThrusterDirection::from(payload[2] & 0x0F),
+            power_enabled: match (payload[2] >> 4) & 0x03 {
+                0 => Some(false),
+                1 => Some(true),
+                _ => None,
+            },
+            retract: RetractControl::from(payload[2] >> 6),
+            speed: read_u8(payload, 3),
+            control_events: payload[4],
+            command_timeout: read_u8(payload, 5).map(|v| v as f32 * 0.005),
+            azimuth: read_u16(payload, 6).map(|v| v as f32 * 0.0001),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        let mut buf = [0xFF; 8];
+        buf[0] = self.sid.unwrap_or(0xFF);
+        buf[1] = self.thruster_id;
+        let power = match self.power_enabled {
+            Some(false) => 0,
+            Some(true) => 1,
+            None => 3,
+        };
+        buf[2] = u8::from(self.direction) & 0x0F | power << 4 | u8::from(self.retract) << 6;
+        buf[3] = self.speed.unwrap_or(0xFF);
+        buf[4] = self.control_events;
+        buf[5] = self
+            .command_timeout
+            .map_or(0xFF, |v| libm::roundf(v / 0.005) as u8);
+        let azimuth = self
+            .azimuth
+            .map_or(0xFFFF, |v| libm::roundf(v / 0.0001) as u16);
+        buf[6..8].copy_from_slice(&azimuth.to_le_bytes());
+        buf
+    }
+}
+
+/// Thruster Information (PGN 128007).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThrusterInformation {
+    pub thruster_id: u8,
+    pub motor_type: ThrusterMotorType,
+    /// Power rating in watts.
+    pub power_rating: Option<u16>,
+    /// Maximum motor temperature in Kelvin.
+    pub max_temperature: Option<f32>,
+    /// Maximum rotational speed in rpm.
+    pub max_rotational_speed: Option<f32>,
+}
+
+impl ThrusterInformation {
+    pub const PGN: u32 = 128007;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 8)?;
+        Ok(Self {
+            thruster_id: payload[0],
+            motor_type: ThrusterMotorType::from(payload[1] & 0x0F),
+            power_rating: read_u16(payload, 2),
+            max_temperature: read_u16(payload, 4).map(|v| v as f32 * 0.01),
+            max_rotational_speed: read_u16(payload, 6).map(|v| v as f32 * 0.25),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        let mut buf = [0xFF; 8];
+        buf[0] = self.thruster_id;
+        buf[1] = u8::from(self.motor_type) & 0x0F | 0xF0;
+        buf[2..4].copy_from_slice(&self.power_rating.unwrap_or(0xFFFF).to_le_bytes());
+        let max_temperature = self
+            .max_temperature
+            .map_or(0xFFFF, |v| libm::roundf(v / 0.01) as u16);
+        buf[4..6].copy_from_slice(&max_temperature.to_le_bytes());
+        let max_rotational_speed = self
+            .max_rotational_speed
+            .map_or(0xFFFF, |v| libm::roundf(v / 0.25) as u16);
+        buf[6..8].copy_from_slice(&max_rotational_speed.to_le_bytes());
+        buf
+    }
+}
+
+/// Thruster Motor Status (PGN 128008).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThrusterMotorStatus {
+    pub sid: Option<u8>,
+    pub thruster_id: u8,
+    /// Over-temperature and over-current cut-outs, low oil level, oil
+    /// over-temperature and controller under-voltage, from bit 0.
+    pub motor_events: u8,
+    /// Motor current in amperes.
+    pub current: Option<u8>,
+    /// Motor temperature in Kelvin.
+    pub temperature: Option<f32>,
+    /// Total operating time in minutes.
+    pub operating_time: Option<u16>,
+}
+
+impl ThrusterMotorStatus {
+    pub const PGN: u32 = 128008;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 8)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            thruster_id: payload[1],
+            motor_events: payload[2],
+            current: read_u8(payload, 3),
+            temperature: read_u16(payload, 4).map(|v| v as f32 * 0.01),
+            operating_time: read_u16(payload, 6),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        let mut buf = [0xFF; 8];
+        buf[0] = self.sid.unwrap_or(0xFF);
+        buf[1] = self.thruster_id;
+        buf[2] = self.motor_events;
+        buf[3] = self.current.unwrap_or(0xFF);
+        let temperature = self
+            .temperature
+            .map_or(0xFFFF, |v| libm::roundf(v / 0.01) as u16);
+        buf[4..6].copy_from_slice(&temperature.to_le_bytes());
+        buf[6..8].copy_from_slice(&self.operating_time.unwrap_or(0xFFFF).to_le_bytes());
+        buf
+    }
+}
+
+impl fmt::Display for ThrusterControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "thruster #{} {:?} speed={}% azimuth={:.0}deg",
+            self.thruster_id,
+            self.direction,
+            Opt(self.speed),
+            deg(self.azimuth)
+        )
+    }
+}
+
+impl fmt::Display for ThrusterInformation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "thruster #{} {:?} {}W",
+            self.thruster_id,
+            self.motor_type,
+            Opt(self.power_rating)
+        )
+    }
+}
+
+impl fmt::Display for ThrusterMotorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "thruster #{} {}A {:.1}C events={:#04x}",
+            self.thruster_id,
+            Opt(self.current),
+            celsius(self.temperature),
+            self.motor_events
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_control() {
+        let control = ThrusterControl {
+            sid: None,
+            thruster_id: 0,
+            direction: ThrusterDirection::ToStarboard,
+            power_enabled: Some(true),
+            retract: RetractControl::Off,
+            speed: Some(60),
+            control_events: 0,
+            command_timeout: Some(0.5),
+            azimuth: None,
+        };
+        let payload = control.to_payload();
+        assert_eq!(payload, [0xFF, 0x00, 0x13, 0x3C, 0x00, 0x64, 0xFF, 0xFF]);
+        assert_eq!(ThrusterControl::from_payload(&payload), Ok(control));
+    }
+
+    #[test]
+    fn test_information() {
+        let payload: [u8; 8] = [0x00, 0xF0, 0xDC, 0x05, 0xA6, 0x8E, 0x40, 0x9C];
+        let info = ThrusterInformation::from_payload(&payload).unwrap();
+        assert_eq!(info.motor_type, ThrusterMotorType::Dc12V);
+        assert_eq!(info.power_rating, Some(1500));
+        assert!((info.max_temperature.unwrap() - 365.18).abs() < 1e-3);
+        assert_eq!(info.max_rotational_speed, Some(10000.0));
+        assert_eq!(info.to_payload(), payload);
+    }
+
+    #[test]
+    fn test_motor_status() {
+        let payload: [u8; 8] = [0x01, 0x00, 0x01, 0x96, 0x6B, 0x8A, 0x2C, 0x01];
+        let status = ThrusterMotorStatus::from_payload(&payload).unwrap();
+        assert_eq!(status.motor_events, 0x01);
+        assert_eq!(status.current, Some(150));
+        assert!((status.temperature.unwrap() - 354.35).abs() < 1e-3);
+        assert_eq!(status.operating_time, Some(300));
+        assert_eq!(status.to_payload(), payload);
+        assert_eq!(
+            ThrusterMotorStatus::from_payload(&payload[..7]),
+            Err(Error::InvalidLength)
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Wind Data (PGN 130306) and apparent/true wind conversions.
+//!
+//! Wind angles are measured in radians, clockwise, and describe where the wind
+//! blows *from*. Boat-referenced angles are relative to the bow (0 = dead
+//! ahead, π/2 = from starboard); ground-referenced directions are relative to
+//! true or magnetic north.
+//!
+//! The "true" wind obtained from apparent wind depends on which boat speed is
+//! used: speed through water yields water-referenced true wind (what sailors
+//! trim to), speed over ground yields ground-referenced true wind (what a
+//! weather station would measure). Combining either with the heading gives the
+//! true wind direction.
+use crate::pgn::{check_len, deg, normalize_angle, read_u16, read_u8, Error, Opt};
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindReference {
+    /// Ground referenced to true north.
+    TrueNorth,
+    /// Ground referenced to magnetic north.
+    Magnetic,
+    Apparent,
+    /// True wind relative to the bow, using speed over ground.
+    TrueBoat,
+    /// True wind relative to the bow, using speed through water.
+    TrueWater,
+    Unknown(u8),
+}
+
+impl From<u8> for WindReference {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::TrueNorth,
+            1 => Self::Magnetic,
+            2 => Self::Apparent,
+            3 => Self::TrueBoat,
+            4 => Self::TrueWater,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<WindReference> for u8 {
+    fn from(value: WindReference) -> u8 {
+        match value {
+            WindReference::TrueNorth => 0,
+            WindReference::Magnetic => 1,
+            WindReference::Apparent => 2,
+            WindReference::TrueBoat => 3,
+            WindReference::TrueWater => 4,
+            WindReference::Unknown(v) => v & 0x07,
+        }
+    }
+}
+
+/// Wind Data (PGN 130306).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindData {
+    pub sid: Option<u8>,
+    /// Wind speed in m/s.
+    pub speed: Option<f32>,
+    /// Wind angle or direction in radians, see `reference`.
+    pub angle: Option<f32>,
+    pub reference: WindReference,
+}
+
+impl WindData {
+    pub const PGN: u32 = 130306;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 6)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            speed: read_u16(payload, 1).map(|v| v as f32 * 0.01),
+            angle: read_u16(payload, 3).map(|v| v as f32 * 1e-4),
+            reference: WindReference::from(payload[5] & 0x07),
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        let mut buf = [0xFF; 8];
+        buf[0] = self.sid.unwrap_or(0xFF);
+        let speed = self.speed.map_or(0xFFFF, |v| libm::roundf(v / 0.01) as u16);
+        buf[1..3].copy_from_slice(&speed.to_le_bytes());
+        let angle = self.angle.map_or(0xFFFF, |v| libm::roundf(v / 1e-4) as u16);
+        buf[3..5].copy_from_slice(&angle.to_le_bytes());
+        buf[5] = 0xF8 | u8::from(self.reference);
+        buf
+    }
+}
+
+/// Wind speed (m/s) and boat-referenced angle (radians).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindVector {
+    pub speed: f32,
+    pub angle: f32,
+}
+
+/// Converts apparent wind to true wind relative to the bow, given the boat's
+/// speed in m/s. The returned angle is normalized to `[0, 2π)`.
+pub fn apparent_to_true(apparent: WindVector, boat_speed: f32) -> WindVector {
+    let x = apparent.speed * libm::cosf(apparent.angle) - boat_speed;
+    let y = apparent.speed * libm::sinf(apparent.angle);
+    WindVector {
+        speed: libm::hypotf(x, y),
+        angle: normalize_angle(libm::atan2f(y, x)),
+    }
+}
+
+/// Converts true wind relative to the bow to apparent wind, given the boat's
+/// speed in m/s. The returned angle is normalized to `[0, 2π)`.
+pub fn true_to_apparent(true_wind: WindVector, boat_speed: f32) -> WindVector {
+    let x = true_wind.speed * libm::cosf(true_wind.angle) + boat_speed;
+    let y = true_wind.speed * libm::sinf(true_wind.angle);
+    WindVector {
+        speed: libm::hypotf(x, y),
+        angle: normalize_angle(libm::atan2f(y, x)),
+    }
+}
+
+/// Turns a boat-referenced true wind angle into a direction relative to the
+/// same north as `heading`.
+pub fn true_wind_direction(true_wind_angle: f32, heading: f32) -> f32 {
+    normalize_angle(true_wind_angle + heading)
+}
+
+impl fmt::Display for WindData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "wind speed={:.1}m/s angle={:.0}deg ({:?})",
+            Opt(self.speed),
+            deg(self.angle),
+            self.reference
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::{FRAC_PI_2, PI};
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_wind_data() {
+        let payload: [u8; 8] = [0x01, 0xF4, 0x01, 0x5C, 0x3D, 0xFA, 0xFF, 0xFF];
+        let wind = WindData::from_payload(&payload).unwrap();
+        assert_eq!(wind.sid, Some(1));
+        assert_close(wind.speed.unwrap(), 5.0);
+        assert_close(wind.angle.unwrap(), FRAC_PI_2);
+        assert_eq!(wind.reference, WindReference::Apparent);
+        assert_eq!(wind.to_payload(), payload);
+
+        assert_eq!(
+            WindData::from_payload(&payload[..5]),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_conversions() {
+        // Motoring at 5 m/s in still air gives 5 m/s of apparent wind on the bow.
+        let apparent = WindVector {
+            speed: 5.0,
+            angle: 0.0,
+        };
+        assert_close(apparent_to_true(apparent, 5.0).speed, 0.0);
+
+        // 10 m/s true wind on the beam at 5 m/s boat speed.
+        let true_wind = WindVector {
+            speed: 10.0,
+            angle: FRAC_PI_2,
+        };
+        let apparent = true_to_apparent(true_wind, 5.0);
+        assert_close(apparent.speed, 125.0f32.sqrt());
+        assert_close(apparent.angle, libm::atan2f(10.0, 5.0));
+
+        let back = apparent_to_true(apparent, 5.0);
+        assert_close(back.speed, 10.0);
+        assert_close(back.angle, FRAC_PI_2);
+
+        // Wind from port is reported as an angle above π.
+        let port = apparent_to_true(
+            WindVector {
+                speed: 10.0,
+                angle: 3.0 * FRAC_PI_2,
+            },
+            0.0,
+        );
+        assert_close(port.angle, 3.0 * FRAC_PI_2);
+
+        assert_close(true_wind_direction(FRAC_PI_2, 3.0 * FRAC_PI_2), 0.0);
+        assert_close(true_wind_direction(PI, FRAC_PI_2), 3.0 * FRAC_PI_2);
+    }
+}
@@ -0,0 +1,351 @@
+//! Anchor windlass PGNs.
+use crate::pgn::{check_len, read_u16, read_u8, Error, Opt};
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindlassDirection {
+    Off,
+    Down,
+    Up,
+    Unknown(u8),
+}
+
+impl From<u8> for WindlassDirection {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Off,
+            1 => Self::Down,
+            2 => Self::Up,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<WindlassDirection> for u8 {
+    fn from(value: WindlassDirection) -> u8 {
+        match value {
+            WindlassDirection::Off => 0,
+            WindlassDirection::Down => 1,
+            WindlassDirection::Up => 2,
+            WindlassDirection::Unknown(v) => v,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindlassMotion {
+    Stopped,
+    Deploying,
+    Retrieving,
+    Unknown(u8),
+}
+
+impl From<u8> for WindlassMotion {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Stopped,
+            1 => Self::Deploying,
+            2 => Self::Retrieving,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<WindlassMotion> for u8 {
+    fn from(value: WindlassMotion) -> u8 {
+        match value {
+            WindlassMotion::Stopped => 0,
+            WindlassMotion::Deploying => 1,
+            WindlassMotion::Retrieving => 2,
+            WindlassMotion::Unknown(v) => v,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RodeType {
+    Chain,
+    Rope,
+    Unknown(u8),
+}
+
+impl From<u8> for RodeType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Chain,
+            1 => Self::Rope,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl From<RodeType> for u8 {
+    fn from(value: RodeType) -> u8 {
+        match value {
+            RodeType::Chain => 0,
+            RodeType::Rope => 1,
+            RodeType::Unknown(v) => v,
+        }
+    }
+}
+
+/// Reads a two-bit off/on field; error and not available decode to `None`.
+fn read_flag(byte: u8, shift: u8) -> Option<bool> {
+    match (byte >> shift) & 0x03 {
+        0 => Some(false),
+        1 => Some(true),
+        _ => None,
+    }
+}
+
+fn write_flag(flag: Option<bool>, shift: u8) -> u8 {
+    (match flag {
+        Some(false) => 0,
+        Some(true) => 1,
+        None => 3,
+    }) << shift
+}
+
+/// Anchor Windlass Control Status (PGN 128776).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindlassControl {
+    pub sid: Option<u8>,
+    pub windlass_id: u8,
+    pub direction: WindlassDirection,
+    pub anchor_docking: Option<bool>,
+    /// 0 single speed, 1 dual speed, 2 proportional.
+    pub speed_control_type: u8,
+    /// On/off for single speed windlasses, otherwise a speed in %.
+    pub speed_control: Option<u8>,
+    pub power_enable: Option<bool>,
+    pub mechanical_lock: Option<bool>,
+    pub deck_and_anchor_wash: Option<bool>,
+    pub anchor_light: Option<bool>,
+    /// Time after which the windlass stops without a new command, in seconds.
+    pub command_timeout: Option<f32>,
+    /// Bit 0 is set while another device is controlling the windlass.
+    pub control_events: u8,
+}
+
+impl WindlassControl {
+    pub const PGN: u32 = 128776;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 7)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            windlass_id: payload[1],
+            direction: WindlassDirection::from(payload[2] & 0x03),
+            anchor_docking: read_flag(payload[2], 2),
+            speed_control_type: (payload[2] >> 4) & 0x03,
+            speed_control: read_u8(payload, 3),
+            power_enable: read_flag(payload[4], 0),
+            mechanical_lock: read_flag(payload[4], 2),
+            deck_and_anchor_wash: read_flag(payload[4], 4),
+            anchor_light: read_flag(payload[4], 6),
+            command_timeout: read_u8(payload, 5).map(|v| v as f32 * 0.005),
+            control_events: payload[6] & 0x0F,
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        let mut buf = [0xFF; 8];
+        buf[0] = self.sid.unwrap_or(0xFF);
+        buf[1] = self.windlass_id;
+        buf[2] = u8::from(self.direction) & 0x03
+            | write_flag(self.anchor_docking, 2)
+            | (self.speed_control_type & 0x03) << 4
+            | 0xC0;
+        buf[3] = self.speed_control.unwrap_or(0xFF);
+        buf[4] = write_flag(self.power_enable, 0)
+            | write_flag(self.mechanical_lock, 2)
+            | write_flag(self.deck_and_anchor_wash, 4)
+            | write_flag(self.anchor_light, 6);
+        buf[5] = self
+            .command_timeout
+            .map_or(0xFF, |v| libm::roundf(v / 0.005) as u8);
+        buf[6] = self.control_events & 0x0F | 0xF0;
+        buf
+    }
+}
+
+/// Anchor Windlass Operating Status (PGN 128777).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindlassOperatingStatus {
+    pub sid: Option<u8>,
+    pub windlass_id: u8,
+    pub direction: WindlassDirection,
+    pub motion: WindlassMotion,
+    pub rode_type: RodeType,
+    /// Rode deployed, in metres.
+    pub rode_counter: Option<f32>,
+    /// Line speed in m/s.
+    pub line_speed: Option<f32>,
+    pub anchor_docked: Option<bool>,
+    /// System error, sensor error, no motion detected, docking distance
+    /// reached and end of rode reached, from bit 0.
+    pub operating_events: u8,
+}
+
+impl WindlassOperatingStatus {
+    pub const PGN: u32 = 128777;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 8)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            windlass_id: payload[1],
+            direction: WindlassDirection::from(payload[2] & 0x03),
+            motion: WindlassMotion::from((payload[2] >> 2) & 0x03),
+            rode_type: RodeType::from((payload[2] >> 4) & 0x03),
+            rode_counter: read_u16(payload, 3).map(|v| v as f32 * 0.1),
+            line_speed: read_u16(payload, 5).map(|v| v as f32 * 0.01),
+            anchor_docked: read_flag(payload[7], 0),
+            operating_events: payload[7] >> 2,
+        })
+    }
+
+    pub fn to_payload(&self) -> [u8; 8] {
+        let mut buf = [0xFF; 8];
+        buf[0] = self.sid.unwrap_or(0xFF);
+        buf[1] = self.windlass_id;
+        buf[2] = u8::from(self.direction) & 0x03
+            | (u8::from(self.motion) & 0x03) << 2
+            | (u8::from(self.rode_type) & 0x03) << 4
+            | 0xC0;
+        let rode_counter = self
+            .rode_counter
+            .map_or(0xFFFF, |v| libm::roundf(v / 0.1) as u16);
+        buf[3..5].copy_from_slice(&rode_counter.to_le_bytes());
+        let line_speed = self
+            .line_speed
+            .map_or(0xFFFF, |v| libm::roundf(v / 0.01) as u16);
+        buf[5..7].copy_from_slice(&line_speed.to_le_bytes());
+        buf[7] = write_flag(self.anchor_docked, 0) | self.operating_events << 2;
+        buf
+    }
+}
+
+/// Anchor Windlass Monitoring Status (PGN 128778).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindlassMonitoringStatus {
+    pub sid: Option<u8>,
+    pub windlass_id: u8,
+    /// Under-voltage, over-current and over-temperature cut-outs and
+    /// controller/power faults, from bit 0.
+    pub monitoring_events: u8,
+    /// Controller voltage in volts.
+    pub controller_voltage: Option<f32>,
+    /// Motor current in amperes.
+    pub motor_current: Option<f32>,
+    /// Total motor run time in seconds.
+    pub total_motor_time: Option<u32>,
+}
+
+impl WindlassMonitoringStatus {
+    pub const PGN: u32 = 128778;
+
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        check_len(payload, 7)?;
+        Ok(Self {
+            sid: read_u8(payload, 0),
+            windlass_id: payload[1],
+            monitoring_events: payload[2],
+            controller_voltage: read_u8(payload, 3).map(|v| v as f32 * 0.2),
+            motor_current: read_u8(payload, 4).map(|v| v as f32),
+            total_motor_time: read_u16(payload, 5).map(|v| v as u32 * 60),
+        })
+    }
+}
+
+impl fmt::Display for WindlassControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "windlass #{} control {:?} speed={}",
+            self.windlass_id,
+            self.direction,
+            Opt(self.speed_control)
+        )
+    }
+}
+
+impl fmt::Display for WindlassOperatingStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "windlass #{} {:?} rode={:.1}m ({:?}) speed={:.2}m/s",
+            self.windlass_id,
+            self.motion,
+            Opt(self.rode_counter),
+            self.rode_type,
+            Opt(self.line_speed)
+        )
+    }
+}
+
+impl fmt::Display for WindlassMonitoringStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "windlass #{} {:.1}V {:.0}A events={:#04x}",
+            self.windlass_id,
+            Opt(self.controller_voltage),
+            Opt(self.motor_current),
+            self.monitoring_events
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_control() {
+        let control = WindlassControl {
+            sid: None,
+            windlass_id: 1,
+            direction: WindlassDirection::Down,
+            anchor_docking: Some(false),
+            speed_control_type: 0,
+            speed_control: Some(1),
+            power_enable: Some(true),
+            mechanical_lock: Some(false),
+            deck_and_anchor_wash: None,
+            anchor_light: Some(true),
+            command_timeout: Some(0.25),
+            control_events: 0,
+        };
+        let payload = control.to_payload();
+        assert_eq!(payload, [0xFF, 0x01, 0xC1, 0x01, 0x71, 0x32, 0xF0, 0xFF]);
+        assert_eq!(WindlassControl::from_payload(&payload), Ok(control));
+    }
+
+    #[test]
+    fn test_operating_status() {
+        // Retrieving chain at 0.5 m/s with 32.4 m deployed.
+        let payload: [u8; 8] = [0x07, 0x01, 0xCA, 0x44, 0x01, 0x32, 0x00, 0x00];
+        let status = WindlassOperatingStatus::from_payload(&payload).unwrap();
+        assert_eq!(status.direction, WindlassDirection::Up);
+        assert_eq!(status.motion, WindlassMotion::Retrieving);
+        assert_eq!(status.rode_type, RodeType::Chain);
+        assert!((status.rode_counter.unwrap() - 32.4).abs() < 1e-4);
+        assert!((status.line_speed.unwrap() - 0.5).abs() < 1e-6);
+        assert_eq!(status.anchor_docked, Some(false));
+        assert_eq!(status.to_payload(), payload);
+        assert_eq!(
+            WindlassOperatingStatus::from_payload(&payload[..7]),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_monitoring_status() {
+        let payload: [u8; 8] = [0xFF, 0x01, 0x00, 0x3F, 0x50, 0x0A, 0x00, 0xFF];
+        let status = WindlassMonitoringStatus::from_payload(&payload).unwrap();
+        assert!((status.controller_voltage.unwrap() - 12.6).abs() < 1e-4);
+        assert_eq!(status.motor_current, Some(80.0));
+        assert_eq!(status.total_motor_time, Some(600));
+    }
+}
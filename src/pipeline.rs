@@ -0,0 +1,354 @@
+//! Receive path composed of stages, e.g. filter, dedup, reassemble, rate
+//! limit and decode.
+use crate::can_id::{AddressFilter, CanId};
+use crate::frame_queue::RxFrame;
+use crate::nmea_message::MAX_NMEA_PACKET_SIZE;
+use crate::rate_limiter::RateLimiter;
+use crate::reassembler::{self, Reassembler};
+use fixed_queue::LinearMap;
+
+/// A frame or, once reassembled, a complete message moving through a
+/// pipeline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Packet {
+    pub timestamp_ms: u64,
+    pub id: CanId,
+    len: usize,
+    data: [u8; MAX_NMEA_PACKET_SIZE],
+}
+
+impl Packet {
+    pub fn from_frame(timestamp_ms: u64, frame: &RxFrame) -> Self {
+        let mut packet = Self {
+            timestamp_ms,
+            id: frame.id,
+            len: 0,
+            data: [0xFF; MAX_NMEA_PACKET_SIZE],
+        };
+        packet.set_payload(&frame.data);
+        packet
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+
+    pub fn payload_mut(&mut self) -> &mut [u8] {
+        &mut self.data[..self.len]
+    }
+
+    /// Replaces the payload, truncated to `MAX_NMEA_PACKET_SIZE` bytes.
+    pub fn set_payload(&mut self, payload: &[u8]) {
+        self.len = payload.len().min(MAX_NMEA_PACKET_SIZE);
+        self.data[..self.len].copy_from_slice(&payload[..self.len]);
+    }
+}
+
+/// A step of the receive path.
+///
+/// Closures taking `&mut Packet` and returning `bool` are stages, and so are
+/// tuples of stages, which run in order.
+pub trait Stage {
+    /// Processes `packet` in place. Returns whether it continues to the next
+    /// stage; a stage holding it back, like `Reassemble` with an incomplete
+    /// message, returns `false`.
+    fn process(&mut self, packet: &mut Packet) -> bool;
+}
+
+impl<F: FnMut(&mut Packet) -> bool> Stage for F {
+    fn process(&mut self, packet: &mut Packet) -> bool {
+        self(packet)
+    }
+}
+
+macro_rules! tuple_stage {
+    ($($stage:ident),*) => {
+        impl<$($stage: Stage),*> Stage for ($($stage,)*) {
+            #[allow(non_snake_case)]
+            fn process(&mut self, packet: &mut Packet) -> bool {
+                let ($($stage,)*) = self;
+                $($stage.process(packet))&&*
+            }
+        }
+    };
+}
+
+tuple_stage!(A, B);
+tuple_stage!(A, B, C);
+tuple_stage!(A, B, C, D);
+tuple_stage!(A, B, C, D, E);
+tuple_stage!(A, B, C, D, E, F);
+
+/// Drops repeats of a packet: the same PGN and payload from the same source
+/// within `window_ms`, as seen when a message reaches us over two bridged
+/// paths. Up to `N` source and PGN pairs are tracked; packets of further
+/// ones are passed through.
+pub struct Dedup<const N: usize> {
+    window_ms: u64,
+    seen: LinearMap<(u8, u32), (u64, u32), N>,
+    dropped: u32,
+}
+
+/// FNV-1a hash of a payload.
+fn hash(payload: &[u8]) -> u32 {
+    payload.iter().fold(0x811C_9DC5, |h, b| {
+        (h ^ *b as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+impl<const N: usize> Dedup<N> {
+    pub const fn new(window_ms: u64) -> Self {
+        Self {
+            window_ms,
+            seen: LinearMap::new(),
+            dropped: 0,
+        }
+    }
+
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+}
+
+impl<const N: usize> Stage for Dedup<N> {
+    fn process(&mut self, packet: &mut Packet) -> bool {
+        let key = (packet.id.source(), packet.id.pgn());
+        let entry = (packet.timestamp_ms, hash(packet.payload()));
+        if let Some(seen) = self.seen.get_mut(&key) {
+            let repeat =
+                seen.1 == entry.1 && packet.timestamp_ms.saturating_sub(seen.0) < self.window_ms;
+            *seen = entry;
+            if repeat {
+                self.dropped += 1;
+            }
+            return !repeat;
+        }
+        let _ = self.seen.insert(key, entry);
+        true
+    }
+}
+
+/// Reassembles the PGNs for which `fast_packet` returns `true`, holding
+/// their frames back until the message is complete. Other packets pass
+/// through unchanged.
+pub struct Reassemble<const N: usize> {
+    fast_packet: fn(u32) -> bool,
+    reassembler: Reassembler<N>,
+    errors: u32,
+    last_error: Option<reassembler::Error>,
+}
+
+impl<const N: usize> Reassemble<N> {
+    pub const fn new(fast_packet: fn(u32) -> bool) -> Self {
+        Self {
+            fast_packet,
+            reassembler: Reassembler::new(),
+            errors: 0,
+            last_error: None,
+        }
+    }
+
+    /// Frames dropped because they didn't fit a session.
+    pub fn errors(&self) -> u32 {
+        self.errors
+    }
+
+    /// Takes the error of the last frame dropped by the reassembler, if it
+    /// hasn't been taken yet.
+    pub fn take_error(&mut self) -> Option<reassembler::Error> {
+        self.last_error.take()
+    }
+}
+
+impl<const N: usize> Stage for Reassemble<N> {
+    fn process(&mut self, packet: &mut Packet) -> bool {
+        let (source, pgn) = (packet.id.source(), packet.id.pgn());
+        if !(self.fast_packet)(pgn) {
+            return true;
+        }
+        let Ok(frame) = <&[u8; 8]>::try_from(packet.payload()) else {
+            self.errors += 1;
+            return false;
+        };
+        match self.reassembler.add_frame(source, pgn, frame) {
+            Ok(true) => {}
+            Ok(false) => return false,
+            Err(e) => {
+                self.errors += 1;
+                self.last_error = Some(e);
+                return false;
+            }
+        }
+        let mut payload = [0xFF; MAX_NMEA_PACKET_SIZE];
+        match self.reassembler.get_payload(source, pgn, &mut payload) {
+            Ok(len) => {
+                packet.set_payload(&payload[..len]);
+                true
+            }
+            Err(_) => {
+                self.errors += 1;
+                false
+            }
+        }
+    }
+}
+
+/// Passes broadcasts and packets addressed to the filter's node.
+impl Stage for AddressFilter {
+    fn process(&mut self, packet: &mut Packet) -> bool {
+        self.accepts(packet.id)
+    }
+}
+
+/// Passes at most `max_per_second` packets of each PGN, see `RateLimiter`.
+pub struct Limit<const N: usize> {
+    limiter: RateLimiter<N>,
+}
+
+impl<const N: usize> Limit<N> {
+    pub const fn new(max_per_second: u16) -> Self {
+        Self {
+            limiter: RateLimiter::new(max_per_second),
+        }
+    }
+}
+
+impl<const N: usize> Stage for Limit<N> {
+    fn process(&mut self, packet: &mut Packet) -> bool {
+        // PGNs that don't fit the table are passed through.
+        self.limiter
+            .allow(packet.id.pgn(), packet.timestamp_ms)
+            .unwrap_or(true)
+    }
+}
+
+/// Runs received frames through `stages`.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::can_id::CanId;
+/// use nmea::frame_queue::RxFrame;
+/// use nmea::pipeline::{Dedup, Packet, Pipeline, Reassemble};
+///
+/// let mut decoded = Vec::new();
+/// let mut pipeline = Pipeline::new((
+///     |packet: &mut Packet| packet.id.source() != 0x42,
+///     Dedup::<8>::new(100),
+///     Reassemble::<4>::new(|pgn| pgn == 129029),
+///     |packet: &mut Packet| {
+///         decoded.push((packet.id.pgn(), packet.payload().len()));
+///         true
+///     },
+/// ));
+///
+/// let id = CanId::new(3, 129029, 0x10, 255).unwrap();
+/// let first = RxFrame { id, data: [0x00, 0x09, 1, 2, 3, 4, 5, 6] };
+/// let second = RxFrame { id, data: [0x01, 7, 8, 9, 0xFF, 0xFF, 0xFF, 0xFF] };
+/// assert!(pipeline.push(0, &first).is_none());
+/// assert!(pipeline.push(1, &second).is_some());
+/// drop(pipeline);
+/// assert_eq!(decoded, [(129029, 9)]);
+/// ```
+pub struct Pipeline<S: Stage> {
+    stages: S,
+    packet: Packet,
+}
+
+impl<S: Stage> Pipeline<S> {
+    pub fn new(stages: S) -> Self {
+        Self {
+            stages,
+            packet: Packet::from_frame(
+                0,
+                &RxFrame {
+                    id: CanId::new(0, 0, 0, 0).unwrap(),
+                    data: [0; 8],
+                },
+            ),
+        }
+    }
+
+    pub fn stages(&self) -> &S {
+        &self.stages
+    }
+
+    pub fn stages_mut(&mut self) -> &mut S {
+        &mut self.stages
+    }
+
+    /// Runs `frame`, received at `timestamp_ms`, through the stages.
+    /// Returns the packet if it made it through all of them.
+    pub fn push(&mut self, timestamp_ms: u64, frame: &RxFrame) -> Option<&Packet> {
+        self.packet = Packet::from_frame(timestamp_ms, frame);
+        self.stages
+            .process(&mut self.packet)
+            .then_some(&self.packet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(pgn: u32, source: u8, data: [u8; 8]) -> RxFrame {
+        RxFrame {
+            id: CanId::new(2, pgn, source, 255).unwrap(),
+            data,
+        }
+    }
+
+    #[test]
+    fn test_dedup() {
+        let mut pipeline = Pipeline::new((Dedup::<4>::new(100), Limit::<4>::new(3)));
+        let heading = frame(127250, 1, [1; 8]);
+        assert!(pipeline.push(0, &heading).is_some());
+        assert!(pipeline.push(10, &heading).is_none());
+        assert!(pipeline.push(20, &frame(127250, 2, [1; 8])).is_some());
+        assert!(pipeline.push(200, &heading).is_some());
+        // Over the rate limit.
+        assert!(pipeline.push(300, &frame(127250, 1, [2; 8])).is_none());
+        assert_eq!(pipeline.stages().0.dropped(), 1);
+    }
+
+    #[test]
+    fn test_custom_stage() {
+        // XOR "decryption" of a proprietary link before reassembly.
+        let mut pipeline = Pipeline::new((
+            |packet: &mut Packet| {
+                if packet.id.pgn() == 130820 {
+                    packet.payload_mut().iter_mut().for_each(|b| *b ^= 0x55);
+                }
+                true
+            },
+            Reassemble::<2>::new(|pgn| pgn == 130820),
+        ));
+        let first = frame(130820, 1, [0x55, 0x5C, 0x54, 0x54, 0x54, 0x54, 0x54, 0x54]);
+        let second = frame(130820, 1, [0x54, 0x56, 0x56, 0x56, 0xAA, 0xAA, 0xAA, 0xAA]);
+        assert!(pipeline.push(0, &first).is_none());
+        let packet = pipeline.push(1, &second).unwrap();
+        assert_eq!(packet.payload(), [1, 1, 1, 1, 1, 1, 3, 3, 3]);
+
+        // A consecutive frame without a session is dropped.
+        assert!(pipeline.push(2, &second).is_none());
+        assert_eq!(pipeline.stages().1.errors(), 1);
+        assert!(pipeline.stages_mut().1.take_error().is_some());
+        assert_eq!(pipeline.stages_mut().1.take_error(), None);
+    }
+
+    #[test]
+    fn test_address_filter() {
+        let mut pipeline = Pipeline::new(AddressFilter::new(0x42));
+        let request = |destination| RxFrame {
+            id: CanId::new(6, 59904, 0x10, destination).unwrap(),
+            data: [0x14, 0xF0, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
+        };
+        assert!(pipeline.push(0, &request(0x42)).is_some());
+        assert!(pipeline.push(0, &request(0x43)).is_none());
+        assert!(pipeline.push(0, &request(255)).is_some());
+        assert!(pipeline.push(0, &frame(127250, 0x10, [0; 8])).is_some());
+
+        pipeline.stages_mut().promiscuous = true;
+        assert!(pipeline.push(0, &request(0x43)).is_some());
+    }
+}
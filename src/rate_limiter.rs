@@ -0,0 +1,129 @@
+use crate::clock::Clock;
+use fixed_queue::LinearMap;
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("PGN table is full")]
+    FullTable,
+}
+
+struct Window {
+    start_ms: u64,
+    count: u16,
+}
+
+/// Downsamples a message stream so that at most `max_per_second` messages of
+/// each PGN are passed through in any one-second window.
+///
+/// Time is supplied by the caller as monotonic milliseconds, so the limiter
+/// can be driven from a hardware timer on embedded targets or from log
+/// timestamps when replaying captures. `N` is the number of distinct PGNs that
+/// can be tracked at once.
+///
+/// ## Example (bridging a 10 Hz position stream onto a 1 Hz link):
+///
+/// ```
+/// use nmea::rate_limiter::RateLimiter;
+///
+/// let mut limiter: RateLimiter<8> = RateLimiter::new(1);
+/// assert!(limiter.allow(129025, 0).unwrap());
+/// assert!(!limiter.allow(129025, 100).unwrap());
+/// assert!(limiter.allow(129025, 1000).unwrap());
+/// ```
+pub struct RateLimiter<const N: usize> {
+    max_per_second: u16,
+    windows: LinearMap<u32, Window, N>,
+}
+
+impl<const N: usize> RateLimiter<N> {
+    pub const fn new(max_per_second: u16) -> Self {
+        Self {
+            max_per_second,
+            windows: LinearMap::new(),
+        }
+    }
+
+    /// Returns whether a message of `pgn` observed at `now_ms` should be passed
+    /// through. Fails if `pgn` is not yet tracked and the table is full.
+    pub fn allow(&mut self, pgn: u32, now_ms: u64) -> Result<bool, Error> {
+        if let Some(window) = self.windows.get_mut(&pgn) {
+            if now_ms.wrapping_sub(window.start_ms) >= 1000 {
+                window.start_ms = now_ms;
+                window.count = 0;
+            }
+            if window.count >= self.max_per_second {
+                return Ok(false);
+            }
+            window.count += 1;
+            return Ok(true);
+        }
+        if self.max_per_second == 0 {
+            return Ok(false);
+        }
+        self.windows
+            .insert(
+                pgn,
+                Window {
+                    start_ms: now_ms,
+                    count: 1,
+                },
+            )
+            .map_err(|_| Error::FullTable)?;
+        Ok(true)
+    }
+
+    /// Like `allow`, reading the current time from `clock`.
+    pub fn allow_now(&mut self, pgn: u32, clock: &impl Clock) -> Result<bool, Error> {
+        self.allow(pgn, clock.now_ms())
+    }
+
+    pub fn max_per_second(&self) -> u16 {
+        self.max_per_second
+    }
+
+    pub fn clear(&mut self) {
+        self.windows = LinearMap::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_per_pgn() {
+        let mut limiter: RateLimiter<4> = RateLimiter::new(2);
+        assert!(limiter.allow(129025, 0).unwrap());
+        assert!(limiter.allow(129025, 100).unwrap());
+        assert!(!limiter.allow(129025, 200).unwrap());
+        // Other PGNs are counted independently.
+        assert!(limiter.allow(129026, 200).unwrap());
+        // A new window opens one second after the first accepted message.
+        assert!(!limiter.allow(129025, 999).unwrap());
+        assert!(limiter.allow(129025, 1000).unwrap());
+    }
+
+    #[test]
+    fn test_allow_now() {
+        use crate::clock::ManualClock;
+
+        let clock = ManualClock::new(0);
+        let mut limiter: RateLimiter<4> = RateLimiter::new(1);
+        assert!(limiter.allow_now(129025, &clock).unwrap());
+        clock.advance(999);
+        assert!(!limiter.allow_now(129025, &clock).unwrap());
+        clock.advance(1);
+        assert!(limiter.allow_now(129025, &clock).unwrap());
+    }
+
+    #[test]
+    fn test_full_table() {
+        let mut limiter: RateLimiter<1> = RateLimiter::new(1);
+        assert!(limiter.allow(129025, 0).unwrap());
+        assert_eq!(limiter.allow(129026, 0).unwrap_err(), Error::FullTable);
+
+        limiter.clear();
+        assert!(limiter.allow(129026, 0).unwrap());
+    }
+}
@@ -0,0 +1,674 @@
+use crate::nmea_frame::{FastPacketFrame, FastPacketSpec, Nmea2000};
+use crate::nmea_message::{self, FastPacketMessage, FirstFramePolicy};
+use fixed_queue::LinearMap;
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Too many concurrent sessions")]
+    FullTable,
+    #[error("No session in progress")]
+    NoSession,
+    #[error("Payload failed verification")]
+    Corrupted,
+    #[error(transparent)]
+    Message(#[from] nmea_message::Error),
+}
+
+/// Identifies a Fast-Packet session on the bus. The sequence counter is only
+/// part of the key under `FirstFramePolicy::Concurrent` and is 0 otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SessionKey {
+    pub source: u8,
+    pub pgn: u32,
+    pub sequence_counter: u8,
+}
+
+/// What the frame checks shared by `Reassembler` and
+/// `frame_pool::PooledReassembler` need to know of a session, however its
+/// frames are stored.
+pub(crate) trait SessionFrames {
+    /// Frames received so far, the first frame included.
+    fn received(&self) -> usize;
+    /// Frames of the complete message.
+    fn num_frames(&self) -> u8;
+    fn sequence_counter(&self) -> u8;
+    /// When the session started, to take complete sessions oldest first.
+    fn started(&self) -> u32;
+
+    fn is_complete(&self) -> bool {
+        self.received() == self.num_frames() as usize
+    }
+}
+
+/// Checks that a first frame may start a session in place of `session`.
+pub(crate) fn check_first_frame<S: FastPacketSpec>(
+    policy: FirstFramePolicy,
+    session: Option<&impl SessionFrames>,
+    payload: &[u8; 8],
+) -> Result<(), nmea_message::Error> {
+    let frame = FastPacketFrame::<S>::from_bytes(payload);
+    if frame.data_len().unwrap_or(0) > S::MAX_LEN {
+        return Err(nmea_message::Error::InvalidPayloadLength);
+    }
+    if policy == FirstFramePolicy::Error && session.is_some_and(|s| !s.is_complete()) {
+        return Err(nmea_message::Error::UnexpectedFirstFrame { frame: *payload });
+    }
+    Ok(())
+}
+
+/// Checks that a consecutive frame continues `session`. A stray frame for a
+/// complete session fails with `Error::FullQueue` and leaves the message to
+/// be taken; any other error breaks the sequence, which can't be recovered.
+pub(crate) fn check_next_frame<S: FastPacketSpec>(
+    session: &impl SessionFrames,
+    payload: &[u8; 8],
+) -> Result<(), nmea_message::Error> {
+    let frame = FastPacketFrame::<S>::from_bytes(payload);
+    if session.is_complete() {
+        return Err(nmea_message::Error::FullQueue);
+    }
+    if session.sequence_counter() != frame.sequence_counter() {
+        return Err(nmea_message::Error::SequenceCountError {
+            expected: session.sequence_counter(),
+            received: frame.sequence_counter(),
+            frame: *payload,
+        });
+    }
+    let expected = session.received() as u8;
+    if frame.frame_counter() != expected {
+        return Err(nmea_message::Error::SequenceMismatch {
+            expected,
+            received: frame.frame_counter(),
+            frame: *payload,
+        });
+    }
+    Ok(())
+}
+
+/// The key of the oldest complete session for `source` and `pgn`. Fails with
+/// `nmea_message::Error::IncompleteMessage` if the sessions for them are all
+/// still in progress, and with `no_session` if there are none.
+pub(crate) fn complete_session<T: SessionFrames, E: From<nmea_message::Error>, const N: usize>(
+    sessions: &LinearMap<SessionKey, T, N>,
+    source: u8,
+    pgn: u32,
+    no_session: E,
+) -> Result<SessionKey, E> {
+    let matching = || {
+        sessions
+            .iter()
+            .filter(|(k, _)| k.source == source && k.pgn == pgn)
+    };
+    match matching()
+        .filter(|(_, s)| s.is_complete())
+        .min_by_key(|(_, s)| s.started())
+    {
+        Some((key, _)) => Ok(*key),
+        None if matching().next().is_some() => Err(nmea_message::Error::IncompleteMessage.into()),
+        None => Err(no_session),
+    }
+}
+
+/// Which session makes way when a first frame arrives while the table is
+/// full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// Keep the sessions in progress and fail with `Error::FullTable`.
+    #[default]
+    RejectNew,
+    /// Drop the session that started first.
+    Oldest,
+    /// Drop the session that least recently received a frame.
+    LeastRecentlyUsed,
+}
+
+struct Session<S: FastPacketSpec> {
+    msg: FastPacketMessage<S>,
+    started: u32,
+    touched: u32,
+}
+
+impl<S: FastPacketSpec> SessionFrames for Session<S> {
+    fn received(&self) -> usize {
+        self.msg.frames().count()
+    }
+
+    fn num_frames(&self) -> u8 {
+        self.msg.num_frames
+    }
+
+    fn sequence_counter(&self) -> u8 {
+        self.msg.sequence_counter
+    }
+
+    fn started(&self) -> u32 {
+        self.started
+    }
+
+    fn is_complete(&self) -> bool {
+        self.msg.is_complete()
+    }
+}
+
+/// Reassembles concurrent Fast-Packet sessions, one per source address and
+/// PGN, into at most `N` in-progress messages.
+///
+/// The `FirstFramePolicy` chosen at construction decides what happens when a
+/// first frame arrives for a source and PGN that already has a session;
+/// under `Concurrent`, messages with different sequence counters are
+/// assembled side by side.
+///
+/// Memory is fixed by `N`; `with_capacity` lowers the number of sessions
+/// used at run time. Once they are all in use, the `EvictionPolicy` decides
+/// whether a new session replaces one in progress. Rejected and evicted
+/// sessions are counted.
+///
+/// A verifier set with `with_verifier` checks every complete message, such
+/// as a CRC trailing a proprietary payload, and a message failing it is
+/// dropped with `Error::Corrupted`.
+///
+/// `new` is a `const fn`, so a reassembler can live in a `static` (behind the
+/// mutex of the application's framework) without lazy initialization.
+pub struct Reassembler<const N: usize, S: FastPacketSpec = Nmea2000> {
+    sessions: LinearMap<SessionKey, Session<S>, N>,
+    policy: FirstFramePolicy,
+    eviction: EvictionPolicy,
+    capacity: usize,
+    /// Increments with every frame, ordering sessions for eviction.
+    clock: u32,
+    rejected: u32,
+    evicted: u32,
+    verify: Option<fn(u32, &FastPacketMessage<S>) -> bool>,
+    corrupted: u32,
+}
+
+impl<const N: usize, S: FastPacketSpec> Default for Reassembler<N, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, S: FastPacketSpec> Reassembler<N, S> {
+    pub const fn new() -> Self {
+        Self::with_policy(FirstFramePolicy::Restart)
+    }
+
+    pub const fn with_policy(policy: FirstFramePolicy) -> Self {
+        Self {
+            sessions: LinearMap::new(),
+            policy,
+            eviction: EvictionPolicy::RejectNew,
+            capacity: N,
+            clock: 0,
+            rejected: 0,
+            evicted: 0,
+            verify: None,
+            corrupted: 0,
+        }
+    }
+
+    /// Sets how a full table makes room for a new session.
+    pub const fn with_eviction(mut self, eviction: EvictionPolicy) -> Self {
+        self.eviction = eviction;
+        self
+    }
+
+    /// Limits the number of concurrent sessions to `capacity`, at most `N`.
+    pub const fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = if capacity < N { capacity } else { N };
+        self
+    }
+
+    /// Checks complete messages with `verify`, which is given the PGN and
+    /// returns `false` to drop the message. It should return `true` for the
+    /// PGNs it doesn't cover.
+    pub const fn with_verifier(mut self, verify: fn(u32, &FastPacketMessage<S>) -> bool) -> Self {
+        self.verify = Some(verify);
+        self
+    }
+
+    /// Sessions refused because the table was full.
+    pub fn rejected(&self) -> u32 {
+        self.rejected
+    }
+
+    /// Sessions in progress dropped to make room for new ones.
+    pub fn evicted(&self) -> u32 {
+        self.evicted
+    }
+
+    /// Complete messages dropped by the verifier.
+    pub fn corrupted(&self) -> u32 {
+        self.corrupted
+    }
+
+    /// Makes room for a new session, returning `false` if there is none.
+    fn make_room(&mut self) -> bool {
+        if self.sessions.len() < self.capacity {
+            return true;
+        }
+        let victim = match self.eviction {
+            EvictionPolicy::RejectNew => None,
+            EvictionPolicy::Oldest => self.sessions.iter().min_by_key(|(_, s)| s.started),
+            EvictionPolicy::LeastRecentlyUsed => {
+                self.sessions.iter().min_by_key(|(_, s)| s.touched)
+            }
+        }
+        .map(|(key, _)| *key);
+        let Some(key) = victim else {
+            self.rejected += 1;
+            return false;
+        };
+        debug!("pgn {} from {}: session evicted", key.pgn, key.source);
+        self.sessions.remove(&key);
+        self.evicted += 1;
+        true
+    }
+
+    fn key(&self, source: u8, pgn: u32, sequence_counter: u8) -> SessionKey {
+        SessionKey {
+            source,
+            pgn,
+            sequence_counter: match self.policy {
+                FirstFramePolicy::Concurrent => sequence_counter,
+                _ => 0,
+            },
+        }
+    }
+
+    /// Adds a frame received from `source` carrying `pgn`. Returns `true` once
+    /// the session is complete and its payload can be taken with `get_payload`.
+    ///
+    /// A first frame starts a new session. Under `FirstFramePolicy::Error` it
+    /// is rejected if a session for the same source and PGN is still in
+    /// progress; otherwise any such session is discarded. A consecutive
+    /// frame for a complete session that hasn't been taken yet fails with
+    /// `nmea_message::Error::FullQueue` and leaves the session alone.
+    pub fn add_frame(&mut self, source: u8, pgn: u32, payload: &[u8; 8]) -> Result<bool, Error> {
+        let frame = FastPacketFrame::<S>::from_bytes(payload);
+        let key = self.key(source, pgn, frame.sequence_counter());
+        self.clock = self.clock.wrapping_add(1);
+        if frame.is_first_frame() {
+            if let Err(e) = check_first_frame::<S>(self.policy, self.sessions.get(&key), payload) {
+                debug!("pgn {} from {}: {}", pgn, source, e);
+                return Err(e.into());
+            }
+            self.sessions.remove(&key);
+            if !self.make_room() {
+                debug!("pgn {} from {}: no free session", pgn, source);
+                return Err(Error::FullTable);
+            }
+            let session = Session {
+                msg: FastPacketMessage::new(),
+                started: self.clock,
+                touched: self.clock,
+            };
+            if self.sessions.insert(key, session).is_err() {
+                return Err(Error::FullTable);
+            }
+        } else {
+            let session = self.sessions.get(&key).ok_or(Error::NoSession)?;
+            if let Err(e) = check_next_frame::<S>(session, payload) {
+                debug!("pgn {} from {}: {}", pgn, source, e);
+                // A broken sequence can't be recovered; wait for a new first
+                // frame. A stray frame leaves a complete message to be taken.
+                if !session.is_complete() {
+                    self.sessions.remove(&key);
+                }
+                return Err(e.into());
+            }
+        }
+        let session = self.sessions.get_mut(&key).ok_or(Error::NoSession)?;
+        session.touched = self.clock;
+        if !session.msg.add_frame(payload)? {
+            trace!("pgn {} from {}: accepted {:02x?}", pgn, source, payload);
+            return Ok(false);
+        }
+        if self.verify.is_some_and(|verify| !verify(pgn, &session.msg)) {
+            debug!("pgn {} from {}: verification failed", pgn, source);
+            self.sessions.remove(&key);
+            self.corrupted += 1;
+            return Err(Error::Corrupted);
+        }
+        debug!("pgn {} from {}: message complete", pgn, source);
+        Ok(true)
+    }
+
+    /// The key of the session `get_payload` takes for `source` and `pgn`.
+    fn payload_key(&self, source: u8, pgn: u32) -> Result<SessionKey, Error> {
+        complete_session(&self.sessions, source, pgn, Error::NoSession)
+    }
+
+    /// The complete message `get_payload` would take for `source` and `pgn`,
+    /// borrowed in place so its `payload_chunks` can be read without copying
+    /// them.
+    pub fn message(&self, source: u8, pgn: u32) -> Option<&FastPacketMessage<S>> {
+        let key = self.payload_key(source, pgn).ok()?;
+        self.sessions.get(&key).map(|session| &session.msg)
+    }
+
+    /// Copies the payload of a complete session into `buf` and ends the
+    /// session. Under `FirstFramePolicy::Concurrent` the oldest complete
+    /// session for `source` and `pgn` is taken. A session still in progress
+    /// fails with `nmea_message::Error::IncompleteMessage`, and a `buf`
+    /// shorter than the payload with `nmea_message::Error::BufferTooSmall`;
+    /// both keep the session.
+    pub fn get_payload(&mut self, source: u8, pgn: u32, buf: &mut [u8]) -> Result<usize, Error> {
+        let key = self.payload_key(source, pgn)?;
+        let msg = &self.sessions.get(&key).ok_or(Error::NoSession)?.msg;
+        if buf.len() < msg.data_len as usize {
+            return Err(nmea_message::Error::BufferTooSmall.into());
+        }
+        self.with_message(source, pgn, |msg| {
+            let len = msg.copy_payload(buf);
+            buf[len..].fill(0xFF);
+            msg.data_len as usize
+        })
+    }
+
+    /// Appends the payload of a complete session to `out` and ends the
+    /// session, like
+    /// `get_payload` without a fixed-size buffer.
+    #[cfg(any(test, feature = "alloc"))]
+    pub fn assemble_into(
+        &mut self,
+        source: u8,
+        pgn: u32,
+        out: &mut alloc::vec::Vec<u8>,
+    ) -> Result<usize, Error> {
+        self.with_message(source, pgn, |msg| msg.assemble_into(out))
+    }
+
+    /// Passes the complete message `get_payload` would take to `f` and ends
+    /// the session, for decoding the payload in place.
+    pub fn with_message<R>(
+        &mut self,
+        source: u8,
+        pgn: u32,
+        f: impl FnOnce(&FastPacketMessage<S>) -> R,
+    ) -> Result<R, Error> {
+        let key = self.payload_key(source, pgn)?;
+        let session = self.sessions.get(&key).ok_or(Error::NoSession)?;
+        let result = f(&session.msg);
+        self.sessions.remove(&key);
+        Ok(result)
+    }
+
+    /// Abandons the sessions for `source` and `pgn`. Returns `true` if any
+    /// was in progress.
+    pub fn abort(&mut self, source: u8, pgn: u32) -> bool {
+        let mut aborted = false;
+        while let Some(key) = self
+            .sessions
+            .iter()
+            .find(|(k, _)| k.source == source && k.pgn == pgn)
+            .map(|(k, _)| *k)
+        {
+            self.sessions.remove(&key);
+            aborted = true;
+        }
+        aborted
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.sessions = LinearMap::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nmea_message::{Message, MAX_NMEA_PACKET_SIZE};
+
+    static STATIC: Reassembler<4> = Reassembler::new();
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_static_and_send_sync() {
+        assert!(STATIC.sessions.is_empty());
+        assert_send_sync::<Reassembler<4>>();
+        assert_send_sync::<crate::nmea_message::Message>();
+        assert_send_sync::<crate::analyzer::Analyzer<16, 4>>();
+        assert_send_sync::<crate::frame_queue::FrameQueue<16>>();
+        assert_send_sync::<crate::rate_limiter::RateLimiter<4>>();
+    }
+
+    const BUF_1: [u8; 8] = [0x00, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D];
+    const BUF_2: [u8; 8] = [0x01, 0x31, 0xF3, 0xD0, 0xAC, 0xF2, 0x23, 0x1A];
+    const BUF_3: [u8; 8] = [0x02, 0x03, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00];
+    const BUF_4: [u8; 8] = [0x03, 0x20, 0xFF, 0xFF, 0x00, 0x70, 0xFF, 0xFF];
+
+    #[test]
+    fn test_interleaved_sessions() {
+        let mut reassembler: Reassembler<4> = Reassembler::new();
+        assert!(!reassembler.add_frame(1, 129029, &BUF_1).unwrap());
+        assert!(!reassembler.add_frame(2, 129029, &BUF_1).unwrap());
+        assert!(!reassembler.add_frame(1, 129029, &BUF_2).unwrap());
+        assert!(!reassembler.add_frame(2, 129029, &BUF_2).unwrap());
+        assert!(!reassembler.add_frame(1, 129029, &BUF_3).unwrap());
+        assert!(reassembler.add_frame(1, 129029, &BUF_4).unwrap());
+        assert_eq!(reassembler.len(), 2);
+        // A stray frame doesn't discard the complete message.
+        assert_eq!(
+            reassembler.add_frame(1, 129029, &BUF_2),
+            Err(Error::Message(nmea_message::Error::FullQueue))
+        );
+
+        assert_eq!(
+            reassembler.get_payload(1, 129029, &mut [0; 24]),
+            Err(Error::Message(nmea_message::Error::BufferTooSmall))
+        );
+        assert_eq!(reassembler.len(), 2);
+        let mut buf = [0xFF; MAX_NMEA_PACKET_SIZE];
+        assert_eq!(reassembler.get_payload(1, 129029, &mut buf).unwrap(), 25);
+        assert_eq!(buf[..6], BUF_1[2..]);
+        assert_eq!(reassembler.len(), 1);
+        assert!(reassembler.message(2, 129029).is_none());
+        assert!(!reassembler.add_frame(2, 129029, &BUF_3).unwrap());
+        assert!(reassembler.add_frame(2, 129029, &BUF_4).unwrap());
+        assert!(reassembler.message(2, 129029).is_some());
+        let first =
+            reassembler.with_message(2, 129029, |msg| msg.payload_chunks().next().unwrap()[0]);
+        assert_eq!(first, Ok(0x12));
+        assert!(reassembler.is_empty());
+        assert_eq!(
+            reassembler.with_message(2, 129029, |_| ()),
+            Err(Error::NoSession)
+        );
+    }
+
+    #[test]
+    fn test_incomplete_session() {
+        let mut reassembler: Reassembler<4> = Reassembler::new();
+        let mut buf = [0xFF; MAX_NMEA_PACKET_SIZE];
+        assert!(!reassembler.add_frame(1, 129029, &BUF_1).unwrap());
+        assert_eq!(
+            reassembler.get_payload(1, 129029, &mut buf),
+            Err(Error::Message(nmea_message::Error::IncompleteMessage))
+        );
+        assert_eq!(buf, [0xFF; MAX_NMEA_PACKET_SIZE]);
+        assert!(reassembler.message(1, 129029).is_none());
+        assert_eq!(
+            reassembler.with_message(1, 129029, |_| ()),
+            Err(Error::Message(nmea_message::Error::IncompleteMessage))
+        );
+        // The session goes on.
+        assert_eq!(reassembler.len(), 1);
+        assert!(!reassembler.add_frame(1, 129029, &BUF_2).unwrap());
+        assert!(!reassembler.add_frame(1, 129029, &BUF_3).unwrap());
+        assert!(reassembler.add_frame(1, 129029, &BUF_4).unwrap());
+        assert_eq!(reassembler.get_payload(1, 129029, &mut buf), Ok(25));
+    }
+
+    #[test]
+    fn test_abort() {
+        let mut reassembler: Reassembler<4> = Reassembler::new();
+        assert!(!reassembler.add_frame(1, 129029, &BUF_1).unwrap());
+        assert!(reassembler.abort(1, 129029));
+        assert!(!reassembler.abort(1, 129029));
+        assert!(reassembler.is_empty());
+        assert_eq!(
+            reassembler.add_frame(1, 129029, &BUF_2).unwrap_err(),
+            Error::NoSession
+        );
+    }
+
+    #[test]
+    fn test_first_frame_policy() {
+        // BUF_1 and BUF_2 again under sequence counter 1.
+        let seq_1: [u8; 8] = [0x20, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D];
+        let seq_1_2: [u8; 8] = [0x21, 0x31, 0xF3, 0xD0, 0xAC, 0xF2, 0x23, 0x1A];
+
+        let mut reassembler: Reassembler<4> = Reassembler::with_policy(FirstFramePolicy::Error);
+        assert!(!reassembler.add_frame(1, 129029, &BUF_1).unwrap());
+        assert_eq!(
+            reassembler.add_frame(1, 129029, &seq_1).unwrap_err(),
+            Error::Message(nmea_message::Error::UnexpectedFirstFrame { frame: seq_1 })
+        );
+        assert!(!reassembler.add_frame(1, 129029, &BUF_2).unwrap());
+
+        let mut reassembler: Reassembler<4> =
+            Reassembler::with_policy(FirstFramePolicy::Concurrent);
+        assert!(!reassembler.add_frame(1, 129029, &BUF_1).unwrap());
+        assert!(!reassembler.add_frame(1, 129029, &seq_1).unwrap());
+        assert!(!reassembler.add_frame(1, 129029, &seq_1_2).unwrap());
+        assert!(!reassembler.add_frame(1, 129029, &BUF_2).unwrap());
+        assert!(!reassembler.add_frame(1, 129029, &BUF_3).unwrap());
+        assert!(reassembler.add_frame(1, 129029, &BUF_4).unwrap());
+        assert_eq!(reassembler.len(), 2);
+
+        let mut buf = [0xFF; MAX_NMEA_PACKET_SIZE];
+        assert_eq!(reassembler.get_payload(1, 129029, &mut buf).unwrap(), 25);
+        assert_eq!(
+            reassembler.get_payload(1, 129029, &mut buf).unwrap_err(),
+            Error::Message(nmea_message::Error::IncompleteMessage)
+        );
+        assert!(reassembler.abort(1, 129029));
+        assert!(reassembler.is_empty());
+
+        // Complete messages are taken oldest first, even after another
+        // session was removed from the table.
+        let first = Message::from_payload(&[1, 2, 3], 0).unwrap().pop_frame();
+        let second = Message::from_payload(&[4, 5, 6], 1).unwrap().pop_frame();
+        assert!(!reassembler.add_frame(2, 129029, &BUF_1).unwrap());
+        assert!(reassembler
+            .add_frame(1, 129029, &first.unwrap().bytes)
+            .unwrap());
+        assert!(reassembler
+            .add_frame(1, 129029, &second.unwrap().bytes)
+            .unwrap());
+        assert!(reassembler.abort(2, 129029));
+        assert_eq!(reassembler.get_payload(1, 129029, &mut buf), Ok(3));
+        assert_eq!(buf[..3], [1, 2, 3]);
+        assert_eq!(reassembler.get_payload(1, 129029, &mut buf), Ok(3));
+        assert_eq!(buf[..3], [4, 5, 6]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut reassembler: Reassembler<1> = Reassembler::new();
+        assert!(!reassembler.add_frame(1, 129029, &BUF_1).unwrap());
+        assert_eq!(
+            reassembler.add_frame(2, 129029, &BUF_1).unwrap_err(),
+            Error::FullTable
+        );
+        assert_eq!(reassembler.rejected(), 1);
+        // Sequence errors end the session.
+        assert_eq!(
+            reassembler.add_frame(1, 129029, &BUF_3).unwrap_err(),
+            Error::Message(nmea_message::Error::SequenceMismatch {
+                expected: 1,
+                received: 2,
+                frame: BUF_3,
+            })
+        );
+        assert!(reassembler.is_empty());
+    }
+
+    #[test]
+    fn test_eviction() {
+        let mut buf = [0xFF; MAX_NMEA_PACKET_SIZE];
+
+        let mut reassembler: Reassembler<4> = Reassembler::new()
+            .with_eviction(EvictionPolicy::Oldest)
+            .with_capacity(2);
+        assert!(!reassembler.add_frame(1, 129029, &BUF_1).unwrap());
+        assert!(!reassembler.add_frame(2, 129029, &BUF_1).unwrap());
+        assert!(!reassembler.add_frame(1, 129029, &BUF_2).unwrap());
+        // Source 1 started first.
+        assert!(!reassembler.add_frame(3, 129029, &BUF_1).unwrap());
+        assert_eq!(reassembler.len(), 2);
+        assert_eq!(reassembler.evicted(), 1);
+        assert_eq!(
+            reassembler.add_frame(1, 129029, &BUF_3).unwrap_err(),
+            Error::NoSession
+        );
+
+        let mut reassembler: Reassembler<2> =
+            Reassembler::new().with_eviction(EvictionPolicy::LeastRecentlyUsed);
+        assert!(!reassembler.add_frame(1, 129029, &BUF_1).unwrap());
+        assert!(!reassembler.add_frame(2, 129029, &BUF_1).unwrap());
+        assert!(!reassembler.add_frame(1, 129029, &BUF_2).unwrap());
+        // Source 2 was idle longest.
+        assert!(!reassembler.add_frame(3, 129029, &BUF_1).unwrap());
+        assert!(!reassembler.add_frame(1, 129029, &BUF_3).unwrap());
+        assert!(reassembler.add_frame(1, 129029, &BUF_4).unwrap());
+        assert_eq!(reassembler.get_payload(1, 129029, &mut buf).unwrap(), 25);
+        assert_eq!(
+            reassembler.get_payload(2, 129029, &mut buf).unwrap_err(),
+            Error::NoSession
+        );
+        assert_eq!((reassembler.evicted(), reassembler.rejected()), (1, 0));
+    }
+
+    #[test]
+    fn test_verifier() {
+        use crate::integrity::crc32;
+
+        // A proprietary PGN ending in the CRC-32 of the rest of the payload.
+        fn verify(pgn: u32, msg: &Message) -> bool {
+            let mut buf = [0; MAX_NMEA_PACKET_SIZE];
+            let len = msg.copy_payload(&mut buf);
+            pgn != 130820 || len >= 4 && buf[len - 4..len] == crc32(&buf[..len - 4]).to_le_bytes()
+        }
+        let mut payload = [0x5A; 20];
+        let crc = crc32(&payload[..16]).to_le_bytes();
+        payload[16..].copy_from_slice(&crc);
+
+        let mut reassembler: Reassembler<2> = Reassembler::new().with_verifier(verify);
+        let mut buf = [0xFF; MAX_NMEA_PACKET_SIZE];
+        for corrupt in [false, true] {
+            let mut msg = Message::from_payload(&payload, 0).unwrap();
+            let mut result = Ok(false);
+            while let Some(mut frame) = msg.pop_frame() {
+                if corrupt {
+                    frame.bytes[7] ^= 1;
+                }
+                result = reassembler.add_frame(7, 130820, &frame.bytes);
+            }
+            if corrupt {
+                assert_eq!(result, Err(Error::Corrupted));
+                assert!(reassembler.is_empty());
+            } else {
+                assert_eq!(result, Ok(true));
+                assert_eq!(reassembler.get_payload(7, 130820, &mut buf), Ok(20));
+            }
+        }
+        assert_eq!(reassembler.corrupted(), 1);
+        // Other PGNs pass unchecked.
+        for frame in [BUF_1, BUF_2, BUF_3, BUF_4] {
+            reassembler.add_frame(7, 129029, &frame).unwrap();
+        }
+        assert_eq!(reassembler.get_payload(7, 129029, &mut buf), Ok(25));
+    }
+}
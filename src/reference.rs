@@ -0,0 +1,111 @@
+//! Allocating reference model of the Fast-Packet encoding.
+//!
+//! These functions favour obviousness over performance and are intended as a
+//! test oracle for the fixed-capacity `Message` implementation, both in this
+//! crate and in downstream property tests.
+use crate::nmea_message::{Assembling, Error, Message, Progress, MAX_NMEA_PACKET_SIZE};
+use alloc::vec::Vec;
+
+/// Splits `payload` into 8-byte CAN frames, padding the last frame with 0xFF.
+pub fn encode(payload: &[u8], sequence_counter: u8) -> Vec<[u8; 8]> {
+    let mut frames = Vec::new();
+    let (head, tail) = payload.split_at(payload.len().min(6));
+
+    let mut first = [0xFF; 8];
+    first[0] = sequence_counter << 5;
+    first[1] = payload.len() as u8;
+    first[2..2 + head.len()].copy_from_slice(head);
+    frames.push(first);
+
+    for (i, chunk) in tail.chunks(7).enumerate() {
+        let mut frame = [0xFF; 8];
+        frame[0] = (sequence_counter << 5) | (i as u8 + 1);
+        frame[1..1 + chunk.len()].copy_from_slice(chunk);
+        frames.push(frame);
+    }
+    frames
+}
+
+/// Reassembles a complete, in-order frame sequence. Returns `None` if the
+/// counters are inconsistent or the number of frames does not match the
+/// length announced in the first frame.
+pub fn decode(frames: &[[u8; 8]]) -> Option<Vec<u8>> {
+    let (first, rest) = frames.split_first()?;
+    if first[0] & 0x1F != 0 {
+        return None;
+    }
+    let sequence_counter = first[0] >> 5;
+    let len = first[1] as usize;
+    if rest.len() != len.saturating_sub(6).div_ceil(7) {
+        return None;
+    }
+
+    let mut payload = first[2..].to_vec();
+    for (i, frame) in rest.iter().enumerate() {
+        if frame[0] >> 5 != sequence_counter || (frame[0] & 0x1F) as usize != i + 1 {
+            return None;
+        }
+        payload.extend_from_slice(&frame[1..]);
+    }
+    payload.truncate(len);
+    Some(payload)
+}
+
+/// Encodes `payload` with `Message::from_payload`, feeds the frames through
+/// `Assembling::add_frame` and returns the reassembled payload.
+pub fn roundtrip(payload: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut encoder = Message::from_payload(payload, 0)?;
+    let mut decoder: Assembling = Assembling::new();
+    while let Some(frame) = encoder.pop_frame() {
+        decoder = match decoder.add_frame(&frame.bytes).map_err(|(_, e)| e)? {
+            Progress::Assembling(decoder) => decoder,
+            Progress::Complete(complete) => {
+                let mut buf = [0xFF; MAX_NMEA_PACKET_SIZE];
+                let len = complete.get_payload(&mut buf)?;
+                return Ok(buf[..len].to_vec());
+            }
+        };
+    }
+    Err(Error::IncompleteMessage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_model() {
+        let buf_1: [u8; 8] = [0x00, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D];
+        let buf_2: [u8; 8] = [0x01, 0x31, 0xF3, 0xD0, 0xAC, 0xF2, 0x23, 0x1A];
+        let buf_3: [u8; 8] = [0x02, 0x03, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00];
+        let buf_4: [u8; 8] = [0x03, 0x20, 0xFF, 0xFF, 0x00, 0x70, 0xFF, 0xFF];
+        let frames = [buf_1, buf_2, buf_3, buf_4];
+
+        let payload = decode(&frames).unwrap();
+        assert_eq!(payload.len(), 25);
+        assert_eq!(encode(&payload, 0), frames);
+
+        // Missing and out-of-order frames are rejected.
+        assert_eq!(decode(&frames[..3]), None);
+        assert_eq!(decode(&[buf_1, buf_3, buf_2, buf_4]), None);
+    }
+
+    #[test]
+    fn test_against_message() {
+        for payload_length in 1..=MAX_NMEA_PACKET_SIZE {
+            let mut payload = [0u8; MAX_NMEA_PACKET_SIZE];
+            rand::fill(&mut payload[..payload_length]);
+            let payload = &payload[..payload_length];
+            let sequence_counter = (payload_length % 8) as u8;
+
+            let mut msg = Message::from_payload(payload, sequence_counter).unwrap();
+            let mut frames = Vec::new();
+            while let Some(frame) = msg.pop_frame() {
+                frames.push(frame.bytes);
+            }
+            assert_eq!(frames, encode(payload, sequence_counter));
+            assert_eq!(decode(&frames).unwrap(), payload);
+            assert_eq!(roundtrip(payload).unwrap(), payload);
+        }
+    }
+}
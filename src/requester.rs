@@ -0,0 +1,211 @@
+//! Requests configuration PGNs from devices as they appear on the bus.
+use crate::can_id::CanId;
+use crate::pgn::iso_request::IsoRequest;
+use fixed_queue::LinearMap;
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Too many outstanding requests")]
+    FullTable,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Outstanding {
+    due_ms: u64,
+    attempts: u8,
+}
+
+/// Sends ISO Requests for a fixed set of PGNs (e.g. Product Information
+/// 126996 or PGN List 126464) to every source address seen on the bus.
+///
+/// Every frame is passed to `observe`. The first frame from an address
+/// schedules one request per PGN, and a frame of a requested PGN from that
+/// address answers it. Unanswered requests are repeated after `retry_ms`,
+/// doubling the delay each time, until `max_attempts` have been sent. Time
+/// is supplied by the caller as monotonic milliseconds. `N` is the number of
+/// requests that can be outstanding at once.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::can_id::CanId;
+/// use nmea::requester::Requester;
+///
+/// let mut requester: Requester<8> = Requester::new(&[126996]);
+/// let heading = CanId::new(2, 127250, 0x23, 255).unwrap();
+/// requester.observe(heading, 0).unwrap();
+///
+/// let request = requester.poll(0).unwrap();
+/// assert_eq!((request.destination, request.pgn), (0x23, 126996));
+/// assert_eq!(requester.poll(0), None);
+/// ```
+pub struct Requester<const N: usize> {
+    pgns: &'static [u32],
+    retry_ms: u64,
+    max_attempts: u8,
+    known: [u32; 8],
+    outstanding: LinearMap<(u8, u32), Outstanding, N>,
+    failed: u32,
+}
+
+impl<const N: usize> Requester<N> {
+    pub const fn new(pgns: &'static [u32]) -> Self {
+        Self::with_retry(pgns, 1000, 3)
+    }
+
+    pub const fn with_retry(pgns: &'static [u32], retry_ms: u64, max_attempts: u8) -> Self {
+        Self {
+            pgns,
+            retry_ms,
+            max_attempts,
+            known: [0; 8],
+            outstanding: LinearMap::new(),
+            failed: 0,
+        }
+    }
+
+    fn is_known(&self, address: u8) -> bool {
+        self.known[address as usize / 32] & (1 << (address % 32)) != 0
+    }
+
+    /// Processes a received frame. Fails if a new device needs more requests
+    /// than there is room for; it is then retried on its next frame.
+    pub fn observe(&mut self, id: CanId, now_ms: u64) -> Result<(), Error> {
+        let source = id.source();
+        // Addresses 254 (cannot claim) and 255 (global) aren't devices.
+        if source >= 254 {
+            return Ok(());
+        }
+        self.outstanding.remove(&(source, id.pgn()));
+        if self.is_known(source) {
+            return Ok(());
+        }
+        for &pgn in self.pgns {
+            if self.outstanding.get(&(source, pgn)).is_some() {
+                continue;
+            }
+            let request = Outstanding {
+                due_ms: now_ms,
+                attempts: 0,
+            };
+            if self.outstanding.insert((source, pgn), request).is_err() {
+                return Err(Error::FullTable);
+            }
+        }
+        self.known[source as usize / 32] |= 1 << (source % 32);
+        Ok(())
+    }
+
+    /// Returns the next request to send, if one is due.
+    pub fn poll(&mut self, now_ms: u64) -> Option<IsoRequest> {
+        loop {
+            let (&key, request) = self
+                .outstanding
+                .iter_mut()
+                .map(|(key, request)| (&*key, request))
+                .find(|(_, request)| request.due_ms <= now_ms)?;
+            if request.attempts >= self.max_attempts {
+                self.outstanding.remove(&key);
+                self.failed += 1;
+                continue;
+            }
+            request.due_ms = now_ms + (self.retry_ms << request.attempts);
+            request.attempts += 1;
+            return Some(IsoRequest {
+                destination: key.0,
+                pgn: key.1,
+            });
+        }
+    }
+
+    /// Forgets `address`, e.g. after it lost its address claim. Its requests
+    /// are sent again when it reappears.
+    pub fn forget(&mut self, address: u8) {
+        self.known[address as usize / 32] &= !(1 << (address % 32));
+        while let Some(key) = self
+            .outstanding
+            .iter()
+            .map(|(key, _)| *key)
+            .find(|key| key.0 == address)
+        {
+            self.outstanding.remove(&key);
+        }
+    }
+
+    pub fn outstanding(&self) -> usize {
+        self.outstanding.len()
+    }
+
+    /// Number of requests abandoned after `max_attempts`.
+    pub fn failed(&self) -> u32 {
+        self.failed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PGNS: &[u32] = &[126996, 126464];
+
+    fn id(pgn: u32, source: u8) -> CanId {
+        CanId::new(6, pgn, source, 255).unwrap()
+    }
+
+    #[test]
+    fn test_discovery_and_response() {
+        let mut requester: Requester<4> = Requester::new(PGNS);
+        requester.observe(id(127250, 0x23), 0).unwrap();
+        assert_eq!(requester.outstanding(), 2);
+        assert_eq!(
+            requester.poll(0),
+            Some(IsoRequest {
+                destination: 0x23,
+                pgn: 126996
+            })
+        );
+        assert_eq!(requester.poll(0).unwrap().pgn, 126464);
+        assert_eq!(requester.poll(0), None);
+
+        // Answers clear the request; further frames don't schedule more.
+        requester.observe(id(126996, 0x23), 10).unwrap();
+        requester.observe(id(127250, 0x23), 10).unwrap();
+        assert_eq!(requester.outstanding(), 1);
+
+        // The product information request is retried on reappearance only.
+        requester.forget(0x23);
+        assert_eq!(requester.outstanding(), 0);
+        requester.observe(id(127250, 0x23), 20).unwrap();
+        assert_eq!(requester.outstanding(), 2);
+    }
+
+    #[test]
+    fn test_backoff() {
+        let mut requester: Requester<4> = Requester::with_retry(&[126996], 100, 3);
+        requester.observe(id(127250, 0x10), 0).unwrap();
+        assert!(requester.poll(0).is_some());
+        assert_eq!(requester.poll(99), None);
+        assert!(requester.poll(100).is_some());
+        assert_eq!(requester.poll(299), None);
+        assert!(requester.poll(300).is_some());
+        assert_eq!(requester.poll(10_000), None);
+        assert_eq!(requester.outstanding(), 0);
+        assert_eq!(requester.failed(), 1);
+    }
+
+    #[test]
+    fn test_full_table() {
+        let mut requester: Requester<3> = Requester::new(PGNS);
+        requester.observe(id(127250, 0x10), 0).unwrap();
+        assert_eq!(
+            requester.observe(id(127250, 0x11), 0),
+            Err(Error::FullTable)
+        );
+        requester.observe(id(126996, 0x10), 0).unwrap();
+        requester.observe(id(126464, 0x10), 0).unwrap();
+        // 0x11 is picked up again on its next frame.
+        requester.observe(id(127250, 0x11), 0).unwrap();
+        assert_eq!(requester.outstanding(), 2);
+    }
+}
@@ -0,0 +1,193 @@
+//! Resampling of irregular decoded values onto a fixed interval, e.g. for
+//! CSV export.
+use alloc::vec::Vec;
+
+/// How the value at each sample time is derived.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    /// The latest value at or before the sample time.
+    Last,
+    /// The mean of the values in the interval starting at the sample time.
+    Mean,
+    /// Interpolated between the values either side of the sample time.
+    Linear,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+    pub timestamp_ms: u64,
+    /// `None` when there is no data to derive the value from.
+    pub value: Option<f64>,
+}
+
+/// Turns a stream of `(timestamp, value)` points into samples every
+/// `interval_ms`, aligned to multiples of the interval.
+///
+/// Points must arrive in time order; older ones are ignored. A sample is
+/// emitted once a point at or after the end of its interval arrives, or by
+/// `finish`.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::resample::{Method, Resampler};
+///
+/// let mut resampler = Resampler::new(1000, Method::Linear);
+/// assert!(resampler.push(0, 1.0).is_empty());
+/// let samples = resampler.push(2000, 3.0);
+/// assert_eq!(samples[0].value, Some(1.0));
+/// assert_eq!(samples[1].value, Some(2.0));
+/// ```
+pub struct Resampler {
+    interval_ms: u64,
+    method: Method,
+    /// Start of the interval being filled.
+    next_ms: Option<u64>,
+    /// Latest point overall.
+    last: Option<(u64, f64)>,
+    /// Latest point at or before the start of the interval.
+    before: Option<(u64, f64)>,
+    /// First point after the start of the interval.
+    after: Option<(u64, f64)>,
+    sum: f64,
+    count: u32,
+}
+
+impl Resampler {
+    pub const fn new(interval_ms: u64, method: Method) -> Self {
+        Self {
+            interval_ms,
+            method,
+            next_ms: None,
+            last: None,
+            before: None,
+            after: None,
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    /// Adds a point and returns the samples it completes.
+    pub fn push(&mut self, timestamp_ms: u64, value: f64) -> Vec<Sample> {
+        let mut samples = Vec::new();
+        if self.last.is_some_and(|(last, _)| timestamp_ms < last) {
+            return samples;
+        }
+        let mut next_ms = *self
+            .next_ms
+            .get_or_insert(timestamp_ms - timestamp_ms % self.interval_ms);
+        while timestamp_ms >= next_ms + self.interval_ms {
+            samples.push(self.sample(next_ms, Some((timestamp_ms, value))));
+            next_ms += self.interval_ms;
+            self.start(next_ms);
+        }
+
+        if timestamp_ms == next_ms {
+            self.before = Some((timestamp_ms, value));
+        } else if self.after.is_none() {
+            self.after = Some((timestamp_ms, value));
+        }
+        self.sum += value;
+        self.count += 1;
+        self.last = Some((timestamp_ms, value));
+        samples
+    }
+
+    /// Returns the sample of the interval in progress, if any, and resets
+    /// the resampler.
+    pub fn finish(&mut self) -> Option<Sample> {
+        let sample = self.sample(self.next_ms?, None);
+        *self = Self::new(self.interval_ms, self.method);
+        Some(sample)
+    }
+
+    fn start(&mut self, next_ms: u64) {
+        self.next_ms = Some(next_ms);
+        self.before = self.last;
+        self.after = None;
+        self.sum = 0.0;
+        self.count = 0;
+    }
+
+    /// The sample at `timestamp_ms`, with `next` the point that ended the
+    /// interval.
+    fn sample(&self, timestamp_ms: u64, next: Option<(u64, f64)>) -> Sample {
+        let value = match self.method {
+            Method::Last => self.before.map(|(_, v)| v),
+            Method::Mean => (self.count > 0).then(|| self.sum / self.count as f64),
+            Method::Linear => match (self.before, self.after.or(next)) {
+                (Some((t, v)), _) if t == timestamp_ms => Some(v),
+                (Some((t0, v0)), Some((t1, v1))) => {
+                    let f = (timestamp_ms - t0) as f64 / (t1 - t0) as f64;
+                    Some(v0 + (v1 - v0) * f)
+                }
+                _ => None,
+            },
+        };
+        Sample {
+            timestamp_ms,
+            value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(method: Method, points: &[(u64, f64)]) -> Vec<Option<f64>> {
+        let mut resampler = Resampler::new(100, method);
+        let mut samples = Vec::new();
+        for &(t, v) in points {
+            samples.extend(resampler.push(t, v));
+        }
+        samples.extend(resampler.finish());
+        for (i, sample) in samples.iter().enumerate() {
+            assert_eq!(sample.timestamp_ms, 100 + 100 * i as u64);
+        }
+        samples.iter().map(|sample| sample.value).collect()
+    }
+
+    const POINTS: &[(u64, f64)] = &[(150, 1.0), (180, 3.0), (250, 5.0), (420, 2.0)];
+
+    #[test]
+    fn test_last() {
+        assert_eq!(
+            run(Method::Last, POINTS),
+            [None, Some(3.0), Some(5.0), Some(5.0)]
+        );
+    }
+
+    #[test]
+    fn test_mean() {
+        assert_eq!(
+            run(Method::Mean, POINTS),
+            [Some(2.0), Some(5.0), None, Some(2.0)]
+        );
+    }
+
+    #[test]
+    fn test_linear() {
+        let expected = [
+            None,
+            Some(3.0 + 2.0 * 20.0 / 70.0),
+            Some(5.0 - 3.0 * 50.0 / 170.0),
+            Some(5.0 - 3.0 * 150.0 / 170.0),
+        ];
+        let values = run(Method::Linear, POINTS);
+        assert_eq!(values.len(), expected.len());
+        for (value, expected) in values.iter().zip(expected) {
+            assert_eq!(value.is_some(), expected.is_some());
+            assert!((value.unwrap_or(0.0) - expected.unwrap_or(0.0)).abs() < 1e-9);
+        }
+
+        // Exact hits and out of order points.
+        assert_eq!(
+            run(
+                Method::Linear,
+                &[(100, 1.0), (90, 7.0), (200, 2.0), (300, 4.0)]
+            ),
+            [Some(1.0), Some(2.0), Some(4.0)]
+        );
+    }
+}
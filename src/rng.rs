@@ -0,0 +1,42 @@
+//! Pseudo-random numbers for fault injection, simulation and address claim
+//! delays.
+
+/// xorshift64*, enough to make faults and simulations reproducible from a
+/// seed.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) const fn new(seed: u64) -> Self {
+        // The state must not be zero.
+        Self(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    pub(crate) fn chance(&mut self, probability: f32) -> bool {
+        probability > 0.0 && self.unit() < probability
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Uniform in `[0, 1)`.
+    #[cfg(any(test, feature = "testing"))]
+    pub(crate) fn unit(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// In `(-1, 1)`, more likely near 0.
+    #[cfg(any(test, feature = "sim"))]
+    pub(crate) fn noise(&mut self) -> f32 {
+        self.unit() + self.unit() - 1.0
+    }
+}
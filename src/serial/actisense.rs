@@ -0,0 +1,236 @@
+//! Actisense NGT-1 binary protocol.
+//!
+//! Frames are `DLE STX command length data.. checksum DLE ETX`, with `DLE`
+//! bytes inside the frame doubled. Received NMEA2000 messages (command
+//! `0x93`) are decoded; other commands are skipped.
+use super::Decoder;
+use crate::nmea_message::MAX_NMEA_PACKET_SIZE;
+
+const DLE: u8 = 0x10;
+const STX: u8 = 0x02;
+const ETX: u8 = 0x03;
+const N2K_MESSAGE_RECEIVED: u8 = 0x93;
+/// Command, length, data and checksum.
+const MAX_FRAME: usize = 258;
+/// Priority, PGN, destination, source, timestamp and payload length.
+const HEADER_LEN: usize = 11;
+
+/// A complete message received by the gateway.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Message {
+    pub priority: u8,
+    pub pgn: u32,
+    pub destination: u8,
+    pub source: u8,
+    /// Gateway timestamp in milliseconds.
+    pub timestamp_ms: u32,
+    len: usize,
+    payload: [u8; MAX_NMEA_PACKET_SIZE],
+}
+
+impl Message {
+    pub fn payload(&self) -> &[u8] {
+        &self.payload[..self.len]
+    }
+}
+
+/// Decodes NGT-1 frames into messages.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::serial::actisense::ActisenseDecoder;
+/// use nmea::serial::Decoder;
+///
+/// let mut decoder = ActisenseDecoder::new();
+/// let bytes = [
+///     0x10, 0x02, 0x93, 0x0E, 0x02, 0x01, 0xF8, 0x01, 0xFF, 0x23, 0xE8, 0x03, 0x00, 0x00,
+///     0x03, 0x01, 0x10, 0x10, 0xFF, 0x43, 0x10, 0x03,
+/// ];
+/// assert!(decoder.push_bytes(&bytes[..9]).next().is_none());
+/// let message = decoder.push_bytes(&bytes[9..]).next().unwrap();
+/// assert_eq!((message.pgn, message.source), (129025, 0x23));
+/// assert_eq!(message.payload(), [0x01, 0x10, 0xFF]);
+/// ```
+pub struct ActisenseDecoder {
+    frame: [u8; MAX_FRAME],
+    len: usize,
+    in_frame: bool,
+    escape: bool,
+    errors: u32,
+}
+
+impl Default for ActisenseDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ActisenseDecoder {
+    pub const fn new() -> Self {
+        Self {
+            frame: [0; MAX_FRAME],
+            len: 0,
+            in_frame: false,
+            escape: false,
+            errors: 0,
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        if self.len == MAX_FRAME {
+            self.errors += 1;
+            self.in_frame = false;
+        } else {
+            self.frame[self.len] = byte;
+            self.len += 1;
+        }
+    }
+
+    /// Decodes the frame collected so far, counting it as an error if it
+    /// is malformed.
+    fn decode(&mut self) -> Option<Message> {
+        let frame = &self.frame[..self.len];
+        let valid = frame.len() >= 3
+            && frame[1] as usize == frame.len() - 3
+            && frame.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) == 0;
+        if !valid {
+            self.errors += 1;
+            return None;
+        }
+        if frame[0] != N2K_MESSAGE_RECEIVED {
+            return None;
+        }
+        let data = &frame[2..frame.len() - 1];
+        let len = *data.get(HEADER_LEN - 1)? as usize;
+        if data.len() != HEADER_LEN + len || len > MAX_NMEA_PACKET_SIZE {
+            self.errors += 1;
+            return None;
+        }
+        let mut payload = [0xFF; MAX_NMEA_PACKET_SIZE];
+        payload[..len].copy_from_slice(&data[HEADER_LEN..]);
+        Some(Message {
+            priority: data[0],
+            pgn: u32::from_le_bytes([data[1], data[2], data[3], 0]),
+            destination: data[4],
+            source: data[5],
+            timestamp_ms: u32::from_le_bytes([data[6], data[7], data[8], data[9]]),
+            len,
+            payload,
+        })
+    }
+}
+
+impl Decoder for ActisenseDecoder {
+    type Record = Message;
+
+    fn push_byte(&mut self, byte: u8) -> Option<Message> {
+        if !self.escape {
+            if byte == DLE {
+                self.escape = true;
+            } else if self.in_frame {
+                self.push(byte);
+            }
+            return None;
+        }
+
+        self.escape = false;
+        match byte {
+            STX => {
+                if self.in_frame {
+                    // The previous frame never ended.
+                    self.errors += 1;
+                }
+                self.in_frame = true;
+                self.len = 0;
+            }
+            ETX if self.in_frame => {
+                self.in_frame = false;
+                return self.decode();
+            }
+            DLE if self.in_frame => self.push(DLE),
+            _ if self.in_frame => {
+                self.errors += 1;
+                self.in_frame = false;
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn errors(&self) -> u32 {
+        self.errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn encode(command: u8, data: &[u8]) -> Vec<u8> {
+        let mut body = Vec::from([command, data.len() as u8]);
+        body.extend_from_slice(data);
+        let sum = body.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+        body.push(sum.wrapping_neg());
+
+        let mut frame = Vec::from([DLE, STX]);
+        for byte in body {
+            frame.push(byte);
+            if byte == DLE {
+                frame.push(DLE);
+            }
+        }
+        frame.extend_from_slice(&[DLE, ETX]);
+        frame
+    }
+
+    fn message(payload: &[u8]) -> Vec<u8> {
+        let mut data = Vec::from([2, 0x12, 0xF1, 0x01, 0xFF, 0x10, 0x10, 0x27, 0, 0]);
+        data.push(payload.len() as u8);
+        data.extend_from_slice(payload);
+        encode(N2K_MESSAGE_RECEIVED, &data)
+    }
+
+    #[test]
+    fn test_chunks() {
+        let mut input = Vec::from(*b"noise");
+        input.extend(message(&[0x10; 8]));
+        input.extend(encode(0xA0, &[1, 2, 3]));
+        input.extend(message(&[0xAB; 100]));
+        for chunk_len in 1..input.len() {
+            let mut decoder = ActisenseDecoder::new();
+            let mut messages = Vec::new();
+            for chunk in input.chunks(chunk_len) {
+                messages.extend(decoder.push_bytes(chunk));
+            }
+            assert_eq!(decoder.errors(), 0);
+            assert_eq!(messages.len(), 2);
+            assert_eq!(messages[0].pgn, 127250);
+            assert_eq!(messages[0].source, 0x10);
+            assert_eq!(messages[0].timestamp_ms, 10000);
+            assert_eq!(messages[0].payload(), [0x10; 8]);
+            assert_eq!(messages[1].payload(), [0xAB; 100]);
+        }
+    }
+
+    #[test]
+    fn test_resync() {
+        let good = message(&[1, 2, 3]);
+        let mut bad_checksum = good.clone();
+        bad_checksum[5] ^= 1;
+        let mut input = Vec::new();
+        // A frame cut short by the next one.
+        input.extend_from_slice(&good[..8]);
+        input.extend(bad_checksum);
+        // An invalid escape.
+        input.extend_from_slice(&[DLE, STX, 0x93, DLE, 0x55]);
+        input.extend(good);
+
+        let mut decoder = ActisenseDecoder::new();
+        let messages: Vec<Message> = decoder.push_bytes(&input).collect();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].payload(), [1, 2, 3]);
+        assert_eq!(decoder.errors(), 3);
+    }
+}
@@ -0,0 +1,28 @@
+//! Incremental decoders for serial CAN gateways.
+//!
+//! Serial reads return whatever bytes have arrived, so decoders are fed
+//! chunks of any size and keep partial records between calls. Malformed
+//! records are dropped and counted, and decoding picks up again at the next
+//! record boundary.
+pub mod actisense;
+pub mod slcan;
+
+/// A push-based decoder of a serial format.
+pub trait Decoder {
+    type Record;
+
+    /// Feeds a single byte, returning the record it completes, if any.
+    fn push_byte(&mut self, byte: u8) -> Option<Self::Record>;
+
+    /// Number of malformed records dropped so far.
+    fn errors(&self) -> u32;
+
+    /// Feeds a chunk of bytes, yielding the records it completes. Bytes
+    /// not consumed because the iterator is dropped early are lost.
+    fn push_bytes<'a>(&'a mut self, bytes: &'a [u8]) -> impl Iterator<Item = Self::Record> + 'a
+    where
+        Self: Sized,
+    {
+        bytes.iter().filter_map(move |&byte| self.push_byte(byte))
+    }
+}
@@ -0,0 +1,169 @@
+//! SLCAN (Lawicel) ASCII protocol, as spoken by CANable and similar USB
+//! adapters.
+//!
+//! Only extended data frames (`Tiiiiiiiildd..[tttt]\r`) are decoded; other
+//! responses, such as standard frames and command acknowledgements, are
+//! skipped.
+use super::Decoder;
+use crate::can_id::CanId;
+
+/// Longest extended frame line: `T`, 8 id digits, length, 16 data digits
+/// and a 4 digit timestamp.
+const MAX_LINE: usize = 30;
+
+/// An extended frame received by the adapter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub id: CanId,
+    pub data: [u8; 8],
+    pub len: usize,
+    /// Adapter timestamp in milliseconds, wrapping at 60000, if enabled.
+    pub timestamp_ms: Option<u16>,
+}
+
+impl Frame {
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+}
+
+/// Decodes SLCAN lines into frames.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::serial::slcan::SlcanDecoder;
+/// use nmea::serial::Decoder;
+///
+/// let mut decoder = SlcanDecoder::new();
+/// assert_eq!(decoder.push_bytes(b"T1DFF040A3E017").count(), 0);
+/// let frame = decoder.push_bytes(b"A3\r").next().unwrap();
+/// assert_eq!(frame.id.pgn(), 130820);
+/// assert_eq!(frame.data(), [0xE0, 0x17, 0xA3]);
+/// ```
+pub struct SlcanDecoder {
+    line: [u8; MAX_LINE],
+    len: usize,
+    overflow: bool,
+    errors: u32,
+}
+
+impl Default for SlcanDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn hex(digits: &[u8]) -> Option<u32> {
+    u32::from_str_radix(core::str::from_utf8(digits).ok()?, 16).ok()
+}
+
+fn parse_line(line: &[u8]) -> Option<Frame> {
+    let id = CanId::try_from(hex(line.get(1..9)?)?).ok()?;
+    let len = hex(line.get(9..10)?)? as usize;
+    if len > 8 {
+        return None;
+    }
+    let mut data = [0; 8];
+    for (i, byte) in data[..len].iter_mut().enumerate() {
+        *byte = hex(line.get(10 + 2 * i..12 + 2 * i)?)? as u8;
+    }
+    let timestamp_ms = match &line[10 + 2 * len..] {
+        [] => None,
+        digits @ [_, _, _, _] => Some(hex(digits)? as u16),
+        _ => return None,
+    };
+    Some(Frame {
+        id,
+        data,
+        len,
+        timestamp_ms,
+    })
+}
+
+impl SlcanDecoder {
+    pub const fn new() -> Self {
+        Self {
+            line: [0; MAX_LINE],
+            len: 0,
+            overflow: false,
+            errors: 0,
+        }
+    }
+}
+
+impl Decoder for SlcanDecoder {
+    type Record = Frame;
+
+    fn push_byte(&mut self, byte: u8) -> Option<Frame> {
+        if byte != b'\r' && byte != b'\n' && byte != 0x07 {
+            if self.len == MAX_LINE {
+                self.overflow = true;
+            } else {
+                self.line[self.len] = byte;
+                self.len += 1;
+            }
+            return None;
+        }
+
+        let line = &self.line[..self.len];
+        let frame = match line.first() {
+            _ if self.overflow => None,
+            Some(b'T') => parse_line(line),
+            // An acknowledgement, a standard frame or an empty line.
+            _ => {
+                self.len = 0;
+                return None;
+            }
+        };
+        if frame.is_none() {
+            self.errors += 1;
+        }
+        self.len = 0;
+        self.overflow = false;
+        frame
+    }
+
+    fn errors(&self) -> u32 {
+        self.errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks() {
+        let input = b"z\rT09F1120A8010203040506070804D2\rgarbage\xFF\rT1DFF040A0\r";
+        for chunk_len in 1..input.len() {
+            let mut decoder = SlcanDecoder::new();
+            let mut frames = [None; 2];
+            let mut count = 0;
+            for chunk in input.chunks(chunk_len) {
+                for frame in decoder.push_bytes(chunk) {
+                    frames[count] = Some(frame);
+                    count += 1;
+                }
+            }
+            assert_eq!(count, 2);
+            let first = frames[0].unwrap();
+            assert_eq!(first.id.pgn(), 127250);
+            assert_eq!(first.data(), [1, 2, 3, 4, 5, 6, 7, 8]);
+            assert_eq!(first.timestamp_ms, Some(1234));
+            assert!(frames[1].unwrap().data().is_empty());
+            // "garbage" is skipped as an unknown response.
+            assert_eq!(decoder.errors(), 0);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut decoder = SlcanDecoder::new();
+        // Bad length, bad digit, overlong line.
+        let input = b"T09F1120A9\rT09F1120A1G0\rT09F1120A80102030405060708000000\r";
+        assert_eq!(decoder.push_bytes(input).count(), 0);
+        assert_eq!(decoder.errors(), 3);
+        assert!(decoder.push_bytes(b"T09F1120A0\r").next().is_some());
+    }
+}
@@ -0,0 +1,3 @@
+//! Simulated traffic for demos and for testing consumers without a bus.
+pub mod scenario;
+pub mod sensors;
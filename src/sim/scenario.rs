@@ -0,0 +1,252 @@
+//! Scripted scenarios: devices starting and stopping, values changing and
+//! faults appearing at given times, for reproducible integration tests.
+//!
+//! A scenario is a list of `Event`s. With the `serde` feature it can be
+//! loaded from a file, e.g. as JSON:
+//!
+//! ```json
+//! [
+//!   { "at_ms": 0, "action": { "Start": { "source": 16, "sensor":
+//!       { "Depth": { "depth": 12.0, "swell": 0.3, "noise": 0.05 } } } } },
+//!   { "at_ms": 5000, "action": { "Faults":
+//!       { "drop": 0.2, "duplicate": 0.0, "bit_flip": 0.0 } } },
+//!   { "at_ms": 10000, "action": { "Stop": { "source": 16 } } }
+//! ]
+//! ```
+use crate::frame_queue::RxFrame;
+use crate::lossy::Faults;
+use crate::rng::Rng;
+use crate::sim::sensors::{Sensor, Sensors};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Action {
+    /// Starts a device sending from `source`.
+    Start { source: u8, sensor: Sensor },
+    /// Stops the device sending from `source`.
+    Stop { source: u8 },
+    /// Replaces the values of the device sending from `source`, keeping its
+    /// timing and, for a GPS track, its position.
+    Set { source: u8, sensor: Sensor },
+    /// Applies `Faults` to all frames from now on.
+    Faults(Faults),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Event {
+    pub at_ms: u64,
+    pub action: Action,
+}
+
+/// A bus of up to `N` simulated devices running the `events` of a scenario,
+/// sorted by time. Yields the frames on the bus with their timestamps until
+/// the last device stops.
+///
+/// Events act before frames due at the same time. Dropped frames are left
+/// out and duplicates follow their original; frames are not reordered.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::lossy::Faults;
+/// use nmea::sim::scenario::{Action, Event, VirtualBus};
+/// use nmea::sim::sensors::{Depth, Sensor};
+///
+/// let depth = Depth { depth: 12.0, swell: 0.0, noise: 0.0 };
+/// let events = [
+///     Event { at_ms: 0, action: Action::Start { source: 0x10, sensor: Sensor::Depth(depth) } },
+///     Event { at_ms: 3000, action: Action::Faults(Faults { drop: 1.0, ..Faults::default() }) },
+///     Event { at_ms: 5000, action: Action::Stop { source: 0x10 } },
+/// ];
+/// let bus: VirtualBus<4> = VirtualBus::new(&events, 1);
+/// let times: Vec<u64> = bus.map(|(t, _)| t).collect();
+/// assert_eq!(times, [0, 1000, 2000]);
+/// ```
+pub struct VirtualBus<'a, const N: usize> {
+    sensors: Sensors<N>,
+    events: &'a [Event],
+    faults: Faults,
+    rng: Rng,
+    duplicate: Option<(u64, RxFrame)>,
+    ignored: u32,
+}
+
+impl<'a, const N: usize> VirtualBus<'a, N> {
+    /// Creates a bus running `events`, with noise and faults from `seed`.
+    pub fn new(events: &'a [Event], seed: u64) -> Self {
+        Self {
+            sensors: Sensors::new(seed),
+            events,
+            faults: Faults::default(),
+            rng: Rng::new(seed.wrapping_add(1)),
+            duplicate: None,
+            ignored: 0,
+        }
+    }
+
+    /// Number of events that couldn't be applied: starting more than `N`
+    /// devices, or stopping or changing one that isn't running.
+    pub fn ignored(&self) -> u32 {
+        self.ignored
+    }
+
+    fn apply(&mut self, event: &Event) {
+        let applied = match event.action {
+            Action::Start { source, sensor } => {
+                self.sensors.add_at(sensor, source, event.at_ms).is_ok()
+            }
+            Action::Stop { source } => self.sensors.remove(source).is_some(),
+            Action::Set { source, sensor } => match self.sensors.get_mut(source) {
+                Some(Sensor::Gps(current)) => match sensor {
+                    Sensor::Gps(gps) => {
+                        current.cog = gps.cog;
+                        current.sog = gps.sog;
+                        current.turn_rate = gps.turn_rate;
+                        true
+                    }
+                    _ => false,
+                },
+                Some(current) => {
+                    *current = sensor;
+                    true
+                }
+                None => false,
+            },
+            Action::Faults(faults) => {
+                self.faults = faults;
+                true
+            }
+        };
+        if !applied {
+            self.ignored += 1;
+        }
+    }
+}
+
+impl<const N: usize> Iterator for VirtualBus<'_, N> {
+    type Item = (u64, RxFrame);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(duplicate) = self.duplicate.take() {
+            return Some(duplicate);
+        }
+        loop {
+            let next_ms = self.sensors.next_ms();
+            match self.events.split_first() {
+                Some((event, rest)) if next_ms.is_none_or(|t| event.at_ms <= t) => {
+                    self.events = rest;
+                    self.apply(event);
+                    continue;
+                }
+                _ => {}
+            }
+            let (t_ms, id, mut data) = self.sensors.next()?;
+            if self.rng.chance(self.faults.drop) {
+                continue;
+            }
+            if self.rng.chance(self.faults.bit_flip) {
+                let bit = self.rng.below(64);
+                data[bit / 8] ^= 1 << (bit % 8);
+            }
+            let frame = RxFrame { id, data };
+            if self.rng.chance(self.faults.duplicate) {
+                self.duplicate = Some((t_ms, frame));
+            }
+            return Some((t_ms, frame));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::sensors::{Engine, GpsTrack, WATER_DEPTH_PGN};
+    use std::vec::Vec;
+
+    const SCRIPT: &str = r#"[
+        { "at_ms": 0, "action": { "Start": { "source": 16, "sensor":
+            { "Depth": { "depth": 12.0, "swell": 0.0, "noise": 0.0 } } } } },
+        { "at_ms": 2000, "action": { "Set": { "source": 16, "sensor":
+            { "Depth": { "depth": 3.0, "swell": 0.0, "noise": 0.0 } } } } },
+        { "at_ms": 3000, "action": { "Faults":
+            { "drop": 0.0, "duplicate": 1.0, "bit_flip": 0.0 } } },
+        { "at_ms": 4000, "action": { "Stop": { "source": 17 } } },
+        { "at_ms": 4000, "action": { "Stop": { "source": 16 } } }
+    ]"#;
+
+    fn depth_cm(frame: &RxFrame) -> u32 {
+        u32::from_le_bytes([frame.data[1], frame.data[2], frame.data[3], frame.data[4]])
+    }
+
+    #[test]
+    fn test_script() {
+        let events: Vec<Event> = serde_json::from_str(SCRIPT).unwrap();
+        let mut bus: VirtualBus<2> = VirtualBus::new(&events, 1);
+        let frames: Vec<(u64, RxFrame)> = bus.by_ref().collect();
+        let times: Vec<u64> = frames.iter().map(|(t, _)| *t).collect();
+        assert_eq!(times, [0, 1000, 2000, 3000, 3000]);
+        assert!(frames.iter().all(|(_, f)| f.id.pgn() == WATER_DEPTH_PGN));
+        assert_eq!(depth_cm(&frames[1].1), 1200);
+        assert_eq!(depth_cm(&frames[2].1), 300);
+        // Stopping a device that isn't running.
+        assert_eq!(bus.ignored(), 1);
+    }
+
+    #[test]
+    fn test_set_keeps_position() {
+        let gps = GpsTrack {
+            latitude: 50.0,
+            longitude: -4.0,
+            cog: 0.0,
+            sog: 10.0,
+            turn_rate: 0.0,
+        };
+        let stopped = GpsTrack { sog: 0.0, ..gps };
+        let engine = Engine {
+            instance: 0,
+            idle: 800.0,
+            max: 800.0,
+            ramp_ms: 1000,
+        };
+        let events = [
+            Event {
+                at_ms: 0,
+                action: Action::Start {
+                    source: 1,
+                    sensor: Sensor::Gps(gps),
+                },
+            },
+            Event {
+                at_ms: 1050,
+                action: Action::Set {
+                    source: 1,
+                    sensor: Sensor::Gps(stopped),
+                },
+            },
+            Event {
+                at_ms: 1050,
+                action: Action::Set {
+                    source: 1,
+                    sensor: Sensor::Engine(engine),
+                },
+            },
+            Event {
+                at_ms: 2000,
+                action: Action::Stop { source: 1 },
+            },
+        ];
+        let mut bus: VirtualBus<1> = VirtualBus::new(&events, 1);
+        let (_, last) = bus.by_ref().last().unwrap();
+        let latitude = i32::from_le_bytes([last.data[0], last.data[1], last.data[2], last.data[3]]);
+        // Moved 10 m north in the first second, then stopped.
+        assert!((latitude as f64 * 1e-7 - 50.0 - 10.0 / 111_320.0).abs() < 1e-6);
+        assert_eq!(bus.ignored(), 1);
+    }
+}
@@ -0,0 +1,343 @@
+//! Time-varying sensor streams: a GPS track, wind, depth and engine speed.
+//!
+//! Each `Sensor` produces a single-frame PGN at its own rate. `Sensors`
+//! merges several into one stream of `(timestamp_ms, CanId, payload)` in
+//! time order. Noise comes from a seeded generator, so a stream is the same
+//! every run.
+use crate::can_id::{CanId, BROADCAST};
+use crate::pgn::depth::WaterDepth;
+use crate::pgn::engine::EngineRapid;
+use crate::pgn::gnss::PositionRapidUpdate;
+use crate::pgn::normalize_angle;
+use crate::pgn::wind::{WindData, WindReference};
+use crate::rng::Rng;
+use core::f64::consts::PI;
+use fixed_queue::Vec;
+
+/// Engine Parameters, Rapid Update.
+pub const ENGINE_RAPID_PGN: u32 = EngineRapid::PGN;
+/// Water Depth.
+pub const WATER_DEPTH_PGN: u32 = WaterDepth::PGN;
+
+/// Meters per degree of latitude.
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// A boat at `latitude` and `longitude` (degrees) moving at `sog` (m/s) on
+/// `cog` (radians, true), turning at `turn_rate` (radians/s). Sends Position
+/// Rapid Updates every 100 ms.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct GpsTrack {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub cog: f64,
+    pub sog: f64,
+    pub turn_rate: f64,
+}
+
+/// Apparent wind of `speed` (m/s) from `angle` (radians), swinging by up to
+/// `gust` m/s and a tenth of a radian over a minute, plus noise. Sends Wind
+/// Data every 100 ms.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Wind {
+    pub speed: f32,
+    pub angle: f32,
+    pub gust: f32,
+}
+
+/// Water `depth` (m) rising and falling by `swell` m over 10 s, with up to
+/// `noise` m of noise. Sends Water Depth every second.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Depth {
+    pub depth: f32,
+    pub swell: f32,
+    pub noise: f32,
+}
+
+/// Engine `instance` ramping from `idle` to `max` rpm and back over
+/// `ramp_ms`, then idling for as long. Sends Engine Parameters, Rapid Update
+/// every 100 ms.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Engine {
+    pub instance: u8,
+    pub idle: f32,
+    pub max: f32,
+    pub ramp_ms: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Sensor {
+    Gps(GpsTrack),
+    Wind(Wind),
+    Depth(Depth),
+    Engine(Engine),
+}
+
+impl Sensor {
+    pub fn pgn(&self) -> u32 {
+        match self {
+            Self::Gps(_) => PositionRapidUpdate::PGN,
+            Self::Wind(_) => WindData::PGN,
+            Self::Depth(_) => WATER_DEPTH_PGN,
+            Self::Engine(_) => ENGINE_RAPID_PGN,
+        }
+    }
+
+    pub fn period_ms(&self) -> u64 {
+        match self {
+            Self::Depth(_) => 1000,
+            _ => 100,
+        }
+    }
+
+    /// Advances the sensor by `dt_ms` to `t_ms` and returns its payload.
+    fn sample(&mut self, t_ms: u64, dt_ms: u64, rng: &mut Rng) -> [u8; 8] {
+        let t = t_ms as f32 / 1000.0;
+        let mut buf = [0xFF; 8];
+        match self {
+            Self::Gps(gps) => {
+                let dt = dt_ms as f64 / 1000.0;
+                let distance = gps.sog * dt;
+                gps.latitude += distance * libm::cos(gps.cog) / METERS_PER_DEGREE;
+                gps.longitude += distance * libm::sin(gps.cog)
+                    / (METERS_PER_DEGREE * libm::cos(gps.latitude * PI / 180.0));
+                gps.cog += gps.turn_rate * dt;
+                let latitude = libm::round(gps.latitude * 1e7) as i32;
+                let longitude = libm::round(gps.longitude * 1e7) as i32;
+                buf[..4].copy_from_slice(&latitude.to_le_bytes());
+                buf[4..].copy_from_slice(&longitude.to_le_bytes());
+            }
+            Self::Wind(wind) => {
+                let phase = 2.0 * core::f32::consts::PI * t / 60.0;
+                let speed = wind.speed + wind.gust * libm::sinf(phase) + 0.2 * rng.noise();
+                let angle = wind.angle + 0.1 * libm::cosf(phase) + 0.02 * rng.noise();
+                buf = WindData {
+                    sid: None,
+                    speed: Some(speed.max(0.0)),
+                    angle: Some(normalize_angle(angle)),
+                    reference: WindReference::Apparent,
+                }
+                .to_payload();
+            }
+            Self::Depth(depth) => {
+                let phase = 2.0 * core::f32::consts::PI * t / 10.0;
+                let meters =
+                    depth.depth + depth.swell * libm::sinf(phase) + depth.noise * rng.noise();
+                buf = WaterDepth {
+                    sid: None,
+                    depth: Some(meters.max(0.0)),
+                    offset: Some(0.0),
+                    range: None,
+                }
+                .to_payload();
+            }
+            Self::Engine(engine) => {
+                let ramp = engine.ramp_ms.max(1);
+                let position = t_ms % (2 * ramp);
+                let level = if position < ramp / 2 {
+                    position as f32 / (ramp / 2).max(1) as f32
+                } else if position < ramp {
+                    (ramp - position) as f32 / (ramp - ramp / 2) as f32
+                } else {
+                    0.0
+                };
+                let rpm = engine.idle + (engine.max - engine.idle) * level;
+                buf = EngineRapid {
+                    instance: engine.instance,
+                    speed: Some(rpm.max(0.0)),
+                    boost: None,
+                    tilt_trim: None,
+                }
+                .to_payload();
+            }
+        }
+        buf
+    }
+}
+
+struct Slot {
+    sensor: Sensor,
+    source: u8,
+    next_ms: u64,
+    last_ms: u64,
+}
+
+/// Up to `N` sensors merged into one stream, starting at time 0.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::pgn::wind::WindData;
+/// use nmea::sim::sensors::{Depth, Sensor, Sensors, Wind};
+///
+/// let mut sensors: Sensors<4> = Sensors::new(7);
+/// let wind = Wind { speed: 6.0, angle: 0.7, gust: 2.0 };
+/// sensors.add(Sensor::Wind(wind), 0x10).unwrap();
+/// let depth = Depth { depth: 12.0, swell: 0.3, noise: 0.05 };
+/// sensors.add(Sensor::Depth(depth), 0x11).unwrap();
+///
+/// // 10 wind and 1 depth messages per second.
+/// let second: Vec<_> = sensors.by_ref().take_while(|(t, _, _)| *t < 1000).collect();
+/// assert_eq!(second.len(), 11);
+/// let (_, id, payload) = second[0];
+/// assert_eq!(id.source(), 0x10);
+/// let speed = WindData::from_payload(&payload).unwrap().speed.unwrap();
+/// assert!((3.0..9.0).contains(&speed));
+/// ```
+pub struct Sensors<const N: usize> {
+    slots: Vec<Slot, N>,
+    rng: Rng,
+}
+
+impl<const N: usize> Sensors<N> {
+    /// Creates an empty set with noise from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            slots: Vec::new(),
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Adds `sensor`, sending from `source`. Returns it back if `N` sensors
+    /// were added already.
+    pub fn add(&mut self, sensor: Sensor, source: u8) -> Result<(), Sensor> {
+        self.add_at(sensor, source, 0)
+    }
+
+    /// Adds `sensor`, sending from `source` from `start_ms` on.
+    pub fn add_at(&mut self, sensor: Sensor, source: u8, start_ms: u64) -> Result<(), Sensor> {
+        self.slots
+            .push(Slot {
+                sensor,
+                source,
+                next_ms: start_ms,
+                last_ms: start_ms,
+            })
+            .map_err(|slot| slot.sensor)
+    }
+
+    /// Removes the sensor sending from `source`.
+    pub fn remove(&mut self, source: u8) -> Option<Sensor> {
+        let index = self.slots.iter().position(|slot| slot.source == source)?;
+        Some(self.slots.swap_remove(index).sensor)
+    }
+
+    /// The sensor sending from `source`, to change its values.
+    pub fn get_mut(&mut self, source: u8) -> Option<&mut Sensor> {
+        self.slots
+            .iter_mut()
+            .find(|slot| slot.source == source)
+            .map(|slot| &mut slot.sensor)
+    }
+
+    /// Time of the next message.
+    pub fn next_ms(&self) -> Option<u64> {
+        self.slots.iter().map(|slot| slot.next_ms).min()
+    }
+}
+
+impl<const N: usize> Iterator for Sensors<N> {
+    type Item = (u64, CanId, [u8; 8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let slot = self.slots.iter_mut().min_by_key(|slot| slot.next_ms)?;
+        let t_ms = slot.next_ms;
+        let payload = slot.sensor.sample(t_ms, t_ms - slot.last_ms, &mut self.rng);
+        slot.last_ms = t_ms;
+        slot.next_ms += slot.sensor.period_ms();
+        // All simulated PGNs are broadcast.
+        let id = CanId::for_pgn(slot.sensor.pgn(), slot.source, BROADCAST).ok()?;
+        Some((t_ms, id, payload))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sensors() -> Sensors<4> {
+        let mut sensors = Sensors::new(1);
+        let gps = GpsTrack {
+            latitude: 50.0,
+            longitude: -4.0,
+            cog: 0.0,
+            sog: 5.0,
+            turn_rate: 0.0,
+        };
+        sensors.add(Sensor::Gps(gps), 1).unwrap();
+        let engine = Engine {
+            instance: 0,
+            idle: 800.0,
+            max: 3000.0,
+            ramp_ms: 10_000,
+        };
+        sensors.add(Sensor::Engine(engine), 2).unwrap();
+        sensors
+    }
+
+    #[test]
+    fn test_track_and_ramp() {
+        let samples: std::vec::Vec<_> = sensors().take_while(|(t, _, _)| *t <= 5000).collect();
+        let gps: std::vec::Vec<_> = samples
+            .iter()
+            .filter(|(_, id, _)| id.pgn() == PositionRapidUpdate::PGN)
+            .collect();
+        assert_eq!(gps.len(), 51);
+        // Due north at 5 m/s for 5 s.
+        let end = PositionRapidUpdate::from_payload(&gps[50].2).unwrap();
+        let moved = (end.latitude.unwrap() - 50.0) * METERS_PER_DEGREE;
+        assert!((moved - 25.0).abs() < 0.05);
+        assert!((end.longitude.unwrap() + 4.0).abs() < 1e-7);
+
+        let rpm = |t: u64| {
+            let (_, _, payload) = samples
+                .iter()
+                .find(|(ts, id, _)| *ts == t && id.pgn() == ENGINE_RAPID_PGN)
+                .unwrap();
+            EngineRapid::from_payload(payload).unwrap().speed.unwrap()
+        };
+        assert_eq!(rpm(0), 800.0);
+        assert_eq!(rpm(2500), 1900.0);
+        assert_eq!(rpm(5000), 3000.0);
+        assert_eq!(samples[1].1.source(), 2);
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let mut a: Sensors<1> = Sensors::new(3);
+        let mut b: Sensors<1> = Sensors::new(3);
+        let depth = Depth {
+            depth: 5.0,
+            swell: 0.0,
+            noise: 0.5,
+        };
+        a.add(Sensor::Depth(depth), 1).unwrap();
+        b.add(Sensor::Depth(depth), 1).unwrap();
+        assert_eq!(a.add(Sensor::Depth(depth), 1), Err(Sensor::Depth(depth)));
+        for _ in 0..10 {
+            let (t, _, payload) = a.next().unwrap();
+            assert_eq!(b.next().unwrap().2, payload);
+            let cm = u32::from_le_bytes([payload[1], payload[2], payload[3], payload[4]]);
+            assert!((450..=550).contains(&cm), "{} cm at {}", cm, t);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! SQLite log sink for raw frames and decoded messages.
+use crate::can_id::CanId;
+use crate::csv::Fields;
+use rusqlite::{params, Connection};
+use std::path::Path;
+use std::string::String;
+use std::vec::Vec;
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+}
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS frames (
+    id INTEGER PRIMARY KEY,
+    timestamp_ms INTEGER NOT NULL,
+    can_id INTEGER NOT NULL,
+    source INTEGER NOT NULL,
+    pgn INTEGER NOT NULL,
+    data BLOB NOT NULL
+);
+CREATE TABLE IF NOT EXISTS messages (
+    id INTEGER PRIMARY KEY,
+    timestamp_ms INTEGER NOT NULL,
+    source INTEGER NOT NULL,
+    pgn INTEGER NOT NULL,
+    payload BLOB NOT NULL
+);
+CREATE TABLE IF NOT EXISTS fields (
+    message_id INTEGER NOT NULL REFERENCES messages(id),
+    name TEXT NOT NULL,
+    value TEXT
+);
+CREATE INDEX IF NOT EXISTS messages_pgn ON messages(pgn, timestamp_ms);
+CREATE INDEX IF NOT EXISTS fields_message ON fields(message_id);
+";
+
+/// Logs frames and messages into three tables:
+///
+/// - `frames`: every raw frame with its timestamp and 29-bit identifier.
+/// - `messages`: complete (reassembled) payloads by source and PGN.
+/// - `fields`: the decoded fields of a message, one row each, with values
+///   formatted as in `csv`. Unavailable values are `NULL`.
+///
+/// Each call is its own statement; wrap bursts in a transaction through
+/// `connection` to keep up with a busy bus.
+pub struct SqliteSink {
+    connection: Connection,
+}
+
+impl SqliteSink {
+    /// Opens or creates the database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, Error> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    pub fn record_frame(&self, timestamp_ms: u64, id: CanId, data: &[u8]) -> Result<(), Error> {
+        self.connection
+            .prepare_cached(
+                "INSERT INTO frames (timestamp_ms, can_id, source, pgn, data)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?
+            .execute(params![
+                timestamp_ms as i64,
+                u32::from(id),
+                id.source(),
+                id.pgn(),
+                data
+            ])?;
+        Ok(())
+    }
+
+    /// Records a complete message and returns its row id.
+    pub fn record_message(
+        &self,
+        timestamp_ms: u64,
+        source: u8,
+        pgn: u32,
+        payload: &[u8],
+    ) -> Result<i64, Error> {
+        self.connection
+            .prepare_cached(
+                "INSERT INTO messages (timestamp_ms, source, pgn, payload)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?
+            .execute(params![timestamp_ms as i64, source, pgn, payload])?;
+        Ok(self.connection.last_insert_rowid())
+    }
+
+    /// Records a complete message along with its decoded fields.
+    pub fn record_decoded(
+        &self,
+        timestamp_ms: u64,
+        source: u8,
+        pgn: u32,
+        payload: &[u8],
+        decoded: &impl Fields,
+    ) -> Result<i64, Error> {
+        let message_id = self.record_message(timestamp_ms, source, pgn, payload)?;
+        let mut fields: Vec<(&'static str, String)> = Vec::new();
+        decoded.fields(&mut |name, value| fields.push((name, value)));
+        let mut insert = self
+            .connection
+            .prepare_cached("INSERT INTO fields (message_id, name, value) VALUES (?1, ?2, ?3)")?;
+        for (name, value) in fields {
+            let value = (!value.is_empty()).then_some(value);
+            insert.execute(params![message_id, name, value])?;
+        }
+        Ok(message_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::wind::WindData;
+
+    #[test]
+    fn test_record() {
+        let sink = SqliteSink::open_in_memory().unwrap();
+        let id = CanId::new(2, WindData::PGN, 0x23, 255).unwrap();
+        let payload = [0x01, 0xF4, 0x01, 0xFF, 0xFF, 0x02, 0xFF, 0xFF];
+        sink.record_frame(1000, id, &payload).unwrap();
+        let wind = WindData::from_payload(&payload).unwrap();
+        let message_id = sink
+            .record_decoded(1000, 0x23, WindData::PGN, &payload, &wind)
+            .unwrap();
+
+        let connection = sink.connection();
+        let (can_id, pgn): (u32, u32) = connection
+            .query_row("SELECT can_id, pgn FROM frames", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((can_id, pgn), (u32::from(id), WindData::PGN));
+
+        let speed: String = connection
+            .query_row(
+                "SELECT value FROM fields WHERE message_id = ?1 AND name = 'speed'",
+                [message_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(speed, "5");
+        let angle: Option<String> = connection
+            .query_row("SELECT value FROM fields WHERE name = 'angle'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(angle, None);
+    }
+}
@@ -0,0 +1,542 @@
+//! Bus traffic counters.
+use core::fmt;
+use fixed_queue::LinearMap;
+
+/// NMEA2000 bus bit rate in bit/s.
+pub const NMEA2000_BITRATE: u32 = 250_000;
+
+/// Bits on the wire for an extended CAN frame with `len` data bytes,
+/// including interframe space and the worst case of bit stuffing.
+///
+/// Of the 54 + 8 * `len` bits from the start of frame to the CRC, every
+/// fourth after the first can be a stuff bit.
+pub const fn frame_bits(len: usize) -> u64 {
+    let len = if len > 8 { 8 } else { len } as u64;
+    67 + 8 * len + (53 + 8 * len) / 4
+}
+
+/// Bus utilization from a stream of timestamped frames, as the fraction of
+/// the bit rate the frames occupy on the wire (see `frame_bits`).
+///
+/// The instantaneous load is that of the last complete window of
+/// `window_ms`; the average spans the first to the last frame.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::stats::{BusLoad, NMEA2000_BITRATE};
+///
+/// let mut load = BusLoad::new(NMEA2000_BITRATE, 100);
+/// for t in 0..100 {
+///     load.record(t, 8);
+/// }
+/// assert_eq!(load.instantaneous(), None);
+/// load.record(100, 8);
+/// // 100 frames of 160 bits in 100 ms.
+/// assert_eq!(load.instantaneous(), Some(0.64));
+/// ```
+#[derive(Clone, Debug)]
+pub struct BusLoad {
+    bitrate: u32,
+    window_ms: u64,
+    bits: u64,
+    first_ms: Option<u64>,
+    last_ms: u64,
+    window_start_ms: u64,
+    window_bits: u64,
+    instantaneous: Option<f32>,
+}
+
+impl BusLoad {
+    pub const fn new(bitrate: u32, window_ms: u64) -> Self {
+        Self {
+            bitrate,
+            window_ms,
+            bits: 0,
+            first_ms: None,
+            last_ms: 0,
+            window_start_ms: 0,
+            window_bits: 0,
+            instantaneous: None,
+        }
+    }
+
+    /// Counts a frame with `len` data bytes seen at `now_ms`. Frames must
+    /// be recorded in time order.
+    pub fn record(&mut self, now_ms: u64, len: usize) {
+        let first_ms = *self.first_ms.get_or_insert(now_ms);
+        if self.bits == 0 {
+            self.window_start_ms = first_ms;
+        }
+        let elapsed_ms = now_ms.saturating_sub(self.window_start_ms);
+        if self.window_ms > 0 && elapsed_ms >= self.window_ms {
+            // Windows after the first that ended are empty.
+            self.instantaneous = if elapsed_ms >= 2 * self.window_ms {
+                Some(0.0)
+            } else {
+                self.load(self.window_bits, self.window_ms)
+            };
+            self.window_start_ms = now_ms - elapsed_ms % self.window_ms;
+            self.window_bits = 0;
+        }
+        let bits = frame_bits(len);
+        self.bits += bits;
+        self.window_bits += bits;
+        self.last_ms = self.last_ms.max(now_ms);
+    }
+
+    /// Load of the last complete window.
+    pub fn instantaneous(&self) -> Option<f32> {
+        self.instantaneous
+    }
+
+    /// Load between the first and the last recorded frame.
+    pub fn average(&self) -> Option<f32> {
+        self.load(self.bits, self.last_ms.saturating_sub(self.first_ms?))
+    }
+
+    fn load(&self, bits: u64, elapsed_ms: u64) -> Option<f32> {
+        if elapsed_ms == 0 || self.bitrate == 0 {
+            return None;
+        }
+        Some((bits * 1000) as f32 / (elapsed_ms * self.bitrate as u64) as f32)
+    }
+}
+
+/// Frame, message and error counters for a bus. Messages are counted per PGN
+/// for up to `N` PGNs; messages of further PGNs only count towards the total.
+///
+/// Time is supplied by the caller as monotonic milliseconds.
+///
+/// Utilization of an NMEA2000 bus is tracked over one second windows by
+/// `load`. Record frames with `record_frame_len` for it to account for
+/// frames shorter than 8 bytes.
+pub struct Stats<const N: usize> {
+    frames: u64,
+    messages: u64,
+    errors: u64,
+    per_pgn: LinearMap<u32, u64, N>,
+    load: BusLoad,
+}
+
+impl<const N: usize> Default for Stats<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Stats<N> {
+    pub const fn new() -> Self {
+        Self {
+            frames: 0,
+            messages: 0,
+            errors: 0,
+            per_pgn: LinearMap::new(),
+            load: BusLoad::new(NMEA2000_BITRATE, 1000),
+        }
+    }
+
+    pub fn record_frame(&mut self, now_ms: u64) {
+        self.record_frame_len(now_ms, 8);
+    }
+
+    /// Counts a frame with `len` data bytes.
+    pub fn record_frame_len(&mut self, now_ms: u64, len: usize) {
+        self.frames += 1;
+        self.load.record(now_ms, len);
+    }
+
+    /// Counts a complete message of `pgn`.
+    pub fn record_message(&mut self, pgn: u32) {
+        self.messages += 1;
+        if let Some(count) = self.per_pgn.get_mut(&pgn) {
+            *count += 1;
+        } else {
+            let _ = self.per_pgn.insert(pgn, 1);
+        }
+    }
+
+    pub fn record_error(&mut self) {
+        self.errors += 1;
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    pub fn messages(&self) -> u64 {
+        self.messages
+    }
+
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    pub fn messages_for(&self, pgn: u32) -> u64 {
+        self.per_pgn.get(&pgn).copied().unwrap_or(0)
+    }
+
+    /// Message counts of the tracked PGNs.
+    pub fn per_pgn(&self) -> impl Iterator<Item = (u32, u64)> + '_ {
+        self.per_pgn.iter().map(|(pgn, count)| (*pgn, *count))
+    }
+
+    /// Bus utilization of the recorded frames.
+    pub fn load(&self) -> &BusLoad {
+        &self.load
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Renders the counters in the Prometheus text exposition format, with
+    /// metric names prefixed by `nmea_`. The bus load is the average of
+    /// `load` and is omitted until it can be estimated.
+    #[cfg(any(test, feature = "std"))]
+    pub fn to_prometheus(&self) -> alloc::string::String {
+        use core::fmt::Write;
+
+        let mut out = alloc::string::String::new();
+        let _ = write!(
+            out,
+            "# HELP nmea_frames_total CAN frames received.\n\
+             # TYPE nmea_frames_total counter\n\
+             nmea_frames_total {}\n\
+             # HELP nmea_errors_total Frames rejected by reassembly.\n\
+             # TYPE nmea_errors_total counter\n\
+             nmea_errors_total {}\n\
+             # HELP nmea_messages_total Complete messages received, by PGN.\n\
+             # TYPE nmea_messages_total counter\n",
+            self.frames, self.errors
+        );
+        for (pgn, count) in self.per_pgn() {
+            let _ = writeln!(out, "nmea_messages_total{{pgn=\"{}\"}} {}", pgn, count);
+        }
+        let untracked = self.messages - self.per_pgn().map(|(_, c)| c).sum::<u64>();
+        if untracked > 0 {
+            let _ = writeln!(out, "nmea_messages_total{{pgn=\"other\"}} {}", untracked);
+        }
+        if let Some(load) = self.load.average() {
+            let _ = write!(
+                out,
+                "# HELP nmea_bus_load Estimated fraction of bus capacity in use.\n\
+                 # TYPE nmea_bus_load gauge\n\
+                 nmea_bus_load {}\n",
+                load
+            );
+        }
+        out
+    }
+}
+
+/// Traffic of one source address, with messages counted per PGN for up to
+/// `P` PGNs.
+pub struct SourceProfile<const P: usize> {
+    frames: u64,
+    bytes: u64,
+    bits: u64,
+    messages: u64,
+    errors: u64,
+    per_pgn: LinearMap<u32, u64, P>,
+    first_ms: u64,
+    last_ms: u64,
+}
+
+impl<const P: usize> SourceProfile<P> {
+    const fn new(now_ms: u64) -> Self {
+        Self {
+            frames: 0,
+            bytes: 0,
+            bits: 0,
+            messages: 0,
+            errors: 0,
+            per_pgn: LinearMap::new(),
+            first_ms: now_ms,
+            last_ms: now_ms,
+        }
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Data bytes of the recorded frames.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    pub fn messages(&self) -> u64 {
+        self.messages
+    }
+
+    /// Errors attributed to this source, e.g. rejected Fast-Packet frames.
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    pub fn messages_for(&self, pgn: u32) -> u64 {
+        self.per_pgn.get(&pgn).copied().unwrap_or(0)
+    }
+
+    /// Message counts of the tracked PGNs.
+    pub fn per_pgn(&self) -> impl Iterator<Item = (u32, u64)> + '_ {
+        self.per_pgn.iter().map(|(pgn, count)| (*pgn, *count))
+    }
+
+    /// Frames per second between the first and the last frame of the
+    /// source.
+    pub fn frame_rate(&self) -> Option<f32> {
+        self.rate(self.frames)
+    }
+
+    /// Messages of `pgn` per second between the first and the last frame
+    /// of the source.
+    pub fn message_rate(&self, pgn: u32) -> Option<f32> {
+        self.rate(self.messages_for(pgn))
+    }
+
+    /// Fraction of an NMEA2000 bus taken by the source, see `BusLoad`.
+    pub fn bus_share(&self) -> Option<f32> {
+        let elapsed_ms = self.last_ms - self.first_ms;
+        (elapsed_ms > 0)
+            .then(|| (self.bits * 1000) as f32 / (elapsed_ms * NMEA2000_BITRATE as u64) as f32)
+    }
+
+    fn rate(&self, count: u64) -> Option<f32> {
+        let elapsed_ms = self.last_ms - self.first_ms;
+        (elapsed_ms > 0).then(|| (count * 1000) as f32 / elapsed_ms as f32)
+    }
+}
+
+/// Per-source traffic summaries for auditing a bus, for up to `S` sources
+/// each with up to `P` PGNs. Traffic of further sources is dropped.
+///
+/// The `Display` implementation renders a report with a line per source,
+/// ordered by address, followed by its PGNs.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::stats::TrafficProfile;
+///
+/// let mut profile: TrafficProfile<8, 4> = TrafficProfile::new();
+/// for t in 0..=10 {
+///     profile.record_frame(t * 100, 0x23, 8);
+///     profile.record_message(0x23, 130306);
+/// }
+/// let source = profile.get(0x23).unwrap();
+/// assert_eq!(source.frame_rate(), Some(11.0));
+/// assert_eq!(source.bytes(), 88);
+/// ```
+pub struct TrafficProfile<const S: usize, const P: usize> {
+    sources: LinearMap<u8, SourceProfile<P>, S>,
+}
+
+impl<const S: usize, const P: usize> Default for TrafficProfile<S, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const S: usize, const P: usize> TrafficProfile<S, P> {
+    pub const fn new() -> Self {
+        Self {
+            sources: LinearMap::new(),
+        }
+    }
+
+    fn source(&mut self, source: u8, now_ms: Option<u64>) -> Option<&mut SourceProfile<P>> {
+        if !self.sources.contains_key(&source) {
+            let profile = SourceProfile::new(now_ms?);
+            self.sources.insert(source, profile).ok()?;
+        }
+        self.sources.get_mut(&source)
+    }
+
+    /// Counts a frame with `len` data bytes from `source`.
+    pub fn record_frame(&mut self, now_ms: u64, source: u8, len: usize) {
+        if let Some(profile) = self.source(source, Some(now_ms)) {
+            profile.frames += 1;
+            profile.bytes += len as u64;
+            profile.bits += frame_bits(len);
+            profile.last_ms = profile.last_ms.max(now_ms);
+        }
+    }
+
+    /// Counts a complete message of `pgn` from `source`. Sources are only
+    /// tracked once a frame of theirs has been recorded.
+    pub fn record_message(&mut self, source: u8, pgn: u32) {
+        if let Some(profile) = self.source(source, None) {
+            profile.messages += 1;
+            if let Some(count) = profile.per_pgn.get_mut(&pgn) {
+                *count += 1;
+            } else {
+                let _ = profile.per_pgn.insert(pgn, 1);
+            }
+        }
+    }
+
+    pub fn record_error(&mut self, source: u8) {
+        if let Some(profile) = self.source(source, None) {
+            profile.errors += 1;
+        }
+    }
+
+    pub fn get(&self, source: u8) -> Option<&SourceProfile<P>> {
+        self.sources.get(&source)
+    }
+
+    /// The profiled sources, in order of their first frame.
+    pub fn sources(&self) -> impl Iterator<Item = (u8, &SourceProfile<P>)> + '_ {
+        self.sources
+            .iter()
+            .map(|(source, profile)| (*source, profile))
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl<const S: usize, const P: usize> fmt::Display for TrafficProfile<S, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "source   frames    bytes   errors  frames/s   load %")?;
+        for source in 0..=u8::MAX {
+            let Some(profile) = self.get(source) else {
+                continue;
+            };
+            writeln!(
+                f,
+                "{:>6} {:>8} {:>8} {:>8} {:>9.1} {:>8.2}",
+                source,
+                profile.frames,
+                profile.bytes,
+                profile.errors,
+                profile.frame_rate().unwrap_or(0.0),
+                profile.bus_share().unwrap_or(0.0) * 100.0
+            )?;
+            for (pgn, count) in profile.per_pgn() {
+                writeln!(
+                    f,
+                    "         pgn {:>6} {:>8} messages {:>7.1}/s",
+                    pgn,
+                    count,
+                    profile.message_rate(pgn).unwrap_or(0.0)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters() {
+        let mut stats: Stats<1> = Stats::new();
+        assert_eq!(stats.load().average(), None);
+        for t in 0..250 {
+            stats.record_frame(t * 4);
+        }
+        stats.record_message(129025);
+        stats.record_message(129025);
+        stats.record_message(127250);
+        stats.record_error();
+        assert_eq!(stats.frames(), 250);
+        assert_eq!(stats.messages(), 3);
+        assert_eq!(stats.messages_for(129025), 2);
+        // The table only has room for one PGN.
+        assert_eq!(stats.messages_for(127250), 0);
+        // 160 bits per frame with stuffing, over the first second.
+        assert_eq!(stats.load().instantaneous(), None);
+        stats.record_frame_len(1000, 0);
+        assert!((stats.load().instantaneous().unwrap() - 0.16).abs() < 1e-6);
+        let average = stats.load().average().unwrap();
+        assert!((average - (250.0 * 160.0 + 80.0) / 250_000.0).abs() < 1e-6);
+
+        stats.reset();
+        assert_eq!(stats.frames(), 0);
+        assert_eq!(stats.per_pgn().count(), 0);
+    }
+
+    #[test]
+    fn test_bus_load() {
+        assert_eq!(frame_bits(8), 160);
+        assert_eq!(frame_bits(0), 80);
+        assert_eq!(frame_bits(64), 160);
+
+        let mut load = BusLoad::new(NMEA2000_BITRATE, 100);
+        load.record(50, 8);
+        load.record(149, 8);
+        assert_eq!(load.instantaneous(), None);
+        load.record(150, 8);
+        assert_eq!(load.instantaneous(), Some(320.0 / 25_000.0));
+        // A silent window in between.
+        load.record(400, 8);
+        assert_eq!(load.instantaneous(), Some(0.0));
+        load.record(450, 8);
+        assert_eq!(load.instantaneous(), Some(160.0 / 25_000.0));
+        assert_eq!(load.average(), Some(800.0 / 100_000.0));
+    }
+
+    #[test]
+    fn test_prometheus() {
+        let mut stats: Stats<4> = Stats::new();
+        stats.record_frame(0);
+        stats.record_message(129025);
+        stats.record_error();
+        assert_eq!(
+            stats.to_prometheus(),
+            "# HELP nmea_frames_total CAN frames received.\n\
+             # TYPE nmea_frames_total counter\n\
+             nmea_frames_total 1\n\
+             # HELP nmea_errors_total Frames rejected by reassembly.\n\
+             # TYPE nmea_errors_total counter\n\
+             nmea_errors_total 1\n\
+             # HELP nmea_messages_total Complete messages received, by PGN.\n\
+             # TYPE nmea_messages_total counter\n\
+             nmea_messages_total{pgn=\"129025\"} 1\n"
+        );
+
+        stats.record_frame(1000);
+        assert!(stats.to_prometheus().ends_with("nmea_bus_load 0.00128\n"));
+    }
+
+    #[test]
+    fn test_traffic_profile() {
+        let mut profile: TrafficProfile<1, 1> = TrafficProfile::new();
+        // Untracked until a frame has been seen.
+        profile.record_error(0x10);
+        assert!(profile.get(0x10).is_none());
+
+        profile.record_frame(0, 0x10, 8);
+        profile.record_frame(500, 0x10, 3);
+        profile.record_frame(1000, 0x10, 8);
+        profile.record_message(0x10, 127250);
+        profile.record_message(0x10, 127251);
+        profile.record_error(0x10);
+        // No room for another source.
+        profile.record_frame(1000, 0x11, 8);
+        assert_eq!(profile.sources().count(), 1);
+
+        let source = profile.get(0x10).unwrap();
+        assert_eq!(source.frames(), 3);
+        assert_eq!(source.bytes(), 19);
+        assert_eq!(source.messages(), 2);
+        assert_eq!(source.errors(), 1);
+        assert_eq!(source.messages_for(127251), 0);
+        assert_eq!(source.frame_rate(), Some(3.0));
+        assert_eq!(source.message_rate(127250), Some(1.0));
+        assert_eq!(source.bus_share(), Some(430.0 / 250_000.0));
+
+        assert_eq!(
+            std::format!("{}", profile),
+            "source   frames    bytes   errors  frames/s   load %\n    \
+             16        3       19        1       3.0     0.17\n         \
+             pgn 127250        1 messages     1.0/s\n"
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! Runtime-configurable templates for topics and paths of decoded values.
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Unknown placeholder {{{0}}}")]
+    UnknownPlaceholder(String),
+    #[error("Unclosed placeholder")]
+    Unclosed,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Literal(String),
+    Pgn,
+    Source,
+    Instance,
+    Name,
+    Field,
+}
+
+/// What a template is rendered for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Context<'a> {
+    pub pgn: u32,
+    pub source: u8,
+    /// The instance field of the message, if it has one.
+    pub instance: Option<&'a str>,
+    /// The name of the PGN, e.g. `wind_data`.
+    pub name: &'a str,
+    /// The field being rendered, for per-field templates.
+    pub field: Option<&'a str>,
+}
+
+/// A template such as `n2k/{pgn}/{src}/{instance}/{field}`.
+///
+/// Placeholders are `{pgn}`, `{src}` (source address), `{instance}` (the
+/// instance field, or the source address for PGNs without one), `{name}`
+/// (the PGN name) and `{field}` (the field name, for templates addressing
+/// single fields). `{{` and `}}` stand for literal braces.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::template::{Context, Template};
+///
+/// let template = Template::parse("boat/{name}/{instance}/{field}").unwrap();
+/// let context = Context {
+///     pgn: 127505,
+///     source: 0x10,
+///     instance: Some("1"),
+///     name: "fluid_level",
+///     field: Some("level"),
+/// };
+/// assert_eq!(template.render(&context), "boat/fluid_level/1/level");
+/// assert!(template.has_field());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self, Error> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(Error::Unclosed),
+                        }
+                    }
+                    let part = match name.as_str() {
+                        "pgn" => Part::Pgn,
+                        "src" => Part::Source,
+                        "instance" => Part::Instance,
+                        "name" => Part::Name,
+                        "field" => Part::Field,
+                        _ => return Err(Error::UnknownPlaceholder(name)),
+                    };
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(core::mem::take(&mut literal)));
+                    }
+                    parts.push(part);
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Whether the template addresses single fields.
+    pub fn has_field(&self) -> bool {
+        self.parts.contains(&Part::Field)
+    }
+
+    /// Renders the template. A missing `field` renders as nothing.
+    pub fn render(&self, context: &Context) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            // Writing to a String can't fail.
+            let _ = match part {
+                Part::Literal(literal) => out.write_str(literal),
+                Part::Pgn => write!(out, "{}", context.pgn),
+                Part::Source => write!(out, "{}", context.source),
+                Part::Instance => match context.instance {
+                    Some(instance) => out.write_str(instance),
+                    None => write!(out, "{}", context.source),
+                },
+                Part::Name => out.write_str(context.name),
+                Part::Field => out.write_str(context.field.unwrap_or("")),
+            };
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let context = Context {
+            pgn: 130306,
+            source: 35,
+            instance: None,
+            name: "wind_data",
+            field: None,
+        };
+        let template = Template::parse("{{n2k}}/{pgn}/{src}-{instance}").unwrap();
+        assert_eq!(template.render(&context), "{n2k}/130306/35-35");
+        assert!(!template.has_field());
+
+        assert_eq!(
+            Template::parse("n2k/{sauce}"),
+            Err(Error::UnknownPlaceholder("sauce".into()))
+        );
+        assert_eq!(Template::parse("n2k/{pgn"), Err(Error::Unclosed));
+    }
+}
@@ -0,0 +1,118 @@
+//! Bundled capture snippets for conformance tests.
+//!
+//! Each capture is a candump log (`(seconds) interface ID#DATA`) of one or
+//! more Fast-Packet sessions, plus `# expect <source> <pgn> <payload>`
+//! comments listing the messages a conforming reassembler produces, in
+//! completion order. The Fusion snippet is the one quoted in the `Frame`
+//! documentation; the others are built from the published field layouts of
+//! their PGNs and exercise interleaving, long messages and restarts.
+use crate::analyzer::CapturedFrame;
+use crate::candump;
+
+pub struct Capture {
+    pub name: &'static str,
+    log: &'static str,
+}
+
+/// A message the capture is expected to yield.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Expected {
+    pub source: u8,
+    pub pgn: u32,
+    payload_hex: &'static str,
+}
+
+impl Expected {
+    /// Decodes the payload into `buf` and returns its length.
+    pub fn payload(&self, buf: &mut [u8]) -> usize {
+        decode_hex(self.payload_hex, buf)
+    }
+}
+
+pub const CAPTURES: &[Capture] = &[
+    Capture {
+        name: "fusion_130820",
+        log: include_str!("../test-vectors/fusion_130820.log"),
+    },
+    Capture {
+        name: "gnss_129029_interleaved",
+        log: include_str!("../test-vectors/gnss_129029_interleaved.log"),
+    },
+    Capture {
+        name: "product_info_126996",
+        log: include_str!("../test-vectors/product_info_126996.log"),
+    },
+    Capture {
+        name: "restart_127237",
+        log: include_str!("../test-vectors/restart_127237.log"),
+    },
+];
+
+fn decode_hex(hex: &str, buf: &mut [u8]) -> usize {
+    let bytes = hex.as_bytes();
+    let len = (bytes.len() / 2).min(buf.len());
+    for (i, byte) in buf[..len].iter_mut().enumerate() {
+        let digits = core::str::from_utf8(&bytes[2 * i..2 * i + 2]).unwrap();
+        *byte = u8::from_str_radix(digits, 16).unwrap();
+    }
+    len
+}
+
+impl Capture {
+    pub fn frames(&self) -> impl Iterator<Item = CapturedFrame> + '_ {
+        self.log
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(candump::parse_line)
+    }
+
+    pub fn expected(&self) -> impl Iterator<Item = Expected> + '_ {
+        self.log.lines().filter_map(|line| {
+            let mut fields = line.strip_prefix("# expect ")?.split_whitespace();
+            Some(Expected {
+                source: fields.next()?.parse().ok()?,
+                pgn: fields.next()?.parse().ok()?,
+                payload_hex: fields.next()?,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nmea_message::MAX_NMEA_PACKET_SIZE;
+    use crate::reassembler::Reassembler;
+
+    #[test]
+    fn test_parse() {
+        let frame = CAPTURES[0].frames().next().unwrap();
+        assert_eq!(frame.timestamp_ms, 1_700_000_000_000);
+        assert_eq!(frame.id.pgn(), 130820);
+        assert_eq!(frame.id.source(), 10);
+        assert_eq!(frame.data, [0xE0, 0x17, 0xA3, 0x99, 0x04, 0x80, 0x05, 0x02]);
+        assert_eq!(CAPTURES[0].frames().count(), 4);
+    }
+
+    #[test]
+    fn test_conformance() {
+        for capture in CAPTURES {
+            let mut reassembler: Reassembler<4> = Reassembler::new();
+            let mut expected = capture.expected();
+            for frame in capture.frames() {
+                let (source, pgn) = (frame.id.source(), frame.id.pgn());
+                if !reassembler.add_frame(source, pgn, &frame.data).unwrap() {
+                    continue;
+                }
+                let want = expected.next().expect(capture.name);
+                assert_eq!((want.source, want.pgn), (source, pgn), "{}", capture.name);
+                let mut buf = [0; MAX_NMEA_PACKET_SIZE];
+                let len = reassembler.get_payload(source, pgn, &mut buf).unwrap();
+                let mut want_buf = [0; MAX_NMEA_PACKET_SIZE];
+                let want_len = want.payload(&mut want_buf);
+                assert_eq!(buf[..len], want_buf[..want_len], "{}", capture.name);
+            }
+            assert_eq!(expected.next(), None, "{}", capture.name);
+        }
+    }
+}
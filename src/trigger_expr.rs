@@ -0,0 +1,470 @@
+//! Flight recorder triggers written as text, so captures can be armed from
+//! a configuration file or a console without writing Rust.
+//!
+//! An expression either compares a decoded field with a number, optionally
+//! for one source only:
+//!
+//! ```text
+//! 127488.speed > 3500
+//! 128267.depth <= 2.5 from 0x23
+//! ```
+//!
+//! or matches a frame-level event, a Fast-Packet frame out of sequence:
+//!
+//! ```text
+//! sequence_error
+//! sequence_error from 35
+//! ```
+//!
+//! Values are in the units of the decoders: metres, m/s, radians, Kelvin,
+//! pascals and rpm. `FIELDS` lists the fields that can be compared.
+use crate::analyzer::{CapturedFrame, Direction};
+use crate::flight_recorder::{CaptureSink, FlightRecorder, Trigger};
+use crate::nmea_message;
+use crate::pgn::depth::WaterDepth;
+use crate::pgn::engine::{EngineDynamic, EngineRapid};
+use crate::pgn::environment::{Humidity, Temperature};
+use crate::pgn::fluid_level::FluidLevel;
+use crate::pgn::gnss::CogSogRapidUpdate;
+use crate::pgn::heading::VesselHeading;
+use crate::pgn::speed::WaterSpeed;
+use crate::pgn::wind::WindData;
+use crate::pipeline::{Packet, Reassemble, Stage};
+use crate::reassembler;
+use fixed_queue::Vec;
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Malformed trigger expression")]
+    Syntax,
+    #[error("Unknown field")]
+    UnknownField,
+    #[error("Too many triggers")]
+    FullTable,
+}
+
+type Extract = fn(&[u8]) -> Option<f32>;
+
+/// The fields expressions can compare, by PGN and name.
+pub const FIELDS: &[(u32, &str, Extract)] = &[
+    (VesselHeading::PGN, "heading", |p| {
+        VesselHeading::from_payload(p).ok()?.heading
+    }),
+    (VesselHeading::PGN, "deviation", |p| {
+        VesselHeading::from_payload(p).ok()?.deviation
+    }),
+    (VesselHeading::PGN, "variation", |p| {
+        VesselHeading::from_payload(p).ok()?.variation
+    }),
+    (EngineRapid::PGN, "speed", |p| {
+        EngineRapid::from_payload(p).ok()?.speed
+    }),
+    (EngineRapid::PGN, "boost", |p| {
+        EngineRapid::from_payload(p).ok()?.boost
+    }),
+    (EngineDynamic::PGN, "oil_pressure", |p| {
+        EngineDynamic::from_payload(p).ok()?.oil_pressure
+    }),
+    (EngineDynamic::PGN, "oil_temperature", |p| {
+        EngineDynamic::from_payload(p).ok()?.oil_temperature
+    }),
+    (EngineDynamic::PGN, "temperature", |p| {
+        EngineDynamic::from_payload(p).ok()?.temperature
+    }),
+    (EngineDynamic::PGN, "alternator_potential", |p| {
+        EngineDynamic::from_payload(p).ok()?.alternator_potential
+    }),
+    (EngineDynamic::PGN, "fuel_rate", |p| {
+        EngineDynamic::from_payload(p).ok()?.fuel_rate
+    }),
+    (FluidLevel::PGN, "level", |p| {
+        FluidLevel::from_payload(p).ok()?.level
+    }),
+    (WaterSpeed::PGN, "water", |p| {
+        WaterSpeed::from_payload(p).ok()?.water
+    }),
+    (WaterDepth::PGN, "depth", |p| {
+        WaterDepth::from_payload(p).ok()?.depth
+    }),
+    (CogSogRapidUpdate::PGN, "cog", |p| {
+        CogSogRapidUpdate::from_payload(p).ok()?.cog
+    }),
+    (CogSogRapidUpdate::PGN, "sog", |p| {
+        CogSogRapidUpdate::from_payload(p).ok()?.sog
+    }),
+    (WindData::PGN, "speed", |p| {
+        WindData::from_payload(p).ok()?.speed
+    }),
+    (WindData::PGN, "angle", |p| {
+        WindData::from_payload(p).ok()?.angle
+    }),
+    (Temperature::PGN, "temperature", |p| {
+        Temperature::from_payload(p).ok()?.temperature
+    }),
+    (Humidity::PGN, "humidity", |p| {
+        Humidity::from_payload(p).ok()?.humidity
+    }),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "<" => Self::Less,
+            "<=" => Self::LessOrEqual,
+            ">" => Self::Greater,
+            ">=" => Self::GreaterOrEqual,
+            "==" => Self::Equal,
+            "!=" => Self::NotEqual,
+            _ => return None,
+        })
+    }
+
+    pub fn holds(self, a: f32, b: f32) -> bool {
+        match self {
+            Self::Less => a < b,
+            Self::LessOrEqual => a <= b,
+            Self::Greater => a > b,
+            Self::GreaterOrEqual => a >= b,
+            Self::Equal => a == b,
+            Self::NotEqual => a != b,
+        }
+    }
+}
+
+/// A parsed trigger expression.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Expr {
+    Field {
+        pgn: u32,
+        field: &'static str,
+        comparison: Comparison,
+        value: f32,
+        source: Option<u8>,
+    },
+    SequenceError {
+        source: Option<u8>,
+    },
+}
+
+fn parse_source(s: &str) -> Option<u8> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+impl Expr {
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let mut words = s.split_whitespace();
+        let first = words.next().ok_or(Error::Syntax)?;
+        let expr = if first == "sequence_error" {
+            Self::SequenceError { source: None }
+        } else {
+            let (pgn, name) = first.split_once('.').ok_or(Error::Syntax)?;
+            let pgn = pgn.parse().map_err(|_| Error::Syntax)?;
+            let field = FIELDS
+                .iter()
+                .find(|(p, n, _)| *p == pgn && *n == name)
+                .map(|(_, n, _)| *n)
+                .ok_or(Error::UnknownField)?;
+            let comparison = words
+                .next()
+                .and_then(Comparison::parse)
+                .ok_or(Error::Syntax)?;
+            let value = words
+                .next()
+                .and_then(|v| v.parse().ok())
+                .ok_or(Error::Syntax)?;
+            Self::Field {
+                pgn,
+                field,
+                comparison,
+                value,
+                source: None,
+            }
+        };
+        let source = match (words.next(), words.next(), words.next()) {
+            (None, _, _) => None,
+            (Some("from"), Some(source), None) => Some(parse_source(source).ok_or(Error::Syntax)?),
+            _ => return Err(Error::Syntax),
+        };
+        Ok(match expr {
+            Self::Field {
+                pgn,
+                field,
+                comparison,
+                value,
+                ..
+            } => Self::Field {
+                pgn,
+                field,
+                comparison,
+                value,
+                source,
+            },
+            Self::SequenceError { .. } => Self::SequenceError { source },
+        })
+    }
+
+    /// Whether a complete message matches the expression.
+    pub fn matches_message(&self, source: u8, pgn: u32, payload: &[u8]) -> bool {
+        let Self::Field {
+            pgn: p,
+            field,
+            comparison,
+            value,
+            source: s,
+        } = *self
+        else {
+            return false;
+        };
+        if p != pgn || s.is_some_and(|s| s != source) {
+            return false;
+        }
+        FIELDS
+            .iter()
+            .find(|(fp, name, _)| *fp == pgn && *name == field)
+            .and_then(|(_, _, extract)| extract(payload))
+            .is_some_and(|x| comparison.holds(x, value))
+    }
+
+    /// Whether a reassembly error on a frame from `source` matches the
+    /// expression.
+    pub fn matches_error(&self, source: u8, error: &reassembler::Error) -> bool {
+        let Self::SequenceError { source: s } = *self else {
+            return false;
+        };
+        let out_of_sequence = matches!(
+            error,
+            reassembler::Error::NoSession
+                | reassembler::Error::Message(
+                    nmea_message::Error::SequenceMismatch { .. }
+                        | nmea_message::Error::SequenceCountError { .. }
+                )
+        );
+        out_of_sequence && s.is_none_or(|s| s == source)
+    }
+}
+
+/// Up to `N` trigger expressions.
+pub struct TriggerSet<const N: usize> {
+    exprs: Vec<Expr, N>,
+}
+
+impl<const N: usize> Default for TriggerSet<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> TriggerSet<N> {
+    pub const fn new() -> Self {
+        Self { exprs: Vec::new() }
+    }
+
+    /// Parses and adds an expression, returning its index.
+    pub fn add(&mut self, expr: &str) -> Result<usize, Error> {
+        let expr = Expr::parse(expr)?;
+        self.exprs.push(expr).map_err(|_| Error::FullTable)?;
+        Ok(self.exprs.len() - 1)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Expr> {
+        self.exprs.get(index)
+    }
+
+    /// The index of the first expression matching a complete message.
+    pub fn check_message(&self, source: u8, pgn: u32, payload: &[u8]) -> Option<usize> {
+        self.exprs
+            .iter()
+            .position(|e| e.matches_message(source, pgn, payload))
+    }
+
+    /// The index of the first expression matching a reassembly error.
+    pub fn check_error(&self, source: u8, error: &reassembler::Error) -> Option<usize> {
+        self.exprs
+            .iter()
+            .position(|e| e.matches_error(source, error))
+    }
+}
+
+/// A pipeline stage recording every frame into a `FlightRecorder`, then
+/// reassembling like `Reassemble` and firing the recorder when a message or
+/// reassembly error matches one of `T` trigger expressions.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::analyzer::CapturedFrame;
+/// use nmea::can_id::CanId;
+/// use nmea::flight_recorder::{CaptureSink, FlightRecorder, Trigger};
+/// use nmea::frame_queue::RxFrame;
+/// use nmea::pipeline::Pipeline;
+/// use nmea::trigger_expr::{Armed, TriggerSet};
+///
+/// #[derive(Default)]
+/// struct Dump(Vec<Trigger>);
+///
+/// impl CaptureSink for Dump {
+///     fn begin(&mut self, trigger: Trigger, _: u64) {
+///         self.0.push(trigger);
+///     }
+///     fn frame(&mut self, _: &CapturedFrame) {}
+///     fn end(&mut self) {}
+/// }
+///
+/// let mut triggers: TriggerSet<4> = TriggerSet::new();
+/// triggers.add("128267.depth < 2.5").unwrap();
+/// let recorder: FlightRecorder<Dump, 64> = FlightRecorder::new(Dump::default(), 5000, 5000);
+/// let mut pipeline = Pipeline::new(Armed::<_, 64, 4, 8>::new(recorder, triggers, |_| false));
+///
+/// // 2 m under the transducer.
+/// let id = CanId::new(3, 128267, 0x23, 255).unwrap();
+/// let depth = RxFrame { id, data: [0x01, 0xC8, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF] };
+/// pipeline.push(1000, &depth);
+/// assert_eq!(pipeline.stages().recorder().sink().0, [Trigger::Expression(0)]);
+/// ```
+pub struct Armed<K: CaptureSink, const CAP: usize, const T: usize, const R: usize> {
+    recorder: FlightRecorder<K, CAP>,
+    triggers: TriggerSet<T>,
+    reassemble: Reassemble<R>,
+}
+
+impl<K: CaptureSink, const CAP: usize, const T: usize, const R: usize> Armed<K, CAP, T, R> {
+    pub const fn new(
+        recorder: FlightRecorder<K, CAP>,
+        triggers: TriggerSet<T>,
+        fast_packet: fn(u32) -> bool,
+    ) -> Self {
+        Self {
+            recorder,
+            triggers,
+            reassemble: Reassemble::new(fast_packet),
+        }
+    }
+
+    pub fn recorder(&self) -> &FlightRecorder<K, CAP> {
+        &self.recorder
+    }
+
+    pub fn recorder_mut(&mut self) -> &mut FlightRecorder<K, CAP> {
+        &mut self.recorder
+    }
+
+    pub fn triggers_mut(&mut self) -> &mut TriggerSet<T> {
+        &mut self.triggers
+    }
+}
+
+impl<K: CaptureSink, const CAP: usize, const T: usize, const R: usize> Stage
+    for Armed<K, CAP, T, R>
+{
+    fn process(&mut self, packet: &mut Packet) -> bool {
+        let (source, pgn, now_ms) = (packet.id.source(), packet.id.pgn(), packet.timestamp_ms);
+        if let Ok(data) = <[u8; 8]>::try_from(packet.payload()) {
+            self.recorder.record(&CapturedFrame {
+                timestamp_ms: now_ms,
+                id: packet.id,
+                data,
+                direction: Direction::Rx,
+            });
+        }
+        let passed = self.reassemble.process(packet);
+        let fired = if passed {
+            self.triggers.check_message(source, pgn, packet.payload())
+        } else {
+            self.reassemble
+                .take_error()
+                .and_then(|e| self.triggers.check_error(source, &e))
+        };
+        if let Some(index) = fired {
+            self.recorder.trigger(Trigger::Expression(index), now_ms);
+        }
+        passed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::can_id::CanId;
+    use crate::frame_queue::RxFrame;
+    use crate::pipeline::Pipeline;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Expr::parse("127488.speed > 3500 from 0x10"),
+            Ok(Expr::Field {
+                pgn: 127488,
+                field: "speed",
+                comparison: Comparison::Greater,
+                value: 3500.0,
+                source: Some(0x10),
+            })
+        );
+        assert_eq!(
+            Expr::parse("sequence_error from 35"),
+            Ok(Expr::SequenceError { source: Some(35) })
+        );
+        assert_eq!(Expr::parse("127488.torque > 1"), Err(Error::UnknownField));
+        assert_eq!(Expr::parse("127488.speed >> 1"), Err(Error::Syntax));
+        assert_eq!(Expr::parse("127488.speed > 1 from"), Err(Error::Syntax));
+        assert_eq!(Expr::parse("sequence_error 5"), Err(Error::Syntax));
+        assert_eq!(Expr::parse(""), Err(Error::Syntax));
+    }
+
+    #[derive(Default)]
+    struct Dump(std::vec::Vec<(Trigger, u64)>);
+
+    impl CaptureSink for Dump {
+        fn begin(&mut self, trigger: Trigger, timestamp_ms: u64) {
+            self.0.push((trigger, timestamp_ms));
+        }
+
+        fn frame(&mut self, _: &CapturedFrame) {}
+
+        fn end(&mut self) {}
+    }
+
+    #[test]
+    fn test_armed() {
+        let mut triggers: TriggerSet<2> = TriggerSet::new();
+        triggers.add("127488.speed > 3500 from 0x10").unwrap();
+        assert_eq!(triggers.add("sequence_error from 0x20"), Ok(1));
+        assert_eq!(triggers.add("sequence_error"), Err(Error::FullTable));
+        let recorder: FlightRecorder<Dump, 16> = FlightRecorder::new(Dump::default(), 100, 100);
+        let mut pipeline = Pipeline::new(Armed::<_, 16, 2, 4>::new(recorder, triggers, |pgn| {
+            pgn == 129029
+        }));
+
+        // 4000 rpm from another engine, then from 0x10.
+        let engine = [0x00, 0x80, 0x3E, 0xFF, 0xFF, 0x7F, 0xFF, 0xFF];
+        let frame = |pgn, source, data| RxFrame {
+            id: CanId::new(2, pgn, source, 255).unwrap(),
+            data,
+        };
+        pipeline.push(0, &frame(127488, 0x11, engine));
+        assert!(pipeline.stages().recorder().sink().0.is_empty());
+        assert!(pipeline.push(10, &frame(127488, 0x10, engine)).is_some());
+        // A consecutive GNSS frame without its first frame.
+        let orphan = [0x01, 0, 0, 0, 0, 0, 0, 0];
+        pipeline.push(500, &frame(129029, 0x21, orphan));
+        pipeline.push(600, &frame(129029, 0x20, orphan));
+        assert_eq!(
+            pipeline.stages().recorder().sink().0,
+            [(Trigger::Expression(0), 10), (Trigger::Expression(1), 600)]
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! Trip computer: distance, speed and time since the last reset, as shown
+//! on a chartplotter's trip page.
+use crate::bridge::FrameSink;
+use crate::device::{self, Destination, N2kDevice};
+use crate::nav_state::Position;
+use crate::pgn::distance_log::DistanceLog;
+use crate::pgn::gnss::{CogSogRapidUpdate, GnssPositionData, PositionRapidUpdate};
+use crate::pgn::Error;
+
+/// Totals since the last reset.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Trip {
+    /// Distance over ground from the positions, in metres.
+    pub distance: f64,
+    /// Distance through the water from the log, in metres.
+    pub water_distance: f64,
+    /// Time since the first input after the reset.
+    pub elapsed_ms: u64,
+    /// Highest speed over ground in m/s.
+    pub max_speed: f32,
+}
+
+impl Trip {
+    /// Average speed over ground in m/s.
+    pub fn average_speed(&self) -> Option<f64> {
+        (self.elapsed_ms > 0).then(|| self.distance / (self.elapsed_ms as f64 / 1000.0))
+    }
+}
+
+/// Accumulates a `Trip` from positions (PGNs 129025 and 129029), COG/SOG
+/// (PGN 129026) and the distance log (PGN 128275).
+///
+/// Positions closer than `min_step` metres to the last one counted are held
+/// back, so that GPS jitter at anchor doesn't add up. A position more than
+/// `max_gap_ms` after the previous one starts a new leg without counting
+/// the distance between them.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::trip::TripComputer;
+///
+/// fn position(latitude: f64) -> [u8; 8] {
+///     let mut payload = [0; 8];
+///     payload[..4].copy_from_slice(&((latitude * 1e7) as i32).to_le_bytes());
+///     payload
+/// }
+///
+/// let mut trip = TripComputer::new(10_000).with_min_step(5.0);
+/// trip.ingest(129025, &position(50.0), 0).unwrap();
+/// trip.ingest(129025, &position(50.001), 60_000).unwrap();
+/// // The minute between the fixes is too long a gap to count.
+/// assert_eq!(trip.trip().distance, 0.0);
+/// trip.ingest(129025, &position(50.002), 65_000).unwrap();
+/// assert!((trip.trip().distance - 111.2).abs() < 0.1);
+/// assert_eq!(trip.trip().elapsed_ms, 65_000);
+/// ```
+pub struct TripComputer {
+    trip: Trip,
+    /// Distance over ground since the computer was created.
+    total: f64,
+    start_ms: Option<u64>,
+    /// Last position counted, and when a position was last received.
+    position: Option<(Position, u64)>,
+    log: Option<u32>,
+    max_gap_ms: u64,
+    min_step: f64,
+}
+
+impl TripComputer {
+    pub const fn new(max_gap_ms: u64) -> Self {
+        Self {
+            trip: Trip {
+                distance: 0.0,
+                water_distance: 0.0,
+                elapsed_ms: 0,
+                max_speed: 0.0,
+            },
+            total: 0.0,
+            start_ms: None,
+            position: None,
+            log: None,
+            max_gap_ms,
+            min_step: 0.0,
+        }
+    }
+
+    /// Ignores moves shorter than `min_step` metres.
+    pub const fn with_min_step(mut self, min_step: f64) -> Self {
+        self.min_step = min_step;
+        self
+    }
+
+    /// Updates the trip from a decoded payload. Returns `false` if `pgn` is
+    /// not one of 128275, 129025, 129026 or 129029.
+    pub fn ingest(&mut self, pgn: u32, payload: &[u8], now_ms: u64) -> Result<bool, Error> {
+        match pgn {
+            PositionRapidUpdate::PGN => {
+                let msg = PositionRapidUpdate::from_payload(payload)?;
+                self.update_position(msg.latitude.zip(msg.longitude), now_ms);
+            }
+            GnssPositionData::PGN => {
+                let msg = GnssPositionData::from_payload(payload)?;
+                self.update_position(msg.latitude.zip(msg.longitude), now_ms);
+            }
+            CogSogRapidUpdate::PGN => {
+                if let Some(sog) = CogSogRapidUpdate::from_payload(payload)?.sog {
+                    self.trip.max_speed = self.trip.max_speed.max(sog);
+                }
+            }
+            DistanceLog::PGN => {
+                let Some(log) = DistanceLog::from_payload(payload)?.log else {
+                    return Ok(true);
+                };
+                // A log that went backwards was reset.
+                if let Some(last) = self.log.filter(|last| log >= *last) {
+                    self.trip.water_distance += (log - last) as f64;
+                }
+                self.log = Some(log);
+            }
+            _ => return Ok(false),
+        }
+        let start_ms = *self.start_ms.get_or_insert(now_ms);
+        self.trip.elapsed_ms = now_ms.saturating_sub(start_ms);
+        Ok(true)
+    }
+
+    fn update_position(&mut self, position: Option<(f64, f64)>, now_ms: u64) {
+        let Some((latitude, longitude)) = position else {
+            return;
+        };
+        let position = Position {
+            latitude,
+            longitude,
+        };
+        let Some((last, last_ms)) = self.position else {
+            self.position = Some((position, now_ms));
+            return;
+        };
+        if now_ms.saturating_sub(last_ms) > self.max_gap_ms {
+            self.position = Some((position, now_ms));
+            return;
+        }
+        let step = last.distance_to(&position);
+        if step < self.min_step {
+            self.position = Some((last, now_ms));
+            return;
+        }
+        self.trip.distance += step;
+        self.total += step;
+        self.position = Some((position, now_ms));
+    }
+
+    pub fn trip(&self) -> Trip {
+        self.trip
+    }
+
+    /// Distance over ground since the computer was created, in metres.
+    pub fn total_distance(&self) -> f64 {
+        self.total
+    }
+
+    /// Starts a new trip. The timer starts again at the next input.
+    pub fn reset(&mut self) {
+        self.trip = Trip::default();
+        self.start_ms = None;
+    }
+
+    /// The trip as a Distance Log, with the distance over ground since the
+    /// computer was created as the total log.
+    pub fn distance_log(&self) -> DistanceLog {
+        DistanceLog {
+            date: None,
+            time: None,
+            log: Some(libm::round(self.total) as u32),
+            trip_log: Some(libm::round(self.trip.distance) as u32),
+        }
+    }
+
+    /// Broadcasts `distance_log` through `device`, returning the number of
+    /// frames sent. A computer publishing PGN 128275 shouldn't also ingest
+    /// it, or it would count its own log as distance through the water.
+    pub fn publish<K: FrameSink, const N: usize>(
+        &self,
+        device: &mut N2kDevice<K, N>,
+    ) -> Result<usize, device::Error> {
+        device.send(&self.distance_log(), Destination::Broadcast, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge;
+    use crate::frame_queue::RxFrame;
+    use std::vec::Vec;
+
+    struct Bus(Vec<RxFrame>);
+
+    impl FrameSink for Bus {
+        fn transmit(&mut self, frame: &RxFrame) -> Result<(), bridge::Error> {
+            self.0.push(*frame);
+            Ok(())
+        }
+    }
+
+    fn position(latitude: f64, longitude: f64) -> [u8; 8] {
+        let mut payload = [0; 8];
+        payload[..4].copy_from_slice(&((latitude * 1e7) as i32).to_le_bytes());
+        payload[4..].copy_from_slice(&((longitude * 1e7) as i32).to_le_bytes());
+        payload
+    }
+
+    fn log(total: u32) -> [u8; DistanceLog::LEN] {
+        DistanceLog {
+            date: None,
+            time: None,
+            log: Some(total),
+            trip_log: None,
+        }
+        .to_payload()
+    }
+
+    #[test]
+    fn test_trip() {
+        let mut trip = TripComputer::new(10_000).with_min_step(10.0);
+        assert!(trip.ingest(129025, &position(50.0, -4.0), 1000).unwrap());
+        // Jitter of a few metres is held back until it adds up.
+        for (i, latitude) in [50.00003, 50.00006, 50.0001].iter().enumerate() {
+            trip.ingest(129025, &position(*latitude, -4.0), 2000 + i as u64 * 1000)
+                .unwrap();
+        }
+        assert!((trip.trip().distance - 11.1).abs() < 0.1);
+
+        // 2.5 m/s over ground.
+        let cog_sog: [u8; 8] = [0x00, 0xFC, 0x00, 0x00, 0xFA, 0x00, 0xFF, 0xFF];
+        assert!(trip.ingest(129026, &cog_sog, 4000).unwrap());
+        assert_eq!(trip.trip().max_speed, 2.5);
+
+        assert!(trip.ingest(DistanceLog::PGN, &log(1000), 5000).unwrap());
+        trip.ingest(DistanceLog::PGN, &log(1250), 6000).unwrap();
+        // The log was reset.
+        trip.ingest(DistanceLog::PGN, &log(10), 7000).unwrap();
+        trip.ingest(DistanceLog::PGN, &log(30), 11_000).unwrap();
+        let totals = trip.trip();
+        assert_eq!(totals.water_distance, 270.0);
+        assert_eq!(totals.elapsed_ms, 10_000);
+        assert!((totals.average_speed().unwrap() - 1.11).abs() < 0.01);
+        assert!(!trip.ingest(127250, &[0; 8], 11_000).unwrap());
+
+        trip.reset();
+        assert_eq!(trip.trip(), Trip::default());
+        assert_eq!(trip.trip().average_speed(), None);
+        trip.ingest(129025, &position(50.0002, -4.0), 12_000)
+            .unwrap();
+        assert!((trip.trip().distance - 11.1).abs() < 0.1);
+        assert_eq!(trip.trip().elapsed_ms, 0);
+        assert!((trip.total_distance() - 22.2).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_publish() {
+        let mut trip = TripComputer::new(5000);
+        let mut device: N2kDevice<Bus, 4> = N2kDevice::new(Bus(Vec::new()), 0x23);
+        trip.ingest(129025, &position(50.0, -4.0), 0).unwrap();
+        trip.ingest(129025, &position(50.001, -4.0), 1000).unwrap();
+        trip.reset();
+        trip.ingest(129025, &position(50.0015, -4.0), 2000).unwrap();
+        assert_eq!(trip.publish(&mut device), Ok(3));
+
+        let frames = &device.sink().0;
+        assert_eq!(frames[0].id.pgn(), DistanceLog::PGN);
+        assert_eq!(trip.distance_log().log, Some(167));
+        assert_eq!(trip.distance_log().trip_log, Some(56));
+    }
+}
@@ -0,0 +1,286 @@
+//! Application data larger than a Fast-Packet message, split across
+//! several messages on a proprietary PGN.
+//!
+//! Each message carries the proprietary header followed by a tunnel header
+//! and up to `CHUNK_LEN` bytes of the blob:
+//!
+//! | Bytes | Field                                      |
+//! |-------|--------------------------------------------|
+//! | 0-1   | Manufacturer and industry code             |
+//! | 2     | Transfer ID, incremented for every blob    |
+//! | 3-4   | Index of this message                      |
+//! | 5-6   | Number of messages in the transfer         |
+use crate::bridge::FrameSink;
+use crate::device::{self, Destination, N2kDevice};
+use crate::nmea_message::MAX_NMEA_PACKET_SIZE;
+use crate::pgn::proprietary::{ProprietaryHeader, INDUSTRY_MARINE};
+use thiserror_no_std::Error;
+
+/// Length of the proprietary and tunnel headers.
+pub const HEADER_LEN: usize = ProprietaryHeader::LEN + 5;
+/// Bytes of the blob carried by each message.
+pub const CHUNK_LEN: usize = MAX_NMEA_PACKET_SIZE - HEADER_LEN;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Blob is too long")]
+    TooLong,
+    #[error("Message is shorter than the tunnel header")]
+    Truncated,
+    #[error("Message {index} of transfer {transfer} from {source} is out of order")]
+    OutOfOrder {
+        source: u8,
+        transfer: u8,
+        index: u16,
+    },
+    #[error(transparent)]
+    Device(#[from] device::Error),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Transfer {
+    source: u8,
+    id: u8,
+    next: u16,
+    count: u16,
+}
+
+/// A tunnel on `pgn` for the manufacturer `manufacturer_code`, sending blobs
+/// of any length and reassembling received blobs of up to `SIZE` bytes.
+///
+/// Received messages are complete Fast-Packet payloads, e.g. from a
+/// `Reassembler`. One transfer is received at a time; a first message
+/// abandons any transfer in progress, and messages of other manufacturers
+/// are ignored, as are those of other senders and transfers while it lasts.
+///
+/// ## Example:
+///
+/// ```
+/// use nmea::bridge::{self, FrameSink};
+/// use nmea::device::{Destination, N2kDevice};
+/// use nmea::frame_queue::RxFrame;
+/// use nmea::reassembler::Reassembler;
+/// use nmea::tunnel::Tunnel;
+///
+/// struct Bus(Vec<RxFrame>);
+///
+/// impl FrameSink for Bus {
+///     fn transmit(&mut self, frame: &RxFrame) -> Result<(), bridge::Error> {
+///         self.0.push(*frame);
+///         Ok(())
+///     }
+/// }
+///
+/// let blob: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+/// let mut device: N2kDevice<Bus, 4> = N2kDevice::new(Bus(Vec::new()), 0x23);
+/// let mut sender: Tunnel<0> = Tunnel::new(130820, 2046);
+/// assert_eq!(sender.send(&mut device, 7, Destination::Broadcast, &blob), Ok(5));
+///
+/// let mut reassembler: Reassembler<4> = Reassembler::new();
+/// let mut receiver: Tunnel<1024> = Tunnel::new(130820, 2046);
+/// let mut received = None;
+/// for frame in &device.sink().0 {
+///     let (source, pgn) = (frame.id.source(), frame.id.pgn());
+///     if reassembler.add_frame(source, pgn, &frame.data).unwrap() {
+///         let mut payload = [0; 223];
+///         let len = reassembler.get_payload(source, pgn, &mut payload).unwrap();
+///         if let Some(blob) = receiver.receive(source, pgn, &payload[..len]).unwrap() {
+///             received = Some(blob.to_vec());
+///         }
+///     }
+/// }
+/// assert_eq!(received, Some(blob));
+/// ```
+pub struct Tunnel<const SIZE: usize> {
+    pgn: u32,
+    header: ProprietaryHeader,
+    transfer_id: u8,
+    rx: Option<Transfer>,
+    len: usize,
+    buf: [u8; SIZE],
+}
+
+impl<const SIZE: usize> Tunnel<SIZE> {
+    pub const fn new(pgn: u32, manufacturer_code: u16) -> Self {
+        Self {
+            pgn,
+            header: ProprietaryHeader {
+                manufacturer_code,
+                industry_code: INDUSTRY_MARINE,
+            },
+            transfer_id: 0,
+            rx: None,
+            len: 0,
+            buf: [0; SIZE],
+        }
+    }
+
+    pub fn pgn(&self) -> u32 {
+        self.pgn
+    }
+
+    /// Sends `blob` through `device`. Returns the number of messages sent.
+    pub fn send<K: FrameSink, const N: usize>(
+        &mut self,
+        device: &mut N2kDevice<K, N>,
+        priority: u8,
+        destination: Destination,
+        blob: &[u8],
+    ) -> Result<u16, Error> {
+        let count =
+            u16::try_from(blob.len().div_ceil(CHUNK_LEN).max(1)).map_err(|_| Error::TooLong)?;
+        let id = self.transfer_id;
+        self.transfer_id = self.transfer_id.wrapping_add(1);
+        let mut payload = [0xFF; MAX_NMEA_PACKET_SIZE];
+        payload[..2].copy_from_slice(&self.header.to_bytes());
+        payload[2] = id;
+        payload[5..7].copy_from_slice(&count.to_le_bytes());
+        for index in 0..count {
+            let start = index as usize * CHUNK_LEN;
+            let chunk = &blob[start..blob.len().min(start + CHUNK_LEN)];
+            payload[3..5].copy_from_slice(&index.to_le_bytes());
+            payload[HEADER_LEN..HEADER_LEN + chunk.len()].copy_from_slice(chunk);
+            device.send_fast_packet(
+                priority,
+                self.pgn,
+                destination,
+                &payload[..HEADER_LEN + chunk.len()],
+            )?;
+        }
+        Ok(count)
+    }
+
+    /// Adds a message received from `source` on `pgn`. Returns the blob once
+    /// its last message has arrived.
+    ///
+    /// A message out of sequence from the sender of the transfer in progress
+    /// abandons the transfer.
+    pub fn receive(
+        &mut self,
+        source: u8,
+        pgn: u32,
+        payload: &[u8],
+    ) -> Result<Option<&[u8]>, Error> {
+        if pgn != self.pgn || ProprietaryHeader::from_payload(payload).ok() != Some(self.header) {
+            return Ok(None);
+        }
+        if payload.len() < HEADER_LEN {
+            return Err(Error::Truncated);
+        }
+        let id = payload[2];
+        let index = u16::from_le_bytes([payload[3], payload[4]]);
+        let count = u16::from_le_bytes([payload[5], payload[6]]);
+        let transfer = match self.rx {
+            _ if index == 0 => {
+                self.len = 0;
+                Transfer {
+                    source,
+                    id,
+                    next: 0,
+                    count,
+                }
+            }
+            // Another sender's, or a stale transfer's, message.
+            Some(rx) if (rx.source, rx.id) != (source, id) => return Ok(None),
+            Some(rx) if (rx.next, rx.count) == (index, count) => rx,
+            _ => {
+                self.rx = None;
+                return Err(Error::OutOfOrder {
+                    source,
+                    transfer: id,
+                    index,
+                });
+            }
+        };
+        self.rx = None;
+        let chunk = &payload[HEADER_LEN..];
+        let end = self.len + chunk.len();
+        if end > SIZE {
+            return Err(Error::TooLong);
+        }
+        self.buf[self.len..end].copy_from_slice(chunk);
+        self.len = end;
+        if transfer.next + 1 < transfer.count {
+            self.rx = Some(Transfer {
+                next: transfer.next + 1,
+                ..transfer
+            });
+            return Ok(None);
+        }
+        Ok(Some(&self.buf[..self.len]))
+    }
+
+    /// Whether a transfer is being received.
+    pub fn is_receiving(&self) -> bool {
+        self.rx.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: u8, index: u16, count: u16, data: &[u8]) -> ([u8; MAX_NMEA_PACKET_SIZE], usize) {
+        let mut payload = [0; MAX_NMEA_PACKET_SIZE];
+        payload[..7].copy_from_slice(&[0xFE, 0x9F, id, index as u8, 0, count as u8, 0]);
+        payload[7..7 + data.len()].copy_from_slice(data);
+        (payload, 7 + data.len())
+    }
+
+    #[test]
+    fn test_receive() {
+        let mut tunnel: Tunnel<8> = Tunnel::new(130820, 2046);
+        let (first, len) = message(1, 0, 2, &[1, 2, 3]);
+        assert_eq!(tunnel.receive(0x10, 130820, &first[..len]), Ok(None));
+        // Another source, transfer, PGN or manufacturer.
+        let (second, second_len) = message(1, 1, 2, &[4]);
+        assert_eq!(
+            tunnel.receive(0x11, 130820, &second[..second_len]),
+            Ok(None)
+        );
+        let (stale, stale_len) = message(0, 1, 2, &[9]);
+        assert_eq!(tunnel.receive(0x10, 130820, &stale[..stale_len]), Ok(None));
+        assert_eq!(
+            tunnel.receive(0x10, 130821, &second[..second_len]),
+            Ok(None)
+        );
+        assert_eq!(tunnel.receive(0x10, 130820, &[0xA3, 0x99, 1]), Ok(None));
+        assert!(tunnel.is_receiving());
+        assert_eq!(
+            tunnel.receive(0x10, 130820, &second[..second_len]),
+            Ok(Some(&[1, 2, 3, 4][..]))
+        );
+        assert!(!tunnel.is_receiving());
+
+        // A gap in the sender's own sequence abandons the transfer.
+        assert_eq!(tunnel.receive(0x10, 130820, &first[..len]), Ok(None));
+        let (third, third_len) = message(1, 2, 3, &[5]);
+        assert_eq!(
+            tunnel.receive(0x10, 130820, &third[..third_len]),
+            Err(Error::OutOfOrder {
+                source: 0x10,
+                transfer: 1,
+                index: 2
+            })
+        );
+        assert!(!tunnel.is_receiving());
+        assert_eq!(
+            tunnel.receive(0x11, 130820, &second[..second_len]),
+            Err(Error::OutOfOrder {
+                source: 0x11,
+                transfer: 1,
+                index: 1
+            })
+        );
+
+        let (long, len) = message(2, 0, 1, &[0; 9]);
+        assert_eq!(
+            tunnel.receive(0x10, 130820, &long[..len]),
+            Err(Error::TooLong)
+        );
+        assert_eq!(
+            tunnel.receive(0x10, 130820, &long[..5]),
+            Err(Error::Truncated)
+        );
+    }
+}
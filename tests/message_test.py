@@ -1,9 +1,12 @@
+import copy
 import math
+import pickle
 import random
 
 import pytest
 from assertpy import assert_that
 from nmea import Message as NmeaMessage
+from nmea import WindData, decode, pgn_info
 
 
 def test_rx():
@@ -104,3 +107,95 @@ def test_tx():
     assert_that(msg.pop_frame()).is_equal_to(buf_2)
     assert_that(msg.pop_frame()).is_equal_to(buf_3)
     assert_that(msg.pop_frame()).is_equal_to(buf_4)
+
+
+def test_from_payload_invalid_length():
+    with pytest.raises(ValueError):
+        NmeaMessage.from_payload(bytes(), 0)
+    with pytest.raises(ValueError):
+        NmeaMessage.from_payload(bytes(224), 0)
+
+
+def test_abort():
+    msg = NmeaMessage()
+    msg.add_frame(bytes([0x00, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D]))
+
+    assert_that(msg.abort()).is_true()
+    assert_that(msg.abort()).is_false()
+    assert_that(msg.num_frames).is_equal_to(0)
+
+
+def test_kind_and_direction():
+    msg = NmeaMessage.from_payload(bytes([0x01, 0x02]), 0)
+    assert_that(msg.kind).is_equal_to("single")
+    assert_that(msg.direction).is_equal_to("tx")
+
+    with pytest.raises(Exception) as exc_info:
+        msg.get_payload()
+    assert_that(str(exc_info.value)).is_equal_to("Wrong transmission type")
+
+
+def test_pgn_info():
+    info = pgn_info(130306)
+    assert_that(info["name"]).is_equal_to("Wind Data")
+    assert_that(info["fields"][1]).is_equal_to(
+        {"name": "speed", "type": "u16", "scale": 0.01, "unit": "m/s"}
+    )
+    assert_that(pgn_info(1)).is_none()
+
+
+def test_pickle_and_copy():
+    msg = NmeaMessage()
+    msg.add_frame(bytes([0x00, 0x19, 0x12, 0x7C, 0xEA, 0xD5, 0x12, 0x3D]))
+
+    for restored in [pickle.loads(pickle.dumps(msg)), copy.copy(msg), copy.deepcopy(msg)]:
+        assert_that(restored.num_frames).is_equal_to(4)
+        assert_that(restored.kind).is_equal_to("consecutive")
+        # The copy continues assembling independently of the original.
+        assert_that(
+            restored.add_frame(bytes([0x01, 0x31, 0xF3, 0xD0, 0xAC, 0xF2, 0x23, 0x1A]))
+        ).is_false()
+
+    with pytest.raises(ValueError):
+        msg.__setstate__(bytes([0x01]))
+    # Two frames claimed for a 20-byte payload, which takes three.
+    with pytest.raises(ValueError):
+        msg.__setstate__(bytes([1, 0, 2, 20, 0, 0, 0, 0x00, 0x14, 1, 2, 3, 4, 5, 6]))
+
+
+def test_decode_frames():
+    np = pytest.importorskip("numpy")
+    from nmea import decode_frames
+
+    # A two-frame 129029 session with a heading frame in between.
+    frames = np.array(
+        [
+            [0x0DF80505, 0x60, 0x09, 1, 2, 3, 4, 5, 6],
+            [0x09F11223, 0, 1, 2, 3, 4, 5, 6, 7],
+            [0x0DF80505, 0x61, 7, 8, 9, 0xFF, 0xFF, 0xFF, 0xFF],
+        ],
+        dtype=np.int64,
+    )
+    messages = decode_frames(frames, [129029])
+    assert_that([m["pgn"] for m in messages]).is_equal_to([127250, 129029])
+    assert_that(messages[1]["row"]).is_equal_to(2)
+    assert_that(messages[1]["source"]).is_equal_to(5)
+    assert_that(messages[1]["payload"]).is_equal_to(bytes(range(1, 10)))
+
+    with pytest.raises(ValueError):
+        decode_frames(frames[:, :8], [])
+
+
+def test_decode():
+    payload = bytes([0x01, 0xF4, 0x01, 0x00, 0x80, 0x02, 0xFF, 0xFF])
+    wind = decode(130306, payload)
+    assert_that(wind).is_instance_of(WindData)
+    assert_that(wind.speed).is_equal_to(5.0)
+    assert_that(wind.reference).is_equal_to("Apparent")
+    assert_that(wind).is_equal_to(decode(130306, payload))
+    assert_that(wind.to_dict()).contains_entry({"sid": 1})
+    assert_that(repr(wind)).starts_with("WindData(sid=1, speed=5.0")
+    assert_that(decode(1, payload)).is_none()
+
+    with pytest.raises(ValueError):
+        decode(130306, payload[:2])